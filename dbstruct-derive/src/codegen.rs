@@ -1,7 +1,7 @@
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote, quote_spanned};
 
-use crate::ir::{Accessor, Ir, NewMethod, Struct};
+use crate::ir::{Accessor, Deprecation, Ir, NewMethod, SaveMethod, Struct, Transaction};
 
 pub fn codegen(ir: Ir) -> TokenStream {
     #![allow(unused_variables)]
@@ -9,6 +9,12 @@ pub fn codegen(ir: Ir) -> TokenStream {
     let definition = definition(ir.definition, &ir.bounds);
     let accessors = accessor_impl(ir.accessors);
     let new_impls = new_impls(ir.new);
+    let (transaction_fn, transaction) = match ir.transaction {
+        Some(tx) => (transaction_fn(&tx), transaction(tx)),
+        None => (TokenStream::new(), TokenStream::new()),
+    };
+    let save_fn = ir.save.map(save_fn).unwrap_or_default();
+    let deprecation = ir.deprecation.map(deprecation_warning).unwrap_or_default();
     let bounds = ir.bounds;
 
     match bounds {
@@ -22,28 +28,145 @@ pub fn codegen(ir: Ir) -> TokenStream {
         ),
         None => quote!(
             #definition
+            #transaction
+            #deprecation
 
             impl #struct_ident {
                 #new_impls
                 #accessors
+                #transaction_fn
+                #save_fn
             }
         ),
     }
 }
 
+fn transaction(
+    Transaction {
+        ident,
+        vis,
+        ds,
+        member_vars,
+        accessors,
+        ..
+    }: Transaction,
+) -> TokenStream {
+    let accessors = accessor_impl(accessors);
+    quote!(
+        #vis struct #ident<'tx> {
+            ds: #ds,
+            #(#member_vars),*
+        }
+
+        impl<'tx> #ident<'tx> {
+            #accessors
+        }
+    )
+}
+
+/// The transaction works on a copy of the lengths, heads and tails. They are
+/// only written back once the transaction commits. The copy is made inside
+/// the closure as the database may run it again on conflict. Their locks are
+/// held for the whole transaction so no other thread changes them meanwhile.
+fn transaction_fn(
+    Transaction {
+        ident,
+        vis,
+        shared,
+        error_ty,
+        tx_error_ty,
+        ..
+    }: &Transaction,
+) -> TokenStream {
+    let ids = shared;
+    let new_ids: Vec<_> = ids.iter().map(|id| format_ident!("new_{}", id)).collect();
+    quote!(
+        /// Runs `op` as a single transaction, either all its changes are
+        /// applied or none are. Return an error from `op` to abort the
+        /// transaction. `op` may run multiple times if the transaction
+        /// conflicts with another.
+        #vis fn transaction<R>(
+            &self,
+            op: impl Fn(&#ident<'_>) -> Result<R, ::dbstruct::Error<#tx_error_ty>>,
+        ) -> Result<R, ::dbstruct::Error<#error_ty>> {
+            #(let mut #ids = self.#ids
+                .lock()
+                .unwrap_or_else(::std::sync::PoisonError::into_inner);)*
+            let (res, #(#new_ids,)*) = ::dbstruct::traits::data_store::Transactional::transaction(
+                &self.ds,
+                |ds| {
+                    let tx = #ident {
+                        ds: ds.clone(),
+                        #(#ids: ::std::sync::Arc::new(::std::sync::Mutex::new(*#ids)),)*
+                    };
+                    let res = op(&tx)?;
+                    #(let #new_ids = *tx.#ids
+                        .lock()
+                        .unwrap_or_else(::std::sync::PoisonError::into_inner);)*
+                    Ok((res, #(#new_ids,)*))
+                },
+            )?;
+            #(*#ids = #new_ids;)*
+            Ok(res)
+        }
+    )
+}
+
+/// Proc macros can not emit warnings on stable. Instead we use an item marked
+/// deprecated giving it the span of the backend option. The compiler then
+/// warns about the backend.
+fn deprecation_warning(
+    Deprecation {
+        span,
+        backend,
+        note,
+    }: Deprecation,
+) -> TokenStream {
+    let ident = syn::Ident::new(&backend, span);
+    quote_spanned!(span=>
+        const _: () = {
+            #[allow(non_camel_case_types)]
+            #[deprecated(note = #note)]
+            struct #ident;
+            let _ = #ident;
+        };
+    )
+}
+
+fn save_fn(
+    SaveMethod {
+        asyncness,
+        vis,
+        store,
+        error_ty,
+    }: SaveMethod,
+) -> TokenStream {
+    quote!(
+        /// Saves a snapshot of the database to `path`. Load it again using
+        /// `open_file`.
+        #vis #asyncness fn save(
+            &self,
+            path: impl AsRef<std::path::Path>,
+        ) -> Result<(), ::dbstruct::Error<#error_ty>> {
+            #store.save_to(path).map_err(::dbstruct::Error::Database)
+        }
+    )
+}
+
 fn new_impls(new: impl IntoIterator<Item = NewMethod>) -> TokenStream {
     new.into_iter()
         .map(
             |NewMethod {
+                 asyncness,
                  locals,
                  members,
                  vis,
-                 arg,
+                 args,
                  error_ty,
                  name,
              }| {
                 quote!(
-                     #vis fn #name(#arg) -> Result<Self, ::dbstruct::Error<#error_ty>> {
+                     #vis #asyncness fn #name(#(#args),*) -> Result<Self, ::dbstruct::Error<#error_ty>> {
                          #(#locals)*
                          Ok(Self {
                              ds,
@@ -115,7 +238,7 @@ mod tests {
             ident: parse_quote!(Test),
             vis: parse_quote!(pub),
             member_vars: fields
-                .into_iter()
+                .iter()
                 .map(|s| parser.parse_str(s))
                 .map(Result::unwrap)
                 .collect(),
@@ -161,10 +284,11 @@ mod tests {
         ["new", "secondary_new"]
             .into_iter()
             .map(|name| NewMethod {
+                asyncness: None,
                 members: vec![parse_quote!(u8field: 0)],
                 vis: parse_quote!(pub),
                 locals: Vec::new(),
-                arg: Some(parse_quote!(ds: DS)),
+                args: vec![parse_quote!(ds: DS)],
                 error_ty: parse_quote!(DS),
                 name: syn::parse_str(name).unwrap(),
             })
@@ -179,7 +303,7 @@ mod tests {
             .split(|token| token.to_string().as_str() == "pub")
             .filter(|tokens| tokens.len() > 1)
         {
-            let stream = TokenStream::from_iter(new_impl.into_iter().cloned());
+            let stream = TokenStream::from_iter(new_impl.iter().cloned());
             assert!(syn::parse2::<syn::ItemFn>(stream).is_ok())
         }
     }
@@ -191,6 +315,9 @@ mod tests {
             new: test_new_impls(),
             accessors: vec![test_accessor()],
             bounds: parse_quote!(where DS: dbstruct::DataStore + std::clone::Clone),
+            transaction: None,
+            save: None,
+            deprecation: None,
         };
         let rust = codegen(ir);
         println!("{rust}");
@@ -1,10 +1,16 @@
 mod accessor;
 mod new_method;
+mod save_method;
 mod struct_def;
+mod transaction;
 
-pub use accessor::Accessor;
+pub use accessor::{Accessor, Store};
 pub use new_method::NewMethod;
+pub use save_method::SaveMethod;
 pub use struct_def::Struct;
+pub use transaction::Transaction;
+
+use proc_macro2::Span;
 use syn::parse_quote;
 
 use crate::model::backend::{Backend, ExtraBound};
@@ -15,20 +21,61 @@ pub struct Ir {
     pub new: Vec<NewMethod>,
     pub accessors: Vec<Accessor>,
     pub bounds: Option<syn::WhereClause>,
+    pub transaction: Option<Transaction>,
+    pub save: Option<SaveMethod>,
+    pub deprecation: Option<Deprecation>,
+}
+
+/// A deprecated backend was chosen, codegen turns this into a warning
+pub struct Deprecation {
+    /// Where the backend was chosen
+    pub span: Span,
+    pub backend: String,
+    pub note: &'static str,
+}
+
+fn deprecation(backend: &Backend) -> Option<Deprecation> {
+    match backend {
+        Backend::HashMap { span } => Some(Deprecation {
+            span: *span,
+            backend: backend.to_string(),
+            note: "use `db=btreemap` instead, it supports all wrappers",
+        }),
+        _ => None,
+    }
 }
 
 fn bound_to_ir(bound: &ExtraBound) -> syn::TraitBound {
     match bound {
         ExtraBound::Atomic => parse_quote!(dbstruct::traits::data_store::Atomic),
         ExtraBound::Ordered => parse_quote!(dbstruct::traits::data_store::Ordered),
+        ExtraBound::RawOrdered => parse_quote!(dbstruct::traits::byte_store::Ordered),
+        ExtraBound::Ranged => parse_quote!(dbstruct::traits::data_store::Ranged),
+    }
+}
+
+fn async_bound_to_ir(bound: &ExtraBound) -> syn::TraitBound {
+    match bound {
+        ExtraBound::Atomic => parse_quote!(dbstruct::traits::async_data_store::Atomic),
+        ExtraBound::Ordered => parse_quote!(dbstruct::traits::async_data_store::Ordered),
+        ExtraBound::RawOrdered => unreachable!("migrations are not supported for async structs"),
+        ExtraBound::Ranged => unreachable!("filtered out, async ranges only need Ordered"),
     }
 }
 
 fn bounds_from(model: &Model) -> Option<syn::WhereClause> {
     match &model.backend {
+        Backend::Trait { bounds } if model.asynchronous => {
+            // The async OrderedMap walks its ranges using Ordered
+            let bounds = bounds
+                .iter()
+                .filter(|bound| **bound != ExtraBound::Ranged)
+                .map(async_bound_to_ir);
+            parse_quote!(where DS: dbstruct::traits::AsyncDataStore + std::clone::Clone #(+ #bounds)*)
+        }
         Backend::Trait { bounds } => {
             let bounds = bounds.iter().map(bound_to_ir);
-            parse_quote!(where DS: dbstruct::DataStore + std::clone::Clone + #(#bounds),*)
+            parse_quote!(where DS: dbstruct::DataStore + std::clone::Clone #(+ #bounds)*)
         }
         _ => None,
     }
@@ -37,7 +84,13 @@ fn bounds_from(model: &Model) -> Option<syn::WhereClause> {
 fn backend_type(backend: &Backend) -> syn::Type {
     match backend {
         Backend::Sled => parse_quote!(::dbstruct::sled::Tree),
-        Backend::HashMap => parse_quote!(::dbstruct::stores::HashMap),
+        Backend::RocksDb => parse_quote!(::dbstruct::stores::RocksDb),
+        Backend::Redb => parse_quote!(::dbstruct::stores::Redb),
+        Backend::Sqlite => parse_quote!(::dbstruct::stores::Sqlite),
+        Backend::Lmdb => parse_quote!(::dbstruct::stores::Lmdb),
+        Backend::Fjall => parse_quote!(::dbstruct::stores::Fjall),
+        Backend::File => parse_quote!(::dbstruct::stores::File),
+        Backend::HashMap { .. } => parse_quote!(::dbstruct::stores::HashMap),
         Backend::BTreeMap => parse_quote!(::dbstruct::stores::BTreeMap),
         Backend::Trait { .. } => parse_quote!(DS),
         #[cfg(test)]
@@ -45,16 +98,79 @@ fn backend_type(backend: &Backend) -> syn::Type {
     }
 }
 
+/// The database wrapped in the adapters chosen using the struct options.
+/// Values are compressed before they are encrypted. The cache holds the
+/// plain values. Read only structs refuse writes before they reach any
+/// other adapter.
+fn store_type(model: &Model) -> syn::Type {
+    let mut store_ty = backend_type(&model.backend);
+    if model.compression.is_some() {
+        store_ty = parse_quote!(::dbstruct::stores::Compressed<#store_ty>);
+    }
+    if model.encrypted {
+        store_ty = parse_quote!(::dbstruct::stores::Encrypted<#store_ty>);
+    }
+    if model.cache.is_some() {
+        store_ty = parse_quote!(::dbstruct::stores::Cached<#store_ty>);
+    }
+    if model.read_only {
+        store_ty = parse_quote!(::dbstruct::stores::ReadOnly<#store_ty>);
+    }
+    store_ty
+}
+
+/// The type of the data store held by the struct. Async structs access a
+/// blocking database through `Blocking`.
+fn ds_type(model: &Model) -> syn::Type {
+    let backend_ty = store_type(model);
+    match model.backend {
+        Backend::Trait { .. } => backend_ty,
+        _ if model.asynchronous => parse_quote!(::dbstruct::stores::Blocking<#backend_ty>),
+        _ => backend_ty,
+    }
+}
+
+/// The data store held by the struct as seen by the accessors
+fn accessor_store(model: &Model) -> Store {
+    let store_ty = store_type(model);
+    match model.backend {
+        Backend::Trait { .. } => Store::Direct(store_ty),
+        _ if model.asynchronous => Store::Blocking(store_ty),
+        // The cache is dropped after each transaction, cached structs keep
+        // counts without them
+        _ if model.backend.supports_transactions() && !model.read_only && model.cache.is_none() => {
+            Store::Transactional(store_ty)
+        }
+        _ => Store::Direct(store_ty),
+    }
+}
+
+/// The module containing the wrappers the accessors return
+fn wrapper_module(model: &Model) -> syn::Path {
+    if model.asynchronous {
+        parse_quote!(dbstruct::wrapper::asynchronous)
+    } else if model.read_only {
+        parse_quote!(dbstruct::wrapper::read_only)
+    } else {
+        parse_quote!(dbstruct::wrapper)
+    }
+}
+
 impl Ir {
     pub fn from(model: Model) -> Self {
         let definition = Struct::from(&model);
         let new = NewMethod::list(&model, &definition);
         let bounds = bounds_from(&model);
-        let backend_ty = backend_type(&model.backend);
+        let backend_ty = store_type(&model);
+        let transaction = Transaction::from(&model, &definition, &backend_ty);
+        let save = SaveMethod::from(&model);
+        let deprecation = deprecation(&model.backend);
+        let store = accessor_store(&model);
+        let module = wrapper_module(&model);
         let accessors = model
             .fields
             .into_iter()
-            .map(|f| Accessor::from(f, backend_ty.clone()))
+            .map(|f| Accessor::from(f, &store, &module))
             .collect();
 
         Self {
@@ -62,6 +178,9 @@ impl Ir {
             new,
             accessors,
             bounds,
+            transaction,
+            save,
+            deprecation,
         }
     }
 }
@@ -1,10 +1,11 @@
-use proc_macro2::Span;
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, ToTokens};
 use syn::spanned::Spanned;
 use syn::{parse_quote, parse_quote_spanned};
 
 use crate::model::{Field, Wrapper};
 
-use super::struct_def::{deque_head_ident, deque_tail_ident, vec_len_ident};
+use super::struct_def::{deque_head_ident, deque_tail_ident, len_ident};
 
 pub struct Accessor {
     pub vis: syn::Visibility,
@@ -13,70 +14,171 @@ pub struct Accessor {
     pub body: syn::Block,
 }
 
+/// The data store held by the struct
+#[derive(Clone)]
+pub enum Store {
+    /// The struct holds this store
+    Direct(syn::Type),
+    /// The struct holds this store, wrappers that write several keys at once
+    /// do so in a transaction
+    Transactional(syn::Type),
+    /// Async structs hold this store wrapped in `Blocking`
+    Blocking(syn::Type),
+}
+
+impl Store {
+    /// The type of the data store passed to the wrapper of `field` and the
+    /// expression creating it. Compressed fields wrap the store in
+    /// `Compressed`.
+    fn for_field(&self, field: &Field) -> (syn::Type, syn::Expr) {
+        let compressed = field.compress.is_some();
+        match self {
+            Store::Direct(ds) | Store::Transactional(ds) if compressed => (
+                parse_quote!(::dbstruct::stores::Compressed<#ds>),
+                parse_quote!(::dbstruct::stores::Compressed::new(self.ds.clone())),
+            ),
+            Store::Direct(ds) | Store::Transactional(ds) => {
+                (ds.clone(), parse_quote!(self.ds.clone()))
+            }
+            Store::Blocking(store) if compressed => (
+                parse_quote!(::dbstruct::stores::Blocking<::dbstruct::stores::Compressed<#store>>),
+                parse_quote!(::dbstruct::stores::Blocking::new(
+                    ::dbstruct::stores::Compressed::new(self.ds.inner().clone())
+                )),
+            ),
+            Store::Blocking(store) => (
+                parse_quote!(::dbstruct::stores::Blocking<#store>),
+                parse_quote!(self.ds.clone()),
+            ),
+        }
+    }
+
+    /// The wrapper constructor called `name`, or its variant that writes
+    /// through transactions if the store supports them
+    fn constructor(&self, name: &str) -> syn::Ident {
+        match self {
+            Store::Transactional(_) => format_ident!("{name}_transactional"),
+            Store::Direct(_) | Store::Blocking(_) => format_ident!("{name}"),
+        }
+    }
+}
+
 impl Accessor {
-    pub fn from(field: Field, ds: syn::Type) -> Self {
+    pub fn from(field: Field, store: &Store, module: &syn::Path) -> Self {
         let key = field.key;
+        let new_constructor = store.constructor("new");
+        let counted_constructor = store.constructor("counted");
+        let (ds, store) = store.for_field(&field);
         let (body, returns) = match field.wrapper {
             #[allow(unused_variables)]
             Wrapper::Vec { ty } => {
-                let len_ident = vec_len_ident(&field.ident);
+                let len_ident = len_ident(&field.ident);
                 let body = parse_quote!({
-                    dbstruct::wrapper::Vec::new(self.ds.clone(), #key, self.#len_ident.clone())
+                    #module::Vec::#new_constructor(#store, #key, self.#len_ident.clone())
                 });
-                let returns = parse_quote_spanned!(ty.span()=> dbstruct::wrapper::Vec<#ty, #ds>);
+                let module = respan(module, ty.span());
+                let returns = parse_quote_spanned!(ty.span()=> #module::Vec<#ty, #ds>);
                 (body, returns)
             }
             Wrapper::VecDeque { ty } => {
                 let head_ident = deque_head_ident(&field.ident);
                 let tail_ident = deque_tail_ident(&field.ident);
                 let body = parse_quote!({
-                    dbstruct::wrapper::VecDeque::new(self.ds.clone(), #key, self.#head_ident.clone(), self.#tail_ident.clone())
+                    #module::VecDeque::new(#store, #key, self.#head_ident.clone(), self.#tail_ident.clone())
                 });
+                let module = respan(module, ty.span());
                 let returns =
-                    parse_quote_spanned!(ty.span()=> dbstruct::wrapper::VecDeque<#ty,#ds>);
+                    parse_quote_spanned!(ty.span()=> #module::VecDeque<#ty,#ds>);
                 (body, returns)
             }
             #[allow(unused_variables)]
-            Wrapper::Map { key_ty, val_ty } => {
-                let body = parse_quote!({
-                    dbstruct::wrapper::Map::new(self.ds.clone(), #key)
-                });
+            Wrapper::Map { key_ty, val_ty, counted } => {
+                let body = if counted {
+                    let len_ident = len_ident(&field.ident);
+                    parse_quote!({
+                        #module::Map::#counted_constructor(#store, #key, self.#len_ident.clone())
+                    })
+                } else {
+                    parse_quote!({
+                        #module::Map::new(#store, #key)
+                    })
+                };
                 // Using proc_macro2 version until
                 // https://github.com/rust-lang/rust/issues/54725 stabalizes
                 let span = key_ty
                     .span()
                     .join(val_ty.span())
                     .unwrap_or(Span::call_site());
+                let module = respan(module, span);
                 let returns =
-                    parse_quote_spanned!(span=> dbstruct::wrapper::Map<#key_ty, #val_ty, #ds>);
+                    parse_quote_spanned!(span=> #module::Map<#key_ty, #val_ty, #ds>);
                 (body, returns)
             }
             #[allow(unused_variables)]
             Wrapper::DefaultTrait { ty } => {
                 let body = parse_quote!({
-                    dbstruct::wrapper::DefaultTrait::new(self.ds.clone(), #key)
+                    #module::DefaultTrait::new(#store, #key)
                 });
+                let module = respan(module, ty.span());
                 let returns =
-                    parse_quote_spanned!(ty.span()=> dbstruct::wrapper::DefaultTrait<#ty, #ds>);
+                    parse_quote_spanned!(ty.span()=> #module::DefaultTrait<#ty, #ds>);
                 (body, returns)
             }
             #[allow(unused_variables)]
             Wrapper::DefaultValue { ty, value } => {
                 let body = parse_quote_spanned!(ty.span()=> {
                     let default_value = #value;
-                    dbstruct::wrapper::DefaultValue::new(self.ds.clone(), #key, default_value)
+                    #module::DefaultValue::new(#store, #key, default_value)
                 });
+                let module = respan(module, ty.span());
                 let returns =
-                    parse_quote_spanned!(ty.span()=> dbstruct::wrapper::DefaultValue<#ty, #ds>);
+                    parse_quote_spanned!(ty.span()=> #module::DefaultValue<#ty, #ds>);
                 (body, returns)
             }
             #[allow(unused_variables)]
             Wrapper::Option { ty } => {
                 let body = parse_quote!({
-                    dbstruct::wrapper::OptionValue::new(self.ds.clone(), #key)
+                    #module::OptionValue::new(#store, #key)
                 });
+                let module = respan(module, ty.span());
                 let returns =
-                    parse_quote_spanned!(ty.span()=> dbstruct::wrapper::OptionValue<#ty, #ds>);
+                    parse_quote_spanned!(ty.span()=> #module::OptionValue<#ty, #ds>);
+                (body, returns)
+            }
+            #[allow(unused_variables)]
+            Wrapper::Set { ty, counted } => {
+                let body = if counted {
+                    let len_ident = len_ident(&field.ident);
+                    parse_quote!({
+                        #module::Set::#counted_constructor(#store, #key, self.#len_ident.clone())
+                    })
+                } else {
+                    parse_quote!({
+                        #module::Set::new(#store, #key)
+                    })
+                };
+                let module = respan(module, ty.span());
+                let returns = parse_quote_spanned!(ty.span()=> #module::Set<#ty, #ds>);
+                (body, returns)
+            }
+            Wrapper::OrderedMap { key_ty, val_ty, counted } => {
+                let body = if counted {
+                    let len_ident = len_ident(&field.ident);
+                    parse_quote!({
+                        #module::OrderedMap::#counted_constructor(#store, #key, self.#len_ident.clone())
+                    })
+                } else {
+                    parse_quote!({
+                        #module::OrderedMap::new(#store, #key)
+                    })
+                };
+                let span = key_ty
+                    .span()
+                    .join(val_ty.span())
+                    .unwrap_or(Span::call_site());
+                let module = respan(module, span);
+                let returns =
+                    parse_quote_spanned!(span=> #module::OrderedMap<#key_ty, #val_ty, #ds>);
                 (body, returns)
             }
         };
@@ -90,6 +192,19 @@ impl Accessor {
     }
 }
 
+/// Give the wrapper module path the span of the field type so errors about
+/// the returned wrapper point at the field.
+fn respan(module: &syn::Path, span: Span) -> TokenStream {
+    module
+        .to_token_stream()
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,9 +218,10 @@ mod tests {
                 ty: parse_quote!(u8),
             },
             key: 1,
+            compress: None,
         };
-        let ds_ty = parse_quote!(DS);
-        let _a = Accessor::from(field, ds_ty);
+        let store = Store::Direct(parse_quote!(DS));
+        let _a = Accessor::from(field, &store, &parse_quote!(dbstruct::wrapper));
     }
 
     #[test]
@@ -118,9 +234,10 @@ mod tests {
                 value: parse_quote!(5 + 12),
             },
             key: 1,
+            compress: None,
         };
-        let ds_ty = parse_quote!(DS);
-        let _a = Accessor::from(field, ds_ty);
+        let store = Store::Direct(parse_quote!(DS));
+        let _a = Accessor::from(field, &store, &parse_quote!(dbstruct::wrapper));
     }
 
     #[test]
@@ -132,9 +249,10 @@ mod tests {
                 ty: parse_quote!(u8),
             },
             key: 1,
+            compress: None,
         };
-        let ds_ty = parse_quote!(DS);
-        let _a = Accessor::from(field, ds_ty);
+        let store = Store::Direct(parse_quote!(DS));
+        let _a = Accessor::from(field, &store, &parse_quote!(dbstruct::wrapper));
     }
 
     #[test]
@@ -146,9 +264,10 @@ mod tests {
                 ty: parse_quote!(u8),
             },
             key: 1,
+            compress: None,
         };
-        let ds_ty = parse_quote!(DS);
-        let _a = Accessor::from(field, ds_ty);
+        let store = Store::Direct(parse_quote!(DS));
+        let _a = Accessor::from(field, &store, &parse_quote!(dbstruct::wrapper));
     }
 
     #[test]
@@ -159,10 +278,66 @@ mod tests {
             wrapper: Wrapper::Map {
                 key_ty: parse_quote!(u8),
                 val_ty: parse_quote!(u16),
+                counted: false,
+            },
+            key: 1,
+            compress: None,
+        };
+        let store = Store::Direct(parse_quote!(DS));
+        let _a = Accessor::from(field, &store, &parse_quote!(dbstruct::wrapper));
+    }
+
+    #[test]
+    fn counted_map_writes_in_transactions() {
+        let field = Field {
+            ident: parse_quote!(test_a),
+            vis: parse_quote!(pub),
+            wrapper: Wrapper::Map {
+                key_ty: parse_quote!(u8),
+                val_ty: parse_quote!(u16),
+                counted: true,
+            },
+            key: 1,
+            compress: None,
+        };
+        let store = Store::Transactional(parse_quote!(::dbstruct::stores::BTreeMap));
+        let a = Accessor::from(field, &store, &parse_quote!(dbstruct::wrapper));
+        let body = a.body.to_token_stream().to_string();
+        assert!(body.contains("Map :: counted_transactional"));
+    }
+
+    #[test]
+    fn vec_shifts_in_transactions() {
+        let field = Field {
+            ident: parse_quote!(test_a),
+            vis: parse_quote!(pub),
+            wrapper: Wrapper::Vec {
+                ty: parse_quote!(u8),
+            },
+            key: 1,
+            compress: None,
+        };
+        let store = Store::Transactional(parse_quote!(::dbstruct::stores::BTreeMap));
+        let a = Accessor::from(field, &store, &parse_quote!(dbstruct::wrapper));
+        let body = a.body.to_token_stream().to_string();
+        assert!(body.contains("Vec :: new_transactional"));
+    }
+
+    #[test]
+    fn compressed_async() {
+        let field = Field {
+            ident: parse_quote!(test_a),
+            vis: parse_quote!(pub),
+            wrapper: Wrapper::Vec {
+                ty: parse_quote!(u8),
             },
             key: 1,
+            compress: Some(Span::call_site()),
         };
-        let ds_ty = parse_quote!(DS);
-        let _a = Accessor::from(field, ds_ty);
+        let store = Store::Blocking(parse_quote!(::dbstruct::stores::BTreeMap));
+        let module = parse_quote!(dbstruct::wrapper::asynchronous);
+        let a = Accessor::from(field, &store, &module);
+        let returns = a.returns.to_token_stream().to_string();
+        assert!(returns.contains("Blocking < :: dbstruct :: stores :: Compressed"));
     }
 }
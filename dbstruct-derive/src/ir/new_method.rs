@@ -1,20 +1,22 @@
 use itertools::Itertools;
 use proc_macro2::Span;
+use quote::quote;
 use syn::punctuated::Punctuated;
+use syn::visit_mut::VisitMut;
 use syn::{parse_quote, Expr, Ident, LocalInit, Pat, PathArguments, Token};
 
+use crate::model::attribute::Migrations;
 use crate::model::backend::Backend;
 use crate::model::{Field, Model, Wrapper};
 
-use super::struct_def::{
-    deque_head_ident, deque_tail_ident, no_syn_phantom_ident, vec_len_ident, Struct,
-};
+use super::struct_def::{deque_head_ident, deque_tail_ident, len_ident, lock_path, Struct};
 
 pub struct NewMethod {
+    pub asyncness: Option<Token![async]>,
     pub locals: Vec<syn::Local>,
     pub members: Vec<syn::FieldValue>,
     pub vis: syn::Visibility,
-    pub arg: Option<syn::FnArg>,
+    pub args: Vec<syn::FnArg>,
     pub error_ty: syn::Type,
     pub name: syn::Ident,
 }
@@ -30,52 +32,63 @@ fn as_member(field: &syn::Field) -> syn::FieldValue {
     }
 }
 
-fn len_expr(ty: &syn::Type, prefix: u8) -> Box<syn::Expr> {
+fn len_expr(prefix: u8, asynchronous: bool) -> Box<syn::Expr> {
+    let stored_len: syn::Expr = if asynchronous {
+        parse_quote!(::dbstruct::wrapper::asynchronous::stored_len(&ds, #prefix).await?)
+    } else {
+        parse_quote!(::dbstruct::wrapper::VecPrefixed::stored_len(&ds, #prefix)?)
+    };
+    let lock = lock_path(asynchronous);
     let expr: syn::Expr = parse_quote!(
         std::sync::Arc::new(
-            std::sync::atomic::AtomicUsize::new(
-                ::dbstruct::traits::data_store::Ordered::get_lt(
-                        &ds,
-                        &::dbstruct::wrapper::VecPrefixed::max(#prefix),
-                    )?
-                    .map(|(key, _): (::dbstruct::wrapper::VecPrefixed, #ty)| key)
-                    .map(|key| key.index() + 1) // a vecs len is index + 1
-                    .unwrap_or(0)
-            ) // atomic new
+            #lock::new(
+                #stored_len
+            ) // lock new
         ) // arc new
     );
     Box::new(expr)
 }
 
-fn tail_expr(ty: &syn::Type, prefix: u8) -> Box<syn::Expr> {
+fn map_len_expr(prefix: u8, key_ty: &syn::Type) -> Box<syn::Expr> {
     let expr: syn::Expr = parse_quote!(
         std::sync::Arc::new(
-            std::sync::atomic::AtomicU64::new(
-                ::dbstruct::traits::data_store::Ordered::get_lt(
-                        &ds,
-                        &::dbstruct::wrapper::DequePrefixed::max(#prefix),
-                    )?
-                    .map(|(key, _): (::dbstruct::wrapper::DequePrefixed, #ty)| key)
-                    .map(|key| key.index() + 1)
-                    .unwrap_or(u64::MAX / 2)
-            ) // atomic new
+            ::std::sync::Mutex::new(
+                ::dbstruct::wrapper::map::stored_len::<#key_ty, _>(&ds, #prefix)?
+            ) // lock new
         ) // arc new
     );
     Box::new(expr)
 }
 
-fn head_expr(ty: &syn::Type, prefix: u8) -> Box<syn::Expr> {
+fn tail_expr(prefix: u8, asynchronous: bool) -> Box<syn::Expr> {
+    let stored_tail: syn::Expr = if asynchronous {
+        parse_quote!(::dbstruct::wrapper::asynchronous::stored_tail(&ds, #prefix).await?)
+    } else {
+        parse_quote!(::dbstruct::wrapper::DequePrefixed::stored_tail(&ds, #prefix)?)
+    };
+    let lock = lock_path(asynchronous);
     let expr: syn::Expr = parse_quote!(
         std::sync::Arc::new(
-            std::sync::atomic::AtomicU64::new(
-                ::dbstruct::traits::data_store::Ordered::get_gt(
-                        &ds,
-                        &::dbstruct::wrapper::DequePrefixed::min(#prefix),
-                    )?
-                    .map(|(key, _): (::dbstruct::wrapper::DequePrefixed, #ty)| key)
-                    .map(|key| key.index() - 1)
-                    .unwrap_or(u64::MAX / 2 - 1)
-            ) // atomic new
+            #lock::new(
+                #stored_tail
+            ) // lock new
+        ) // arc new
+    );
+    Box::new(expr)
+}
+
+fn head_expr(prefix: u8, asynchronous: bool) -> Box<syn::Expr> {
+    let stored_head: syn::Expr = if asynchronous {
+        parse_quote!(::dbstruct::wrapper::asynchronous::stored_head(&ds, #prefix).await?)
+    } else {
+        parse_quote!(::dbstruct::wrapper::DequePrefixed::stored_head(&ds, #prefix)?)
+    };
+    let lock = lock_path(asynchronous);
+    let expr: syn::Expr = parse_quote!(
+        std::sync::Arc::new(
+            #lock::new(
+                #stored_head
+            ) // lock new
         ) // arc new
     );
     Box::new(expr)
@@ -109,58 +122,144 @@ fn local_init(expr: Box<Expr>, ident: Ident) -> syn::Local {
     }
 }
 
-fn vec_len_init(field: &Field) -> syn::Local {
-    let ty = match &field.wrapper {
-        Wrapper::Vec { ty } => ty,
-        _ => unreachable!("checked by caller"),
-    };
+fn vec_len_init(field: &Field, asynchronous: bool) -> syn::Local {
+    local_init(len_expr(field.key, asynchronous), len_ident(&field.ident))
+}
 
-    local_init(len_expr(ty, field.key), vec_len_ident(&field.ident))
+fn map_len_init(field: &Field, key_ty: &syn::Type) -> syn::Local {
+    local_init(map_len_expr(field.key, key_ty), len_ident(&field.ident))
 }
 
-fn deque_head_init(field: &Field) -> syn::Local {
-    let ty = match &field.wrapper {
-        Wrapper::VecDeque { ty } => ty,
-        _ => unreachable!("checked by caller"),
+/// Fields that are not counted drop a stored count, writes through them
+/// would not keep it up to date
+fn forget_len(field: &Field, asynchronous: bool) -> syn::Local {
+    let prefix = field.key;
+    let stmt: syn::Stmt = if asynchronous {
+        parse_quote!(
+            let () = ::dbstruct::wrapper::asynchronous::map::forget_len(&ds, #prefix).await?;
+        )
+    } else {
+        parse_quote!(
+            let () = ::dbstruct::wrapper::map::forget_len(&ds, #prefix)?;
+        )
     };
+    match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    }
+}
 
-    local_init(head_expr(ty, field.key), deque_head_ident(&field.ident))
+fn deque_head_init(field: &Field, asynchronous: bool) -> syn::Local {
+    local_init(
+        head_expr(field.key, asynchronous),
+        deque_head_ident(&field.ident),
+    )
 }
 
-fn deque_tail_init(field: &Field) -> syn::Local {
-    let ty = match &field.wrapper {
-        Wrapper::VecDeque { ty } => ty,
-        _ => unreachable!("checked by caller"),
-    };
+fn deque_tail_init(field: &Field, asynchronous: bool) -> syn::Local {
+    local_init(
+        tail_expr(field.key, asynchronous),
+        deque_tail_ident(&field.ident),
+    )
+}
+
+/// Strips the module path from every type path, `std::string::String` and
+/// `String` name the same type but are written differently.
+struct StripPaths;
 
-    local_init(tail_expr(ty, field.key), deque_tail_ident(&field.ident))
+impl VisitMut for StripPaths {
+    fn visit_type_path_mut(&mut self, ty: &mut syn::TypePath) {
+        if ty.qself.is_none() {
+            let last = ty.path.segments.pop().expect("paths are never empty");
+            ty.path.segments = Punctuated::from_iter([last.into_value()]);
+            ty.path.leading_colon = None;
+        }
+        syn::visit_mut::visit_type_path_mut(self, ty);
+    }
 }
 
-fn no_sync_phantom_local_init() -> syn::Local {
-    let ident = syn::PathSegment {
-        ident: no_syn_phantom_ident(),
-        arguments: PathArguments::None,
-    };
-    let ident = syn::Path {
-        leading_colon: None,
-        segments: Punctuated::from_iter(std::iter::once(ident)),
+fn normalized(ty: &syn::Type) -> syn::Type {
+    let mut ty = ty.clone();
+    StripPaths.visit_type_mut(&mut ty);
+    ty
+}
+
+fn schema_field(field: &Field) -> syn::Expr {
+    let (wrapper, tys) = match &field.wrapper {
+        Wrapper::Vec { ty } => (quote!(Vec), vec![ty]),
+        Wrapper::VecDeque { ty } => (quote!(VecDeque), vec![ty]),
+        Wrapper::Map { key_ty, val_ty, .. } => (quote!(Map), vec![key_ty, val_ty]),
+        Wrapper::DefaultTrait { ty } => (quote!(DefaultTrait), vec![ty]),
+        Wrapper::DefaultValue { ty, .. } => (quote!(DefaultValue), vec![ty]),
+        Wrapper::Option { ty } => (quote!(OptionValue), vec![ty]),
+        Wrapper::Set { ty, .. } => (quote!(Set), vec![ty]),
+        Wrapper::OrderedMap { key_ty, val_ty, .. } => (quote!(OrderedMap), vec![key_ty, val_ty]),
     };
-    let ident = syn::PatPath {
-        attrs: Vec::new(),
-        qself: None,
-        path: ident,
+    let tys = tys.into_iter().map(normalized);
+    let name = field.ident.to_string();
+    let key = field.key;
+    let ty = quote!(#(#tys),*).to_string();
+    parse_quote!(::dbstruct::schema::Field {
+        name: #name.to_owned(),
+        key: #key,
+        wrapper: ::dbstruct::schema::Wrapper::#wrapper,
+        ty: #ty.to_owned(),
+    })
+}
+
+/// Runs any pending migrations then stores the layout of the struct or checks
+/// if it matches the stored one. Must run before any data is read.
+fn schema_check(model: &Model) -> syn::Local {
+    let fields = model.fields.iter().map(schema_field);
+    let schema: syn::Expr = parse_quote!(::dbstruct::schema::Schema {
+        fields: vec![#(#fields),*],
+    });
+    let stmt: syn::Stmt = match &model.migrations {
+        None if model.asynchronous => parse_quote!(
+            let () = #schema.check_async(&ds, 0).await?;
+        ),
+        None => parse_quote!(
+            let () = #schema.check(&ds, 0)?;
+        ),
+        Some(Migrations { steps }) => parse_quote!(
+            let () = ::dbstruct::migrate::run(&ds, &#schema, &[#(#steps),*])?;
+        ),
     };
-    let eq_token = Token![=](Span::call_site());
-    syn::Local {
-        attrs: Vec::new(),
-        let_token: Token![let](Span::call_site()),
-        pat: Pat::Path(ident),
-        init: Some(LocalInit {
-            eq_token,
-            expr: parse_quote!(::std::marker::PhantomData),
-            diverge: None,
-        }),
-        semi_token: Token![;](Span::call_site()),
+    match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    }
+}
+
+/// Re-encodes the keys of maps and sets in databases written by version 0.6
+/// or earlier. Must run before the schema check as that writes the key format
+/// for new databases. Stores without order can not list the keys to upgrade.
+fn upgrade_keys(model: &Model) -> Option<syn::Local> {
+    if !model.backend.is_ordered() {
+        return None;
+    }
+    let fields = model
+        .fields
+        .iter()
+        .filter_map(|field| match &field.wrapper {
+            Wrapper::Map { key_ty, .. }
+            | Wrapper::OrderedMap { key_ty, .. }
+            | Wrapper::Set { ty: key_ty, .. } => Some((field.key, key_ty)),
+            _ => None,
+        })
+        .map(|(prefix, key_ty)| -> syn::Expr {
+            parse_quote!(::dbstruct::schema::KeyedField {
+                prefix: #prefix,
+                upgrade: ::dbstruct::schema::upgrade_key::<#key_ty>,
+            })
+        })
+        .collect_vec();
+    let stmt: syn::Stmt = parse_quote!(
+        let () = ::dbstruct::traits::data_store::Ordered::upgrade_keys(&ds, &[#(#fields),*])?;
+    );
+    match stmt {
+        syn::Stmt::Local(local) => Some(local),
+        _ => unreachable!(),
     }
 }
 
@@ -225,6 +324,266 @@ fn sled_from_tree() -> DbSetup {
     }
 }
 
+fn rocksdb_from_path() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::RocksDb::open_path(path).map_err(::dbstruct::Error::Database)?;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(path: impl AsRef<std::path::Path>)),
+        error_ty: parse_quote!(::dbstruct::stores::RocksDbError),
+        name: "open_path",
+    }
+}
+
+fn rocksdb_from_db() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::RocksDb::open_cf(db, "DbStruct")
+            .map_err(::dbstruct::Error::Database)?;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(
+            db: ::std::sync::Arc<::dbstruct::rocksdb::TransactionDB<::dbstruct::rocksdb::MultiThreaded>>
+        )),
+        error_ty: parse_quote!(::dbstruct::stores::RocksDbError),
+        name: "open_db",
+    }
+}
+
+fn rocksdb_from_cf() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = cf;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(cf: ::dbstruct::stores::RocksDb)),
+        error_ty: parse_quote!(::dbstruct::stores::RocksDbError),
+        name: "open_cf",
+    }
+}
+
+fn redb_from_path() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::Redb::open_path(path).map_err(::dbstruct::Error::Database)?;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(path: impl AsRef<std::path::Path>)),
+        error_ty: parse_quote!(::dbstruct::stores::RedbError),
+        name: "open_path",
+    }
+}
+
+fn redb_from_table() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = table;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(table: ::dbstruct::stores::Redb)),
+        error_ty: parse_quote!(::dbstruct::stores::RedbError),
+        name: "open_table",
+    }
+}
+
+fn sqlite_from_path() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::Sqlite::open_path(path).map_err(::dbstruct::Error::Database)?;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(path: impl AsRef<std::path::Path>)),
+        error_ty: parse_quote!(::dbstruct::stores::SqliteError),
+        name: "open_path",
+    }
+}
+
+fn sqlite_from_table() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = table;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(table: ::dbstruct::stores::Sqlite)),
+        error_ty: parse_quote!(::dbstruct::stores::SqliteError),
+        name: "open_table",
+    }
+}
+
+fn lmdb_from_path() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::Lmdb::open_path(path).map_err(::dbstruct::Error::Database)?;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(path: impl AsRef<std::path::Path>)),
+        error_ty: parse_quote!(::dbstruct::stores::LmdbError),
+        name: "open_path",
+    }
+}
+
+fn lmdb_from_env() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::Lmdb::open_database(env, "DbStruct")
+            .map_err(::dbstruct::Error::Database)?;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(env: ::dbstruct::heed::Env)),
+        error_ty: parse_quote!(::dbstruct::stores::LmdbError),
+        name: "open_env",
+    }
+}
+
+fn lmdb_from_database() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = database;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(database: ::dbstruct::stores::Lmdb)),
+        error_ty: parse_quote!(::dbstruct::stores::LmdbError),
+        name: "open_database",
+    }
+}
+
+fn fjall_from_path() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::Fjall::open_path(path).map_err(::dbstruct::Error::Database)?;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(path: impl AsRef<std::path::Path>)),
+        error_ty: parse_quote!(::dbstruct::stores::FjallError),
+        name: "open_path",
+    }
+}
+
+fn fjall_from_keyspace() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::Fjall::open_partition(keyspace, "DbStruct")
+            .map_err(::dbstruct::Error::Database)?;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(keyspace: ::dbstruct::fjall::TxKeyspace)),
+        error_ty: parse_quote!(::dbstruct::stores::FjallError),
+        name: "open_keyspace",
+    }
+}
+
+fn fjall_from_partition() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = partition;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(partition: ::dbstruct::stores::Fjall)),
+        error_ty: parse_quote!(::dbstruct::stores::FjallError),
+        name: "open_partition",
+    }
+}
+
+fn file_from_path() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::File::open_path(path).map_err(::dbstruct::Error::Database)?;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(path: impl AsRef<std::path::Path>)),
+        error_ty: parse_quote!(::dbstruct::stores::FileError),
+        name: "open_path",
+    }
+}
+
+fn file_from_store() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = file;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(file: ::dbstruct::stores::File)),
+        error_ty: parse_quote!(::dbstruct::stores::FileError),
+        name: "open_file",
+    }
+}
+
 fn hashmap() -> DbSetup {
     let stmt: syn::Stmt = parse_quote!(
         let ds = ::dbstruct::stores::HashMap::new();
@@ -259,24 +618,139 @@ fn btreemap() -> DbSetup {
     }
 }
 
+fn btreemap_from_file() -> DbSetup {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::BTreeMap::load_from(path)
+            .map_err(::dbstruct::Error::Database)?;
+    );
+    let local = match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    };
+
+    DbSetup {
+        local: Some(local),
+        arg: Some(parse_quote!(path: impl AsRef<std::path::Path>)),
+        error_ty: parse_quote!(::dbstruct::stores::BTreeMapError),
+        name: "open_file",
+    }
+}
+
 fn for_trait() -> DbSetup {
     DbSetup {
         local: None,
         arg: Some(parse_quote!(ds: DS)),
-        error_ty: parse_quote!(DS::DbError),
+        error_ty: parse_quote!(<DS as ::dbstruct::DataStore>::DbError),
         name: "open",
     }
 }
 
+fn for_async_trait() -> DbSetup {
+    DbSetup {
+        local: None,
+        arg: Some(parse_quote!(ds: DS)),
+        error_ty: parse_quote!(<DS as ::dbstruct::traits::AsyncDataStore>::DbError),
+        name: "open",
+    }
+}
+
+/// Compressed structs wrap the database in `Compressed`
+fn compress_local() -> syn::Local {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::Compressed::new(ds);
+    );
+    match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    }
+}
+
+/// Encrypted structs wrap the database in `Encrypted`
+fn encrypt_local() -> syn::Local {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::Encrypted::new(ds, key);
+    );
+    match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    }
+}
+
+/// Cached structs wrap the database in `Cached`
+fn cache_local(capacity: usize) -> syn::Local {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::Cached::new(ds, #capacity);
+    );
+    match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    }
+}
+
+/// Read only structs wrap the database in `ReadOnly`
+fn read_only_local() -> syn::Local {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::ReadOnly::new(ds);
+    );
+    match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    }
+}
+
+/// Opening the database returns its own error, once wrapped in adapters the
+/// struct returns theirs. `store_error` wraps the error `e` of the database.
+fn open_then_wrap_error(
+    open: syn::Local,
+    error_ty: &syn::Type,
+    store_error: &syn::Expr,
+) -> syn::Local {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = (|| {
+            #open
+            Ok::<_, ::dbstruct::Error<#error_ty>>(ds)
+        })()
+        .map_err(|e| e.map_database(|e| #store_error))?;
+    );
+    match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    }
+}
+
+/// Async structs access blocking databases through `Blocking`
+fn blocking_local() -> syn::Local {
+    let stmt: syn::Stmt = parse_quote!(
+        let ds = ::dbstruct::stores::Blocking::new(ds);
+    );
+    match stmt {
+        syn::Stmt::Local(local) => local,
+        _ => unreachable!(),
+    }
+}
+
 impl NewMethod {
     pub fn list(model: &Model, struct_def: &Struct) -> Vec<Self> {
         match model.backend {
             Backend::Sled => vec![sled_from_db(), sled_from_path(), sled_from_tree()],
-            Backend::HashMap => {
+            Backend::RocksDb => vec![rocksdb_from_db(), rocksdb_from_path(), rocksdb_from_cf()],
+            Backend::Redb => vec![redb_from_path(), redb_from_table()],
+            Backend::Sqlite => vec![sqlite_from_path(), sqlite_from_table()],
+            Backend::Lmdb => vec![lmdb_from_env(), lmdb_from_path(), lmdb_from_database()],
+            Backend::Fjall => vec![
+                fjall_from_keyspace(),
+                fjall_from_path(),
+                fjall_from_partition(),
+            ],
+            Backend::File => vec![file_from_path(), file_from_store()],
+            Backend::HashMap { .. } => {
                 vec![hashmap()]
             }
             Backend::BTreeMap => {
-                vec![btreemap()]
+                vec![btreemap(), btreemap_from_file()]
+            }
+            Backend::Trait { .. } if model.asynchronous => {
+                vec![for_async_trait()]
             }
             Backend::Trait { .. } => {
                 vec![for_trait()]
@@ -285,6 +759,8 @@ impl NewMethod {
             Backend::Test => unreachable!("test not used in new method"),
         }
         .into_iter()
+        // a new in memory store is empty, reading it is pointless
+        .filter(|db| !model.read_only || db.arg.is_some())
         .map(|db| db_setup_to_new_method(db, model, struct_def))
         .collect_vec()
     }
@@ -294,20 +770,85 @@ fn db_setup_to_new_method(db: DbSetup, model: &Model, struct_def: &Struct) -> Ne
     let DbSetup {
         local,
         arg,
-        error_ty,
+        mut error_ty,
         name,
     } = db;
 
-    let mut locals = local.into_iter().collect_vec();
+    let asynchronous = model.asynchronous;
+    let mut args = arg.into_iter().collect_vec();
+    let db_error_ty = error_ty.clone();
+    // set once an adapter returns its own error
+    let mut store_error: Option<syn::Expr> = None;
+    let mut adapters = Vec::new();
+    if model.compression.is_some() {
+        adapters.push(compress_local());
+        error_ty = parse_quote!(::dbstruct::stores::CompressedError<#error_ty>);
+        let e = store_error.unwrap_or(parse_quote!(e));
+        store_error = Some(parse_quote!(::dbstruct::stores::CompressedError::Store(#e)));
+    }
+    if model.encrypted {
+        adapters.push(encrypt_local());
+        args.push(parse_quote!(key: &::dbstruct::stores::EncryptionKey));
+        error_ty = parse_quote!(::dbstruct::stores::EncryptedError<#error_ty>);
+        let e = store_error.unwrap_or(parse_quote!(e));
+        store_error = Some(parse_quote!(::dbstruct::stores::EncryptedError::Store(#e)));
+    }
+    if let Some(capacity) = model.cache {
+        adapters.push(cache_local(capacity));
+    }
+    if model.read_only {
+        adapters.push(read_only_local());
+        error_ty = parse_quote!(::dbstruct::stores::ReadOnlyError<#error_ty>);
+        let e = store_error.unwrap_or(parse_quote!(e));
+        store_error = Some(parse_quote!(::dbstruct::stores::ReadOnlyError::Store(#e)));
+    }
+    let mut locals = match (local, store_error) {
+        (Some(open), Some(store_error)) => {
+            vec![open_then_wrap_error(open, &db_error_ty, &store_error)]
+        }
+        (local, _) => local.into_iter().collect_vec(),
+    };
+    locals.extend(adapters);
+    let async_trait = asynchronous && matches!(model.backend, Backend::Trait { .. });
+    if !async_trait {
+        locals.extend(upgrade_keys(model));
+    }
+    if asynchronous && !async_trait {
+        locals.push(blocking_local());
+    }
+    locals.push(schema_check(model));
     let inits = model.fields.iter().flat_map(|field| match &field.wrapper {
-        Wrapper::Vec { .. } => [vec_len_init(field)].to_vec(),
-        Wrapper::VecDeque { .. } => [deque_head_init(field), deque_tail_init(field)].to_vec(),
+        Wrapper::Vec { .. } => [vec_len_init(field, asynchronous)].to_vec(),
+        Wrapper::Map {
+            key_ty,
+            counted: true,
+            ..
+        }
+        | Wrapper::OrderedMap {
+            key_ty,
+            counted: true,
+            ..
+        }
+        | Wrapper::Set {
+            ty: key_ty,
+            counted: true,
+        } => [map_len_init(field, key_ty)].to_vec(),
+        Wrapper::Map { .. } | Wrapper::OrderedMap { .. } | Wrapper::Set { .. }
+            if !model.read_only =>
+        {
+            [forget_len(field, asynchronous)].to_vec()
+        }
+        Wrapper::VecDeque { .. } => [
+            deque_head_init(field, asynchronous),
+            deque_tail_init(field, asynchronous),
+        ]
+        .to_vec(),
         _ => Vec::new(),
     });
     locals.extend(inits);
-    locals.push(no_sync_phantom_local_init());
 
     NewMethod {
+        asyncness: asynchronous.then(|| Token![async](Span::call_site())),
         locals,
         members: struct_def
             .member_vars
@@ -315,7 +856,7 @@ fn db_setup_to_new_method(db: DbSetup, model: &Model, struct_def: &Struct) -> Ne
             .map(as_member)
             .collect(),
         vis: model.vis.clone(),
-        arg,
+        args,
         error_ty,
         name: syn::Ident::new(name, struct_def.ident.span()),
     }
@@ -323,6 +864,8 @@ fn db_setup_to_new_method(db: DbSetup, model: &Model, struct_def: &Struct) -> Ne
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use quote::ToTokens;
 
     use super::*;
@@ -332,7 +875,7 @@ mod tests {
         let model = Model::mock_vec();
         let struct_def = Struct::from(&model);
         let new_methods = NewMethod::list(&model, &struct_def);
-        assert_eq!(new_methods[0].locals.len(), 3);
+        assert_eq!(new_methods[0].locals.len(), 4);
     }
 
     #[test]
@@ -340,7 +883,7 @@ mod tests {
         let model = Model::mock_vecdeque();
         let struct_def = Struct::from(&model);
         let new_methods = NewMethod::list(&model, &struct_def);
-        assert_eq!(new_methods[0].locals.len(), 4);
+        assert_eq!(new_methods[0].locals.len(), 5);
     }
 
     #[test]
@@ -363,6 +906,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn encrypted_takes_key() {
+        let mut model = Model::mock_vec();
+        model.encrypted = true;
+        let struct_def = Struct::from(&model);
+        for new_method in NewMethod::list(&model, &struct_def) {
+            assert_eq!(new_method.args.len(), 2);
+            assert_eq!(new_method.locals.len(), 5);
+        }
+    }
+
+    #[test]
+    fn compressed_and_encrypted() {
+        let mut model = Model::mock_vec();
+        model.encrypted = true;
+        model.compression = Some(crate::model::attribute::Compression::Zstd);
+        let struct_def = Struct::from(&model);
+        let new_method = NewMethod::list(&model, &struct_def).remove(0);
+        let error_ty = new_method.error_ty.to_token_stream().to_string();
+        assert!(error_ty.contains("EncryptedError < :: dbstruct :: stores :: CompressedError"));
+        assert_eq!(new_method.locals.len(), 6);
+    }
+
+    #[test]
+    fn read_only_btreemap_is_loaded() {
+        let mut model = Model::mock_vec();
+        model.backend = Backend::BTreeMap;
+        model.read_only = true;
+        let struct_def = Struct::from(&model);
+        let new_methods = NewMethod::list(&model, &struct_def);
+        assert_eq!(new_methods.len(), 1);
+        assert_eq!(new_methods[0].name, "open_file");
+        let error_ty = new_methods[0].error_ty.to_token_stream().to_string();
+        assert!(error_ty.starts_with(":: dbstruct :: stores :: ReadOnlyError"));
+    }
+
+    #[test]
+    fn map_keys_are_upgraded() {
+        let input_attr = proc_macro2::TokenStream::from_str("db=sled").unwrap();
+        let input_struct: syn::ItemStruct = syn::parse_str(
+            "
+pub struct Test {
+    names: HashMap<String, u8>,
+}",
+        )
+        .unwrap();
+        let model = Model::try_from(input_struct, input_attr).unwrap();
+        let upgrade = upgrade_keys(&model).unwrap().to_token_stream().to_string();
+        assert!(upgrade.contains("upgrade_key :: < String >"));
+    }
+
+    #[test]
+    fn unordered_keys_are_not_upgraded() {
+        let mut model = Model::mock_u8field();
+        model.backend = Backend::HashMap {
+            span: proc_macro2::Span::call_site(),
+        };
+        assert!(upgrade_keys(&model).is_none());
+    }
+
     #[test]
     fn three_factories_for_sled() {
         let model = Model::mock_vecdeque(); // uses sled
@@ -0,0 +1,46 @@
+use proc_macro2::Span;
+use syn::{parse_quote, Token};
+
+use crate::model::backend::Backend;
+use crate::model::Model;
+
+/// Writes an in memory store to disk, only generated for `db=btreemap`
+pub struct SaveMethod {
+    pub asyncness: Option<Token![async]>,
+    pub vis: syn::Visibility,
+    /// Evaluates to the store
+    pub store: syn::Expr,
+    pub error_ty: syn::Type,
+}
+
+impl SaveMethod {
+    /// Returns None if the backend can not be saved
+    pub fn from(model: &Model) -> Option<Self> {
+        if !matches!(model.backend, Backend::BTreeMap) {
+            return None;
+        }
+
+        let mut store: syn::Expr = parse_quote!(self.ds);
+        if model.asynchronous {
+            store = parse_quote!(#store.inner());
+        }
+        if model.read_only {
+            store = parse_quote!(#store.inner());
+        }
+        if model.cache.is_some() {
+            store = parse_quote!(#store.inner());
+        }
+        if model.encrypted {
+            store = parse_quote!(#store.inner());
+        }
+        if model.compression.is_some() {
+            store = parse_quote!(#store.inner());
+        }
+        Some(Self {
+            asyncness: model.asynchronous.then(|| Token![async](Span::call_site())),
+            vis: model.vis.clone(),
+            store,
+            error_ty: parse_quote!(::dbstruct::stores::BTreeMapError),
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use syn::parse_quote;
+
+use crate::model::{Model, Wrapper};
+
+use super::struct_def::{deque_head_ident, deque_tail_ident, len_ident, Struct};
+use super::{Accessor, Store};
+
+/// A copy of the struct whose wrappers operate inside a transaction
+pub struct Transaction {
+    pub ident: syn::Ident,
+    pub vis: syn::Visibility,
+    /// The data store used during the transaction
+    pub ds: syn::Type,
+    pub member_vars: Vec<syn::Field>,
+    /// The `Vec` lengths, `VecDeque` heads and tails and map counts. The
+    /// transaction works on a copy which is written back once the
+    /// transaction commits.
+    pub shared: Vec<syn::Ident>,
+    pub accessors: Vec<Accessor>,
+    pub error_ty: syn::Type,
+    pub tx_error_ty: syn::Type,
+}
+
+impl Transaction {
+    /// Returns None if the backend does not support transactions or the
+    /// struct is read only
+    pub fn from(model: &Model, struct_def: &Struct, backend_ty: &syn::Type) -> Option<Self> {
+        if model.asynchronous || model.read_only || !model.backend.supports_transactions() {
+            return None;
+        }
+
+        let transactional: syn::Path = parse_quote!(::dbstruct::traits::data_store::Transactional);
+        let ds: syn::Type = parse_quote!(<#backend_ty as #transactional>::Tx<'tx>);
+        let shared = model
+            .fields
+            .iter()
+            .flat_map(|field| match &field.wrapper {
+                Wrapper::Vec { .. }
+                | Wrapper::Map { counted: true, .. }
+                | Wrapper::OrderedMap { counted: true, .. }
+                | Wrapper::Set { counted: true, .. } => vec![len_ident(&field.ident)],
+                Wrapper::VecDeque { .. } => vec![
+                    deque_head_ident(&field.ident),
+                    deque_tail_ident(&field.ident),
+                ],
+                _ => Vec::new(),
+            })
+            .collect();
+        let store = Store::Direct(ds.clone());
+        let accessors = model
+            .fields
+            .iter()
+            .cloned()
+            .map(|field| Accessor::from(field, &store, &parse_quote!(dbstruct::wrapper)))
+            .collect();
+
+        Some(Self {
+            ident: syn::Ident::new(&format!("{}Transaction", model.ident), model.ident.span()),
+            vis: model.vis.clone(),
+            ds,
+            member_vars: struct_def.member_vars.clone(),
+            shared,
+            accessors,
+            error_ty: parse_quote!(<#backend_ty as ::dbstruct::DataStore>::DbError),
+            tx_error_ty: parse_quote!(<#backend_ty as #transactional>::TxError),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_vec_len() {
+        let model = Model::mock_vec();
+        let struct_def = Struct::from(&model);
+        let transaction =
+            Transaction::from(&model, &struct_def, &parse_quote!(::dbstruct::sled::Tree)).unwrap();
+        assert_eq!(transaction.shared.len(), 1);
+        assert_eq!(transaction.accessors.len(), 1);
+    }
+}
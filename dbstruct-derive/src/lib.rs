@@ -33,7 +33,7 @@ pub fn dbstruct(
 fn emit_and_abort(err: model::Error) -> ! {
     match err {
         model::Error::DbKey(e) => {
-            abort!(e.span(), "{}", e)
+            abort!(e.span(), e.to_string(); help =? e.help(););
         }
         model::Error::Field(mut errs) => {
             let last = errs.pop().expect("minimum err vec len is one");
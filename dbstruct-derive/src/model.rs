@@ -1,4 +1,4 @@
-mod attribute;
+pub mod attribute;
 pub mod backend;
 mod field;
 pub mod key;
@@ -31,6 +31,17 @@ pub struct Model {
     pub vis: Visibility,
     pub fields: Vec<Field>,
     pub backend: Backend,
+    pub migrations: Option<attribute::Migrations>,
+    /// Generate async accessors and constructors
+    pub asynchronous: bool,
+    /// Wrap the database in `Encrypted`
+    pub encrypted: bool,
+    /// Wrap the database in `Compressed`
+    pub compression: Option<attribute::Compression>,
+    /// Wrap the database in `Cached` holding this many values
+    pub cache: Option<usize>,
+    /// Wrap the database in `ReadOnly` and return wrappers that can not write
+    pub read_only: bool,
 }
 
 impl Model {
@@ -48,13 +59,27 @@ impl Model {
         }
 
         let options = attribute::parse(attr)?;
-        let backend = Backend::try_from(&options, &fields)?;
+        let migrations = attribute::migrations(&options)?;
+        let asynchronous = attribute::asynchronous(&options)?;
+        let encrypted = attribute::encrypted(&options)?;
+        let compression = attribute::compression(&options)?;
+        attribute::compressed_fields(&options, &fields)?;
+        let cache = attribute::cache(&options)?;
+        let read_only = attribute::read_only(&options)?;
+        attribute::counted(&options, &fields)?;
+        let backend = Backend::try_from(&options, &fields, migrations.is_some())?;
 
         Ok(Self {
             vis: input.vis,
             ident: input.ident,
             fields,
             backend,
+            migrations,
+            asynchronous,
+            encrypted,
+            compression,
+            cache,
+            read_only,
         })
     }
 }
@@ -146,6 +171,96 @@ pub struct Test {
             assert!(matches!(model.backend, Backend::Sled));
         }
 
+        #[test]
+        fn rocksdb() {
+            let input_attr = proc_macro2::TokenStream::from_str("db=rocksdb").unwrap();
+            let input_struct: syn::ItemStruct = parse_str(
+                "
+pub struct Test {
+    queue: VecDeque<u8>,
+}",
+            )
+            .unwrap();
+
+            let model = Model::try_from(input_struct, input_attr).unwrap();
+            assert!(matches!(model.backend, Backend::RocksDb));
+        }
+
+        #[test]
+        fn redb() {
+            let input_attr = proc_macro2::TokenStream::from_str("db=redb").unwrap();
+            let input_struct: syn::ItemStruct = parse_str(
+                "
+pub struct Test {
+    queue: VecDeque<u8>,
+}",
+            )
+            .unwrap();
+
+            let model = Model::try_from(input_struct, input_attr).unwrap();
+            assert!(matches!(model.backend, Backend::Redb));
+        }
+
+        #[test]
+        fn sqlite() {
+            let input_attr = proc_macro2::TokenStream::from_str("db=sqlite").unwrap();
+            let input_struct: syn::ItemStruct = parse_str(
+                "
+pub struct Test {
+    queue: VecDeque<u8>,
+}",
+            )
+            .unwrap();
+
+            let model = Model::try_from(input_struct, input_attr).unwrap();
+            assert!(matches!(model.backend, Backend::Sqlite));
+        }
+
+        #[test]
+        fn lmdb() {
+            let input_attr = proc_macro2::TokenStream::from_str("db=lmdb").unwrap();
+            let input_struct: syn::ItemStruct = parse_str(
+                "
+pub struct Test {
+    queue: VecDeque<u8>,
+}",
+            )
+            .unwrap();
+
+            let model = Model::try_from(input_struct, input_attr).unwrap();
+            assert!(matches!(model.backend, Backend::Lmdb));
+        }
+
+        #[test]
+        fn fjall() {
+            let input_attr = proc_macro2::TokenStream::from_str("db=fjall").unwrap();
+            let input_struct: syn::ItemStruct = parse_str(
+                "
+pub struct Test {
+    queue: VecDeque<u8>,
+}",
+            )
+            .unwrap();
+
+            let model = Model::try_from(input_struct, input_attr).unwrap();
+            assert!(matches!(model.backend, Backend::Fjall));
+        }
+
+        #[test]
+        fn file() {
+            let input_attr = proc_macro2::TokenStream::from_str("db=file").unwrap();
+            let input_struct: syn::ItemStruct = parse_str(
+                "
+pub struct Test {
+    queue: VecDeque<u8>,
+}",
+            )
+            .unwrap();
+
+            let model = Model::try_from(input_struct, input_attr).unwrap();
+            assert!(matches!(model.backend, Backend::File));
+        }
+
         #[test]
         fn none() {
             let input_attr = proc_macro2::TokenStream::from_str("db=trait").unwrap();
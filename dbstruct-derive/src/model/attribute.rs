@@ -6,9 +6,17 @@ use std::iter::Peekable;
 
 use proc_macro2::TokenTree;
 
+use super::{Field, Wrapper};
+
 #[derive(Debug, Clone, Copy)]
 pub enum BackendOptionVariant {
     Sled,
+    RocksDb,
+    Redb,
+    Sqlite,
+    Lmdb,
+    Fjall,
+    File,
     HashMap,
     BTreeMap,
     Trait,
@@ -25,7 +33,25 @@ pub struct BackendOption {
 #[derive(Debug)]
 pub enum Options {
     Backend(BackendOption),
-    Async,
+    Async { span: Span },
+    Encrypt { span: Span },
+    Compress { algorithm: Compression, span: Span },
+    Cache { capacity: usize, span: Span },
+    ReadOnly { span: Span },
+    Version { version: u32, span: Span },
+    Migrations { steps: Vec<syn::Path>, span: Span },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Zstd,
+}
+
+/// The version of the struct and the functions that migrate the database to
+/// it. There is one function per version.
+#[derive(Debug)]
+pub struct Migrations {
+    pub steps: Vec<syn::Path>,
 }
 
 /// attrs is the TokenStream returned by Attribute::parse_args();
@@ -67,6 +93,12 @@ fn parse_db(
                 Some(TokenTree::Ident(ident)) => {
                     let backend = match ident.to_string().as_str() {
                         "sled" => Sled,
+                        "rocksdb" => RocksDb,
+                        "redb" => Redb,
+                        "sqlite" => Sqlite,
+                        "lmdb" => Lmdb,
+                        "fjall" => Fjall,
+                        "file" => File,
                         "hashmap" => HashMap,
                         "btreemap" => BTreeMap,
                         "trait" => Trait,
@@ -86,6 +118,325 @@ fn parse_db(
     }
 }
 
+fn parse_version(
+    span: proc_macro2::Span,
+    tokens: &mut Peekable<impl Iterator<Item = TokenTree>>,
+) -> Result<Options, Error> {
+    use ErrorVariant::*;
+    match tokens.next() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => match tokens.next() {
+            None => Err(MissingVersionValue.with_span(punct)),
+            Some(TokenTree::Literal(lit)) => match syn::Lit::new(lit.clone()) {
+                syn::Lit::Int(int) => Ok(Options::Version {
+                    version: int
+                        .base10_parse()
+                        .map_err(|_| InvalidVersion.with_span(&lit))?,
+                    span: lit.span(),
+                }),
+                _ => Err(InvalidVersion.with_span(lit)),
+            },
+            Some(other) => Err(InvalidVersion.with_span(other)),
+        },
+        _ => Err(MissingVersionValue.with_span(span)),
+    }
+}
+
+fn parse_migrations(
+    span: proc_macro2::Span,
+    tokens: &mut Peekable<impl Iterator<Item = TokenTree>>,
+) -> Result<Options, Error> {
+    use syn::parse::Parser;
+    use ErrorVariant::*;
+
+    let group = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Punct(punct)), Some(TokenTree::Group(group)))
+            if punct.as_char() == '='
+                && group.delimiter() == proc_macro2::Delimiter::Bracket =>
+        {
+            group
+        }
+        (Some(TokenTree::Punct(punct)), Some(other)) if punct.as_char() == '=' => {
+            return Err(InvalidMigrations.with_span(other))
+        }
+        _ => return Err(InvalidMigrations.with_span(span)),
+    };
+
+    let parser = syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated;
+    let steps = parser
+        .parse2(group.stream())
+        .map_err(|_| InvalidMigrations.with_span(group.span()))?;
+    Ok(Options::Migrations {
+        steps: steps.into_iter().collect(),
+        span: group.span(),
+    })
+}
+
+fn parse_cache(
+    span: proc_macro2::Span,
+    tokens: &mut Peekable<impl Iterator<Item = TokenTree>>,
+) -> Result<Options, Error> {
+    use ErrorVariant::*;
+    match tokens.next() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => match tokens.next() {
+            None => Err(MissingCacheSize.with_span(punct)),
+            Some(TokenTree::Literal(lit)) => match syn::Lit::new(lit.clone()) {
+                syn::Lit::Int(int) => match int.base10_parse() {
+                    Ok(0) | Err(_) => Err(InvalidCacheSize.with_span(&lit)),
+                    Ok(capacity) => Ok(Options::Cache {
+                        capacity,
+                        span: lit.span(),
+                    }),
+                },
+                _ => Err(InvalidCacheSize.with_span(lit)),
+            },
+            Some(other) => Err(InvalidCacheSize.with_span(other)),
+        },
+        _ => Err(MissingCacheSize.with_span(span)),
+    }
+}
+
+fn parse_compress(
+    span: proc_macro2::Span,
+    tokens: &mut Peekable<impl Iterator<Item = TokenTree>>,
+) -> Result<Options, Error> {
+    use ErrorVariant::*;
+    match tokens.next() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => match tokens.next() {
+            None => Err(MissingCompressionValue.with_span(punct)),
+            Some(TokenTree::Literal(lit)) => match syn::Lit::new(lit.clone()) {
+                syn::Lit::Str(str) if str.value() == "zstd" => Ok(Options::Compress {
+                    algorithm: Compression::Zstd,
+                    span: lit.span(),
+                }),
+                _ => Err(UnknownCompression.with_span(lit)),
+            },
+            Some(other) => Err(UnknownCompression.with_span(other)),
+        },
+        _ => Err(MissingCompressionValue.with_span(span)),
+    }
+}
+
+/// Checks the version and migrations options match up
+pub fn migrations(options: &[Options]) -> Result<Option<Migrations>, Error> {
+    use ErrorVariant::*;
+
+    let mut version = None;
+    let mut steps = None;
+    for option in options.iter() {
+        match option {
+            Options::Version { version: v, span } if version.is_none() => {
+                version = Some((*v, *span))
+            }
+            Options::Migrations { steps: s, span } if steps.is_none() => {
+                steps = Some((s.clone(), *span))
+            }
+            Options::Version { span, .. } | Options::Migrations { span, .. } => {
+                return Err(DuplicateOption.with_span(*span))
+            }
+            _ => (),
+        }
+    }
+
+    match (version, steps) {
+        (None, None) => Ok(None),
+        (Some((_, span)), None) => Err(MissingMigrations.with_span(span)),
+        (None, Some((_, span))) => Err(MissingVersion.with_span(span)),
+        (Some((version, _)), Some((steps, span))) if steps.len() != version as usize => {
+            Err(MigrationCount {
+                version,
+                count: steps.len(),
+            }
+            .with_span(span))
+        }
+        (Some(_), Some((steps, _))) => Ok(Some(Migrations { steps })),
+    }
+}
+
+/// Whether to generate the async flavour of the struct
+pub fn asynchronous(options: &[Options]) -> Result<bool, Error> {
+    use ErrorVariant::*;
+
+    let mut spans = options.iter().filter_map(|option| match option {
+        Options::Async { span } => Some(*span),
+        _ => None,
+    });
+    let Some(span) = spans.next() else {
+        return Ok(false);
+    };
+    if let Some(second) = spans.next() {
+        return Err(DuplicateOption.with_span(second));
+    }
+    if options
+        .iter()
+        .any(|option| matches!(option, Options::Migrations { .. }))
+    {
+        return Err(AsyncMigrations.with_span(span));
+    }
+    Ok(true)
+}
+
+/// Whether to encrypt the data before it is written to the database
+pub fn encrypted(options: &[Options]) -> Result<bool, Error> {
+    use ErrorVariant::*;
+
+    let mut spans = options.iter().filter_map(|option| match option {
+        Options::Encrypt { span } => Some(*span),
+        _ => None,
+    });
+    let Some(span) = spans.next() else {
+        return Ok(false);
+    };
+    if let Some(second) = spans.next() {
+        return Err(DuplicateOption.with_span(second));
+    }
+    if uses_trait(options) {
+        return Err(NotForTrait {
+            option: "encrypt",
+            adapter: "Encrypted",
+        }
+        .with_span(span));
+    }
+    Ok(true)
+}
+
+/// The algorithm to compress values with before they are written
+pub fn compression(options: &[Options]) -> Result<Option<Compression>, Error> {
+    use ErrorVariant::*;
+
+    let mut chosen = options.iter().filter_map(|option| match option {
+        Options::Compress { algorithm, span } => Some((*algorithm, *span)),
+        _ => None,
+    });
+    let Some((algorithm, span)) = chosen.next() else {
+        return Ok(None);
+    };
+    if let Some((_, second)) = chosen.next() {
+        return Err(DuplicateOption.with_span(second));
+    }
+    if uses_trait(options) {
+        return Err(NotForTrait {
+            option: "compress",
+            adapter: "Compressed",
+        }
+        .with_span(span));
+    }
+    Ok(Some(algorithm))
+}
+
+/// How many values to keep in memory
+pub fn cache(options: &[Options]) -> Result<Option<usize>, Error> {
+    use ErrorVariant::*;
+
+    let mut chosen = options.iter().filter_map(|option| match option {
+        Options::Cache { capacity, span } => Some((*capacity, *span)),
+        _ => None,
+    });
+    let Some((capacity, span)) = chosen.next() else {
+        return Ok(None);
+    };
+    if let Some((_, second)) = chosen.next() {
+        return Err(DuplicateOption.with_span(second));
+    }
+    if uses_trait(options) {
+        return Err(NotForTrait {
+            option: "cache",
+            adapter: "Cached",
+        }
+        .with_span(span));
+    }
+    Ok(Some(capacity))
+}
+
+/// Whether to generate a struct that can only read the database
+pub fn read_only(options: &[Options]) -> Result<bool, Error> {
+    use ErrorVariant::*;
+
+    let mut spans = options.iter().filter_map(|option| match option {
+        Options::ReadOnly { span } => Some(*span),
+        _ => None,
+    });
+    let Some(span) = spans.next() else {
+        return Ok(false);
+    };
+    if let Some(second) = spans.next() {
+        return Err(DuplicateOption.with_span(second));
+    }
+    if uses_trait(options) {
+        return Err(NotForTrait {
+            option: "readonly",
+            adapter: "ReadOnly",
+        }
+        .with_span(span));
+    }
+    if options
+        .iter()
+        .any(|option| matches!(option, Options::Async { .. }))
+    {
+        return Err(AsyncReadOnly.with_span(span));
+    }
+    Ok(true)
+}
+
+/// Checks the struct options allow the `#[dbstruct(counted)]` fields
+pub fn counted(options: &[Options], fields: &[Field]) -> Result<(), Error> {
+    use ErrorVariant::*;
+
+    let any_counted = fields.iter().any(|field| {
+        matches!(
+            field.wrapper,
+            Wrapper::Map { counted: true, .. }
+                | Wrapper::OrderedMap { counted: true, .. }
+                | Wrapper::Set { counted: true, .. }
+        )
+    });
+    if !any_counted {
+        return Ok(());
+    }
+    match options.iter().find_map(|option| match option {
+        Options::Async { span } => Some(*span),
+        _ => None,
+    }) {
+        Some(span) => Err(AsyncCounted.with_span(span)),
+        None => Ok(()),
+    }
+}
+
+/// Checks the struct options allow the `#[dbstruct(compress = "zstd")]`
+/// fields
+pub fn compressed_fields(options: &[Options], fields: &[Field]) -> Result<(), Error> {
+    use ErrorVariant::*;
+
+    let Some(span) = fields.iter().find_map(|field| field.compress) else {
+        return Ok(());
+    };
+    if uses_trait(options) {
+        return Err(NotForTrait {
+            option: "compress",
+            adapter: "Compressed",
+        }
+        .with_span(span));
+    }
+    if options
+        .iter()
+        .any(|option| matches!(option, Options::Compress { .. }))
+    {
+        return Err(StructCompressed.with_span(span));
+    }
+    Ok(())
+}
+
+fn uses_trait(options: &[Options]) -> bool {
+    options.iter().any(|option| {
+        matches!(
+            option,
+            Options::Backend(BackendOption {
+                backend: BackendOptionVariant::Trait,
+                ..
+            })
+        )
+    })
+}
+
 fn parse_item(tokens: &mut Peekable<impl Iterator<Item = TokenTree>>) -> Result<Options, Error> {
     use ErrorVariant::*;
     let first_token = tokens
@@ -96,7 +447,19 @@ fn parse_item(tokens: &mut Peekable<impl Iterator<Item = TokenTree>>) -> Result<
             let backend = parse_db(ident.span(), tokens)?;
             Ok(Options::Backend(backend))
         }
-        TokenTree::Ident(ident) if ident == "async" => Ok(Options::Async),
+        TokenTree::Ident(ident) if ident == "async" => Ok(Options::Async { span: ident.span() }),
+        TokenTree::Ident(ident) if ident == "encrypt" => {
+            Ok(Options::Encrypt { span: ident.span() })
+        }
+        TokenTree::Ident(ident) if ident == "compress" => parse_compress(ident.span(), tokens),
+        TokenTree::Ident(ident) if ident == "cache" => parse_cache(ident.span(), tokens),
+        TokenTree::Ident(ident) if ident == "readonly" => {
+            Ok(Options::ReadOnly { span: ident.span() })
+        }
+        TokenTree::Ident(ident) if ident == "version" => parse_version(ident.span(), tokens),
+        TokenTree::Ident(ident) if ident == "migrations" => {
+            parse_migrations(ident.span(), tokens)
+        }
         TokenTree::Ident(ident) => Err(NotAnOption(ident).has_span()),
         _ => Err(InvalidSyntax(first_token).has_span()),
     }
@@ -137,7 +500,7 @@ mod tests {
     fn parse_multiple_option() {
         let attr = proc_macro2::TokenStream::from_str("db=sled,async").unwrap();
         let mut attributes = parse(attr).unwrap();
-        assert!(matches!(attributes.pop().unwrap(), Options::Async));
+        assert!(matches!(attributes.pop().unwrap(), Options::Async { .. }));
         assert!(matches!(
             attributes.pop().unwrap(),
             Options::Backend(BackendOption {
@@ -146,4 +509,151 @@ mod tests {
             })
         ));
     }
+
+    #[test]
+    fn parse_migrations_option() {
+        let attr = proc_macro2::TokenStream::from_str(
+            "db=sled, version = 2, migrations = [to_v1, migrations::to_v2]",
+        )
+        .unwrap();
+        let options = parse(attr).unwrap();
+        let migrations = migrations(&options).unwrap().unwrap();
+        assert_eq!(migrations.steps.len(), 2);
+    }
+
+    #[test]
+    fn migration_count_must_match_version() {
+        let attr =
+            proc_macro2::TokenStream::from_str("db=sled, version = 3, migrations = [to_v1]")
+                .unwrap();
+        let options = parse(attr).unwrap();
+        let err = migrations(&options).unwrap_err();
+        assert!(matches!(
+            err.variant,
+            ErrorVariant::MigrationCount {
+                version: 3,
+                count: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn async_option() {
+        let attr = proc_macro2::TokenStream::from_str("db=sled, async").unwrap();
+        let options = parse(attr).unwrap();
+        assert!(asynchronous(&options).unwrap());
+    }
+
+    #[test]
+    fn async_does_not_support_migrations() {
+        let attr = proc_macro2::TokenStream::from_str(
+            "db=sled, async, version = 1, migrations = [to_v1]",
+        )
+        .unwrap();
+        let options = parse(attr).unwrap();
+        let err = asynchronous(&options).unwrap_err();
+        assert!(matches!(err.variant, ErrorVariant::AsyncMigrations));
+    }
+
+    #[test]
+    fn encrypt_option() {
+        let attr = proc_macro2::TokenStream::from_str("db=sled, encrypt").unwrap();
+        let options = parse(attr).unwrap();
+        assert!(encrypted(&options).unwrap());
+    }
+
+    #[test]
+    fn trait_can_not_be_encrypted() {
+        let attr = proc_macro2::TokenStream::from_str("db=trait, encrypt").unwrap();
+        let options = parse(attr).unwrap();
+        let err = encrypted(&options).unwrap_err();
+        assert!(matches!(err.variant, ErrorVariant::NotForTrait { .. }));
+    }
+
+    #[test]
+    fn compress_option() {
+        let attr = proc_macro2::TokenStream::from_str(r#"db=sled, compress = "zstd""#).unwrap();
+        let options = parse(attr).unwrap();
+        assert_eq!(compression(&options).unwrap(), Some(Compression::Zstd));
+    }
+
+    #[test]
+    fn unknown_compression() {
+        let attr = proc_macro2::TokenStream::from_str(r#"db=sled, compress = "lzma""#).unwrap();
+        let err = parse(attr).unwrap_err();
+        assert!(matches!(err.variant, ErrorVariant::UnknownCompression));
+    }
+
+    #[test]
+    fn cache_option() {
+        let attr = proc_macro2::TokenStream::from_str("db=sled, cache = 1024").unwrap();
+        let options = parse(attr).unwrap();
+        assert_eq!(cache(&options).unwrap(), Some(1024));
+    }
+
+    #[test]
+    fn empty_cache() {
+        let attr = proc_macro2::TokenStream::from_str("db=sled, cache = 0").unwrap();
+        let err = parse(attr).unwrap_err();
+        assert!(matches!(err.variant, ErrorVariant::InvalidCacheSize));
+    }
+
+    #[test]
+    fn read_only_option() {
+        let attr = proc_macro2::TokenStream::from_str("db=sled, readonly").unwrap();
+        let options = parse(attr).unwrap();
+        assert!(read_only(&options).unwrap());
+    }
+
+    #[test]
+    fn async_can_not_be_read_only() {
+        let attr = proc_macro2::TokenStream::from_str("db=sled, async, readonly").unwrap();
+        let options = parse(attr).unwrap();
+        let err = read_only(&options).unwrap_err();
+        assert!(matches!(err.variant, ErrorVariant::AsyncReadOnly));
+    }
+
+    #[test]
+    fn async_can_not_be_counted() {
+        let attr = proc_macro2::TokenStream::from_str("db=sled, async").unwrap();
+        let options = parse(attr).unwrap();
+        let fields = [Field {
+            ident: syn::parse_quote!(sessions),
+            vis: syn::parse_quote!(pub),
+            wrapper: Wrapper::Map {
+                key_ty: syn::parse_quote!(u64),
+                val_ty: syn::parse_quote!(String),
+                counted: true,
+            },
+            key: 1,
+            compress: None,
+        }];
+        let err = counted(&options, &fields).unwrap_err();
+        assert!(matches!(err.variant, ErrorVariant::AsyncCounted));
+    }
+
+    #[test]
+    fn field_compressed_twice() {
+        let attr = proc_macro2::TokenStream::from_str(r#"db=sled, compress = "zstd""#).unwrap();
+        let options = parse(attr).unwrap();
+        let fields = [Field {
+            ident: syn::parse_quote!(blobs),
+            vis: syn::parse_quote!(pub),
+            wrapper: Wrapper::Vec {
+                ty: syn::parse_quote!(Vec<u8>),
+            },
+            key: 1,
+            compress: Some(proc_macro2::Span::call_site()),
+        }];
+        let err = compressed_fields(&options, &fields).unwrap_err();
+        assert!(matches!(err.variant, ErrorVariant::StructCompressed));
+    }
+
+    #[test]
+    fn version_needs_migrations() {
+        let attr = proc_macro2::TokenStream::from_str("db=sled, version = 1").unwrap();
+        let options = parse(attr).unwrap();
+        let err = migrations(&options).unwrap_err();
+        assert!(matches!(err.variant, ErrorVariant::MissingMigrations));
+    }
 }
@@ -18,11 +18,46 @@ pub enum ErrorVariant {
     InvalidSyntax(TokenTree),
     #[error("Not a known database backend: `{0}`")]
     NotABackend(proc_macro2::Ident),
+    #[error("version option has no value set")]
+    MissingVersionValue,
+    #[error("the version must be a positive integer")]
+    InvalidVersion,
+    #[error("migrations should be a list of functions")]
+    InvalidMigrations,
+    #[error("option can only be set once")]
+    DuplicateOption,
+    #[error("a version is set but no migrations are provided")]
+    MissingMigrations,
+    #[error("migrations are provided but no version is set")]
+    MissingVersion,
+    #[error("version {version} needs {version} migrations, got {count}")]
+    MigrationCount { version: u32, count: usize },
+    #[error("migrations are not supported for async structs")]
+    AsyncMigrations,
+    #[error("the `{option}` option is not supported for `db=trait`")]
+    NotForTrait {
+        option: &'static str,
+        adapter: &'static str,
+    },
+    #[error("compress option has no value set")]
+    MissingCompressionValue,
+    #[error("not a supported compression algorithm")]
+    UnknownCompression,
+    #[error("cache option has no value set")]
+    MissingCacheSize,
+    #[error("the cache size must be a positive integer")]
+    InvalidCacheSize,
+    #[error("async structs can not be read only")]
+    AsyncReadOnly,
+    #[error("counted fields are not supported for async structs")]
+    AsyncCounted,
+    #[error("the struct already compresses all values")]
+    StructCompressed,
 }
 
 #[derive(thiserror::Error, Debug)]
 pub struct Error {
-    variant: ErrorVariant,
+    pub(super) variant: ErrorVariant,
     span: Option<Span>,
 }
 
@@ -39,9 +74,32 @@ impl Help for Error {
             MissingDb => "try specifying an db, for example: `db=sled`",
             MissingBackendValue => "try setting a supported backend, for example `db=sled`",
             InvalidBackendSyntax => "a backend should be a single world not enclosed in \"",
-            NotAnOption(_) => "the only supported option currently is: db",
+            NotAnOption(_) => "the supported options are: db, async, encrypt, compress, cache, readonly, version and migrations",
             InvalidSyntax(_) => "the option should be a single word not enclosed in \"",
-            NotABackend(_) => "try sled as database backend",
+            NotABackend(_) => "try sled, redb, sqlite, lmdb, fjall, rocksdb or file as database backend",
+            MissingVersionValue | InvalidVersion => "set the version like this: `version = 2`",
+            InvalidMigrations => "list the migrations like this: `migrations = [to_v1, to_v2]`",
+            DuplicateOption => "remove one of the options",
+            MissingMigrations => "provide a function for each version: `migrations = [to_v1]`",
+            MissingVersion => "set the version to the number of migrations: `version = 1`",
+            MigrationCount { .. } => {
+                "the first migration goes from version 0 to 1, the second from 1 to 2 etc"
+            }
+            AsyncMigrations => "migrate the database using a struct without the async option",
+            NotForTrait { adapter, .. } => {
+                return Some(format!(
+                    "wrap the store in `dbstruct::stores::{adapter}` before passing it in"
+                ))
+            }
+            MissingCompressionValue | UnknownCompression => {
+                "set the compression like this: `compress = \"zstd\"`"
+            }
+            MissingCacheSize | InvalidCacheSize => {
+                "set the number of values to cache like this: `cache = 1024`"
+            }
+            AsyncReadOnly => "remove the async option, read only structs are blocking",
+            AsyncCounted => "remove the async option or the `counted` field options",
+            StructCompressed => "remove the `compress` option from the field or the struct",
         }.to_owned())
     }
 }
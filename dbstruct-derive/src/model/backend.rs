@@ -74,12 +74,25 @@ impl Error {
 pub enum ExtraBound {
     Atomic,
     Ordered,
+    /// Ordered access to the raw bytes, needed to run migrations
+    RawOrdered,
+    /// Iterating over a range of keys
+    Ranged,
 }
 
 #[derive(Debug, Clone)]
 pub enum Backend {
     Sled,
-    HashMap,
+    RocksDb,
+    Redb,
+    Sqlite,
+    Lmdb,
+    Fjall,
+    File,
+    /// Deprecated, `span` points to where it was chosen
+    HashMap {
+        span: Span,
+    },
     BTreeMap,
     Trait {
         bounds: Vec<ExtraBound>,
@@ -92,7 +105,13 @@ impl fmt::Display for Backend {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Backend::Sled => write!(f, "sled"),
-            Backend::HashMap => write!(f, "hashmap"),
+            Backend::RocksDb => write!(f, "rocksdb"),
+            Backend::Redb => write!(f, "redb"),
+            Backend::Sqlite => write!(f, "sqlite"),
+            Backend::Lmdb => write!(f, "lmdb"),
+            Backend::Fjall => write!(f, "fjall"),
+            Backend::File => write!(f, "file"),
+            Backend::HashMap { .. } => write!(f, "hashmap"),
             Backend::BTreeMap => write!(f, "btreemap"),
             Backend::Trait { .. } => write!(f, "trait"),
             #[cfg(test)]
@@ -102,7 +121,11 @@ impl fmt::Display for Backend {
 }
 
 impl Backend {
-    pub fn try_from(options: &[attribute::Options], fields: &[Field]) -> Result<Backend, Error> {
+    pub fn try_from(
+        options: &[attribute::Options],
+        fields: &[Field],
+        migrations: bool,
+    ) -> Result<Backend, Error> {
         use BackendOptionVariant::*;
         use ErrorVariant::*;
 
@@ -120,24 +143,36 @@ impl Backend {
             }
         };
 
+        let migration_bound = migrations.then_some(ExtraBound::RawOrdered);
         let backend = match backend_option.backend {
             Trait => {
-                return Ok(Backend::Trait {
-                    bounds: fields
-                        .iter()
-                        .flat_map(|f| f.wrapper.needed_traits().into_iter())
-                        .collect(),
-                })
+                let mut bounds: Vec<_> = fields
+                    .iter()
+                    .flat_map(|f| f.wrapper.needed_traits().into_iter())
+                    .chain(migration_bound)
+                    .collect();
+                bounds.sort_by_key(|b| *b as u8);
+                bounds.dedup();
+                return Ok(Backend::Trait { bounds });
             }
-            HashMap => Backend::HashMap,
+            HashMap => Backend::HashMap {
+                span: backend_option.span,
+            },
             BTreeMap => Backend::BTreeMap,
             Sled => Backend::Sled,
+            RocksDb => Backend::RocksDb,
+            Redb => Backend::Redb,
+            Sqlite => Backend::Sqlite,
+            Lmdb => Backend::Lmdb,
+            Fjall => Backend::Fjall,
+            File => Backend::File,
             #[cfg(test)]
             Test => Backend::Test,
         };
 
-        for field in fields {
-            let needed = field.wrapper.needed_traits();
+        let needed_per_field = fields.iter().map(|field| field.wrapper.needed_traits());
+        let needed_for_migrations = migration_bound.map(|bound| HashSet::from([bound]));
+        for needed in needed_per_field.chain(needed_for_migrations) {
             let missing: HashSet<_> = needed.difference(&backend.traits()).copied().collect();
             if !missing.is_empty() {
                 return Err(MissesTraits { backend, needed }.with_span(backend_option.span));
@@ -150,9 +185,15 @@ impl Backend {
     fn traits(&self) -> HashSet<ExtraBound> {
         use ExtraBound::*;
         match self {
-            Backend::Sled => vec![Atomic, Ordered].into_iter(),
-            Backend::HashMap => vec![].into_iter(),
-            Backend::BTreeMap => vec![Atomic, Ordered].into_iter(),
+            Backend::Sled => vec![Atomic, Ordered, RawOrdered, Ranged].into_iter(),
+            Backend::RocksDb => vec![Atomic, Ordered, RawOrdered, Ranged].into_iter(),
+            Backend::Redb => vec![Atomic, Ordered, RawOrdered, Ranged].into_iter(),
+            Backend::Sqlite => vec![Atomic, Ordered, RawOrdered, Ranged].into_iter(),
+            Backend::Lmdb => vec![Atomic, Ordered, RawOrdered, Ranged].into_iter(),
+            Backend::Fjall => vec![Atomic, Ordered, RawOrdered, Ranged].into_iter(),
+            Backend::File => vec![Atomic, Ordered, RawOrdered, Ranged].into_iter(),
+            Backend::HashMap { .. } => vec![].into_iter(),
+            Backend::BTreeMap => vec![Atomic, Ordered, RawOrdered, Ranged].into_iter(),
             Backend::Trait { .. } => unreachable!("should never be called when backend is Trait"),
             #[cfg(test)]
             Backend::Test => vec![].into_iter(),
@@ -160,8 +201,42 @@ impl Backend {
         .collect()
     }
 
-    fn provided() -> [Backend; 2] {
-        [Backend::Sled, Backend::HashMap]
+    /// Whether the backend keeps its keys in order, for `db=trait` whether
+    /// the struct requires that
+    pub fn is_ordered(&self) -> bool {
+        match self {
+            Backend::Trait { bounds } => bounds.contains(&ExtraBound::Ordered),
+            backend => backend.traits().contains(&ExtraBound::Ordered),
+        }
+    }
+
+    /// Whether the backend implements `Transactional`
+    pub fn supports_transactions(&self) -> bool {
+        match self {
+            Backend::Sled
+            | Backend::RocksDb
+            | Backend::Redb
+            | Backend::Sqlite
+            | Backend::Lmdb
+            | Backend::Fjall
+            | Backend::BTreeMap => true,
+            Backend::File | Backend::HashMap { .. } | Backend::Trait { .. } => false,
+            #[cfg(test)]
+            Backend::Test => false,
+        }
+    }
+
+    fn provided() -> [Backend; 8] {
+        [
+            Backend::Sled,
+            Backend::RocksDb,
+            Backend::Redb,
+            Backend::Sqlite,
+            Backend::Lmdb,
+            Backend::Fjall,
+            Backend::File,
+            Backend::BTreeMap,
+        ]
     }
 }
 
@@ -188,8 +263,9 @@ mod tests {
                 ty: parse_quote!(u8),
             },
             key: 1,
+            compress: None,
         }];
-        let err = Backend::try_from(&options, &fields).unwrap_err();
+        let err = Backend::try_from(&options, &fields, false).unwrap_err();
         match err.variant {
             ErrorVariant::MissesTraits {
                 needed: missing, ..
@@ -215,8 +291,9 @@ mod tests {
                 ty: parse_quote!(u8),
             },
             key: 1,
+            compress: None,
         }];
-        let backend = Backend::try_from(&options, &fields).unwrap();
+        let backend = Backend::try_from(&options, &fields, false).unwrap();
         assert!(matches!(backend, Backend::Sled));
     }
 
@@ -240,8 +317,9 @@ mod tests {
                 ty: parse_quote!(u8),
             },
             key: 1,
+            compress: None,
         }];
-        let err = Backend::try_from(&options, &fields).unwrap_err();
+        let err = Backend::try_from(&options, &fields, false).unwrap_err();
         assert!(matches!(err.variant, ErrorVariant::MultipleBackends));
     }
 }
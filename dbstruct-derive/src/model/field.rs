@@ -1,19 +1,25 @@
 mod wrapper;
+pub use wrapper::compressed;
+pub use wrapper::pinned_key;
 pub use wrapper::Error;
 pub use wrapper::Wrapper;
 
 use super::DbKey;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Field {
     pub ident: syn::Ident,
     pub vis: syn::Visibility,
     pub wrapper: Wrapper,
     pub key: u8,
+    /// Where `#[dbstruct(compress = "zstd")]` is set, the values of this
+    /// field are compressed
+    pub compress: Option<proc_macro2::Span>,
 }
 
 impl Field {
     pub fn analyze(mut field: syn::Field, keys: &DbKey) -> Result<Self, Error> {
+        let compress = compressed(&field.attrs);
         let wrapper = Wrapper::try_from(&mut field.attrs, field.ty)?;
         let ident = field
             .ident
@@ -25,6 +31,7 @@ impl Field {
             vis: field.vis,
             wrapper,
             key,
+            compress,
         })
     }
 }
@@ -7,9 +7,10 @@ use proc_macro2::TokenTree;
 mod errors;
 pub use errors::{Error, ErrorVariant};
 
+use crate::errors::GetSpan;
 use crate::model::backend::ExtraBound;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Wrapper {
     Vec {
         ty: syn::Type,
@@ -20,6 +21,9 @@ pub enum Wrapper {
     Map {
         key_ty: syn::Type,
         val_ty: syn::Type,
+        /// Keep a persisted count of the entries, set with
+        /// `#[dbstruct(counted)]`
+        counted: bool,
     },
     DefaultTrait {
         ty: syn::Type,
@@ -31,12 +35,28 @@ pub enum Wrapper {
     Option {
         ty: syn::Type,
     },
+    Set {
+        ty: syn::Type,
+        /// Keep a persisted count of the elements, set with
+        /// `#[dbstruct(counted)]`
+        counted: bool,
+    },
+    OrderedMap {
+        key_ty: syn::Type,
+        val_ty: syn::Type,
+        /// Keep a persisted count of the entries, set with
+        /// `#[dbstruct(counted)]`
+        counted: bool,
+    },
 }
 
 #[derive(Debug)]
 pub enum Attribute {
     DefaultTrait { span: proc_macro2::Span },
     DefaultValue { expr: syn::Expr },
+    Key { key: u8, span: proc_macro2::Span },
+    Counted { span: proc_macro2::Span },
+    Compress { span: proc_macro2::Span },
 }
 
 fn is_relevant(att: &syn::Attribute) -> bool {
@@ -63,12 +83,8 @@ fn parse_default(
 ) -> Result<Attribute, Error> {
     use ErrorVariant::*;
     match tokens.peek() {
-        None => {
-            tokens.next();
-            Ok(Attribute::DefaultTrait { span })
-        }
+        None => Ok(Attribute::DefaultTrait { span }),
         Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {
-            tokens.next();
             Ok(Attribute::DefaultTrait { span })
         }
         Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {
@@ -89,6 +105,52 @@ fn parse_default(
     }
 }
 
+fn parse_key(
+    span: proc_macro2::Span,
+    tokens: &mut Peekable<impl Iterator<Item = TokenTree>>,
+) -> Result<Attribute, Error> {
+    use ErrorVariant::*;
+    match tokens.next() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => match tokens.next() {
+            None => Err(MissingKeyValue.with_span(punct)),
+            Some(TokenTree::Literal(lit)) => match syn::Lit::new(lit.clone()) {
+                syn::Lit::Int(int) => {
+                    let key = int
+                        .base10_parse::<u8>()
+                        .map_err(|_| InvalidKey.with_span(&lit))?;
+                    Ok(Attribute::Key {
+                        key,
+                        span: lit.span(),
+                    })
+                }
+                _ => Err(InvalidKey.with_span(lit)),
+            },
+            Some(other) => Err(InvalidKey.with_span(other)),
+        },
+        _ => Err(MissingKeyValue.with_span(span)),
+    }
+}
+
+fn parse_compress(
+    span: proc_macro2::Span,
+    tokens: &mut Peekable<impl Iterator<Item = TokenTree>>,
+) -> Result<Attribute, Error> {
+    use ErrorVariant::*;
+    match tokens.next() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => match tokens.next() {
+            None => Err(MissingCompression.with_span(punct)),
+            Some(TokenTree::Literal(lit)) => match syn::Lit::new(lit.clone()) {
+                syn::Lit::Str(str) if str.value() == "zstd" => Ok(Attribute::Compress {
+                    span: lit.span(),
+                }),
+                _ => Err(UnknownCompression.with_span(lit)),
+            },
+            Some(other) => Err(UnknownCompression.with_span(other)),
+        },
+        _ => Err(MissingCompression.with_span(span)),
+    }
+}
+
 fn parse(tokens: &mut Peekable<impl Iterator<Item = TokenTree>>) -> Result<Attribute, Error> {
     use ErrorVariant::*;
     let first_token = tokens
@@ -98,12 +160,19 @@ fn parse(tokens: &mut Peekable<impl Iterator<Item = TokenTree>>) -> Result<Attri
         TokenTree::Ident(ident) if ident == "Default" => {
             parse_default(ident.span(), tokens)
         }
+        TokenTree::Ident(ident) if ident == "key" => parse_key(ident.span(), tokens),
+        TokenTree::Ident(ident) if ident == "counted" => Ok(Attribute::Counted {
+            span: ident.span(),
+        }),
+        TokenTree::Ident(ident) if ident == "compress" => parse_compress(ident.span(), tokens),
         TokenTree::Ident(ident) => Err(NotAWrapper(ident).has_span()),
         _ => Err(InvalidSyntax(first_token).has_span()),
     }
 }
 
-fn as_wrapper(att: syn::Attribute) -> Result<Option<Attribute>, Error> {
+/// Parses all the comma separated options in a single `#[dbstruct(..)]`
+/// field attribute.
+fn parse_options(att: syn::Attribute) -> Result<Vec<Attribute>, Error> {
     use ErrorVariant::*;
     let tokens = match att.meta {
         syn::Meta::Path(_) => return Err(EmptyAttribute.with_span(att)),
@@ -111,18 +180,47 @@ fn as_wrapper(att: syn::Attribute) -> Result<Option<Attribute>, Error> {
         syn::Meta::NameValue(_) => return Err(InvalidTokenTree.with_span(att)),
     };
 
-    let mut res = None;
+    let mut res = Vec::new();
     let mut tokens = tokens.into_iter().peekable();
-    while let Some(token) = tokens.peek() {
-        if res.is_none() {
-            res = Some(parse(&mut tokens)?);
-        } else {
-            return Err(MultipleWrapperAttributes.with_span(token));
+    while tokens.peek().is_some() {
+        res.push(parse(&mut tokens)?);
+        match tokens.next() {
+            None => break,
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => continue,
+            Some(other) => return Err(MultipleWrapperAttributes.with_span(other)),
         }
     }
     Ok(res)
 }
 
+/// Returns the key set using `#[dbstruct(key = N)]` if there is one. Invalid
+/// attributes are ignored here, they are reported by [`Wrapper::try_from`].
+pub fn pinned_key(attributes: &[syn::Attribute]) -> Option<(u8, proc_macro2::Span)> {
+    attributes
+        .iter()
+        .filter(|att| is_relevant(att))
+        .filter_map(|att| parse_options(att.clone()).ok())
+        .flatten()
+        .find_map(|option| match option {
+            Attribute::Key { key, span } => Some((key, span)),
+            _ => None,
+        })
+}
+
+/// Returns where `#[dbstruct(compress = "zstd")]` is set if it is. Invalid
+/// attributes are ignored here, they are reported by [`Wrapper::try_from`].
+pub fn compressed(attributes: &[syn::Attribute]) -> Option<proc_macro2::Span> {
+    attributes
+        .iter()
+        .filter(|att| is_relevant(att))
+        .filter_map(|att| parse_options(att.clone()).ok())
+        .flatten()
+        .find_map(|option| match option {
+            Attribute::Compress { span } => Some(span),
+            _ => None,
+        })
+}
+
 impl Wrapper {
     /// Takes relevant attributes from `attributes` and determines the wrapper
     pub fn try_from(attributes: &mut Vec<syn::Attribute>, ty: syn::Type) -> Result<Self, Error> {
@@ -134,12 +232,50 @@ impl Wrapper {
         *attributes = other; /* TODO: use drain_filter when it stabilizes <31-07-22> */
         /* TODO: emit error when other (non dbstruct) attributes are present <27-08-22, dvdsk> */
 
-        let attribute = relevant.pop().map(as_wrapper).transpose()?.flatten();
+        let options = relevant.pop().map(parse_options).transpose()?;
         if let Some(other) = relevant.pop() {
             return Err(MultipleAttributes.with_span(&other));
         }
 
-        Ok(match (outer_type(&ty)?.as_str(), attribute) {
+        // The key is handled by `DbKey`, it only needs to be valid here
+        let (keys, wrappers): (Vec<_>, Vec<_>) = options
+            .into_iter()
+            .flatten()
+            .partition(|option| matches!(option, Key { .. }));
+        if let Some(second) = keys.get(1) {
+            return Err(MultipleKeys.with_span(second.span()));
+        }
+        let (counted, wrappers): (Vec<_>, Vec<_>) = wrappers
+            .into_iter()
+            .partition(|option| matches!(option, Counted { .. }));
+        if let Some(second) = counted.get(1) {
+            return Err(MultipleCounted.with_span(second.span()));
+        }
+        // Compression is handled by `Field`, it only needs to be valid here
+        let (compress, mut wrappers): (Vec<_>, Vec<_>) = wrappers
+            .into_iter()
+            .partition(|option| matches!(option, Compress { .. }));
+        if let Some(second) = compress.get(1) {
+            return Err(MultipleCompress.with_span(second.span()));
+        }
+        if let Some(second) = wrappers.get(1) {
+            return Err(MultipleWrapperAttributes.with_span(second.span()));
+        }
+        let attribute = wrappers.pop();
+
+        let outer_ty = outer_type(&ty)?;
+        let countable = attribute.is_none()
+            && matches!(
+                outer_ty.as_str(),
+                "HashMap" | "BTreeMap" | "HashSet" | "BTreeSet"
+            );
+        let counted = match counted.first() {
+            Some(_) if countable => true,
+            Some(option) => return Err(CountedNotACollection.with_span(option.span())),
+            None => false,
+        };
+
+        Ok(match (outer_ty.as_str(), attribute) {
             ("Vec", None) => Self::Vec {
                 ty: inner_type(&ty, "Vec")?,
             },
@@ -153,19 +289,47 @@ impl Wrapper {
             // error at the type and the default trait attribute
             ("Option", Some(DefaultTrait { span })) => return Err(OptionNotAllowed.with_span(span)),
             ("HashMap", None) => {
-                let (key_ty, val_ty) = hashmap_types(&ty)?;
-                Self::Map { key_ty, val_ty }
+                let (key_ty, val_ty) = map_types(&ty, "HashMap")?;
+                Self::Map {
+                    key_ty,
+                    val_ty,
+                    counted,
+                }
             }
+            ("BTreeMap", None) => {
+                let (key_ty, val_ty) = map_types(&ty, "BTreeMap")?;
+                Self::OrderedMap {
+                    key_ty,
+                    val_ty,
+                    counted,
+                }
+            }
+            ("HashSet", None) => Self::Set {
+                ty: inner_type(&ty, "HashSet")?,
+                counted,
+            },
+            ("BTreeSet", None) => Self::Set {
+                ty: inner_type(&ty, "BTreeSet")?,
+                counted,
+            },
             (_, None) => return Err(NoDefaultType.with_span(ty)),
             (_, Some(DefaultTrait { .. })) => Self::DefaultTrait { ty },
             (_, Some(DefaultValue { expr })) => Self::DefaultValue { ty, value: expr },
+            (_, Some(Key { .. } | Counted { .. } | Compress { .. })) => {
+                unreachable!("keys, counted and compress are filtered out above")
+            }
         })
     }
 
     pub(crate) fn needed_traits(&self) -> HashSet<ExtraBound> {
         use ExtraBound::*;
         match self {
-            Wrapper::Vec { .. } => vec![Ordered].into_iter(),
+            Wrapper::Vec { .. } | Wrapper::VecDeque { .. } => vec![Ordered].into_iter(),
+            Wrapper::OrderedMap { .. } => vec![Ordered, Ranged].into_iter(),
+            // The count is recovered by reading the entries if it is missing
+            Wrapper::Map { counted: true, .. } | Wrapper::Set { counted: true, .. } => {
+                vec![Ordered].into_iter()
+            }
             _ => vec![].into_iter(),
         }
         .collect()
@@ -196,13 +360,13 @@ fn inner_type(ty: &syn::Type, outer_ty: &'static str) -> Result<syn::Type, Error
     Ok(ty)
 }
 
-fn hashmap_types(ty: &syn::Type) -> Result<(syn::Type, syn::Type), Error> {
-    let mut generics = generic_types(ty, "HashMap", 2)?;
+fn map_types(ty: &syn::Type, outer_ty: &'static str) -> Result<(syn::Type, syn::Type), Error> {
+    let mut generics = generic_types(ty, outer_ty, 2)?;
     let key_ty = generics
         .next()
         .ok_or(
             ErrorVariant::TooFewGenerics {
-                ty: "HashMap",
+                ty: outer_ty,
                 n_needed: 2,
             }
             .with_span(ty),
@@ -212,7 +376,7 @@ fn hashmap_types(ty: &syn::Type) -> Result<(syn::Type, syn::Type), Error> {
         .next()
         .ok_or(
             ErrorVariant::TooFewGenerics {
-                ty: "HashMap",
+                ty: outer_ty,
                 n_needed: 2,
             }
             .with_span(ty),
@@ -221,7 +385,7 @@ fn hashmap_types(ty: &syn::Type) -> Result<(syn::Type, syn::Type), Error> {
 
     if let Some(other_generic) = generics.next() {
         return Err(ErrorVariant::TooManyGenerics {
-            ty: "HashMap",
+            ty: outer_ty,
             n_needed: 2,
         }
         .with_span(other_generic?));
@@ -240,7 +404,7 @@ fn generic_types<'a>(
             path: syn::Path { segments, .. },
             ..
         }) => segments,
-        _ => unreachable!("should only run in match arm when matching HashMap"),
+        _ => unreachable!("should only run in match arm when matching a generic type"),
     };
 
     // Aliasing HashMap can result in a macro panic. The person making
@@ -336,7 +500,87 @@ mod tests {
         let val_ty: syn::Type = parse_quote!(Vec<u16>);
         let ty_hashmap: syn::Type = parse_quote!(HashMap<u8, Vec<u16>>);
         let wrapper = Wrapper::try_from(&mut Vec::new(), ty_hashmap.clone()).unwrap();
-        assert_eq!(wrapper, Wrapper::Map { key_ty, val_ty })
+        assert_eq!(
+            wrapper,
+            Wrapper::Map {
+                key_ty,
+                val_ty,
+                counted: false
+            }
+        )
+    }
+
+    #[test]
+    fn counted_map() {
+        let attributes: &[syn::Attribute] = &[parse_quote!(#[dbstruct(counted)])];
+        let ty: syn::Type = parse_quote!(HashMap<u8, String>);
+        let wrapper = Wrapper::try_from(&mut attributes.to_vec(), ty).unwrap();
+        assert!(matches!(wrapper, Wrapper::Map { counted: true, .. }))
+    }
+
+    #[test]
+    fn counted_set() {
+        let attributes: &[syn::Attribute] = &[parse_quote!(#[dbstruct(counted)])];
+        let ty: syn::Type = parse_quote!(BTreeSet<String>);
+        let wrapper = Wrapper::try_from(&mut attributes.to_vec(), ty).unwrap();
+        assert!(matches!(wrapper, Wrapper::Set { counted: true, .. }))
+    }
+
+    #[test]
+    fn only_maps_and_sets_are_counted() {
+        let attributes: &[syn::Attribute] = &[parse_quote!(#[dbstruct(counted)])];
+        let ty: syn::Type = parse_quote!(Vec<u8>);
+        let err = Wrapper::try_from(&mut attributes.to_vec(), ty).unwrap_err();
+        assert!(matches!(err.variant, ErrorVariant::CountedNotACollection))
+    }
+
+    #[test]
+    fn compressed_map() {
+        let attributes: &[syn::Attribute] =
+            &[parse_quote!(#[dbstruct(counted, compress = "zstd")])];
+        assert!(compressed(attributes).is_some());
+        let ty: syn::Type = parse_quote!(HashMap<u8, String>);
+        let wrapper = Wrapper::try_from(&mut attributes.to_vec(), ty).unwrap();
+        assert!(matches!(wrapper, Wrapper::Map { counted: true, .. }))
+    }
+
+    #[test]
+    fn unknown_compression() {
+        let attributes: &[syn::Attribute] = &[parse_quote!(#[dbstruct(compress = "lzma")])];
+        let ty: syn::Type = parse_quote!(Vec<u8>);
+        let err = Wrapper::try_from(&mut attributes.to_vec(), ty).unwrap_err();
+        assert!(matches!(err.variant, ErrorVariant::UnknownCompression))
+    }
+
+    #[test]
+    fn ordered_map() {
+        let key_ty: syn::Type = parse_quote!(u64);
+        let val_ty: syn::Type = parse_quote!(String);
+        let ty: syn::Type = parse_quote!(BTreeMap<u64, String>);
+        let wrapper = Wrapper::try_from(&mut Vec::new(), ty).unwrap();
+        assert_eq!(
+            wrapper,
+            Wrapper::OrderedMap {
+                key_ty,
+                val_ty,
+                counted: false
+            }
+        )
+    }
+
+    #[test]
+    fn set() {
+        let inner_ty: syn::Type = parse_quote!(String);
+        for ty in [parse_quote!(HashSet<String>), parse_quote!(BTreeSet<String>)] {
+            let wrapper = Wrapper::try_from(&mut Vec::new(), ty).unwrap();
+            assert_eq!(
+                wrapper,
+                Wrapper::Set {
+                    ty: inner_ty.clone(),
+                    counted: false
+                }
+            )
+        }
     }
 
     #[test]
@@ -36,6 +36,22 @@ pub enum ErrorVariant {
     TooFewGenerics { ty: &'static str, n_needed: u8 },
     #[error("Too many generics for {ty}, expected {n_needed}")]
     TooManyGenerics { ty: &'static str, n_needed: u8 },
+    #[error("Invalid syntax: missing a value for the key")]
+    MissingKeyValue,
+    #[error("The key must be an integer between 0 and 254")]
+    InvalidKey,
+    #[error("A field can only have a single key")]
+    MultipleKeys,
+    #[error("Only map and set fields can be counted")]
+    CountedNotACollection,
+    #[error("A field can only be counted once")]
+    MultipleCounted,
+    #[error("Invalid syntax: missing a value for compress")]
+    MissingCompression,
+    #[error("Not a supported compression algorithm")]
+    UnknownCompression,
+    #[error("A field can only be compressed once")]
+    MultipleCompress,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -57,6 +73,16 @@ impl Help for Error {
                 "try one of these: `#[dbstruct(Default)]`, `#[dbstruct(Default=\"<expr>\"]`"
             }
             OptionNotAllowed => "try removing the attribute",
+            MissingKeyValue | InvalidKey => "set the key like this: `#[dbstruct(key = 3)]`",
+            MultipleKeys => "remove one of the keys",
+            CountedNotACollection => {
+                "counted works on `HashMap`, `BTreeMap`, `HashSet` and `BTreeSet` fields"
+            }
+            MultipleCounted => "remove one of the `counted` options",
+            MissingCompression | UnknownCompression => {
+                "set the compression like this: `#[dbstruct(compress = \"zstd\")]`"
+            }
+            MultipleCompress => "remove one of the `compress` options",
             MultipleWrapperAttributes => {
                 "when using Default=\"<expr>\" make sure the <expr> string is properly escaped"
             }
@@ -94,6 +120,9 @@ impl GetSpan for super::Attribute {
             // NoWrap { span } => *span,
             DefaultTrait { span } => *span,
             DefaultValue { expr } => syn::spanned::Spanned::span(expr),
+            Key { span, .. } => *span,
+            Counted { span } => *span,
+            Compress { span } => *span,
         }
     }
 }
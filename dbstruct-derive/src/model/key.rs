@@ -1,17 +1,40 @@
 use std::collections::HashMap;
+use proc_macro2::Span;
 use syn::Ident;
 
-use crate::errors::GetSpan;
+use crate::errors::{GetSpan, Help};
+
+use super::field;
 
 #[derive(thiserror::Error, Debug)]
-#[error("A dbstruct can only have 254 fields")]
-pub struct Error {
-    span: proc_macro2::Span,
+pub enum Error {
+    #[error("A dbstruct can only have 254 fields")]
+    TooManyFields { span: Span },
+    #[error("Key {key} is already used by field `{other}`")]
+    DuplicateKey { key: u8, other: Ident, span: Span },
+    #[error("Key {} is reserved for data dbstruct itself needs", Prefix::MAX)]
+    ReservedKey { span: Span },
 }
 
 impl GetSpan for Error {
     fn span(&self) -> proc_macro2::Span {
-        self.span
+        match self {
+            Error::TooManyFields { span }
+            | Error::DuplicateKey { span, .. }
+            | Error::ReservedKey { span } => *span,
+        }
+    }
+}
+
+impl Help for Error {
+    fn help(&self) -> Option<String> {
+        match self {
+            Error::TooManyFields { .. } => None,
+            Error::DuplicateKey { .. } => {
+                Some("every field needs its own key, try another number".to_owned())
+            }
+            Error::ReservedKey { .. } => Some(format!("use a key below {}", Prefix::MAX)),
+        }
     }
 }
 
@@ -20,23 +43,46 @@ type Prefix = u8;
 pub struct DbKey(HashMap<Ident, Prefix>);
 
 impl DbKey {
+    /// Fields with a key set through `#[dbstruct(key = N)]` get that key as
+    /// prefix. The other fields are sorted by name and get the lowest
+    /// prefixes not yet taken. The highest prefix is reserved for metadata
+    /// such as the schema.
     pub(crate) fn new(fields: &syn::Fields) -> Result<Self, Error> {
-        let mut idents: Vec<_> = fields
-            .iter()
-            .map(|f| f.ident.clone())
-            .map(|i| i.expect("should already be verified this is a named struct"))
-            .collect();
-        idents.sort();
-
-        if let Some(ident) = idents.get(u8::MAX as usize) {
-            return Err(Error { span: ident.span() });
-        };
-
-        let map = idents
-            .into_iter()
-            .enumerate()
-            .map(|(id, ident)| (ident, id as Prefix))
-            .collect();
+        let mut map = HashMap::new();
+        let mut taken: HashMap<Prefix, Ident> = HashMap::new();
+        let mut unpinned = Vec::new();
+
+        for field in fields {
+            let ident = field
+                .ident
+                .clone()
+                .expect("should already be verified this is a named struct");
+            match field::pinned_key(&field.attrs) {
+                Some((Prefix::MAX, span)) => return Err(Error::ReservedKey { span }),
+                Some((key, span)) => {
+                    if let Some(other) = taken.get(&key) {
+                        return Err(Error::DuplicateKey {
+                            key,
+                            other: other.clone(),
+                            span,
+                        });
+                    }
+                    taken.insert(key, ident.clone());
+                    map.insert(ident, key);
+                }
+                None => unpinned.push(ident),
+            }
+        }
+        unpinned.sort();
+
+        let mut free = (0..Prefix::MAX).filter(|prefix| !taken.contains_key(prefix));
+        for ident in unpinned {
+            let Some(prefix) = free.next() else {
+                return Err(Error::TooManyFields { span: ident.span() });
+            };
+            map.insert(ident, prefix);
+        }
+
         Ok(Self(map))
     }
 
@@ -47,3 +93,71 @@ impl DbKey {
             .expect("every field's ident should be in the DbKey map")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn keys(input: syn::ItemStruct) -> Result<DbKey, Error> {
+        DbKey::new(&input.fields)
+    }
+
+    #[test]
+    fn sorted_by_name() {
+        let keys = keys(parse_quote!(
+            struct Test {
+                b: Option<u8>,
+                a: Option<u8>,
+            }
+        ))
+        .unwrap();
+        assert_eq!(keys.prefix(&parse_quote!(a)), 0);
+        assert_eq!(keys.prefix(&parse_quote!(b)), 1);
+    }
+
+    #[test]
+    fn pinned_keys_are_skipped() {
+        let keys = keys(parse_quote!(
+            struct Test {
+                a: Option<u8>,
+                #[dbstruct(key = 0)]
+                b: Option<u8>,
+                #[dbstruct(Default, key = 2)]
+                c: u8,
+                d: Option<u8>,
+            }
+        ))
+        .unwrap();
+        assert_eq!(keys.prefix(&parse_quote!(a)), 1);
+        assert_eq!(keys.prefix(&parse_quote!(b)), 0);
+        assert_eq!(keys.prefix(&parse_quote!(c)), 2);
+        assert_eq!(keys.prefix(&parse_quote!(d)), 3);
+    }
+
+    #[test]
+    fn duplicate_key() {
+        let err = keys(parse_quote!(
+            struct Test {
+                #[dbstruct(key = 5)]
+                a: Option<u8>,
+                #[dbstruct(key = 5)]
+                b: Option<u8>,
+            }
+        ))
+        .unwrap_err();
+        assert!(matches!(err, Error::DuplicateKey { key: 5, .. }));
+    }
+
+    #[test]
+    fn reserved_key() {
+        let err = keys(parse_quote!(
+            struct Test {
+                #[dbstruct(key = 255)]
+                a: Option<u8>,
+            }
+        ))
+        .unwrap_err();
+        assert!(matches!(err, Error::ReservedKey { .. }));
+    }
+}
@@ -68,7 +68,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(&"What is the Universe".to_owned(), second);
 
     let computers = db.computers().get()?;
-    let earth = computers.get(&"Earth".to_owned());
+    let earth = computers.get("Earth");
     assert_eq!(earth.map(|c| c.secret), Some(true));
 
     Ok(())
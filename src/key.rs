@@ -0,0 +1,827 @@
+//! Encoding of keys whose byte order matches the order of the keys. The
+//! [`OrderedMap`](crate::wrapper::OrderedMap) and the iterators of the other
+//! wrappers rely on this, the database sorts the encoded keys.
+//!
+//! The encoding resembles that of bincode with big endian fixed width
+//! integers. Where that would break the order it differs:
+//!  - Signed integers have their sign bit flipped so negative numbers sort
+//!    before positive ones. Negative floats have all their bits flipped,
+//!    positive floats only their sign bit.
+//!  - Strings and byte slices are not preceded by their length. Every zero
+//!    byte is followed by `0xFF` and the string ends with two zero bytes.
+//!    Shorter strings therefore sort before longer strings that start with
+//!    them.
+//!  - Each element of a sequence or map is preceded by a `1` byte and the
+//!    sequence ends with a `0` byte.
+//!
+//! Values decode from the start of the slice, any bytes after them are
+//! ignored.
+//!
+//! Up to version 0.6 keys were encoded using bincode. Databases holding such
+//! keys are upgraded when they are opened, see [`FORMAT`].
+
+use bincode::error::{DecodeError, EncodeError};
+use serde::de::{self, DeserializeOwned, IntoDeserializer};
+use serde::ser::{self, Serialize};
+
+/// Ends a string or byte slice
+const END: [u8; 2] = [0, 0];
+/// Follows a zero byte that is part of a string or byte slice
+const ESCAPED_ZERO: u8 = 0xFF;
+/// Precedes each element of a sequence or map
+const ELEMENT: u8 = 1;
+/// Ends a sequence or map
+const SEQ_END: u8 = 0;
+
+/// The version of the key encoding, stored in the database once its keys
+/// use this encoding. Without it the keys of map and set fields are assumed
+/// to be encoded using bincode and are re-encoded.
+pub(crate) const FORMAT: u8 = 1;
+
+pub(crate) fn encode<T: Serialize + ?Sized>(key: &T) -> Result<Vec<u8>, EncodeError> {
+    let mut serializer = Serializer { output: Vec::new() };
+    key.serialize(&mut serializer)?;
+    Ok(serializer.output)
+}
+
+pub(crate) fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DecodeError> {
+    T::deserialize(&mut Deserializer { input: bytes })
+}
+
+/// Decodes a key written by version 0.6 or earlier, those used bincode with
+/// big endian fixed width integers.
+pub(crate) fn decode_bincode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DecodeError> {
+    let config = bincode::config::standard()
+        .with_big_endian()
+        .with_fixed_int_encoding();
+    let (key, read) = bincode::serde::decode_from_slice(bytes, config)?;
+    if read != bytes.len() {
+        return Err(DecodeError::OtherString(format!(
+            "{} bytes left after the key",
+            bytes.len() - read
+        )));
+    }
+    Ok(key)
+}
+
+struct Serializer {
+    output: Vec<u8>,
+}
+
+impl Serializer {
+    fn escaped(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.output.push(*byte);
+            if *byte == 0 {
+                self.output.push(ESCAPED_ZERO);
+            }
+        }
+        self.output.extend_from_slice(&END);
+    }
+}
+
+impl ser::Serializer for &mut Serializer {
+    type Ok = ();
+    type Error = EncodeError;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<(), EncodeError> {
+        self.output.push(v as u8);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), EncodeError> {
+        self.serialize_u8((v as u8) ^ (1 << 7))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), EncodeError> {
+        self.serialize_u16((v as u16) ^ (1 << 15))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), EncodeError> {
+        self.serialize_u32((v as u32) ^ (1 << 31))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), EncodeError> {
+        self.serialize_u64((v as u64) ^ (1 << 63))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), EncodeError> {
+        self.serialize_u128((v as u128) ^ (1 << 127))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), EncodeError> {
+        self.output.push(v);
+        Ok(())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), EncodeError> {
+        self.output.extend_from_slice(&v.to_be_bytes());
+        Ok(())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), EncodeError> {
+        self.output.extend_from_slice(&v.to_be_bytes());
+        Ok(())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), EncodeError> {
+        self.output.extend_from_slice(&v.to_be_bytes());
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), EncodeError> {
+        self.output.extend_from_slice(&v.to_be_bytes());
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), EncodeError> {
+        let bits = v.to_bits();
+        let sign = 1 << 31;
+        self.serialize_u32(if bits & sign == 0 { bits ^ sign } else { !bits })
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), EncodeError> {
+        let bits = v.to_bits();
+        let sign = 1 << 63;
+        self.serialize_u64(if bits & sign == 0 { bits ^ sign } else { !bits })
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), EncodeError> {
+        self.serialize_u32(v as u32)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), EncodeError> {
+        self.escaped(v.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), EncodeError> {
+        self.escaped(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), EncodeError> {
+        self.output.push(0);
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), EncodeError> {
+        self.output.push(1);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), EncodeError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        variant_index: u32,
+        _: &'static str,
+    ) -> Result<(), EncodeError> {
+        self.serialize_u32(variant_index)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        variant_index: u32,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        self.serialize_u32(variant_index)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self, EncodeError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self, EncodeError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self, EncodeError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        variant_index: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, EncodeError> {
+        self.serialize_u32(variant_index)?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self, EncodeError> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, EncodeError> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        variant_index: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, EncodeError> {
+        self.serialize_u32(variant_index)?;
+        Ok(self)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl ser::SerializeSeq for &mut Serializer {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.output.push(ELEMENT);
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        self.output.push(SEQ_END);
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for &mut Serializer {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), EncodeError> {
+        self.output.push(ELEMENT);
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        self.output.push(SEQ_END);
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for &mut Serializer {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for &mut Serializer {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for &mut Serializer {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for &mut Serializer {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for &mut Serializer {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+struct Deserializer<'de> {
+    input: &'de [u8],
+}
+
+impl<'de> Deserializer<'de> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        if self.input.len() < N {
+            return Err(DecodeError::UnexpectedEnd {
+                additional: N - self.input.len(),
+            });
+        }
+        let (taken, rest) = self.input.split_at(N);
+        self.input = rest;
+        Ok(taken.try_into().expect("split_at returns N bytes"))
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        self.take::<1>().map(|[byte]| byte)
+    }
+
+    fn escaped(&mut self) -> Result<Vec<u8>, DecodeError> {
+        let mut bytes = Vec::new();
+        loop {
+            match self.byte()? {
+                0 => match self.byte()? {
+                    0 => return Ok(bytes),
+                    ESCAPED_ZERO => bytes.push(0),
+                    other => {
+                        return Err(DecodeError::OtherString(format!(
+                            "zero byte in string followed by {other} instead of {ESCAPED_ZERO}"
+                        )))
+                    }
+                },
+                byte => bytes.push(byte),
+            }
+        }
+    }
+
+    /// Whether another element of a sequence or map follows
+    fn next_element(&mut self) -> Result<bool, DecodeError> {
+        match self.byte()? {
+            ELEMENT => Ok(true),
+            SEQ_END => Ok(false),
+            other => Err(DecodeError::OtherString(format!(
+                "expected an element or the end of a sequence, found {other}"
+            ))),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, _: V) -> Result<V::Value, DecodeError> {
+        Err(DecodeError::Other(
+            "keys can not be decoded without knowing their type",
+        ))
+    }
+
+    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        match self.byte()? {
+            0 => visitor.visit_bool(false),
+            1 => visitor.visit_bool(true),
+            other => Err(DecodeError::InvalidBooleanValue(other)),
+        }
+    }
+
+    fn deserialize_i8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        let [byte] = self.take()?;
+        visitor.visit_i8((byte ^ (1 << 7)) as i8)
+    }
+
+    fn deserialize_i16<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        let bits = u16::from_be_bytes(self.take()?);
+        visitor.visit_i16((bits ^ (1 << 15)) as i16)
+    }
+
+    fn deserialize_i32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        let bits = u32::from_be_bytes(self.take()?);
+        visitor.visit_i32((bits ^ (1 << 31)) as i32)
+    }
+
+    fn deserialize_i64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        let bits = u64::from_be_bytes(self.take()?);
+        visitor.visit_i64((bits ^ (1 << 63)) as i64)
+    }
+
+    fn deserialize_i128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        let bits = u128::from_be_bytes(self.take()?);
+        visitor.visit_i128((bits ^ (1 << 127)) as i128)
+    }
+
+    fn deserialize_u8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_u8(self.byte()?)
+    }
+
+    fn deserialize_u16<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_u16(u16::from_be_bytes(self.take()?))
+    }
+
+    fn deserialize_u32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_u32(u32::from_be_bytes(self.take()?))
+    }
+
+    fn deserialize_u64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_u64(u64::from_be_bytes(self.take()?))
+    }
+
+    fn deserialize_u128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_u128(u128::from_be_bytes(self.take()?))
+    }
+
+    fn deserialize_f32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        let bits = u32::from_be_bytes(self.take()?);
+        let sign = 1 << 31;
+        let bits = if bits & sign == 0 { !bits } else { bits ^ sign };
+        visitor.visit_f32(f32::from_bits(bits))
+    }
+
+    fn deserialize_f64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        let bits = u64::from_be_bytes(self.take()?);
+        let sign = 1 << 63;
+        let bits = if bits & sign == 0 { !bits } else { bits ^ sign };
+        visitor.visit_f64(f64::from_bits(bits))
+    }
+
+    fn deserialize_char<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        let bytes = self.take()?;
+        match char::from_u32(u32::from_be_bytes(bytes)) {
+            Some(c) => visitor.visit_char(c),
+            None => Err(DecodeError::InvalidCharEncoding(bytes)),
+        }
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        let bytes = self.escaped()?;
+        let string = String::from_utf8(bytes).map_err(|e| DecodeError::Utf8 {
+            inner: e.utf8_error(),
+        })?;
+        visitor.visit_string(string)
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        visitor.visit_byte_buf(self.escaped()?)
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        match self.byte()? {
+            0 => visitor.visit_none(),
+            1 => visitor.visit_some(self),
+            found => Err(DecodeError::UnexpectedVariant {
+                type_name: "Option",
+                allowed: &bincode::error::AllowedEnumVariants::Range { min: 0, max: 1 },
+                found: found.into(),
+            }),
+        }
+    }
+
+    fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_seq(Sequence { de: self })
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        visitor.visit_seq(Fixed { de: self, len })
+    }
+
+    fn deserialize_tuple_struct<V: de::Visitor<'de>>(
+        self,
+        _: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_map(Sequence { de: self })
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_identifier<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(self, _: V) -> Result<V::Value, DecodeError> {
+        Err(DecodeError::Other("keys can not skip values"))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// A sequence or map, its elements are preceded by [`ELEMENT`]
+struct Sequence<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'de> de::SeqAccess<'de> for Sequence<'_, 'de> {
+    type Error = DecodeError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DecodeError> {
+        if !self.de.next_element()? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+impl<'de> de::MapAccess<'de> for Sequence<'_, 'de> {
+    type Error = DecodeError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DecodeError> {
+        if !self.de.next_element()? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, DecodeError> {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+/// A tuple or struct, `len` elements follow each other
+struct Fixed<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    len: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for Fixed<'_, 'de> {
+    type Error = DecodeError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DecodeError> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+impl<'de> de::EnumAccess<'de> for &mut Deserializer<'de> {
+    type Error = DecodeError;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), DecodeError> {
+        let index = u32::from_be_bytes(self.take()?);
+        let variant = seed.deserialize(index.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for &mut Deserializer<'de> {
+    type Error = DecodeError;
+
+    fn unit_variant(self) -> Result<(), DecodeError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, DecodeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    /// Encodes the values, which must be sorted, and checks the encoded keys
+    /// are sorted too and decode to the same values.
+    fn assert_order_kept<T>(sorted: &[T])
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let encoded: Vec<_> = sorted.iter().map(|v| encode(v).unwrap()).collect();
+        for (pair, values) in encoded.windows(2).zip(sorted.windows(2)) {
+            assert!(
+                pair[0] < pair[1],
+                "{:?} sorts after {:?}",
+                values[0],
+                values[1]
+            );
+        }
+        for (bytes, value) in encoded.iter().zip(sorted) {
+            assert_eq!(&decode::<T>(bytes).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn signed_integers() {
+        assert_order_kept(&[i8::MIN, -1, 0, 1, i8::MAX]);
+        assert_order_kept(&[i32::MIN, -300, -1, 0, 1, 300, i32::MAX]);
+        assert_order_kept(&[i64::MIN, -1, 0, i64::MAX]);
+        assert_order_kept(&[i128::MIN, -1, 0, i128::MAX]);
+    }
+
+    #[test]
+    fn floats() {
+        assert_order_kept(&[f64::NEG_INFINITY, -2.5, -0.0, 0.0, 1e-9, 2.5, f64::INFINITY]);
+        assert_order_kept(&[f32::MIN, -1.0, 0.0, 1.0, f32::MAX]);
+    }
+
+    #[test]
+    fn strings() {
+        assert_order_kept(&[
+            String::new(),
+            "\0".to_owned(),
+            "\0\0".to_owned(),
+            "\u{1}".to_owned(),
+            "a".to_owned(),
+            "a\0".to_owned(),
+            "ab".to_owned(),
+            "b".to_owned(),
+            "é".to_owned(),
+        ]);
+    }
+
+    #[test]
+    fn sequences_and_tuples() {
+        assert_order_kept(&[vec![], vec![0u8], vec![0, 0], vec![1]]);
+        assert_order_kept(&[
+            ("a".to_owned(), -1i32),
+            ("a".to_owned(), 5),
+            ("ab".to_owned(), -7),
+            ("b".to_owned(), i32::MIN),
+        ]);
+        assert_order_kept(&[None, Some(-1i16), Some(3)]);
+    }
+
+    #[test]
+    fn enums_and_structs() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        enum Shape {
+            Dot,
+            Circle(i32),
+            Rect { w: u8, h: String },
+        }
+        assert_order_kept(&[
+            Shape::Dot,
+            Shape::Circle(-4),
+            Shape::Circle(2),
+            Shape::Rect {
+                w: 1,
+                h: "x".to_owned(),
+            },
+        ]);
+    }
+
+    #[test]
+    fn unsigned_integers_match_bincode() {
+        let config = bincode::config::standard()
+            .with_big_endian()
+            .with_fixed_int_encoding();
+        let key = (3u8, 70_000u64, true);
+        let bincode = bincode::serde::encode_to_vec(key, config).unwrap();
+        assert_eq!(encode(&key).unwrap(), bincode);
+        assert_eq!(decode::<u8>(&bincode).unwrap(), 3);
+    }
+}
@@ -51,6 +51,12 @@
 //!| Name                                    | advantage | attribute option |
 //!|-----------------------------------------|-----------|------------------|
 //!| [Sled](https://crates.io/crates/sled)   | pure Rust | `db=sled`        |
+//!| [RocksDB](https://rocksdb.org)          | mature, needs the `rocksdb` feature | `db=rocksdb` |
+//!| [redb](https://crates.io/crates/redb)   | pure Rust, stable file format, needs the `redb` feature | `db=redb` |
+//!| [SQLite](https://sqlite.org)            | inspect with standard tools, needs the `sqlite` feature | `db=sqlite` |
+//!| [LMDB](http://www.lmdb.tech/doc/)       | fast reads without copying, needs the `lmdb` feature | `db=lmdb` |
+//!| [fjall](https://crates.io/crates/fjall) | pure Rust LSM-tree, fast writes, needs the `fjall` feature | `db=fjall` |
+//!| [log file](stores::File)                | no dependencies, keeps all keys in memory | `db=file` |
 //!
 //! ## How it works
 //! dbstruct replaces the *fields* in your struct *with methods*. Each method returns a [`wrapper`]
@@ -71,17 +77,143 @@
 //! how to handle missing values (see above)
 //!
 //! See [`wrapper`] for a complete list.
+//!
+//! ##### Keys
+//! Every field is stored under its own prefix in the database. By default
+//! these are handed out by sorting the fields by name. Adding or renaming a
+//! field can therefore change the prefix of other fields and make existing
+//! data unreadable. Pin the prefix of a field using `#[dbstruct(key = N)]`,
+//! where N is a number between 0 and 254. Fields without a pinned key get the
+//! lowest prefix not pinned by another field. You can combine the key with
+//! other options: `#[dbstruct(Default, key = 3)]`.
+//!
+//! The keys, wrappers and types of all fields are stored in the database
+//! when it is first opened. Opening the database again with a struct that
+//! does not match returns [`Error::SchemaMismatch`]. Adding fields is fine,
+//! see [`schema`] for the details.
+//!
+//! ##### Migrations
+//! To change the struct in other ways set a version and provide a function for
+//! each version that migrates the data: `#[dbstruct(db=sled, version = 2,
+//! migrations = [to_v1, to_v2])]`. The constructor runs the migrations the
+//! database still needs. See [`migrate`] for an example.
+//!
+//!```rust
+//!#[dbstruct::dbstruct(db=btreemap)]
+//!pub struct Test {
+//!    #[dbstruct(key = 0)]
+//!    name: Option<String>,
+//!    #[dbstruct(Default, key = 1)]
+//!    visits: u32,
+//!}
+//!```
+//!
+//! ##### Transactions
+//! Structs using `db=sled`, `db=rocksdb`, `db=redb`, `db=sqlite`, `db=lmdb`,
+//! `db=fjall` or `db=btreemap` get a `transaction` method. It runs a closure
+//! that changes multiple fields, either all changes are applied or none are.
+//! Return an error, for example [`Error::TransactionAborted`], from the
+//! closure to abort. Sled and RocksDB may run the closure multiple times if it
+//! conflicts with another transaction. The lengths of `Vec` and `VecDeque`
+//! fields, and for btreemap, redb, sqlite, lmdb and fjall the whole store, are
+//! locked during the transaction. Using the struct itself inside the closure
+//! deadlocks.
+//! Only inside a btreemap transaction can the wrappers iterate. With other
+//! backends methods that need to walk over the entries, such as `iter`,
+//! `is_empty` and `clear` on a `HashMap` field, are not available on the
+//! transaction. Sled does not support iterating inside a transaction at all.
+//!
+//!```rust
+//!#[dbstruct::dbstruct(db=btreemap)]
+//!pub struct Test {
+//!    queue: VecDeque<u32>,
+//!    done: HashMap<u32, bool>,
+//!}
+//!
+//!# fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!let db = Test::new()?;
+//!db.queue().push_back(&42)?;
+//!db.transaction(|tx| {
+//!    if let Some(job) = tx.queue().pop_front()? {
+//!        tx.done().insert(&job, &true)?;
+//!    }
+//!    Ok(())
+//!})?;
+//!assert_eq!(db.done().get(&42)?, Some(true));
+//!# Ok(())
+//!# }
+//!```
+//!
+//! ##### Async
+//! With the `async` feature enabled the `async` option generates a struct
+//! whose constructors and wrapper methods are async. Sync backends are run on
+//! tokio's blocking thread pool using [`stores::Blocking`]. With `db=trait`
+//! the backend must implement [`AsyncDataStore`](traits::AsyncDataStore),
+//! usually through [`AsyncByteStore`](traits::AsyncByteStore). Async structs
+//! do not support migrations and have no `transaction` method. See the
+//! [async wrappers](wrapper::asynchronous) for an example.
+//!
+//! ##### Encryption
+//! With the `encryption` feature enabled the `encrypt` option encrypts every
+//! value before it is written to the database using [`stores::Encrypted`].
+//! The constructors then take an extra [`EncryptionKey`](stores::EncryptionKey)
+//! argument, for example `Test::open_path(path, &key)`. Opening a database
+//! using the wrong key fails with a decrypt error. The keys stay readable.
+//! With `db=trait` wrap the store in [`Encrypted`](stores::Encrypted)
+//! yourself instead.
+//!
+//! ##### Compression
+//! With the `compression` feature enabled `compress = "zstd"` compresses
+//! large values before they are written using [`stores::Compressed`]. This
+//! builds the C zstd library, which needs a C compiler. Values
+//! written before compression was enabled can still be read. Combined with
+//! `encrypt` values are compressed before they are encrypted. To compress
+//! only some fields put the option on them instead, for example
+//! `#[dbstruct(Default, compress = "zstd")]`. The wrappers of those fields
+//! return [`CompressedError`](stores::CompressedError) for database errors,
+//! convert them using [`Error::from_compressed`] to return them from a
+//! transaction.
+//!
+//! ##### Cache
+//! With the `cache` feature enabled `cache = 1024` keeps up to 1024 recently
+//! read values in memory using [`stores::Cached`]. Reading a cached value
+//! does not touch the database. Changes made through the struct keep the
+//! cache up to date, do not change the database in another way while the
+//! struct is open.
+//!
+//! ##### Read only
+//! The `readonly` option generates a struct whose wrappers come from
+//! [`wrapper::read_only`], they have no methods that write. Writing to it
+//! therefore does not compile. The database is also wrapped in
+//! [`stores::ReadOnly`] which refuses any write that might slip through.
+//! A read only struct has no `transaction` method and can not be async. The
+//! database must have been opened by a struct that can write first, opening
+//! an empty database fails as the layout can not be stored.
 
 use core::fmt;
 
 #[doc(hidden)]
 pub use dbstruct_derive::*;
 
+mod key;
+
+pub mod migrate;
+pub mod schema;
 pub mod stores;
 pub mod traits;
 pub use traits::{ByteStore, DataStore};
 pub mod wrapper;
 
+#[cfg(feature = "fjall")]
+pub use fjall;
+#[cfg(feature = "lmdb")]
+pub use heed;
+#[cfg(feature = "redb")]
+pub use redb;
+#[cfg(feature = "rocksdb")]
+pub use rocksdb;
+#[cfg(feature = "sqlite")]
+pub use rusqlite;
 pub use sled;
 
 /// An Error type encapsulating various issues that may come up during
@@ -98,6 +230,31 @@ pub enum Error<DbError: fmt::Debug> {
     SerializingKey(bincode::error::EncodeError),
     #[error("the database returned an error")]
     Database(DbError),
+    #[error("the struct does not match the data in the database: {0}")]
+    SchemaMismatch(schema::Mismatch),
+    #[error("the database was written by version {stored} of the struct, which is newer than this version ({compiled})")]
+    NewerVersion { stored: u32, compiled: u32 },
+    #[error("the transaction was aborted")]
+    TransactionAborted,
+    #[error("element {index} of a list is missing from the database")]
+    MissingElement { index: usize },
+}
+
+impl<DbError: fmt::Debug> Error<DbError> {
+    /// Converts the error returned by the database using `op`
+    pub fn map_database<E: fmt::Debug>(self, op: impl FnOnce(DbError) -> E) -> Error<E> {
+        match self {
+            Error::DeSerializingVal(e) => Error::DeSerializingVal(e),
+            Error::DeSerializingKey(e) => Error::DeSerializingKey(e),
+            Error::SerializingValue(e) => Error::SerializingValue(e),
+            Error::SerializingKey(e) => Error::SerializingKey(e),
+            Error::Database(e) => Error::Database(op(e)),
+            Error::SchemaMismatch(e) => Error::SchemaMismatch(e),
+            Error::NewerVersion { stored, compiled } => Error::NewerVersion { stored, compiled },
+            Error::TransactionAborted => Error::TransactionAborted,
+            Error::MissingElement { index } => Error::MissingElement { index },
+        }
+    }
 }
 
 #[doc = include_str!("../Readme.md")]
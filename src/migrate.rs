@@ -0,0 +1,318 @@
+//! Migrate the data in a database to a new version of the struct.
+//!
+//! Set the version of the struct and list one migration function per version
+//! using `#[dbstruct::dbstruct(db=sled, version = 2, migrations = [to_v1, to_v2])]`.
+//! The first function brings the database from version 0 to version 1, the
+//! second from 1 to 2 and so on. A database that has never stored a version
+//! is at version 0.
+//!
+//! The constructor of the struct runs the migrations the database still needs
+//! and records the version after each one. Once done it replaces the stored
+//! [`Schema`](crate::schema::Schema) with that of the struct. A database with
+//! a newer version than the struct is refused with [`Error::NewerVersion`].
+//!
+//! Migration functions get a [`Migration`] which gives access to the raw
+//! bytes in the database. Every field is stored under its own prefix, the
+//! first byte of all its keys. You can find the prefixes by pinning them using
+//! `#[dbstruct(key = N)]` or by looking at the stored
+//! [`Schema`](crate::schema::Schema).
+//!
+//! A migration is applied as a whole or not at all. Changes made by a
+//! migration function are collected in memory. Once it returns they are
+//! written, together with the new version, as a single journal entry. Only
+//! then are they applied to the data. Should that get interrupted the journal
+//! is applied again the next time the database is opened. Every migration
+//! must therefore fit in memory.
+//!
+//! # Example
+//! ```
+//! use dbstruct::migrate::Migration;
+//! use dbstruct::traits::byte_store;
+//!
+//! /// The name used to be stored as a number
+//! fn to_v1<DS: byte_store::Ordered>(
+//!     migration: &Migration<DS>,
+//! ) -> Result<(), dbstruct::Error<DS::DbError>> {
+//!     migration.transform_values(0, |id: u32| format!("user {id}"))
+//! }
+//!
+//! #[dbstruct::dbstruct(db=trait, version = 1, migrations = [to_v1])]
+//! struct Test {
+//!     #[dbstruct(key = 0)]
+//!     name: Option<String>,
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let ds = dbstruct::stores::BTreeMap::new();
+//! let db = Test::open(ds)?;
+//! db.name().set(Some("Daneel"))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use core::fmt;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::key;
+use crate::schema::{KeyedField, Meta, Schema, META_PREFIX};
+use crate::traits::byte_store::{self, val_config};
+use crate::traits::{ByteStore, DataStore};
+use crate::Error;
+
+/// A migration function, brings the database from one version to the next.
+pub type Step<DS> = fn(&Migration<'_, DS>) -> Result<(), Error<<DS as ByteStore>::DbError>>;
+
+/// Changes made by a migration, `None` marks a removed key
+type Changes = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+/// Access to the database while migrating, see the [module][self]
+/// documentation.
+pub struct Migration<'a, DS> {
+    ds: &'a DS,
+    changes: RefCell<Changes>,
+}
+
+impl<'a, E, DS> Migration<'a, DS>
+where
+    E: fmt::Debug,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    /// The value stored under `key`, including changes made earlier in this
+    /// migration.
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error<E>> {
+        if let Some(change) = self.changes.borrow().get(key) {
+            return Ok(change.clone());
+        }
+        let val = self.ds.get(key).map_err(Error::Database)?;
+        Ok(val.map(|val| val.as_ref().to_vec()))
+    }
+
+    /// Stores `val` under `key` once the migration completes
+    pub fn insert(&self, key: &[u8], val: &[u8]) {
+        self.changes
+            .borrow_mut()
+            .insert(key.to_vec(), Some(val.to_vec()));
+    }
+
+    /// Removes `key` once the migration completes
+    pub fn remove(&self, key: &[u8]) {
+        self.changes.borrow_mut().insert(key.to_vec(), None);
+    }
+
+    /// All keys and values stored under `prefix`, in order. Includes changes
+    /// made earlier in this migration.
+    #[allow(clippy::type_complexity)]
+    pub fn entries(&self, prefix: u8) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error<E>> {
+        let mut entries = BTreeMap::new();
+        // a field that is not a collection is stored under just its prefix
+        let mut cursor = vec![prefix];
+        if let Some(val) = self.ds.get(&cursor).map_err(Error::Database)? {
+            entries.insert(cursor.clone(), val.as_ref().to_vec());
+        }
+        while let Some((key, val)) = self.ds.get_gt(&cursor).map_err(Error::Database)? {
+            if key.as_ref().first() != Some(&prefix) {
+                break;
+            }
+            cursor = key.as_ref().to_vec();
+            entries.insert(cursor.clone(), val.as_ref().to_vec());
+        }
+
+        let changes = self.changes.borrow();
+        let changed = changes
+            .range(vec![prefix]..)
+            .take_while(|(key, _)| key.first() == Some(&prefix));
+        for (key, change) in changed {
+            match change {
+                Some(val) => entries.insert(key.clone(), val.clone()),
+                None => entries.remove(key),
+            };
+        }
+        Ok(entries.into_iter().collect())
+    }
+
+    /// Moves everything stored under prefix `from` to prefix `to`. Use this
+    /// when the key of a field changed.
+    pub fn move_prefix(&self, from: u8, to: u8) -> Result<(), Error<E>> {
+        for (key, val) in self.entries(from)? {
+            let mut new_key = key.clone();
+            new_key[0] = to;
+            self.remove(&key);
+            self.insert(&new_key, &val);
+        }
+        Ok(())
+    }
+
+    /// Removes everything stored under `prefix`. Use this when a field is
+    /// removed.
+    pub fn drop_prefix(&self, prefix: u8) -> Result<(), Error<E>> {
+        for (key, _) in self.entries(prefix)? {
+            self.remove(&key);
+        }
+        Ok(())
+    }
+
+    /// Replaces every value stored under `prefix` by the result of `op`. Use
+    /// this when the type of a field changed. For a `Map` or `Vec` this
+    /// changes the type of the items.
+    pub fn transform_values<Old, New>(
+        &self,
+        prefix: u8,
+        mut op: impl FnMut(Old) -> New,
+    ) -> Result<(), Error<E>>
+    where
+        Old: DeserializeOwned,
+        New: Serialize,
+    {
+        for (key, val) in self.entries(prefix)? {
+            let (old, _) = bincode::serde::decode_from_slice(&val, val_config())
+                .map_err(Error::DeSerializingVal)?;
+            let new = bincode::serde::encode_to_vec(op(old), val_config())
+                .map_err(Error::SerializingValue)?;
+            self.insert(&key, &new);
+        }
+        Ok(())
+    }
+}
+
+/// The changes of a completed migration that still need to be applied
+#[derive(Serialize, Deserialize)]
+struct Journal {
+    version: u32,
+    /// Set by the last migration
+    schema: Option<Schema>,
+    changes: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl Journal {
+    /// Applies the changes then records the new version and removes the
+    /// journal. Applying a journal twice gives the same result.
+    fn apply<DS: ByteStore>(&self, ds: &DS) -> Result<(), Error<<DS as ByteStore>::DbError>> {
+        for (key, change) in &self.changes {
+            match change {
+                Some(val) => ByteStore::insert(ds, key, val).map_err(Error::Database)?,
+                None => ByteStore::remove(ds, key).map_err(Error::Database)?,
+            };
+        }
+        if let Some(schema) = &self.schema {
+            schema.store(ds)?;
+        }
+        set_version(ds, self.version)?;
+        ds.clear(&(META_PREFIX, Meta::Journal))
+    }
+}
+
+/// The version of the struct that last wrote to the database.
+pub(crate) fn stored_version<DS: DataStore>(ds: &DS) -> Result<u32, Error<DS::DbError>> {
+    Ok(ds.get(&(META_PREFIX, Meta::Version))?.unwrap_or(0))
+}
+
+fn set_version<DS: DataStore>(ds: &DS, version: u32) -> Result<(), Error<DS::DbError>> {
+    ds.insert::<_, _, u32>(&(META_PREFIX, Meta::Version), &version)?;
+    Ok(())
+}
+
+/// Runs the migrations the database still needs then stores or checks the
+/// schema. The version of the struct is the number of migrations.
+#[doc(hidden)]
+pub fn run<DS>(
+    ds: &DS,
+    schema: &Schema,
+    steps: &[Step<DS>],
+) -> Result<(), Error<<DS as DataStore>::DbError>>
+where
+    DS: byte_store::Ordered,
+{
+    finish_interrupted(ds)?;
+
+    let compiled = steps.len() as u32;
+    let stored = stored_version(ds)?;
+    if stored >= compiled {
+        return schema.check(ds, compiled);
+    }
+
+    for (version, step) in (1..).zip(steps).skip(stored as usize) {
+        let migration = Migration {
+            ds,
+            changes: RefCell::new(Changes::new()),
+        };
+        step(&migration)?;
+        let schema = if version == compiled {
+            let stored = DataStore::get(ds, &(META_PREFIX, Meta::Schema))?.unwrap_or_default();
+            Some(schema.migrated(&stored))
+        } else {
+            None
+        };
+        let journal = Journal {
+            version,
+            schema,
+            changes: migration.changes.into_inner().into_iter().collect(),
+        };
+        DataStore::insert::<_, _, Journal>(ds, &(META_PREFIX, Meta::Journal), &journal)?;
+        journal.apply(ds)?;
+    }
+    Ok(())
+}
+
+/// Applies the journal of a migration or key upgrade that got interrupted
+fn finish_interrupted<DS: ByteStore>(ds: &DS) -> Result<(), Error<<DS as ByteStore>::DbError>> {
+    if let Some(journal) = DataStore::get::<_, Journal>(ds, &(META_PREFIX, Meta::Journal))? {
+        journal.apply(ds)?;
+    }
+    Ok(())
+}
+
+/// Re-encodes the keys of `fields` if the database was written by version 0.6
+/// or earlier, then records the current key format.
+pub(crate) fn upgrade_keys<DS>(
+    ds: &DS,
+    fields: &[KeyedField],
+) -> Result<(), Error<<DS as ByteStore>::DbError>>
+where
+    DS: byte_store::Ordered,
+{
+    finish_interrupted(ds)?;
+
+    let format_key = (META_PREFIX, Meta::KeyFormat);
+    if DataStore::contains(ds, &format_key)? {
+        return Ok(());
+    }
+
+    let migration = Migration {
+        ds,
+        changes: RefCell::new(Changes::new()),
+    };
+    let mut upgraded = Vec::new();
+    for field in fields {
+        for (old, val) in migration.entries(field.prefix)? {
+            let new = (field.upgrade)(&old).map_err(|e| e.map_database(|never| match never {}))?;
+            if new != old {
+                migration.remove(&old);
+                upgraded.push((new, val));
+            }
+        }
+    }
+    if upgraded.is_empty() {
+        DataStore::insert::<_, _, u8>(ds, &format_key, &key::FORMAT)?;
+        return Ok(());
+    }
+
+    // inserted after all removes, a new key can equal an old one
+    for (new, val) in upgraded {
+        migration.insert(&new, &val);
+    }
+    let format = bincode::serde::encode_to_vec(key::FORMAT, val_config())
+        .map_err(Error::SerializingValue)?;
+    let format_key = key::encode(&format_key).map_err(Error::SerializingKey)?;
+    migration.insert(&format_key, &format);
+    let journal = Journal {
+        version: stored_version(ds)?,
+        schema: None,
+        changes: migration.changes.into_inner().into_iter().collect(),
+    };
+    DataStore::insert::<_, _, Journal>(ds, &(META_PREFIX, Meta::Journal), &journal)?;
+    journal.apply(ds)
+}
@@ -0,0 +1,330 @@
+//! The layout of a dbstruct: which field is stored under which key, using
+//! which wrapper and type.
+//!
+//! The generated constructors store the layout in the database the first time
+//! it is opened. On later opens the stored layout is compared with the one of
+//! the struct. Changes that would make existing data unreadable are reported
+//! as a [`Mismatch`] instead of surfacing as deserialization errors later on.
+//!
+//! Adding fields is always allowed. Removing fields is allowed too, the removed
+//! field keeps its entry in the stored layout so its key is not silently
+//! reused by a field with another type. For any other change write a
+//! [migration](crate::migrate).
+//!
+//! Next to the layout the database records how keys are encoded. Version 0.6
+//! and earlier encoded them using bincode, which does not keep the order of
+//! signed numbers, floats or strings. The first time such a database is opened
+//! the keys of its `Map`, `OrderedMap` and `Set` fields are re-encoded. This
+//! is applied as a whole or not at all, just like a migration.
+
+use core::fmt;
+use std::convert::Infallible;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::key;
+use crate::migrate;
+#[cfg(feature = "async")]
+use crate::traits::AsyncDataStore;
+use crate::traits::DataStore;
+use crate::Error;
+
+/// Fields can not use this prefix, it is reserved for data dbstruct itself
+/// needs such as the [`Schema`].
+pub(crate) const META_PREFIX: u8 = u8::MAX;
+
+/// Keys under the [`META_PREFIX`]
+#[derive(Serialize)]
+pub(crate) enum Meta {
+    Schema,
+    Version,
+    /// The number of entries in the counted map under this prefix
+    Count(u8),
+    /// A migration that still needs to be applied
+    Journal,
+    /// The version of the key encoding, see [`key::FORMAT`]
+    KeyFormat,
+}
+
+/// A field whose keys need to be re-encoded when the database was written by
+/// version 0.6 or earlier.
+#[doc(hidden)]
+#[allow(clippy::type_complexity)]
+pub struct KeyedField {
+    pub prefix: u8,
+    /// Turns an old key into one using the current encoding
+    pub upgrade: fn(&[u8]) -> Result<Vec<u8>, Error<Infallible>>,
+}
+
+/// Re-encodes a key of a `Map`, `OrderedMap` or `Set` with keys of type `K`
+#[doc(hidden)]
+pub fn upgrade_key<K: Serialize + DeserializeOwned>(
+    old: &[u8],
+) -> Result<Vec<u8>, Error<Infallible>> {
+    let (prefix, key): (u8, K) = key::decode_bincode(old).map_err(Error::DeSerializingKey)?;
+    key::encode(&(prefix, key)).map_err(Error::SerializingKey)
+}
+
+/// The wrapper a field is accessed through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Wrapper {
+    Vec,
+    VecDeque,
+    Map,
+    OptionValue,
+    DefaultTrait,
+    DefaultValue,
+    Set,
+    OrderedMap,
+}
+
+/// A single field of a dbstruct
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Field {
+    pub name: String,
+    /// The prefix all database keys for this field start with
+    pub key: u8,
+    pub wrapper: Wrapper,
+    /// The type as written in the struct definition. For `Vec`, `VecDeque`,
+    /// `Set` and `Option` this is the type of the items, for `Map` and
+    /// `OrderedMap` the types of the key and the value. Module paths are left
+    /// out so `std::string::String` and `String` match. Type aliases are
+    /// compared by their name, not by the type they stand for.
+    pub ty: String,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` (key: {}, wrapper: {:?}, type: {})",
+            self.name, self.key, self.wrapper, self.ty
+        )
+    }
+}
+
+/// The layout of a dbstruct, see the [module][self] documentation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schema {
+    pub fields: Vec<Field>,
+}
+
+/// A difference between the stored and compiled [`Schema`] that makes the
+/// existing data unreadable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// The key is now used by a field with another name, wrapper or type
+    Changed { stored: Field, compiled: Field },
+    /// The field is now stored under another key
+    Moved { stored: Field, compiled: Field },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Changed { stored, compiled } => {
+                write!(f, "key {} was {stored} now it is {compiled}", stored.key)
+            }
+            Difference::Moved { stored, compiled } => {
+                write!(
+                    f,
+                    "field `{}` moved from key {} to key {}",
+                    stored.name, stored.key, compiled.key
+                )
+            }
+        }
+    }
+}
+
+/// The struct does not match the data in the database
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub struct Mismatch(pub Vec<Difference>);
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let differences: Vec<_> = self.0.iter().map(ToString::to_string).collect();
+        f.write_str(&differences.join(", "))
+    }
+}
+
+impl Schema {
+    /// Compares self, the compiled schema, against the `stored` schema. If
+    /// they are compatible returns the schema that should be stored.
+    pub fn merge(&self, stored: &Schema) -> Result<Schema, Mismatch> {
+        let mut differences = Vec::new();
+        let mut merged = stored.clone();
+
+        for compiled in &self.fields {
+            let same_key = stored.fields.iter().find(|f| f.key == compiled.key);
+            let same_name = stored.fields.iter().find(|f| f.name == compiled.name);
+            match (same_key, same_name) {
+                (Some(stored), _) if stored != compiled => differences.push(Difference::Changed {
+                    stored: stored.clone(),
+                    compiled: compiled.clone(),
+                }),
+                (Some(_), _) => (),
+                (None, Some(stored)) => differences.push(Difference::Moved {
+                    stored: stored.clone(),
+                    compiled: compiled.clone(),
+                }),
+                (None, None) => merged.fields.push(compiled.clone()),
+            }
+        }
+
+        if differences.is_empty() {
+            merged.fields.sort_by_key(|f| f.key);
+            Ok(merged)
+        } else {
+            Err(Mismatch(differences))
+        }
+    }
+
+    /// Stores the schema if the database has none yet. Otherwise checks if
+    /// the stored schema matches and stores any new fields. Fails if the
+    /// database was written by a newer version of the struct.
+    #[doc(hidden)]
+    pub fn check<DS: DataStore>(&self, ds: &DS, version: u32) -> Result<(), Error<DS::DbError>> {
+        let stored_version = migrate::stored_version(ds)?;
+        let key = (META_PREFIX, Meta::Schema);
+        let stored: Schema = ds.get(&key)?.unwrap_or_default();
+        if let Some(merged) = self.to_store(stored, stored_version, version)? {
+            ds.insert::<_, _, Schema>(&key, &merged)?;
+        }
+        Ok(())
+    }
+
+    /// The async version of [`check`](Self::check)
+    #[cfg(feature = "async")]
+    #[doc(hidden)]
+    pub async fn check_async<DS: AsyncDataStore>(
+        &self,
+        ds: &DS,
+        version: u32,
+    ) -> Result<(), Error<DS::DbError>> {
+        let stored_version = ds.get(&(META_PREFIX, Meta::Version)).await?.unwrap_or(0);
+        let key = (META_PREFIX, Meta::Schema);
+        let stored: Schema = ds.get(&key).await?.unwrap_or_default();
+        if let Some(merged) = self.to_store(stored, stored_version, version)? {
+            ds.insert::<_, _, Schema>(&key, &merged).await?;
+        }
+        Ok(())
+    }
+
+    /// The schema to store if it changed
+    fn to_store<E: fmt::Debug>(
+        &self,
+        stored: Schema,
+        stored_version: u32,
+        version: u32,
+    ) -> Result<Option<Schema>, Error<E>> {
+        if stored_version > version {
+            return Err(Error::NewerVersion {
+                stored: stored_version,
+                compiled: version,
+            });
+        }
+
+        let merged = self.merge(&stored).map_err(Error::SchemaMismatch)?;
+        Ok((merged != stored).then_some(merged))
+    }
+
+    /// The schema to store after migrating from `stored`. Fields of the
+    /// struct replace those stored under the same key or name. Removed fields
+    /// are kept so their key is not silently reused.
+    pub(crate) fn migrated(&self, stored: &Schema) -> Schema {
+        let removed = stored.fields.iter().filter(|stored| {
+            !self
+                .fields
+                .iter()
+                .any(|f| f.key == stored.key || f.name == stored.name)
+        });
+        let mut fields: Vec<_> = self.fields.iter().chain(removed).cloned().collect();
+        fields.sort_by_key(|f| f.key);
+        Schema { fields }
+    }
+
+    /// Replaces the stored schema, used after migrating.
+    pub(crate) fn store<DS: DataStore>(&self, ds: &DS) -> Result<(), Error<DS::DbError>> {
+        ds.insert::<_, _, Schema>(&(META_PREFIX, Meta::Schema), self)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, key: u8, ty: &str) -> Field {
+        Field {
+            name: name.to_owned(),
+            key,
+            wrapper: Wrapper::OptionValue,
+            ty: ty.to_owned(),
+        }
+    }
+
+    fn schema(fields: &[Field]) -> Schema {
+        Schema {
+            fields: fields.to_vec(),
+        }
+    }
+
+    #[test]
+    fn added_field_is_merged() {
+        let stored = schema(&[field("a", 0, "u8")]);
+        let compiled = schema(&[field("b", 1, "u8"), field("a", 0, "u8")]);
+        let merged = compiled.merge(&stored).unwrap();
+        assert_eq!(merged, schema(&[field("a", 0, "u8"), field("b", 1, "u8")]));
+    }
+
+    #[test]
+    fn removed_field_is_kept() {
+        let stored = schema(&[field("a", 0, "u8"), field("b", 1, "u8")]);
+        let compiled = schema(&[field("a", 0, "u8")]);
+        assert_eq!(compiled.merge(&stored).unwrap(), stored);
+    }
+
+    #[test]
+    fn changed_type() {
+        let stored = schema(&[field("a", 0, "u8")]);
+        let compiled = schema(&[field("a", 0, "u16")]);
+        let Mismatch(differences) = compiled.merge(&stored).unwrap_err();
+        assert!(matches!(differences[..], [Difference::Changed { .. }]));
+    }
+
+    #[test]
+    fn key_reused_by_new_field() {
+        let stored = schema(&[field("a", 0, "u8")]);
+        let compiled = schema(&[field("b", 0, "u8")]);
+        let Mismatch(differences) = compiled.merge(&stored).unwrap_err();
+        assert!(matches!(differences[..], [Difference::Changed { .. }]));
+    }
+
+    #[test]
+    fn migrated_keeps_removed_fields() {
+        let stored = schema(&[
+            field("a", 0, "u8"),
+            field("b", 1, "u8"),
+            field("c", 2, "u8"),
+        ]);
+        let compiled = schema(&[field("a", 0, "String"), field("b", 3, "u8")]);
+        let migrated = compiled.migrated(&stored);
+        assert_eq!(
+            migrated,
+            schema(&[
+                field("a", 0, "String"),
+                field("c", 2, "u8"),
+                field("b", 3, "u8")
+            ])
+        );
+    }
+
+    #[test]
+    fn moved_field() {
+        let stored = schema(&[field("a", 0, "u8")]);
+        let compiled = schema(&[field("a", 1, "u8")]);
+        let Mismatch(differences) = compiled.merge(&stored).unwrap_err();
+        assert!(matches!(differences[..], [Difference::Moved { .. }]));
+    }
+}
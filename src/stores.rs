@@ -1,18 +1,78 @@
 //! provides a non persistent store for testing
 
+use std::ops::Bound;
+
+#[cfg(feature = "fjall")]
+mod fjall;
+#[cfg(feature = "lmdb")]
+mod lmdb;
+#[cfg(feature = "redb")]
+mod redb;
 #[cfg(feature = "rocksdb")]
 mod rocksdb;
 #[cfg(feature = "sled")]
 mod sled;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 
+#[cfg(feature = "async")]
+mod blocking;
 mod btreemap;
+#[cfg(feature = "cache")]
+mod cached;
+#[cfg(feature = "compression")]
+mod compressed;
+#[cfg(feature = "encryption")]
+mod encrypted;
+mod faulty;
+mod file;
 mod hashmap;
+mod read_only;
 
+#[cfg(feature = "fjall")]
+pub use self::fjall::{Error as FjallError, Fjall, FjallTx, RangeIter as FjallRangeIter};
+#[cfg(feature = "redb")]
+pub use self::redb::{Error as RedbError, RangeIter as RedbRangeIter, Redb, RedbTx};
+#[cfg(feature = "rocksdb")]
+pub use self::rocksdb::{Error as RocksDbError, RangeIter as RocksDbRangeIter, RocksDb, RocksDbTx};
+#[cfg(feature = "lmdb")]
+pub use lmdb::{Error as LmdbError, Lmdb, LmdbBytes, LmdbTx, RangeIter as LmdbRangeIter};
+#[cfg(feature = "sled")]
+pub use sled::SledTx;
+#[cfg(feature = "sqlite")]
+pub use sqlite::{Error as SqliteError, RangeIter as SqliteRangeIter, Sqlite, SqliteTx};
+
+#[cfg(feature = "async")]
+pub use blocking::Blocking;
 pub use btreemap::BTreeMap;
+pub use btreemap::BTreeMapTx;
 pub use btreemap::Error as BTreeMapError;
+#[cfg(feature = "cache")]
+pub use cached::{Cached, RangeIter as CachedRangeIter};
+#[cfg(feature = "compression")]
+pub use compressed::{Compressed, Error as CompressedError, RangeIter as CompressedRangeIter};
+#[cfg(feature = "encryption")]
+pub use encrypted::{
+    Encrypted, EncryptedKeys, EncryptionKey, Error as EncryptedError, KeyMode, PlainKeys,
+    RangeIter as EncryptedRangeIter,
+};
+pub use faulty::{Error as FaultyError, Faulty, RangeIter as FaultyRangeIter};
+pub use file::{Error as FileError, File, RangeIter as FileRangeIter};
 pub use hashmap::Error as HashMapError;
 #[deprecated(since = "0.3.0", note = "Use BTreeMap test backend instead")]
 pub use hashmap::HashMap;
+pub use read_only::{Error as ReadOnlyError, RangeIter as ReadOnlyRangeIter, ReadOnly};
 // interesting discussion about key value db alternatives to sled:
 // https://gitlab.com/famedly/conduit/-/issues/74
 // one interesting one is heed (wraps LMDB)
+
+/// True if no key can lie in the range, for example because its start comes
+/// after its end. The std `BTreeMap::range` panics on some of these.
+pub(crate) fn is_empty_range<K: Ord + ?Sized>(start: Bound<&K>, end: Bound<&K>) -> bool {
+    match (start, end) {
+        (Bound::Included(start), Bound::Included(end)) => start > end,
+        (Bound::Included(start) | Bound::Excluded(start), Bound::Excluded(end))
+        | (Bound::Excluded(start), Bound::Included(end)) => start >= end,
+        _ => false,
+    }
+}
@@ -0,0 +1,109 @@
+use std::future::Future;
+
+use crate::traits::{async_byte_store, byte_store, ByteStore};
+
+/// Use a blocking [`ByteStore`] from async code. Every operation runs on the
+/// blocking thread pool of tokio using
+/// [`spawn_blocking`](tokio::task::spawn_blocking), keeping the executor free.
+///
+/// This is what structs using `#[dbstruct(async)]` with a blocking database
+/// such as `db=sled` use.
+#[derive(Debug, Clone)]
+pub struct Blocking<S>(S);
+
+impl<S> Blocking<S> {
+    pub fn new(store: S) -> Self {
+        Self(store)
+    }
+
+    /// The wrapped blocking store
+    pub fn inner(&self) -> &S {
+        &self.0
+    }
+}
+
+impl<S> Blocking<S>
+where
+    S: Clone + Send + 'static,
+{
+    /// Runs `op` on a clone of the store on the blocking thread pool. A panic
+    /// in `op` is resumed here.
+    fn run<R>(&self, op: impl FnOnce(S) -> R + Send + 'static) -> impl Future<Output = R> + Send
+    where
+        R: Send + 'static,
+    {
+        let store = self.0.clone();
+        let task = tokio::task::spawn_blocking(move || op(store));
+        async move {
+            match task.await {
+                Ok(res) => res,
+                Err(e) => std::panic::resume_unwind(e.into_panic()),
+            }
+        }
+    }
+}
+
+fn to_vec(bytes: impl AsRef<[u8]>) -> Vec<u8> {
+    bytes.as_ref().to_vec()
+}
+
+impl<S> async_byte_store::AsyncByteStore for Blocking<S>
+where
+    S: ByteStore + Clone + Send + Sync + 'static,
+    S::DbError: Send + 'static,
+{
+    type DbError = S::DbError;
+
+    async fn get(&self, key: Vec<u8>) -> Result<Option<Vec<u8>>, Self::DbError> {
+        self.run(move |s| Ok(s.get(&key)?.map(to_vec))).await
+    }
+
+    async fn remove(&self, key: Vec<u8>) -> Result<Option<Vec<u8>>, Self::DbError> {
+        self.run(move |s| Ok(s.remove(&key)?.map(to_vec))).await
+    }
+
+    async fn insert(&self, key: Vec<u8>, val: Vec<u8>) -> Result<Option<Vec<u8>>, Self::DbError> {
+        self.run(move |s| Ok(s.insert(&key, &val)?.map(to_vec)))
+            .await
+    }
+}
+
+impl<S> async_byte_store::Atomic for Blocking<S>
+where
+    S: byte_store::Atomic + Clone + Send + Sync + 'static,
+    S::DbError: Send + 'static,
+{
+    async fn atomic_update(
+        &self,
+        key: Vec<u8>,
+        op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>> + Send + 'static,
+    ) -> Result<(), Self::DbError> {
+        self.run(move |s| s.atomic_update(&key, op)).await
+    }
+
+    async fn conditional_update(
+        &self,
+        key: Vec<u8>,
+        new: Vec<u8>,
+        expected: Vec<u8>,
+    ) -> Result<(), Self::DbError> {
+        self.run(move |s| s.conditional_update(&key, &new, &expected))
+            .await
+    }
+}
+
+impl<S> async_byte_store::Ordered for Blocking<S>
+where
+    S: byte_store::Ordered + Clone + Send + Sync + 'static,
+    S::DbError: Send + 'static,
+{
+    async fn get_lt(&self, key: Vec<u8>) -> Result<Option<(Vec<u8>, Vec<u8>)>, Self::DbError> {
+        self.run(move |s| Ok(s.get_lt(&key)?.map(|(k, v)| (to_vec(k), to_vec(v)))))
+            .await
+    }
+
+    async fn get_gt(&self, key: Vec<u8>) -> Result<Option<(Vec<u8>, Vec<u8>)>, Self::DbError> {
+        self.run(move |s| Ok(s.get_gt(&key)?.map(|(k, v)| (to_vec(k), to_vec(v)))))
+            .await
+    }
+}
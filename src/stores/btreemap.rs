@@ -1,27 +1,200 @@
+use std::cell::RefCell;
 use std::collections;
-use std::sync::{Arc, RwLock};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use std::ops::RangeBounds;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
 
-use crate::traits::{byte_store, ByteStore};
+use tracing::error;
+
+use super::file::crc32;
+use crate::traits::{byte_store, data_store, ByteStore};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("rwlock was poisoned (another thread panicked while holding the lock)")]
     Poisoned,
+    #[error("could not read or write the snapshot")]
+    Io(#[from] io::Error),
+    #[error("the file is not a dbstruct snapshot")]
+    NotASnapshot,
+    #[error("snapshot format version {0} is not supported")]
+    UnsupportedVersion(u8),
+    #[error("the snapshot is damaged, its checksum does not match")]
+    CorruptSnapshot,
 }
 
+type Map = collections::BTreeMap<Vec<u8>, Vec<u8>>;
+
 /// This is a very simple backend that offers no persistence but also needs no
 /// path argument. It only supports some wrapper. Use it for testing.
 ///
 /// ### ALL CHANGES ARE LOST WHEN THE OBJECT IS DROPPED
-/// again: use for testing the API only
+/// again: use for testing the API only. Unless you save a snapshot of the map
+/// using [`save_to`](BTreeMap::save_to) or
+/// [`autosave_on_drop`](BTreeMap::autosave_on_drop).
 ///
+/// ### Snapshot format
+/// A snapshot stores all entries of the map in a single file, numbers are
+/// little endian:
+///
+///| bytes | content                                        |
+///|-------|------------------------------------------------|
+///| 8     | the magic bytes `dbstruct`                     |
+///| 1     | format version, currently 1                    |
+///| 8     | number of entries                              |
+///|       | per entry: key length (8), key, value length (8), value |
+///| 4     | CRC-32 (as used by zip) over all bytes before it |
 #[derive(Default, Clone)]
-pub struct BTreeMap(Arc<RwLock<collections::BTreeMap<Vec<u8>, Vec<u8>>>>);
+pub struct BTreeMap(Arc<Shared>);
+
+#[derive(Default)]
+struct Shared {
+    map: RwLock<Map>,
+    /// save the map here once the last clone of the store is dropped
+    autosave: Mutex<Option<PathBuf>>,
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        let Some(path) = self.autosave.get_mut().ok().and_then(Option::take) else {
+            return;
+        };
+        let Ok(map) = self.map.get_mut() else {
+            error!("not saving to {}, the map is poisoned", path.display());
+            return;
+        };
+        if let Err(e) = save(map, &path) {
+            error!("could not save to {}: {e}", path.display());
+        }
+    }
+}
+
+const MAGIC: &[u8; 8] = b"dbstruct";
+const VERSION: u8 = 1;
 
 impl BTreeMap {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Loads a snapshot made with [`save_to`](BTreeMap::save_to). If there
+    /// is no file at `path` the map starts out empty.
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let map = decode(&bytes)?;
+        Ok(Self(Arc::new(Shared {
+            map: RwLock::new(map),
+            autosave: Mutex::default(),
+        })))
+    }
+
+    /// Writes all entries to a snapshot at `path`. An existing snapshot is
+    /// only replaced once the new one is complete.
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let map = self.map().read().map_err(|_| Error::Poisoned)?;
+        save(&map, path.as_ref())
+    }
+
+    /// Saves a snapshot to `path` when the last clone of this map is
+    /// dropped. Errors while saving are logged.
+    pub fn autosave_on_drop(&self, path: impl Into<PathBuf>) -> Result<(), Error> {
+        let mut autosave = self.0.autosave.lock().map_err(|_| Error::Poisoned)?;
+        *autosave = Some(path.into());
+        Ok(())
+    }
+
+    fn map(&self) -> &RwLock<Map> {
+        &self.0.map
+    }
+}
+
+fn save(map: &Map, path: &Path) -> Result<(), Error> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&(map.len() as u64).to_le_bytes());
+    for (key, val) in map {
+        bytes.extend_from_slice(&(key.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(key);
+        bytes.extend_from_slice(&(val.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(val);
+    }
+    let checksum = crc32(&[&bytes]);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+
+    let mut tmp = OsString::from(path);
+    tmp.push(".tmp");
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(&bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn decode(bytes: &[u8]) -> Result<Map, Error> {
+    let Some(content) = bytes.strip_prefix(MAGIC) else {
+        return Err(Error::NotASnapshot);
+    };
+    let Some((version, content)) = content.split_first() else {
+        return Err(Error::CorruptSnapshot);
+    };
+    if *version != VERSION {
+        return Err(Error::UnsupportedVersion(*version));
+    }
+    let Some(checksum_start) = bytes.len().checked_sub(4) else {
+        return Err(Error::CorruptSnapshot);
+    };
+    let (checked, checksum) = bytes.split_at(checksum_start);
+    let checksum = u32::from_le_bytes(checksum.try_into().expect("is 4 long"));
+    if checked.len() < MAGIC.len() + 1 || crc32(&[checked]) != checksum {
+        return Err(Error::CorruptSnapshot);
+    }
+
+    let mut content = Snapshot(&content[..content.len() - 4]);
+    let mut map = Map::new();
+    for _ in 0..content.len()? {
+        let key = content.bytes()?.to_vec();
+        let val = content.bytes()?.to_vec();
+        map.insert(key, val);
+    }
+    if !content.0.is_empty() {
+        return Err(Error::CorruptSnapshot);
+    }
+    Ok(map)
+}
+
+/// The part of a snapshot that has not been decoded yet
+struct Snapshot<'a>(&'a [u8]);
+
+impl<'a> Snapshot<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if n > self.0.len() {
+            return Err(Error::CorruptSnapshot);
+        }
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn len(&mut self) -> Result<u64, Error> {
+        let len = self.take(8)?;
+        Ok(u64::from_le_bytes(len.try_into().expect("is 8 long")))
+    }
+
+    /// Bytes preceded by their length
+    fn bytes(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.len()?;
+        let len = usize::try_from(len).map_err(|_| Error::CorruptSnapshot)?;
+        self.take(len)
+    }
 }
 
 impl ByteStore for BTreeMap {
@@ -29,50 +202,67 @@ impl ByteStore for BTreeMap {
     type Bytes = Vec<u8>;
 
     fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
-        let map = self.0.read().map_err(|_| Self::DbError::Poisoned)?;
+        let map = self.map().read().map_err(|_| Self::DbError::Poisoned)?;
         Ok(map.get(key).cloned())
     }
 
     fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
-        let mut map = self.0.write().map_err(|_| Self::DbError::Poisoned)?;
+        let mut map = self.map().write().map_err(|_| Self::DbError::Poisoned)?;
         Ok(map.remove(key))
     }
 
     fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
-        let mut map = self.0.write().map_err(|_| Self::DbError::Poisoned)?;
+        let mut map = self.map().write().map_err(|_| Self::DbError::Poisoned)?;
         Ok(map.insert(key.to_vec(), val.to_vec()))
     }
 }
 
 impl byte_store::Ordered for BTreeMap {
     fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
-        let map = self.0.write().map_err(|_| Self::DbError::Poisoned)?;
-        let zero = vec![0];
-        let range = zero..=key.to_vec();
-        let Some((k, v)) = map.range(range).next_back() else {
+        let map = self.map().read().map_err(|_| Self::DbError::Poisoned)?;
+        let range = (Unbounded, Excluded(key));
+        let Some((k, v)) = map.range::<[u8], _>(range).next_back() else {
             return Ok(None);
         };
-        dbg!(k);
         Ok(Some((k.to_vec(), v.to_vec())))
     }
     fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
-        use std::ops::Bound::*;
-        let map = self.0.write().map_err(|_| Self::DbError::Poisoned)?;
-        let range = (Excluded(key.to_vec()), Unbounded);
-        let Some((k, v)) = map.range(range).next() else {
+        let map = self.map().read().map_err(|_| Self::DbError::Poisoned)?;
+        let range = (Excluded(key), Unbounded);
+        let Some((k, v)) = map.range::<[u8], _>(range).next() else {
             return Ok(None);
         };
         Ok(Some((k.to_vec(), v.to_vec())))
     }
 }
 
+/// The entries in the range are copied when the iterator is created. Changes
+/// made while iterating are not seen.
+impl byte_store::Ranged for BTreeMap {
+    type Key = Vec<u8>;
+    type Iter = std::vec::IntoIter<Result<(Vec<u8>, Vec<u8>), Error>>;
+
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        let Ok(map) = self.map().read() else {
+            return vec![Err(Error::Poisoned)].into_iter();
+        };
+        if super::is_empty_range(range.start_bound(), range.end_bound()) {
+            return Vec::new().into_iter();
+        }
+        map.range(range)
+            .map(|(key, val)| Ok((key.clone(), val.clone())))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
 impl byte_store::Atomic for BTreeMap {
     fn atomic_update(
         &self,
         key: &[u8],
         mut op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>>,
     ) -> Result<(), Self::DbError> {
-        let mut map = self.0.write().map_err(|_| Self::DbError::Poisoned)?;
+        let mut map = self.map().write().map_err(|_| Self::DbError::Poisoned)?;
         let curr = map.get(key).map(|c| c.as_slice());
         if let Some(new) = op(curr) {
             map.insert(key.to_vec(), new);
@@ -86,7 +276,7 @@ impl byte_store::Atomic for BTreeMap {
         new: &[u8],
         expected: &[u8],
     ) -> Result<(), Self::DbError> {
-        let mut map = self.0.write().map_err(|_| Self::DbError::Poisoned)?;
+        let mut map = self.map().write().map_err(|_| Self::DbError::Poisoned)?;
         let curr = map.get(key).map(|c| c.as_slice());
         if let Some(curr) = curr {
             if curr == expected {
@@ -98,27 +288,163 @@ impl byte_store::Atomic for BTreeMap {
     }
 }
 
-#[cfg(test)]
-impl BTreeMap {
-    pub(crate) fn force_error(&self) {
-        // poison the lock such that we get an error on the next use of self
-        let map = self.0.clone();
-        let handle = std::thread::spawn(move || {
-            let _lock = map.write().unwrap();
-            panic!("panicking here to poinson the lock")
-        });
+/// Changed entries, `None` marks a removed entry
+type Changes = collections::BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+/// The data store used inside a transaction on a [`BTreeMap`]. Changes are
+/// collected next to the map and applied once the transaction completes.
+#[derive(Clone)]
+pub struct BTreeMapTx<'tx> {
+    map: &'tx Map,
+    changes: &'tx RefCell<Changes>,
+}
+
+impl BTreeMapTx<'_> {
+    /// The entries in `range` as seen from inside the transaction
+    fn entries(&self, range: impl RangeBounds<[u8]> + Clone) -> Map {
+        if super::is_empty_range(range.start_bound(), range.end_bound()) {
+            return Map::new();
+        }
+        let changes = self.changes.borrow();
+        let unchanged = self
+            .map
+            .range::<[u8], _>(range.clone())
+            .filter(|(key, _)| !changes.contains_key(*key));
+        let changed = changes
+            .range::<[u8], _>(range)
+            .filter_map(|(key, val)| Some((key, val.as_ref()?)));
+        unchanged
+            .chain(changed)
+            .map(|(key, val)| (key.clone(), val.clone()))
+            .collect()
+    }
+}
+
+impl ByteStore for BTreeMapTx<'_> {
+    type DbError = Error;
+    type Bytes = Vec<u8>;
 
-        let res = handle.join();
-        assert!(res.is_err());
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        if let Some(change) = self.changes.borrow().get(key) {
+            return Ok(change.clone());
+        }
+        Ok(self.map.get(key).cloned())
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let existing = self.get(key)?;
+        self.changes.borrow_mut().insert(key.to_vec(), None);
+        Ok(existing)
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let existing = self.get(key)?;
+        self.changes
+            .borrow_mut()
+            .insert(key.to_vec(), Some(val.to_vec()));
+        Ok(existing)
+    }
+}
+
+impl byte_store::Ordered for BTreeMapTx<'_> {
+    fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let changes = self.changes.borrow();
+        let range = (Unbounded, Excluded(key));
+        let unchanged = self
+            .map
+            .range::<[u8], _>(range)
+            .rev()
+            .find(|(key, _)| !changes.contains_key(*key));
+        let changed = changes
+            .range::<[u8], _>(range)
+            .rev()
+            .find_map(|(key, val)| Some((key, val.as_ref()?)));
+        let last = match (unchanged, changed) {
+            (Some(a), Some(b)) => Some(if a.0 > b.0 { a } else { b }),
+            (a, b) => a.or(b),
+        };
+        Ok(last.map(|(k, v)| (k.clone(), v.clone())))
+    }
+    fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let changes = self.changes.borrow();
+        let range = (Excluded(key), Unbounded);
+        let unchanged = self
+            .map
+            .range::<[u8], _>(range)
+            .find(|(key, _)| !changes.contains_key(*key));
+        let changed = changes
+            .range::<[u8], _>(range)
+            .find_map(|(key, val)| Some((key, val.as_ref()?)));
+        let first = match (unchanged, changed) {
+            (Some(a), Some(b)) => Some(if a.0 < b.0 { a } else { b }),
+            (a, b) => a.or(b),
+        };
+        Ok(first.map(|(k, v)| (k.clone(), v.clone())))
+    }
+}
+
+fn as_slice(bound: Bound<&Vec<u8>>) -> Bound<&[u8]> {
+    match bound {
+        Included(key) => Included(key),
+        Excluded(key) => Excluded(key),
+        Unbounded => Unbounded,
+    }
+}
+
+/// The entries in the range are copied when the iterator is created, like
+/// for [`BTreeMap`].
+impl byte_store::Ranged for BTreeMapTx<'_> {
+    type Key = Vec<u8>;
+    type Iter = std::vec::IntoIter<Result<(Vec<u8>, Vec<u8>), Error>>;
+
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        let range = (as_slice(range.start_bound()), as_slice(range.end_bound()));
+        self.entries(range)
+            .into_iter()
+            .map(Ok)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+/// The map stays locked during the transaction. Do not use the map outside
+/// of the transaction from within `op`, that will deadlock.
+impl data_store::Transactional for BTreeMap {
+    type TxError = Error;
+    type Tx<'tx> = BTreeMapTx<'tx>;
+
+    fn transaction<R>(
+        &self,
+        op: impl Fn(&Self::Tx<'_>) -> Result<R, crate::Error<Self::TxError>>,
+    ) -> Result<R, crate::Error<Self::DbError>> {
+        let mut map = self
+            .map()
+            .write()
+            .map_err(|_| crate::Error::Database(Error::Poisoned))?;
+        let changes = RefCell::new(Changes::new());
+        let tx = BTreeMapTx {
+            map: &map,
+            changes: &changes,
+        };
+        let res = op(&tx)?;
+        for (key, change) in changes.into_inner() {
+            match change {
+                Some(val) => map.insert(key, val),
+                None => map.remove(&key),
+            };
+        }
+        Ok(res)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::ops::Bound;
+
     use tracing_subscriber::EnvFilter;
 
-    use super::BTreeMap;
-    use crate::traits::data_store::Ordered;
+    use super::{BTreeMap, Error};
+    use crate::traits::data_store::{Ordered, Ranged};
     use crate::traits::DataStore;
 
     #[test]
@@ -144,6 +470,9 @@ mod tests {
         let (key, val): (u16, u16) = ds.get_lt::<u16, u16, u16>(&11).unwrap().unwrap();
         assert_eq!(key, 10);
         assert_eq!(val, 4);
+        let (key, _): (u16, u16) = ds.get_lt::<u16, u16, u16>(&10).unwrap().unwrap();
+        assert_eq!(key, 1);
+        assert!(ds.get_lt::<u16, u16, u16>(&1).unwrap().is_none());
     }
 
     #[test]
@@ -156,4 +485,84 @@ mod tests {
         assert_eq!(key, 20);
         assert_eq!(val, 8);
     }
+
+    #[test]
+    fn range() {
+        let ds = BTreeMap::new();
+        for key in 0..100u16 {
+            ds.insert::<u16, u16, u16>(&key, &(key * 2)).unwrap();
+        }
+        let entries: Vec<(u16, u16)> = ds
+            .range::<u16, u16, u16>(10..15)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(entries, [(10, 20), (11, 22), (12, 24), (13, 26), (14, 28)]);
+        assert_eq!(ds.range::<u16, u16, u16>(98..).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn inverted_range_is_empty() {
+        let ds = BTreeMap::new();
+        for key in 0..100u16 {
+            ds.insert::<u16, u16, u16>(&key, &key).unwrap();
+        }
+        let inverted = (Bound::Included(15u16), Bound::Excluded(10u16));
+        assert_eq!(ds.range::<u16, u16, u16>(inverted).unwrap().count(), 0);
+        let point = (Bound::Excluded(10u16), Bound::Excluded(10u16));
+        assert_eq!(ds.range::<u16, u16, u16>(point).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn snapshot() {
+        let dir = tempdir::TempDir::new("dbstruct_btreemap_snapshot").unwrap();
+        let path = dir.path().join("snapshot");
+        let ds = BTreeMap::load_from(&path).unwrap();
+        ds.insert::<u16, u16, u16>(&1, &2).unwrap();
+        ds.insert::<u16, String, String>(&10, &"ten".to_owned())
+            .unwrap();
+        ds.save_to(&path).unwrap();
+
+        let ds = BTreeMap::load_from(&path).unwrap();
+        let val: Option<u16> = ds.get(&1u16).unwrap();
+        assert_eq!(val, Some(2));
+        let val: Option<String> = ds.get(&10u16).unwrap();
+        assert_eq!(val.as_deref(), Some("ten"));
+    }
+
+    #[test]
+    fn damaged_snapshot() {
+        let dir = tempdir::TempDir::new("dbstruct_btreemap_damaged").unwrap();
+        let path = dir.path().join("snapshot");
+        let ds = BTreeMap::new();
+        ds.insert::<u16, u16, u16>(&1, &2).unwrap();
+        ds.save_to(&path).unwrap();
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[20] ^= 0xFF;
+        std::fs::write(&path, &bytes).unwrap();
+        let res = BTreeMap::load_from(&path);
+        assert!(matches!(res, Err(Error::CorruptSnapshot)));
+
+        std::fs::write(&path, b"not a snapshot").unwrap();
+        let res = BTreeMap::load_from(&path);
+        assert!(matches!(res, Err(Error::NotASnapshot)));
+    }
+
+    #[test]
+    fn autosave_on_drop() {
+        let dir = tempdir::TempDir::new("dbstruct_btreemap_autosave").unwrap();
+        let path = dir.path().join("snapshot");
+        let ds = BTreeMap::new();
+        ds.autosave_on_drop(&path).unwrap();
+        let clone = ds.clone();
+        ds.insert::<u16, u16, u16>(&1, &2).unwrap();
+
+        drop(ds);
+        assert!(!path.exists());
+        drop(clone);
+        let ds = BTreeMap::load_from(&path).unwrap();
+        let val: Option<u16> = ds.get(&1u16).unwrap();
+        assert_eq!(val, Some(2));
+    }
 }
@@ -0,0 +1,378 @@
+use std::any::Any;
+use std::ops::RangeBounds;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use hashlink::LruCache;
+
+use crate::traits::{byte_store, data_store, ByteStore};
+
+type Bytes = Arc<[u8]>;
+
+#[derive(Clone)]
+struct Entry {
+    /// Missing values are cached too, as `None`
+    bytes: Option<Bytes>,
+    /// The value decoded by the last `get_decoded` of this key
+    decoded: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+struct Lru {
+    entries: LruCache<Vec<u8>, Entry>,
+    /// Increased on every change to the store. A value read from the store
+    /// is only cached if no change happened meanwhile, otherwise it may
+    /// already be outdated.
+    generation: u64,
+}
+
+/// Wraps any store keeping the most recently used values in memory. This is
+/// the store used by `#[dbstruct(cache = N)]`. Getting a cached value does
+/// not touch the database.
+///
+/// The cache holds the value bytes. Values read through
+/// [`DataStore::get_cloned`](crate::DataStore::get_cloned) are also kept
+/// decoded, getting them again clones the decoded value instead of
+/// deserializing it. The `DefaultValue` wrapper reads that way. The other
+/// wrappers do not require their values to be `Clone`, they decode the
+/// cached bytes on every get.
+///
+/// Every change made through this store or one of its clones invalidates the
+/// changed key, a transaction invalidates everything. Do not change the
+/// wrapped store in another way while it is cached.
+///
+/// Needs the `cache` feature.
+#[derive(Clone)]
+pub struct Cached<S> {
+    store: S,
+    cache: Arc<Mutex<Lru>>,
+}
+
+impl<S> Cached<S> {
+    /// Caches at most `capacity` values
+    pub fn new(store: S, capacity: usize) -> Self {
+        Self {
+            store,
+            cache: Arc::new(Mutex::new(Lru {
+                entries: LruCache::new(capacity),
+                generation: 0,
+            })),
+        }
+    }
+
+    /// The wrapped store
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    /// Drops every cached value
+    pub fn clear_cache(&self) {
+        let mut cache = self.lock();
+        cache.entries.clear();
+        cache.generation += 1;
+    }
+
+    /// The cache only ever holds complete entries, it is fine to keep using
+    /// it after a panic.
+    fn lock(&self) -> MutexGuard<'_, Lru> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Call after `key` was changed in the store
+    fn invalidate(&self, key: &[u8]) {
+        let mut cache = self.lock();
+        cache.entries.remove(key);
+        cache.generation += 1;
+    }
+
+    /// Caches `entry` unless the store changed since `generation`
+    fn keep(&self, key: &[u8], generation: u64, entry: Entry) {
+        let mut cache = self.lock();
+        if cache.generation == generation {
+            cache.entries.insert(key.to_vec(), entry);
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for Cached<S>
+where
+    S: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cache = self.lock();
+        f.debug_struct("Cached")
+            .field("store", &self.store)
+            .field("cached", &cache.entries.len())
+            .field("capacity", &cache.entries.capacity())
+            .finish()
+    }
+}
+
+fn to_bytes(bytes: impl AsRef<[u8]>) -> Bytes {
+    Arc::from(bytes.as_ref())
+}
+
+impl<S: ByteStore> ByteStore for Cached<S> {
+    type DbError = S::DbError;
+    type Bytes = Bytes;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let generation = {
+            let mut cache = self.lock();
+            if let Some(cached) = cache.entries.get(key) {
+                return Ok(cached.bytes.clone());
+            }
+            cache.generation
+        };
+
+        let val = self.store.get(key)?.map(to_bytes);
+        let entry = Entry {
+            bytes: val.clone(),
+            decoded: None,
+        };
+        self.keep(key, generation, entry);
+        Ok(val)
+    }
+
+    fn get_decoded<V>(
+        &self,
+        key: &[u8],
+        decode: impl FnOnce(&[u8]) -> Result<V, bincode::error::DecodeError>,
+    ) -> Result<Option<V>, crate::Error<Self::DbError>>
+    where
+        V: Clone + Send + Sync + 'static,
+    {
+        let (cached, generation) = {
+            let mut cache = self.lock();
+            let generation = cache.generation;
+            match cache.entries.get(key) {
+                Some(Entry { bytes: None, .. }) => return Ok(None),
+                Some(Entry {
+                    bytes: Some(bytes),
+                    decoded,
+                }) => {
+                    // the key may have been read as another type before
+                    let decoded = decoded.as_deref().and_then(|val| val.downcast_ref::<V>());
+                    if let Some(val) = decoded {
+                        return Ok(Some(val.clone()));
+                    }
+                    (Some(bytes.clone()), generation)
+                }
+                None => (None, generation),
+            }
+        };
+
+        let bytes = match cached {
+            Some(bytes) => bytes,
+            None => match self.store.get(key).map_err(crate::Error::Database)? {
+                Some(bytes) => to_bytes(bytes),
+                None => {
+                    let entry = Entry {
+                        bytes: None,
+                        decoded: None,
+                    };
+                    self.keep(key, generation, entry);
+                    return Ok(None);
+                }
+            },
+        };
+        let val = decode(&bytes).map_err(crate::Error::DeSerializingVal)?;
+        let entry = Entry {
+            bytes: Some(bytes),
+            decoded: Some(Arc::new(val.clone())),
+        };
+        self.keep(key, generation, entry);
+        Ok(Some(val))
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let old = self.store.remove(key);
+        self.invalidate(key);
+        Ok(old?.map(to_bytes))
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let old = self.store.insert(key, val);
+        self.invalidate(key);
+        Ok(old?.map(to_bytes))
+    }
+}
+
+impl<S: byte_store::Atomic> byte_store::Atomic for Cached<S> {
+    fn atomic_update(
+        &self,
+        key: &[u8],
+        op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>>,
+    ) -> Result<(), Self::DbError> {
+        let res = self.store.atomic_update(key, op);
+        self.invalidate(key);
+        res
+    }
+
+    fn conditional_update(
+        &self,
+        key: &[u8],
+        new: &[u8],
+        expected: &[u8],
+    ) -> Result<(), Self::DbError> {
+        let res = self.store.conditional_update(key, new, expected);
+        self.invalidate(key);
+        res
+    }
+}
+
+/// Reads the wrapped store directly, only the value of a single key is
+/// cached.
+impl<S: byte_store::Ordered> byte_store::Ordered for Cached<S> {
+    fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let entry = self.store.get_lt(key)?;
+        Ok(entry.map(|(key, val)| (to_bytes(key), to_bytes(val))))
+    }
+
+    fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let entry = self.store.get_gt(key)?;
+        Ok(entry.map(|(key, val)| (to_bytes(key), to_bytes(val))))
+    }
+}
+
+impl<S> byte_store::Ranged for Cached<S>
+where
+    S: byte_store::Ranged<Key = Vec<u8>>,
+{
+    type Key = Vec<u8>;
+    type Iter = RangeIter<S>;
+
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        RangeIter(self.store.range(range))
+    }
+}
+
+/// Iterates over a range of the store wrapped by [`Cached`].
+pub struct RangeIter<S: byte_store::Ranged>(S::Iter);
+
+impl<S: byte_store::Ranged> Iterator for RangeIter<S> {
+    type Item = Result<(Bytes, Bytes), S::DbError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.0.next()?;
+        Some(entry.map(|(key, val)| (to_bytes(key), to_bytes(val))))
+    }
+}
+
+/// The transaction runs on the wrapped store without cache. Afterwards the
+/// whole cache is invalidated as we do not track what the transaction
+/// changed.
+impl<S> data_store::Transactional for Cached<S>
+where
+    S: ByteStore + data_store::Transactional<DbError = <S as ByteStore>::DbError>,
+{
+    type TxError = S::TxError;
+    type Tx<'tx> = S::Tx<'tx>;
+
+    fn transaction<R>(
+        &self,
+        op: impl Fn(&Self::Tx<'_>) -> Result<R, crate::Error<Self::TxError>>,
+    ) -> Result<R, crate::Error<Self::DbError>> {
+        let res = self.store.transaction(op);
+        self.clear_cache();
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::Cached;
+    use crate::stores::BTreeMap;
+    use crate::traits::byte_store::{Atomic, ByteStore};
+    use crate::traits::data_store::{self, Transactional};
+
+    fn cached() -> Cached<BTreeMap> {
+        Cached::new(BTreeMap::new(), 2)
+    }
+
+    #[test]
+    fn get_is_cached() {
+        let ds = cached();
+        ds.insert(b"key", b"value").unwrap();
+        assert_eq!(
+            ds.get(b"key").unwrap().as_deref(),
+            Some(b"value".as_slice())
+        );
+
+        // bypass the cache to show it is used
+        ds.inner().insert(b"key", b"changed").unwrap();
+        assert_eq!(
+            ds.get(b"key").unwrap().as_deref(),
+            Some(b"value".as_slice())
+        );
+        ds.clear_cache();
+        assert_eq!(
+            ds.get(b"key").unwrap().as_deref(),
+            Some(b"changed".as_slice())
+        );
+    }
+
+    #[test]
+    fn decoded_values_are_kept() {
+        let ds = cached();
+        data_store::DataStore::insert::<_, _, String>(&ds, &1u8, "value").unwrap();
+        let val: Option<String> = data_store::DataStore::get_cloned(&ds, &1u8).unwrap();
+        assert_eq!(val.as_deref(), Some("value"));
+
+        // the cached bytes no longer decode, the decoded value is returned
+        ds.lock().entries.get_mut([1].as_slice()).unwrap().bytes = Some(Arc::from([]));
+        let val: Option<String> = data_store::DataStore::get_cloned(&ds, &1u8).unwrap();
+        assert_eq!(val.as_deref(), Some("value"));
+        // reading as another type decodes the bytes
+        let val: Result<Option<u64>, _> = data_store::DataStore::get_cloned(&ds, &1u8);
+        assert!(matches!(val, Err(crate::Error::DeSerializingVal(_))));
+
+        data_store::DataStore::insert::<_, _, String>(&ds, &1u8, "changed").unwrap();
+        let val: Option<String> = data_store::DataStore::get_cloned(&ds, &1u8).unwrap();
+        assert_eq!(val.as_deref(), Some("changed"));
+    }
+
+    #[test]
+    fn changes_invalidate() {
+        let ds = cached();
+        assert_eq!(ds.get(b"key").unwrap(), None);
+        ds.insert(b"key", &[1]).unwrap();
+        assert_eq!(ds.get(b"key").unwrap().as_deref(), Some([1].as_slice()));
+
+        ds.atomic_update(b"key", |_| Some(vec![2])).unwrap();
+        assert_eq!(ds.get(b"key").unwrap().as_deref(), Some([2].as_slice()));
+        ds.conditional_update(b"key", &[3], &[2]).unwrap();
+        assert_eq!(ds.get(b"key").unwrap().as_deref(), Some([3].as_slice()));
+        ds.remove(b"key").unwrap();
+        assert_eq!(ds.get(b"key").unwrap(), None);
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let ds = cached();
+        for key in [b"a", b"b", b"c"] {
+            ds.insert(key, key).unwrap();
+            ds.get(key).unwrap();
+        }
+        ds.inner().insert(b"a", b"changed").unwrap();
+        ds.inner().insert(b"c", b"changed").unwrap();
+        assert_eq!(
+            ds.get(b"a").unwrap().as_deref(),
+            Some(b"changed".as_slice())
+        );
+        assert_eq!(ds.get(b"c").unwrap().as_deref(), Some(b"c".as_slice()));
+    }
+
+    #[test]
+    fn transaction_invalidates() {
+        let ds = cached();
+        ds.insert(b"key", &[1]).unwrap();
+        ds.get(b"key").unwrap();
+        ds.transaction(|tx| {
+            ByteStore::insert(tx, b"key", &[2]).map_err(crate::Error::Database)?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(ds.get(b"key").unwrap().as_deref(), Some([2].as_slice()));
+    }
+}
@@ -0,0 +1,490 @@
+use core::fmt;
+use std::cell::RefCell;
+use std::io::Read;
+use std::ops::RangeBounds;
+
+use crate::traits::{byte_store, data_store, ByteStore};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error<E: fmt::Debug> {
+    #[error("the wrapped store returned an error: {0:?}")]
+    Store(E),
+    #[error("could not decompress a value, it is corrupt")]
+    Decompress,
+    #[error("zstd could not compress a value")]
+    Compress,
+    #[error("a value decompresses to more than the maximum of {max} bytes")]
+    TooLarge { max: usize },
+}
+
+impl<E: fmt::Debug> Error<E> {
+    /// Separates errors from the wrapped store from our own
+    fn split(self) -> Result<E, Error<()>> {
+        match self {
+            Error::Store(e) => Ok(e),
+            Error::Decompress => Err(Error::Decompress),
+            Error::Compress => Err(Error::Compress),
+            Error::TooLarge { max } => Err(Error::TooLarge { max }),
+        }
+    }
+
+    fn from_codec(error: Error<()>) -> Self {
+        match error {
+            Error::Store(()) => unreachable!("codec errors never come from the store"),
+            Error::Decompress => Error::Decompress,
+            Error::Compress => Error::Compress,
+            Error::TooLarge { max } => Error::TooLarge { max },
+        }
+    }
+}
+
+impl<E: fmt::Debug> crate::Error<E> {
+    /// Converts an error of a compressed field to the error of the
+    /// uncompressed store. Failing to compress or decompress becomes a
+    /// (de)serialization error. Use it to return errors of a compressed field
+    /// from a transaction: `.map_err(Error::from_compressed)?`.
+    pub fn from_compressed(error: crate::Error<Error<E>>) -> Self {
+        use bincode::error::{DecodeError, EncodeError};
+        match error {
+            crate::Error::Database(Error::Store(e)) => crate::Error::Database(e),
+            crate::Error::Database(Error::Compress) => crate::Error::SerializingValue(
+                EncodeError::OtherString(Error::<()>::Compress.to_string()),
+            ),
+            crate::Error::Database(codec) => {
+                crate::Error::DeSerializingVal(DecodeError::OtherString(codec.to_string()))
+            }
+            other => other.map_database(|_| unreachable!("handled above")),
+        }
+    }
+}
+
+/// Marks a compressed value, it is followed by a zstd frame
+const COMPRESSED: u8 = 0xFF;
+/// The first bytes of every zstd frame
+const ZSTD_MAGIC: [u8; 4] = 0xFD2FB528u32.to_le_bytes();
+const HEADER_LEN: usize = 1 + ZSTD_MAGIC.len();
+
+/// Wraps any store compressing values using zstd before they are written.
+/// This is the store used by `#[dbstruct(compress = "zstd")]`. Keys are
+/// left as is.
+///
+/// Only values of at least [`threshold`](Compressed::with_threshold) bytes
+/// are compressed, they are stored behind a header byte. Values without the
+/// header are read as is. That keeps values written before compression was
+/// enabled readable. Reading a value that decompresses to more than
+/// [`max_size`](Compressed::with_max_size) bytes fails, a corrupt value can
+/// therefore not exhaust the memory.
+///
+/// Needs the `compression` feature.
+#[derive(Debug, Clone)]
+pub struct Compressed<S> {
+    store: S,
+    threshold: usize,
+    level: i32,
+    max_size: usize,
+}
+
+impl<S> Compressed<S> {
+    /// Values smaller than this are not worth compressing
+    pub const DEFAULT_THRESHOLD: usize = 64;
+    /// The largest value that is decompressed, 1 GiB
+    pub const DEFAULT_MAX_SIZE: usize = 1 << 30;
+
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            threshold: Self::DEFAULT_THRESHOLD,
+            level: zstd::DEFAULT_COMPRESSION_LEVEL,
+            max_size: Self::DEFAULT_MAX_SIZE,
+        }
+    }
+
+    /// Only compress values of at least `bytes` long
+    pub fn with_threshold(mut self, bytes: usize) -> Self {
+        self.threshold = bytes;
+        self
+    }
+
+    /// The zstd compression level, from 1 (fastest) to 22 (smallest)
+    pub fn with_level(mut self, level: i32) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Refuse to decompress values larger than `bytes`, reading them returns
+    /// [`Error::TooLarge`]
+    pub fn with_max_size(mut self, bytes: usize) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    /// The wrapped store
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    fn wrap<T>(&self, store: T) -> Compressed<T> {
+        Compressed {
+            store,
+            threshold: self.threshold,
+            level: self.level,
+            max_size: self.max_size,
+        }
+    }
+}
+
+fn has_header(stored: &[u8]) -> bool {
+    stored.len() > HEADER_LEN && stored[0] == COMPRESSED && stored[1..HEADER_LEN] == ZSTD_MAGIC
+}
+
+fn compress(val: &[u8], level: i32) -> Result<Vec<u8>, Error<()>> {
+    let mut compressed = vec![COMPRESSED];
+    zstd::stream::copy_encode(val, &mut compressed, level).map_err(|_| Error::Compress)?;
+    Ok(compressed)
+}
+
+/// The content size in the frame header can not be trusted. Therefore the
+/// value is streamed into a buffer that grows as needed up to `max_size`.
+fn decompress(compressed: &[u8], max_size: usize) -> Result<Vec<u8>, Error<()>> {
+    let frame = &compressed[1..];
+    let decoder = zstd::stream::read::Decoder::with_buffer(frame)
+        .map_err(|_| Error::Decompress)?
+        .single_frame();
+    let mut val = Vec::new();
+    decoder
+        .take((max_size as u64).saturating_add(1))
+        .read_to_end(&mut val)
+        .map_err(|_| Error::Decompress)?;
+    if val.len() > max_size {
+        return Err(Error::TooLarge { max: max_size });
+    }
+    Ok(val)
+}
+
+/// Values that look compressed are always compressed so they are not
+/// mistaken for a compressed value when read back.
+fn encode(val: &[u8], threshold: usize, level: i32) -> Result<Vec<u8>, Error<()>> {
+    if val.len() < threshold && !has_header(val) {
+        return Ok(val.to_vec());
+    }
+    let compressed = compress(val, level)?;
+    if compressed.len() >= val.len() && !has_header(val) {
+        Ok(val.to_vec())
+    } else {
+        Ok(compressed)
+    }
+}
+
+fn decode(stored: &[u8], max_size: usize) -> Result<Vec<u8>, Error<()>> {
+    if has_header(stored) {
+        decompress(stored, max_size)
+    } else {
+        Ok(stored.to_vec())
+    }
+}
+
+type Entry = (Vec<u8>, Vec<u8>);
+
+impl<S: ByteStore> Compressed<S> {
+    fn encode(&self, val: &[u8]) -> Result<Vec<u8>, Error<S::DbError>> {
+        encode(val, self.threshold, self.level).map_err(Error::from_codec)
+    }
+
+    fn decode(&self, stored: Option<S::Bytes>) -> Result<Option<Vec<u8>>, Error<S::DbError>> {
+        stored
+            .map(|stored| decode(stored.as_ref(), self.max_size))
+            .transpose()
+            .map_err(Error::from_codec)
+    }
+
+    fn decode_entry(
+        &self,
+        entry: Option<(S::Bytes, S::Bytes)>,
+    ) -> Result<Option<Entry>, Error<S::DbError>> {
+        let Some((key, val)) = entry else {
+            return Ok(None);
+        };
+        let val = decode(val.as_ref(), self.max_size).map_err(Error::from_codec)?;
+        Ok(Some((key.as_ref().to_vec(), val)))
+    }
+}
+
+impl<S: ByteStore> ByteStore for Compressed<S> {
+    type DbError = Error<S::DbError>;
+    type Bytes = Vec<u8>;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let stored = self.store.get(key).map_err(Error::Store)?;
+        self.decode(stored)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let old = self.store.remove(key).map_err(Error::Store)?;
+        self.decode(old)
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let encoded = self.encode(val)?;
+        let old = self.store.insert(key, &encoded).map_err(Error::Store)?;
+        self.decode(old)
+    }
+}
+
+impl<S: byte_store::Atomic> byte_store::Atomic for Compressed<S> {
+    /// `op` gets the decompressed value. If decompressing or compressing
+    /// fails the value is left as is.
+    fn atomic_update(
+        &self,
+        key: &[u8],
+        mut op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>>,
+    ) -> Result<(), Self::DbError> {
+        let mut failed = None;
+        self.store
+            .atomic_update(key, |current| {
+                failed = None;
+                let decoded = current.map(|current| decode(current, self.max_size));
+                let new = match decoded.transpose() {
+                    Ok(decoded) => op(decoded.as_deref()),
+                    Err(e) => {
+                        failed = Some(e);
+                        return current.map(<[u8]>::to_vec);
+                    }
+                };
+                let encoded = new
+                    .map(|new| encode(&new, self.threshold, self.level))
+                    .transpose();
+                encoded.unwrap_or_else(|e| {
+                    failed = Some(e);
+                    current.map(<[u8]>::to_vec)
+                })
+            })
+            .map_err(Error::Store)?;
+        match failed {
+            Some(e) => Err(Error::from_codec(e)),
+            None => Ok(()),
+        }
+    }
+
+    /// The stored value may have been written without compression or with
+    /// another level. Therefore this compares the decompressed value.
+    fn conditional_update(
+        &self,
+        key: &[u8],
+        new: &[u8],
+        expected: &[u8],
+    ) -> Result<(), Self::DbError> {
+        self.atomic_update(key, |current| match current {
+            Some(current) if current == expected => Some(new.to_vec()),
+            current => current.map(<[u8]>::to_vec),
+        })
+    }
+}
+
+impl<S: byte_store::Ordered> byte_store::Ordered for Compressed<S> {
+    fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let entry = self.store.get_lt(key).map_err(Error::Store)?;
+        self.decode_entry(entry)
+    }
+
+    fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let entry = self.store.get_gt(key).map_err(Error::Store)?;
+        self.decode_entry(entry)
+    }
+}
+
+impl<S> byte_store::Ranged for Compressed<S>
+where
+    S: byte_store::Ranged<Key = Vec<u8>>,
+{
+    type Key = Vec<u8>;
+    type Iter = RangeIter<S>;
+
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        RangeIter {
+            entries: self.store.range(range),
+            max_size: self.max_size,
+        }
+    }
+}
+
+/// Iterates over a range of a [`Compressed`] store decompressing the values.
+pub struct RangeIter<S: byte_store::Ranged> {
+    entries: S::Iter,
+    max_size: usize,
+}
+
+impl<S: byte_store::Ranged> Iterator for RangeIter<S> {
+    type Item = Result<Entry, Error<S::DbError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, val) = match self.entries.next()? {
+            Ok(entry) => entry,
+            Err(e) => return Some(Err(Error::Store(e))),
+        };
+        let val = decode(val.as_ref(), self.max_size).map_err(Error::from_codec);
+        Some(val.map(|val| (key.as_ref().to_vec(), val)))
+    }
+}
+
+/// Changes made in the transaction are compressed before they are passed to
+/// the transaction of the wrapped store.
+impl<S> data_store::Transactional for Compressed<S>
+where
+    S: ByteStore + data_store::Transactional<DbError = <S as ByteStore>::DbError>,
+    for<'tx> S::Tx<'tx>: ByteStore<DbError = S::TxError>,
+{
+    type TxError = Error<S::TxError>;
+    type Tx<'tx> = Compressed<S::Tx<'tx>>;
+
+    fn transaction<R>(
+        &self,
+        op: impl Fn(&Self::Tx<'_>) -> Result<R, crate::Error<Self::TxError>>,
+    ) -> Result<R, crate::Error<Self::DbError>> {
+        // The wrapped store can not return our errors. If decompressing fails
+        // we abort its transaction and return the error once it is done.
+        let failed = RefCell::new(None);
+        let res = self.store.transaction(|tx| {
+            *failed.borrow_mut() = None;
+            match op(&self.wrap(tx.clone())) {
+                Ok(res) => Ok(res),
+                Err(crate::Error::Database(e)) => match e.split() {
+                    Ok(e) => Err(crate::Error::Database(e)),
+                    Err(codec) => {
+                        *failed.borrow_mut() = Some(codec);
+                        Err(crate::Error::TransactionAborted)
+                    }
+                },
+                Err(e) => Err(e.map_database(|_| unreachable!("handled above"))),
+            }
+        });
+        res.map_err(|e| match failed.into_inner() {
+            Some(codec) => crate::Error::Database(Error::from_codec(codec)),
+            None => e.map_database(Error::Store),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Compressed, Error, COMPRESSED, ZSTD_MAGIC};
+    use crate::stores::BTreeMap;
+    use crate::traits::byte_store::{self, Atomic};
+    use crate::traits::data_store::Ranged;
+    use crate::traits::DataStore;
+
+    fn compressed() -> Compressed<BTreeMap> {
+        Compressed::new(BTreeMap::new())
+    }
+
+    #[test]
+    fn large_values_are_compressed() {
+        let ds = compressed();
+        let large = vec![42u8; 1000];
+        byte_store::ByteStore::insert(&ds, b"large", &large).unwrap();
+        byte_store::ByteStore::insert(&ds, b"small", b"tiny").unwrap();
+
+        let stored = byte_store::ByteStore::get(ds.inner(), b"large")
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored[0], COMPRESSED);
+        assert!(stored.len() < 100);
+        let stored = byte_store::ByteStore::get(ds.inner(), b"small")
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored, b"tiny");
+
+        let val = byte_store::ByteStore::get(&ds, b"large").unwrap().unwrap();
+        assert_eq!(val, large);
+    }
+
+    #[test]
+    fn reads_uncompressed_values() {
+        let ds = compressed();
+        let legacy = vec![7u8; 1000];
+        byte_store::ByteStore::insert(ds.inner(), b"legacy", &legacy).unwrap();
+        let val = byte_store::ByteStore::get(&ds, b"legacy").unwrap().unwrap();
+        assert_eq!(val, legacy);
+    }
+
+    #[test]
+    fn values_looking_compressed() {
+        let ds = compressed();
+        let mut tricky = vec![COMPRESSED];
+        tricky.extend_from_slice(&ZSTD_MAGIC);
+        tricky.push(1);
+        byte_store::ByteStore::insert(&ds, b"tricky", &tricky).unwrap();
+        let val = byte_store::ByteStore::get(&ds, b"tricky").unwrap().unwrap();
+        assert_eq!(val, tricky);
+
+        byte_store::ByteStore::insert(ds.inner(), b"tricky", &tricky).unwrap();
+        assert!(matches!(
+            byte_store::ByteStore::get(&ds, b"tricky"),
+            Err(Error::Decompress)
+        ));
+    }
+
+    #[test]
+    fn refuses_values_above_max_size() {
+        let ds = compressed().with_max_size(999);
+        let large = vec![42u8; 1000];
+        byte_store::ByteStore::insert(&ds, b"large", &large).unwrap();
+        assert!(matches!(
+            byte_store::ByteStore::get(&ds, b"large"),
+            Err(Error::TooLarge { max: 999 })
+        ));
+
+        let ds = ds.with_max_size(1000);
+        let val = byte_store::ByteStore::get(&ds, b"large").unwrap().unwrap();
+        assert_eq!(val, large);
+    }
+
+    #[test]
+    fn frame_header_claims_a_huge_value() {
+        let ds = compressed();
+        let mut lying = vec![COMPRESSED];
+        lying.extend_from_slice(&ZSTD_MAGIC);
+        // single segment frame with an 8 byte content size of 1 TiB
+        lying.push(0xE0);
+        lying.extend_from_slice(&(1u64 << 40).to_le_bytes());
+        // the last block, it is raw and holds a single byte
+        lying.extend_from_slice(&[0x09, 0, 0, 42]);
+        byte_store::ByteStore::insert(ds.inner(), b"lying", &lying).unwrap();
+        assert!(matches!(
+            byte_store::ByteStore::get(&ds, b"lying"),
+            Err(Error::Decompress)
+        ));
+    }
+
+    #[test]
+    fn atomic_update() {
+        let ds = compressed().with_threshold(0);
+        byte_store::ByteStore::insert(&ds, b"key", &[1; 100]).unwrap();
+        ds.atomic_update(b"key", |current| {
+            assert_eq!(current, Some([1; 100].as_slice()));
+            Some(vec![2; 100])
+        })
+        .unwrap();
+        ds.conditional_update(b"key", &[3], &[1; 100]).unwrap();
+        let val = byte_store::ByteStore::get(&ds, b"key").unwrap().unwrap();
+        assert_eq!(val, [2; 100]);
+        ds.conditional_update(b"key", &[3], &[2; 100]).unwrap();
+        let val = byte_store::ByteStore::get(&ds, b"key").unwrap().unwrap();
+        assert_eq!(val, [3]);
+    }
+
+    #[test]
+    fn range() {
+        let ds = compressed().with_threshold(0);
+        for key in 0..20u16 {
+            let val = key.to_string().repeat(50);
+            ds.insert::<u16, String, String>(&key, &val).unwrap();
+        }
+        let entries: Vec<(u16, String)> = ds
+            .range::<u16, u16, String>(10..12)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(entries[0], (10, "10".repeat(50)));
+        assert_eq!(entries[1], (11, "11".repeat(50)));
+    }
+}
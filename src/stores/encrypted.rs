@@ -0,0 +1,585 @@
+use core::fmt;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::ops::RangeBounds;
+use std::sync::Arc;
+
+use aes_gcm_siv::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm_siv::{Aes256GcmSiv, Nonce};
+
+use crate::traits::{byte_store, data_store, ByteStore};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error<E: fmt::Debug> {
+    #[error("the wrapped store returned an error: {0:?}")]
+    Store(E),
+    #[error("could not decrypt, the data was changed or encrypted with another key")]
+    Decrypt,
+    #[error("could not encrypt, the data is too large")]
+    Encrypt,
+}
+
+/// The 256 bit key used by [`Encrypted`]. Store it somewhere safe, without
+/// it the data can not be read.
+#[derive(Clone)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// A new random key
+    pub fn generate() -> Self {
+        Self(Aes256GcmSiv::generate_key(&mut OsRng).into())
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// Only the values of an [`Encrypted`] store are encrypted
+pub struct PlainKeys;
+/// Both the keys and the values of an [`Encrypted`] store are encrypted
+pub struct EncryptedKeys;
+
+/// Wraps any store encrypting everything written to it. This is the store
+/// used by `#[dbstruct(encrypt)]`. Values are encrypted with AES-256-GCM-SIV
+/// using a random nonce. The key of each value is authenticated along with
+/// it, moving a value to another key makes reading it fail.
+///
+/// By default only the values are encrypted. Use
+/// [`with_encrypted_keys`](Encrypted::with_encrypted_keys) to also encrypt
+/// the keys. They are then encrypted deterministically, such that a key
+/// still finds its value. Their first byte, the field prefix, stays readable
+/// so the entries of a field stay together. The order within a field is
+/// lost, as are [`Ordered`](byte_store::Ordered) and
+/// [`Ranged`](byte_store::Ranged). Therefore `Vec`, `VecDeque` and
+/// migrations need plain keys.
+///
+/// Needs the `encryption` feature.
+pub struct Encrypted<S, Keys = PlainKeys> {
+    store: S,
+    cipher: Arc<Aes256GcmSiv>,
+    keys: PhantomData<Keys>,
+}
+
+impl<S: Clone, Keys> Clone for Encrypted<S, Keys> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            cipher: self.cipher.clone(),
+            keys: PhantomData,
+        }
+    }
+}
+
+impl<S> Encrypted<S> {
+    pub fn new(store: S, key: &EncryptionKey) -> Self {
+        Self {
+            store,
+            cipher: Arc::new(Aes256GcmSiv::new(&key.0.into())),
+            keys: PhantomData,
+        }
+    }
+
+    pub fn with_encrypted_keys(self) -> Encrypted<S, EncryptedKeys> {
+        Encrypted {
+            store: self.store,
+            cipher: self.cipher,
+            keys: PhantomData,
+        }
+    }
+}
+
+impl<S, Keys> Encrypted<S, Keys> {
+    /// The wrapped store
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+}
+
+const NONCE_LEN: usize = 12;
+/// Keys are encrypted deterministically, AES-GCM-SIV stays secure when
+/// reusing a nonce, it only reveals whether two keys are equal.
+const KEY_NONCE: [u8; NONCE_LEN] = [0; NONCE_LEN];
+/// Keeps encrypted keys apart from encrypted values
+const KEY_AAD: &[u8] = b"dbstruct key";
+
+/// An encrypted value is the nonce followed by the ciphertext
+fn encrypt(cipher: &Aes256GcmSiv, key: &[u8], val: &[u8]) -> Result<Vec<u8>, Error<()>> {
+    let nonce = Aes256GcmSiv::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: val, aad: key })
+        .map_err(|_| Error::Encrypt)?;
+    let mut encrypted = nonce.to_vec();
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
+}
+
+fn decrypt(cipher: &Aes256GcmSiv, key: &[u8], encrypted: &[u8]) -> Result<Vec<u8>, Error<()>> {
+    if encrypted.len() < NONCE_LEN {
+        return Err(Error::Decrypt);
+    }
+    let (nonce, ciphertext) = encrypted.split_at(NONCE_LEN);
+    let payload = Payload {
+        msg: ciphertext,
+        aad: key,
+    };
+    cipher
+        .decrypt(Nonce::from_slice(nonce), payload)
+        .map_err(|_| Error::Decrypt)
+}
+
+impl<E: fmt::Debug> Error<E> {
+    /// Separates errors from the wrapped store from our own
+    fn split(self) -> Result<E, Error<()>> {
+        match self {
+            Error::Store(e) => Ok(e),
+            Error::Decrypt => Err(Error::Decrypt),
+            Error::Encrypt => Err(Error::Encrypt),
+        }
+    }
+
+    fn from_crypto(error: Error<()>) -> Self {
+        match error {
+            Error::Store(()) => unreachable!("crypto errors never come from the store"),
+            Error::Decrypt => Error::Decrypt,
+            Error::Encrypt => Error::Encrypt,
+        }
+    }
+}
+
+/// How the keys given to an [`Encrypted`] store are written to the store it
+/// wraps
+pub trait KeyMode {
+    #[doc(hidden)]
+    fn encrypt_key<'a>(cipher: &Aes256GcmSiv, key: &'a [u8]) -> Result<Cow<'a, [u8]>, Error<()>>;
+}
+
+impl KeyMode for PlainKeys {
+    fn encrypt_key<'a>(_: &Aes256GcmSiv, key: &'a [u8]) -> Result<Cow<'a, [u8]>, Error<()>> {
+        Ok(Cow::Borrowed(key))
+    }
+}
+
+impl KeyMode for EncryptedKeys {
+    fn encrypt_key<'a>(cipher: &Aes256GcmSiv, key: &'a [u8]) -> Result<Cow<'a, [u8]>, Error<()>> {
+        let Some((prefix, rest)) = key.split_first() else {
+            return Ok(Cow::Borrowed(key));
+        };
+        if rest.is_empty() {
+            return Ok(Cow::Borrowed(key));
+        }
+        let payload = Payload {
+            msg: rest,
+            aad: KEY_AAD,
+        };
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&KEY_NONCE), payload)
+            .map_err(|_| Error::Encrypt)?;
+        let mut encrypted = vec![*prefix];
+        encrypted.extend_from_slice(&ciphertext);
+        Ok(Cow::Owned(encrypted))
+    }
+}
+
+impl<S, Keys> Encrypted<S, Keys>
+where
+    S: ByteStore,
+    Keys: KeyMode,
+{
+    fn store_key<'a>(&self, key: &'a [u8]) -> Result<Cow<'a, [u8]>, Error<S::DbError>> {
+        Keys::encrypt_key(&self.cipher, key).map_err(Error::from_crypto)
+    }
+
+    fn encrypt(&self, key: &[u8], val: &[u8]) -> Result<Vec<u8>, Error<S::DbError>> {
+        encrypt(&self.cipher, key, val).map_err(Error::from_crypto)
+    }
+
+    fn decrypt(
+        &self,
+        key: &[u8],
+        encrypted: Option<S::Bytes>,
+    ) -> Result<Option<Vec<u8>>, Error<S::DbError>> {
+        encrypted
+            .map(|encrypted| decrypt(&self.cipher, key, encrypted.as_ref()))
+            .transpose()
+            .map_err(Error::from_crypto)
+    }
+
+    /// Decrypts the value stored at `key` before `write` replaces it, so a
+    /// value that does not decrypt fails the call without changing anything.
+    fn replace(
+        &self,
+        key: &[u8],
+        write: impl FnOnce(&[u8]) -> Result<Option<S::Bytes>, S::DbError>,
+    ) -> Result<Option<Vec<u8>>, Error<S::DbError>> {
+        let store_key = self.store_key(key)?;
+        let current = self.store.get(&store_key).map_err(Error::Store)?;
+        let stored = current.as_ref().map(|bytes| bytes.as_ref().to_vec());
+        let decrypted = self.decrypt(key, current)?;
+
+        let old = write(&store_key).map_err(Error::Store)?;
+        if old.as_ref().map(AsRef::as_ref) == stored.as_deref() {
+            Ok(decrypted)
+        } else {
+            // changed by someone else since we read it
+            self.decrypt(key, old)
+        }
+    }
+}
+
+impl<S, Keys> ByteStore for Encrypted<S, Keys>
+where
+    S: ByteStore,
+    Keys: KeyMode,
+{
+    type DbError = Error<S::DbError>;
+    type Bytes = Vec<u8>;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let stored = self
+            .store
+            .get(&self.store_key(key)?)
+            .map_err(Error::Store)?;
+        self.decrypt(key, stored)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.replace(key, |store_key| self.store.remove(store_key))
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let encrypted = self.encrypt(key, val)?;
+        self.replace(key, |store_key| self.store.insert(store_key, &encrypted))
+    }
+}
+
+impl<S, Keys> byte_store::Atomic for Encrypted<S, Keys>
+where
+    S: byte_store::Atomic,
+    Keys: KeyMode,
+{
+    /// `op` gets the decrypted value. Its result is encrypted before it is
+    /// stored. If decrypting or encrypting fails the value is left as is.
+    fn atomic_update(
+        &self,
+        key: &[u8],
+        mut op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>>,
+    ) -> Result<(), Self::DbError> {
+        let mut failed = None;
+        self.store
+            .atomic_update(&self.store_key(key)?, |current| {
+                failed = None;
+                let decrypted = current
+                    .map(|current| decrypt(&self.cipher, key, current))
+                    .transpose();
+                let new = match decrypted {
+                    Ok(decrypted) => op(decrypted.as_deref()),
+                    Err(e) => {
+                        failed = Some(e);
+                        return current.map(<[u8]>::to_vec);
+                    }
+                };
+                match new.map(|new| encrypt(&self.cipher, key, &new)).transpose() {
+                    Ok(new) => new,
+                    Err(e) => {
+                        failed = Some(e);
+                        current.map(<[u8]>::to_vec)
+                    }
+                }
+            })
+            .map_err(Error::Store)?;
+        match failed {
+            Some(e) => Err(Error::from_crypto(e)),
+            None => Ok(()),
+        }
+    }
+
+    /// The ciphertext of `expected` differs from the stored one as each
+    /// encryption uses a new nonce. Therefore this decrypts the stored value
+    /// and compares that.
+    fn conditional_update(
+        &self,
+        key: &[u8],
+        new: &[u8],
+        expected: &[u8],
+    ) -> Result<(), Self::DbError> {
+        self.atomic_update(key, |current| match current {
+            Some(current) if current == expected => Some(new.to_vec()),
+            current => current.map(<[u8]>::to_vec),
+        })
+    }
+}
+
+impl<S> byte_store::Ordered for Encrypted<S>
+where
+    S: byte_store::Ordered,
+{
+    fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let entry = self.store.get_lt(key).map_err(Error::Store)?;
+        self.decrypt_entry(entry)
+    }
+
+    fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let entry = self.store.get_gt(key).map_err(Error::Store)?;
+        self.decrypt_entry(entry)
+    }
+}
+
+type Entry = (Vec<u8>, Vec<u8>);
+
+impl<S: ByteStore> Encrypted<S> {
+    fn decrypt_entry(
+        &self,
+        entry: Option<(S::Bytes, S::Bytes)>,
+    ) -> Result<Option<Entry>, Error<S::DbError>> {
+        let Some((key, val)) = entry else {
+            return Ok(None);
+        };
+        let key = key.as_ref().to_vec();
+        let val = self.decrypt(&key, Some(val))?.expect("is Some");
+        Ok(Some((key, val)))
+    }
+}
+
+impl<S> byte_store::Ranged for Encrypted<S>
+where
+    S: byte_store::Ranged<Key = Vec<u8>>,
+{
+    type Key = Vec<u8>;
+    type Iter = RangeIter<S>;
+
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        RangeIter {
+            store: Encrypted {
+                store: (),
+                cipher: self.cipher.clone(),
+                keys: PhantomData,
+            },
+            entries: self.store.range(range),
+        }
+    }
+}
+
+/// Iterates over a range of an [`Encrypted`] store decrypting the values.
+pub struct RangeIter<S: byte_store::Ranged> {
+    store: Encrypted<()>,
+    entries: S::Iter,
+}
+
+impl<S: byte_store::Ranged> Iterator for RangeIter<S> {
+    type Item = Result<Entry, Error<S::DbError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = match self.entries.next()? {
+            Ok(entry) => entry,
+            Err(e) => return Some(Err(Error::Store(e))),
+        };
+        let key = entry.0.as_ref().to_vec();
+        let val = decrypt(&self.store.cipher, &key, entry.1.as_ref());
+        Some(val.map(|val| (key, val)).map_err(Error::from_crypto))
+    }
+}
+
+/// Changes made in the transaction are encrypted before they are passed to
+/// the transaction of the wrapped store.
+impl<S, Keys> data_store::Transactional for Encrypted<S, Keys>
+where
+    S: ByteStore + data_store::Transactional<DbError = <S as ByteStore>::DbError>,
+    for<'tx> S::Tx<'tx>: ByteStore<DbError = S::TxError>,
+    Keys: KeyMode,
+{
+    type TxError = Error<S::TxError>;
+    type Tx<'tx> = Encrypted<S::Tx<'tx>, Keys>;
+
+    fn transaction<R>(
+        &self,
+        op: impl Fn(&Self::Tx<'_>) -> Result<R, crate::Error<Self::TxError>>,
+    ) -> Result<R, crate::Error<Self::DbError>> {
+        // The wrapped store can not return our errors. If decrypting fails
+        // we abort its transaction and return the error once it is done.
+        let failed = RefCell::new(None);
+        let res = self.store.transaction(|tx| {
+            *failed.borrow_mut() = None;
+            let tx = Encrypted {
+                store: tx.clone(),
+                cipher: self.cipher.clone(),
+                keys: PhantomData,
+            };
+            match op(&tx) {
+                Ok(res) => Ok(res),
+                Err(crate::Error::Database(e)) => match e.split() {
+                    Ok(e) => Err(crate::Error::Database(e)),
+                    Err(crypto) => {
+                        *failed.borrow_mut() = Some(crypto);
+                        Err(crate::Error::TransactionAborted)
+                    }
+                },
+                Err(e) => Err(e.map_database(|_| unreachable!("handled above"))),
+            }
+        });
+        res.map_err(|e| match failed.into_inner() {
+            Some(crypto) => crate::Error::Database(Error::from_crypto(crypto)),
+            None => e.map_database(Error::Store),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Encrypted, EncryptionKey, Error};
+    use crate::stores::BTreeMap;
+    use crate::traits::byte_store::{self, Atomic};
+    use crate::traits::data_store::{Ranged, Transactional};
+    use crate::traits::DataStore;
+
+    fn encrypted() -> Encrypted<BTreeMap> {
+        Encrypted::new(BTreeMap::new(), &EncryptionKey::generate())
+    }
+
+    #[test]
+    fn get_then_insert() {
+        let ds = encrypted();
+        let existing: Option<u16> = ds.insert(&1u16, &2u16).unwrap();
+        assert_eq!(existing, None);
+        let val: u16 = ds.remove(&1u16).unwrap().unwrap();
+        assert_eq!(val, 2);
+    }
+
+    #[test]
+    fn stored_values_are_encrypted() {
+        let ds = encrypted();
+        byte_store::ByteStore::insert(&ds, b"key", b"secret value").unwrap();
+        let stored = byte_store::ByteStore::get(ds.inner(), b"key")
+            .unwrap()
+            .unwrap();
+        assert!(!stored
+            .windows(b"secret".len())
+            .any(|window| window == b"secret"));
+        let val = byte_store::ByteStore::get(&ds, b"key").unwrap().unwrap();
+        assert_eq!(val, b"secret value");
+    }
+
+    #[test]
+    fn wrong_key_or_moved_value() {
+        let ds = encrypted();
+        byte_store::ByteStore::insert(&ds, b"key", b"value").unwrap();
+
+        let other = Encrypted::new(ds.inner().clone(), &EncryptionKey::generate());
+        assert!(matches!(
+            byte_store::ByteStore::get(&other, b"key"),
+            Err(Error::Decrypt)
+        ));
+
+        let stored = byte_store::ByteStore::get(ds.inner(), b"key")
+            .unwrap()
+            .unwrap();
+        byte_store::ByteStore::insert(ds.inner(), b"other", &stored).unwrap();
+        assert!(matches!(
+            byte_store::ByteStore::get(&ds, b"other"),
+            Err(Error::Decrypt)
+        ));
+    }
+
+    #[test]
+    fn undecryptable_value_is_not_overwritten() {
+        let ds = encrypted();
+        byte_store::ByteStore::insert(&ds, b"key", b"value").unwrap();
+        let stored = byte_store::ByteStore::get(ds.inner(), b"key").unwrap();
+
+        let other = Encrypted::new(ds.inner().clone(), &EncryptionKey::generate());
+        assert!(matches!(
+            byte_store::ByteStore::insert(&other, b"key", b"new"),
+            Err(Error::Decrypt)
+        ));
+        assert!(matches!(
+            byte_store::ByteStore::remove(&other, b"key"),
+            Err(Error::Decrypt)
+        ));
+        let after = byte_store::ByteStore::get(ds.inner(), b"key").unwrap();
+        assert_eq!(after, stored);
+    }
+
+    #[test]
+    fn atomic_update() {
+        let ds = encrypted();
+        byte_store::ByteStore::insert(&ds, b"key", &[1]).unwrap();
+        ds.atomic_update(b"key", |current| {
+            assert_eq!(current, Some([1].as_slice()));
+            Some(vec![2])
+        })
+        .unwrap();
+        ds.conditional_update(b"key", &[3], &[1]).unwrap();
+        assert_eq!(
+            byte_store::ByteStore::get(&ds, b"key").unwrap().unwrap(),
+            [2]
+        );
+        ds.conditional_update(b"key", &[3], &[2]).unwrap();
+        assert_eq!(
+            byte_store::ByteStore::get(&ds, b"key").unwrap().unwrap(),
+            [3]
+        );
+    }
+
+    #[test]
+    fn encrypted_keys() {
+        let ds = encrypted().with_encrypted_keys();
+        byte_store::ByteStore::insert(&ds, b"akey", b"value").unwrap();
+        assert!(byte_store::ByteStore::get(ds.inner(), b"akey")
+            .unwrap()
+            .is_none());
+        let val = byte_store::ByteStore::get(&ds, b"akey").unwrap().unwrap();
+        assert_eq!(val, b"value");
+
+        let (stored_key, _) = byte_store::Ordered::get_gt(ds.inner(), &[])
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored_key[0], b'a');
+        assert_ne!(&stored_key[1..], b"key");
+    }
+
+    #[test]
+    fn range() {
+        let ds = encrypted();
+        for key in 0..20u16 {
+            ds.insert::<u16, u16, u16>(&key, &(key * 2)).unwrap();
+        }
+        let entries: Vec<(u16, u16)> = ds
+            .range::<u16, u16, u16>(10..13)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(entries, [(10, 20), (11, 22), (12, 24)]);
+    }
+
+    #[test]
+    fn transaction() {
+        let ds = encrypted();
+        ds.transaction(|tx| {
+            tx.insert::<u16, u16, u16>(&1, &2)?;
+            Ok(())
+        })
+        .unwrap();
+        let val: Option<u16> = ds.get(&1u16).unwrap();
+        assert_eq!(val, Some(2));
+
+        let other = Encrypted::new(ds.inner().clone(), &EncryptionKey::generate());
+        let res = other.transaction(|tx| {
+            tx.insert::<u16, u16, u16>(&2, &4)?;
+            tx.get::<u16, u16>(&1u16)
+        });
+        assert!(matches!(res, Err(crate::Error::Database(Error::Decrypt))));
+        let val: Option<u16> = ds.get(&2u16).unwrap();
+        assert_eq!(val, None);
+    }
+}
@@ -0,0 +1,392 @@
+use core::fmt;
+use std::cell::RefCell;
+use std::ops::RangeBounds;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::traits::{byte_store, data_store, ByteStore};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error<E: fmt::Debug> {
+    #[error("the wrapped store returned an error: {0:?}")]
+    Store(E),
+    #[error("fault injected by the Faulty store")]
+    Injected,
+}
+
+impl<E: fmt::Debug> Error<E> {
+    /// Separates the errors of the wrapped store from injected ones
+    fn split(self) -> Result<E, Injected> {
+        match self {
+            Error::Store(e) => Ok(e),
+            Error::Injected => Err(Injected),
+        }
+    }
+}
+
+struct Injected;
+
+/// A small seeded random number generator (splitmix64). Good enough to
+/// pick which operations fail, and it keeps a failing test reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn next_f64(&mut self) -> f64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[derive(Default)]
+struct Faults {
+    /// Operations done so far
+    ops: u64,
+    /// The operation that will fail
+    nth: Option<u64>,
+    prefixes: Vec<Vec<u8>>,
+    random: Option<(f64, Rng)>,
+}
+
+impl Faults {
+    /// Counts the operation then decides if it fails
+    fn should_fail(&mut self, key: Option<&[u8]>) -> bool {
+        self.ops += 1;
+        if self.nth == Some(self.ops) {
+            self.nth = None;
+            return true;
+        }
+        if let Some(key) = key {
+            if self.prefixes.iter().any(|prefix| key.starts_with(prefix)) {
+                return true;
+            }
+        }
+        match &mut self.random {
+            Some((probability, rng)) => rng.next_f64() < *probability,
+            None => false,
+        }
+    }
+}
+
+/// Wraps any store, failing operations on request. Use it to test how your
+/// application handles database errors. For example what it does with the
+/// [`ExtendError`](crate::traits::ExtendError) returned when extending a
+/// `Vec` fails halfway.
+///
+/// All clones share the same faults. Keep a clone around to inject faults
+/// into a struct that owns the store. Every call to the store counts as one
+/// operation, as does every entry read while iterating over a range. Faults
+/// are injected before the wrapped store is called, a failed operation
+/// therefore did not change anything.
+///
+/// # Examples
+/// ```
+/// use dbstruct::stores::{BTreeMap, Faulty};
+///
+/// #[dbstruct::dbstruct(db=trait)]
+/// struct Test {
+///     list: Vec<u32>,
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let ds = Faulty::new(BTreeMap::new());
+/// let db = Test::open(ds.clone())?;
+///
+/// ds.fail_nth(3);
+/// let err = db.list().extend(&[1, 2, 3, 4]).unwrap_err();
+/// assert_eq!(err.unadded, &3);
+/// assert_eq!(err.iter.collect::<Vec<_>>(), [&4]);
+/// assert_eq!(db.list().len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Faulty<S> {
+    store: S,
+    faults: Arc<Mutex<Faults>>,
+}
+
+impl<S: Clone> Clone for Faulty<S> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            faults: self.faults.clone(),
+        }
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for Faulty<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Faulty")
+            .field("store", &self.store)
+            .field("ops", &self.ops())
+            .finish()
+    }
+}
+
+impl<S> Faulty<S> {
+    /// Does not inject any faults until configured to
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            faults: Arc::new(Mutex::new(Faults::default())),
+        }
+    }
+
+    /// The wrapped store
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    /// The number of operations done so far
+    pub fn ops(&self) -> u64 {
+        self.lock().ops
+    }
+
+    /// Fail the `n`th operation from now, `fail_nth(1)` fails the next one.
+    /// Only that operation fails. Replaces an earlier call that did not
+    /// trigger yet.
+    pub fn fail_nth(&self, n: u64) {
+        let mut faults = self.lock();
+        faults.nth = Some(faults.ops + n);
+    }
+
+    /// Fail every operation on a key starting with `prefix`. The first byte
+    /// of a key is the key of the field, `fail_prefix([2])` fails every
+    /// operation on the field with key 2.
+    pub fn fail_prefix(&self, prefix: impl Into<Vec<u8>>) {
+        self.lock().prefixes.push(prefix.into());
+    }
+
+    /// Fail each operation with chance `probability`, between 0 and 1. The
+    /// same seed fails the same operations.
+    pub fn fail_randomly(&self, probability: f64, seed: u64) {
+        self.lock().random = Some((probability, Rng(seed)));
+    }
+
+    /// Stop injecting faults
+    pub fn heal(&self) {
+        let mut faults = self.lock();
+        faults.nth = None;
+        faults.prefixes.clear();
+        faults.random = None;
+    }
+
+    /// A panic while holding the lock can not leave the faults inconsistent
+    fn lock(&self) -> MutexGuard<'_, Faults> {
+        self.faults.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn check<E: fmt::Debug>(&self, key: Option<&[u8]>) -> Result<(), Error<E>> {
+        if self.lock().should_fail(key) {
+            Err(Error::Injected)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<S: ByteStore> ByteStore for Faulty<S> {
+    type DbError = Error<S::DbError>;
+    type Bytes = S::Bytes;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.check(Some(key))?;
+        self.store.get(key).map_err(Error::Store)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.check(Some(key))?;
+        self.store.remove(key).map_err(Error::Store)
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.check(Some(key))?;
+        self.store.insert(key, val).map_err(Error::Store)
+    }
+}
+
+impl<S: byte_store::Atomic> byte_store::Atomic for Faulty<S> {
+    fn atomic_update(
+        &self,
+        key: &[u8],
+        op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>>,
+    ) -> Result<(), Self::DbError> {
+        self.check(Some(key))?;
+        self.store.atomic_update(key, op).map_err(Error::Store)
+    }
+
+    fn conditional_update(
+        &self,
+        key: &[u8],
+        new: &[u8],
+        expected: &[u8],
+    ) -> Result<(), Self::DbError> {
+        self.check(Some(key))?;
+        self.store
+            .conditional_update(key, new, expected)
+            .map_err(Error::Store)
+    }
+}
+
+impl<S: byte_store::Ordered> byte_store::Ordered for Faulty<S> {
+    fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        self.check(Some(key))?;
+        self.store.get_lt(key).map_err(Error::Store)
+    }
+
+    fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        self.check(Some(key))?;
+        self.store.get_gt(key).map_err(Error::Store)
+    }
+}
+
+impl<S> byte_store::Ranged for Faulty<S>
+where
+    S: byte_store::Ranged<Key = Vec<u8>> + Clone,
+{
+    type Key = Vec<u8>;
+    type Iter = RangeIter<S>;
+
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        RangeIter {
+            inner: self.store.range(range),
+            faulty: self.clone(),
+        }
+    }
+}
+
+/// Iterates over a range of the store wrapped by [`Faulty`]. Injects the
+/// faults as entries are read.
+pub struct RangeIter<S: byte_store::Ranged> {
+    inner: S::Iter,
+    faulty: Faulty<S>,
+}
+
+impl<S: byte_store::Ranged> Iterator for RangeIter<S> {
+    type Item = Result<(S::Bytes, S::Bytes), Error<S::DbError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, val) = match self.inner.next()? {
+            Ok(entry) => entry,
+            Err(e) => return Some(Err(Error::Store(e))),
+        };
+        Some(self.faulty.check(Some(key.as_ref())).map(|()| (key, val)))
+    }
+}
+
+/// Starting the transaction counts as an operation, as does every operation
+/// inside it. An injected fault aborts the transaction.
+impl<S> data_store::Transactional for Faulty<S>
+where
+    S: ByteStore + data_store::Transactional<DbError = <S as ByteStore>::DbError>,
+    for<'tx> S::Tx<'tx>: ByteStore<DbError = S::TxError>,
+{
+    type TxError = Error<S::TxError>;
+    type Tx<'tx> = Faulty<S::Tx<'tx>>;
+
+    fn transaction<R>(
+        &self,
+        op: impl Fn(&Self::Tx<'_>) -> Result<R, crate::Error<Self::TxError>>,
+    ) -> Result<R, crate::Error<Self::DbError>> {
+        self.check(None).map_err(crate::Error::Database)?;
+
+        // The wrapped store can not return our errors. On an injected fault
+        // we abort its transaction and return the fault once it is done.
+        let injected = RefCell::new(false);
+        let res = self.store.transaction(|tx| {
+            *injected.borrow_mut() = false;
+            let tx = Faulty {
+                store: tx.clone(),
+                faults: self.faults.clone(),
+            };
+            match op(&tx) {
+                Ok(res) => Ok(res),
+                Err(crate::Error::Database(e)) => match e.split() {
+                    Ok(e) => Err(crate::Error::Database(e)),
+                    Err(Injected) => {
+                        *injected.borrow_mut() = true;
+                        Err(crate::Error::TransactionAborted)
+                    }
+                },
+                Err(e) => Err(e.map_database(|_| unreachable!("handled above"))),
+            }
+        });
+        res.map_err(|e| {
+            if injected.into_inner() {
+                crate::Error::Database(Error::Injected)
+            } else {
+                e.map_database(Error::Store)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Faulty};
+    use crate::stores::BTreeMap;
+    use crate::traits::byte_store::{ByteStore, Ranged};
+    use crate::traits::data_store::Transactional;
+
+    #[test]
+    fn fails_nth_operation_once() {
+        let ds = Faulty::new(BTreeMap::new());
+        ds.insert(b"a", b"1").unwrap();
+        ds.fail_nth(2);
+        ds.get(b"a").unwrap();
+        assert!(matches!(ds.get(b"a"), Err(Error::Injected)));
+        ds.get(b"a").unwrap();
+        assert_eq!(ds.ops(), 4);
+    }
+
+    #[test]
+    fn fails_prefix() {
+        let ds = Faulty::new(BTreeMap::new());
+        ds.fail_prefix([2]);
+        ds.insert(&[1, 0], b"1").unwrap();
+        assert!(matches!(ds.insert(&[2, 0], b"2"), Err(Error::Injected)));
+        assert_eq!(ds.inner().get(&[2, 0]).unwrap(), None);
+
+        ds.heal();
+        ds.insert(&[2, 0], b"2").unwrap();
+    }
+
+    #[test]
+    fn random_faults_are_reproducible() {
+        let failures = |seed| {
+            let ds = Faulty::new(BTreeMap::new());
+            ds.fail_randomly(0.5, seed);
+            (0..64).map(|_| ds.get(b"a").is_err()).collect::<Vec<_>>()
+        };
+        let first = failures(42);
+        assert_eq!(first, failures(42));
+        assert!(first.contains(&true));
+        assert!(first.contains(&false));
+    }
+
+    #[test]
+    fn fails_while_iterating() {
+        let ds = Faulty::new(BTreeMap::new());
+        for key in [[1], [2], [3]] {
+            ds.insert(&key, b"").unwrap();
+        }
+        ds.fail_prefix([2]);
+        let res: Vec<_> = ds.range(..).map(|res| res.is_ok()).collect();
+        assert_eq!(res, [true, false, true]);
+    }
+
+    #[test]
+    fn fault_aborts_transaction() {
+        let ds = Faulty::new(BTreeMap::new());
+        ds.fail_prefix([2]);
+        let res = ds.transaction(|tx| {
+            tx.insert(&[1], &[1]).map_err(crate::Error::Database)?;
+            tx.insert(&[2], &[2]).map_err(crate::Error::Database)?;
+            Ok(())
+        });
+        assert!(matches!(res, Err(crate::Error::Database(Error::Injected))));
+        assert_eq!(ds.inner().get(&[1]).unwrap(), None);
+    }
+}
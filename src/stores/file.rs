@@ -0,0 +1,651 @@
+use std::collections::{self, VecDeque};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tracing::warn;
+
+use crate::traits::{byte_store, ByteStore};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("could not read or write the log file")]
+    Io(#[from] io::Error),
+    #[error("keys and values must be smaller then 4 GiB")]
+    TooLarge,
+    #[error("mutex was poisoned (another thread panicked while holding the log)")]
+    Poisoned,
+}
+
+/// A persistent store that needs no extra dependencies. This is the store
+/// behind `db=file`. Every change is appended to a single log file, the keys
+/// and where to find their values are kept in memory.
+///
+/// Overwritten and removed entries stay in the log until you call
+/// [`compact`](File::compact). Changes are handed to the OS right away, they
+/// survive your program crashing. Call [`flush`](File::flush) to also have
+/// them survive a power loss. A record that was only partly written when
+/// that happened is dropped when the log is opened again, together with
+/// everything after it. Opening stops at the first record whose checksum
+/// does not match.
+///
+/// Only open a log once at the time, opening it a second time, also from
+/// another process, corrupts it.
+#[derive(Clone)]
+pub struct File(Arc<Mutex<Log>>);
+
+impl File {
+    /// Opens the log at `path` creating it if it does not exist.
+    pub fn open_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let log = Log::open(path.as_ref().to_path_buf())?;
+        Ok(Self(Arc::new(Mutex::new(log))))
+    }
+
+    /// Rewrites the log keeping only the current value of each key. The new
+    /// log replaces the old one once it is complete.
+    pub fn compact(&self) -> Result<(), Error> {
+        self.lock()?.compact()
+    }
+
+    /// The number of bytes in the log taken up by entries that have been
+    /// overwritten or removed. These are freed by [`compact`](File::compact).
+    pub fn stale_bytes(&self) -> Result<u64, Error> {
+        Ok(self.lock()?.stale)
+    }
+
+    /// Waits till all changes have been written to disk.
+    pub fn flush(&self) -> Result<(), Error> {
+        Ok(self.lock()?.file.sync_data()?)
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Log>, Error> {
+        self.0.lock().map_err(|_| Error::Poisoned)
+    }
+}
+
+/// Each record starts with a header: a checksum over the rest of the record
+/// followed by the length of the key and of the value. The value length is
+/// [`TOMBSTONE`] for a removed key. All numbers are little endian.
+const HEADER_LEN: u64 = 12;
+const TOMBSTONE: u32 = u32::MAX;
+
+/// Where the value of a key is in the log
+#[derive(Clone, Copy)]
+struct Slot {
+    offset: u64,
+    len: u32,
+}
+
+impl Slot {
+    fn record_len(&self, key: &[u8]) -> u64 {
+        HEADER_LEN + key.len() as u64 + u64::from(self.len)
+    }
+}
+
+type Index = collections::BTreeMap<Vec<u8>, Slot>;
+
+struct Log {
+    path: PathBuf,
+    file: fs::File,
+    index: Index,
+    /// new records are written here
+    end: u64,
+    /// bytes taken by records that no longer matter
+    stale: u64,
+}
+
+impl Log {
+    fn open(path: PathBuf) -> Result<Self, Error> {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        let mut log = Self {
+            path,
+            file,
+            index: Index::new(),
+            end: 0,
+            stale: 0,
+        };
+        log.replay()?;
+        Ok(log)
+    }
+
+    /// Rebuilds the index from the log. A damaged record is the result of a
+    /// crash or failure during writing, it and everything after it is removed.
+    fn replay(&mut self) -> Result<(), Error> {
+        let len = self.file.metadata()?.len();
+        let mut reader = BufReader::new(&self.file);
+        let mut header = [0u8; HEADER_LEN as usize];
+
+        while self.end < len {
+            if self.end + HEADER_LEN > len {
+                break;
+            }
+            reader.read_exact(&mut header)?;
+            let [c0, c1, c2, c3, k0, k1, k2, k3, v0, v1, v2, v3] = header;
+            let checksum = u32::from_le_bytes([c0, c1, c2, c3]);
+            let key_len = u32::from_le_bytes([k0, k1, k2, k3]);
+            let val_len = u32::from_le_bytes([v0, v1, v2, v3]);
+
+            let body_len = u64::from(key_len)
+                + if val_len == TOMBSTONE {
+                    0
+                } else {
+                    u64::from(val_len)
+                };
+            let record_end = self.end + HEADER_LEN + body_len;
+            if record_end > len {
+                break;
+            }
+
+            let mut body = vec![0u8; body_len as usize];
+            reader.read_exact(&mut body)?;
+            if crc32(&[&header[4..], &body]) != checksum {
+                break;
+            }
+
+            body.truncate(key_len as usize);
+            let key = body;
+            if val_len == TOMBSTONE {
+                let old = self.index.remove(&key);
+                self.stale += record_end - self.end;
+                self.stale += old.map_or(0, |old| old.record_len(&key));
+            } else {
+                let slot = Slot {
+                    offset: self.end + HEADER_LEN + u64::from(key_len),
+                    len: val_len,
+                };
+                if let Some(old) = self.index.insert(key.clone(), slot) {
+                    self.stale += old.record_len(&key);
+                }
+            }
+            self.end = record_end;
+        }
+
+        if self.end < len {
+            warn!(
+                "dropping the last {} bytes of {}, they do not start with a complete record",
+                len - self.end,
+                self.path.display()
+            );
+            self.file.set_len(self.end)?;
+        }
+        Ok(())
+    }
+
+    fn read(&self, slot: Slot) -> Result<Vec<u8>, Error> {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(slot.offset))?;
+        let mut val = vec![0u8; slot.len as usize];
+        file.read_exact(&mut val)?;
+        Ok(val)
+    }
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        self.index.get(key).map(|slot| self.read(*slot)).transpose()
+    }
+
+    /// Appends a record to the log returning where its value starts. When
+    /// writing fails the next record overwrites whatever made it to disk,
+    /// anything left after the last record is dropped on the next open.
+    fn append(&mut self, key: &[u8], val: Option<&[u8]>) -> Result<u64, Error> {
+        let record = encode(key, val)?;
+        self.file.seek(SeekFrom::Start(self.end))?;
+        self.file.write_all(&record)?;
+        let offset = self.end + HEADER_LEN + key.len() as u64;
+        self.end += record.len() as u64;
+        Ok(offset)
+    }
+
+    fn insert(&mut self, key: &[u8], val: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let old = self.get(key)?;
+        let offset = self.append(key, Some(val))?;
+        let slot = Slot {
+            offset,
+            len: val.len() as u32,
+        };
+        if let Some(old) = self.index.insert(key.to_vec(), slot) {
+            self.stale += old.record_len(key);
+        }
+        Ok(old)
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let Some(old) = self.get(key)? else {
+            return Ok(None);
+        };
+        self.append(key, None)?;
+        if let Some(slot) = self.index.remove(key) {
+            self.stale += slot.record_len(key) + HEADER_LEN + key.len() as u64;
+        }
+        Ok(Some(old))
+    }
+
+    fn entry(&self, entry: Option<(&Vec<u8>, &Slot)>) -> Result<Option<Entry>, Error> {
+        entry
+            .map(|(key, slot)| Ok((key.clone(), self.read(*slot)?)))
+            .transpose()
+    }
+
+    fn compact(&mut self) -> Result<(), Error> {
+        let mut path = OsString::from(&self.path);
+        path.push(".compact");
+        let path = PathBuf::from(path);
+
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        let mut writer = BufWriter::new(&file);
+        let mut index = Index::new();
+        let mut end = 0;
+        for (key, slot) in &self.index {
+            let val = self.read(*slot)?;
+            let record = encode(key, Some(&val))?;
+            writer.write_all(&record)?;
+            let slot = Slot {
+                offset: end + HEADER_LEN + key.len() as u64,
+                len: slot.len,
+            };
+            index.insert(key.clone(), slot);
+            end += record.len() as u64;
+        }
+        writer.flush()?;
+        drop(writer);
+        file.sync_all()?;
+        fs::rename(&path, &self.path)?;
+        sync_dir(&self.path)?;
+
+        self.file = file;
+        self.index = index;
+        self.end = end;
+        self.stale = 0;
+        Ok(())
+    }
+}
+
+/// Makes a rename of the file at `path` survive a power loss
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::File::open(dir)?.sync_all()
+}
+
+/// Windows can not open directories, renames are made durable by the file
+/// system there
+#[cfg(not(unix))]
+fn sync_dir(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn encode(key: &[u8], val: Option<&[u8]>) -> Result<Vec<u8>, Error> {
+    let key_len = u32::try_from(key.len()).map_err(|_| Error::TooLarge)?;
+    let val_len = match val {
+        Some(val) => u32::try_from(val.len())
+            .ok()
+            .filter(|len| *len != TOMBSTONE)
+            .ok_or(Error::TooLarge)?,
+        None => TOMBSTONE,
+    };
+
+    let val = val.unwrap_or_default();
+    let mut record = Vec::with_capacity(HEADER_LEN as usize + key.len() + val.len());
+    record.extend_from_slice(&[0; 4]);
+    record.extend_from_slice(&key_len.to_le_bytes());
+    record.extend_from_slice(&val_len.to_le_bytes());
+    record.extend_from_slice(key);
+    record.extend_from_slice(val);
+    let checksum = crc32(&[&record[4..]]);
+    record[..4].copy_from_slice(&checksum.to_le_bytes());
+    Ok(record)
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 (as used by zip and ethernet) over all `parts` in order
+pub(super) fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = !0u32;
+    for byte in parts.iter().flat_map(|part| part.iter()) {
+        crc = CRC_TABLE[((crc ^ u32::from(*byte)) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+impl ByteStore for File {
+    type DbError = Error;
+    type Bytes = Vec<u8>;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.lock()?.get(key)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.lock()?.remove(key)
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.lock()?.insert(key, val)
+    }
+}
+
+impl byte_store::Atomic for File {
+    fn atomic_update(
+        &self,
+        key: &[u8],
+        mut op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>>,
+    ) -> Result<(), Self::DbError> {
+        let mut log = self.lock()?;
+        let current = log.get(key)?;
+        match op(current.as_deref()) {
+            Some(new) => log.insert(key, &new)?,
+            None => log.remove(key)?,
+        };
+        Ok(())
+    }
+
+    fn conditional_update(
+        &self,
+        key: &[u8],
+        new: &[u8],
+        expected: &[u8],
+    ) -> Result<(), Self::DbError> {
+        let mut log = self.lock()?;
+        if log.get(key)?.as_deref() == Some(expected) {
+            log.insert(key, new)?;
+        }
+        Ok(())
+    }
+}
+
+type Entry = (Vec<u8>, Vec<u8>);
+
+impl byte_store::Ordered for File {
+    fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let log = self.lock()?;
+        let range = (Bound::Unbounded, Bound::Excluded(key));
+        log.entry(log.index.range::<[u8], _>(range).next_back())
+    }
+
+    fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let log = self.lock()?;
+        let range = (Bound::Excluded(key), Bound::Unbounded);
+        log.entry(log.index.range::<[u8], _>(range).next())
+    }
+}
+
+impl byte_store::Ranged for File {
+    type Key = Vec<u8>;
+    type Iter = RangeIter;
+
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        RangeIter {
+            store: self.clone(),
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+            batch: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+/// Iterates over a range of a [`File`] store. The entries are read in small
+/// batches, each batch sees the latest state of the store.
+pub struct RangeIter {
+    store: File,
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+    batch: VecDeque<Entry>,
+    done: bool,
+}
+
+impl RangeIter {
+    const BATCH_SIZE: usize = 64;
+
+    fn read_batch(&mut self) -> Result<(), Error> {
+        if super::is_empty_range(self.start.as_ref(), self.end.as_ref()) {
+            self.done = true;
+            return Ok(());
+        }
+        let log = self.store.lock()?;
+        let range = (self.start.clone(), self.end.clone());
+        for (key, slot) in log.index.range(range).take(Self::BATCH_SIZE) {
+            self.batch.push_back((key.clone(), log.read(*slot)?));
+        }
+
+        match self.batch.back() {
+            Some((last, _)) if self.batch.len() == Self::BATCH_SIZE => {
+                self.start = Bound::Excluded(last.clone());
+            }
+            _ => self.done = true,
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for RangeIter {
+    type Item = Result<Entry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.batch.is_empty() && !self.done {
+            if let Err(e) = self.read_batch() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        self.batch.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byte_store::{Atomic, Ordered, Ranged};
+
+    fn store() -> (tempdir::TempDir, PathBuf, File) {
+        let dir = tempdir::TempDir::new("dbstruct_file").unwrap();
+        let path = dir.path().join("log");
+        let store = File::open_path(&path).unwrap();
+        (dir, path, store)
+    }
+
+    #[test]
+    fn checksum() {
+        assert_eq!(crc32(&[b"1234", b"56789"]), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn insert_returns_old_value() {
+        let (_dir, _, store) = store();
+        assert_eq!(store.insert(&[1], &[2]).unwrap(), None);
+        assert_eq!(store.insert(&[1], &[3]).unwrap(), Some(vec![2]));
+        assert_eq!(store.remove(&[1]).unwrap(), Some(vec![3]));
+        assert_eq!(store.remove(&[1]).unwrap(), None);
+        assert_eq!(store.get(&[1]).unwrap(), None);
+    }
+
+    #[test]
+    fn reopen() {
+        let (_dir, path, store) = store();
+        store.insert(&[1], &[1]).unwrap();
+        store.insert(&[2], &[2]).unwrap();
+        store.insert(&[1], &[3]).unwrap();
+        store.remove(&[2]).unwrap();
+        let stale = store.stale_bytes().unwrap();
+        drop(store);
+
+        let store = File::open_path(&path).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![3]));
+        assert_eq!(store.get(&[2]).unwrap(), None);
+        assert_eq!(store.stale_bytes().unwrap(), stale);
+    }
+
+    #[test]
+    fn partly_written_record_is_dropped() {
+        let (_dir, path, store) = store();
+        store.insert(&[1], &[1]).unwrap();
+        store.insert(&[2], &[2, 2, 2]).unwrap();
+        drop(store);
+
+        let len = fs::metadata(&path).unwrap().len();
+        let file = fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(len - 2).unwrap();
+        drop(file);
+
+        let store = File::open_path(&path).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![1]));
+        assert_eq!(store.get(&[2]).unwrap(), None);
+        store.insert(&[3], &[3]).unwrap();
+        drop(store);
+
+        let store = File::open_path(&path).unwrap();
+        assert_eq!(store.get(&[3]).unwrap(), Some(vec![3]));
+    }
+
+    #[test]
+    fn log_is_cut_at_a_corrupt_record() {
+        let (_dir, path, store) = store();
+        store.insert(&[1], &[1]).unwrap();
+        store.insert(&[2], &[2]).unwrap();
+        store.insert(&[3], &[3]).unwrap();
+        drop(store);
+
+        let record_len = HEADER_LEN as usize + 2;
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[record_len + HEADER_LEN as usize + 1] ^= 0xFF;
+        fs::write(&path, bytes).unwrap();
+
+        let store = File::open_path(&path).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![1]));
+        assert_eq!(store.get(&[2]).unwrap(), None);
+        assert_eq!(store.get(&[3]).unwrap(), None);
+        assert_eq!(fs::metadata(&path).unwrap().len(), record_len as u64);
+    }
+
+    #[test]
+    fn torn_record_behind_a_shorter_one_is_dropped() {
+        let (_dir, path, store) = store();
+        store.insert(&[1], &[1]).unwrap();
+
+        // a long record of which only the start made it to disk
+        let torn = encode(&[2], Some(&[0; 64])).unwrap();
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&torn[..40]).unwrap();
+        drop(file);
+
+        store.insert(&[3], &[3]).unwrap();
+        drop(store);
+
+        let store = File::open_path(&path).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![1]));
+        assert_eq!(store.get(&[2]).unwrap(), None);
+        assert_eq!(store.get(&[3]).unwrap(), Some(vec![3]));
+    }
+
+    #[test]
+    fn compact() {
+        let (_dir, path, store) = store();
+        for i in 0..10u8 {
+            store.insert(&[1], &[i]).unwrap();
+            store.insert(&[i], &[i]).unwrap();
+        }
+        store.remove(&[5]).unwrap();
+        let len = fs::metadata(&path).unwrap().len();
+        assert!(store.stale_bytes().unwrap() > 0);
+
+        store.compact().unwrap();
+        assert_eq!(store.stale_bytes().unwrap(), 0);
+        assert!(fs::metadata(&path).unwrap().len() < len);
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![9]));
+        store.insert(&[5], &[5]).unwrap();
+        drop(store);
+
+        let store = File::open_path(&path).unwrap();
+        let keys: Vec<_> = store.range(..).map(|entry| entry.unwrap().0[0]).collect();
+        assert_eq!(keys, (0..10).collect::<Vec<_>>());
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![9]));
+    }
+
+    #[test]
+    fn get_lt_and_gt_skip_the_key() {
+        let (_dir, _, store) = store();
+        for key in [1u8, 2, 3] {
+            store.insert(&[key], &[key]).unwrap();
+        }
+        assert_eq!(store.get_lt(&[2]).unwrap(), Some((vec![1], vec![1])));
+        assert_eq!(store.get_gt(&[2]).unwrap(), Some((vec![3], vec![3])));
+        assert_eq!(store.get_lt(&[1]).unwrap(), None);
+        assert_eq!(store.get_gt(&[3]).unwrap(), None);
+    }
+
+    #[test]
+    fn range() {
+        let (_dir, _, store) = store();
+        for key in 0..200u8 {
+            store.insert(&[key], &[]).unwrap();
+        }
+        let keys: Vec<_> = store
+            .range(vec![5]..=vec![150])
+            .map(|entry| entry.unwrap().0[0])
+            .collect();
+        assert_eq!(keys, (5..=150).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn inverted_range_is_empty() {
+        let (_dir, _, store) = store();
+        for key in 0..200u8 {
+            store.insert(&[key], &[]).unwrap();
+        }
+        let inverted = (Bound::Included(vec![150]), Bound::Excluded(vec![5]));
+        assert_eq!(store.range(inverted).count(), 0);
+        let point = (Bound::Excluded(vec![5]), Bound::Excluded(vec![5]));
+        assert_eq!(store.range(point).count(), 0);
+        // the store is still usable afterwards
+        assert_eq!(store.get(&[5]).unwrap(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn atomic_update() {
+        let (_dir, _, store) = store();
+        store.insert(&[1], &[1]).unwrap();
+        store
+            .atomic_update(&[1], |old| old.map(|v| vec![v[0] + 1]))
+            .unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![2]));
+        store.conditional_update(&[1], &[5], &[9]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![2]));
+        store.conditional_update(&[1], &[5], &[2]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![5]));
+        store.atomic_update(&[1], |_| None).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), None);
+    }
+}
@@ -0,0 +1,318 @@
+use std::cell::RefCell;
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+
+use fjall::{
+    Config, KvPair, PartitionCreateOptions, Slice, TxKeyspace, TxPartitionHandle, WriteTransaction,
+};
+
+use crate::traits::{byte_store, data_store, ByteStore};
+
+/// The partition used by the generated `open_path` and `open_keyspace`
+pub const DEFAULT_PARTITION: &str = "DbStruct";
+
+/// Errors returned by [`Fjall`], the fjall error is boxed as it is large
+#[derive(thiserror::Error, Debug)]
+#[error(transparent)]
+pub struct Error(Box<fjall::Error>);
+
+impl Error {
+    pub fn into_inner(self) -> fjall::Error {
+        *self.0
+    }
+}
+
+impl From<fjall::Error> for Error {
+    fn from(e: fjall::Error) -> Self {
+        Self(Box::new(e))
+    }
+}
+
+/// A partition in a [fjall](https://crates.io/crates/fjall) keyspace. This
+/// is the store behind `db=fjall`. Fjall is a log-structured merge tree, it
+/// suits write heavy use such as appending to a `VecDeque`. Multiple structs
+/// can share one keyspace by each using their own partition.
+///
+/// The keyspace must be transactional, open it using
+/// [`Config::open_transactional`]. Every change outside a transaction is
+/// committed on its own. Fjall allows only one write transaction at the time,
+/// writes wait for any running transaction to finish. Reads return
+/// [`Slice`]s, they are reference counted and do not copy.
+///
+/// Needs the `fjall` feature.
+#[derive(Clone)]
+pub struct Fjall {
+    keyspace: TxKeyspace,
+    partition: TxPartitionHandle,
+}
+
+impl Fjall {
+    /// Opens the keyspace in the directory `path` creating it if it does not
+    /// exist. Uses the partition [`DEFAULT_PARTITION`].
+    pub fn open_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let keyspace = Config::new(path).open_transactional()?;
+        Self::open_partition(keyspace, DEFAULT_PARTITION)
+    }
+
+    /// Use partition `name` of `keyspace`, it is created if it does not
+    /// exist.
+    ///
+    /// # Panics
+    /// Fjall panics if the name is empty, longer than 255 characters or
+    /// contains something other than alphanumerics, `_`, `-`, `#` and `$`.
+    pub fn open_partition(keyspace: TxKeyspace, name: &str) -> Result<Self, Error> {
+        let partition = keyspace.open_partition(name, PartitionCreateOptions::default())?;
+        Ok(Self {
+            keyspace,
+            partition,
+        })
+    }
+
+    /// The keyspace this partition is part of.
+    pub fn keyspace(&self) -> &TxKeyspace {
+        &self.keyspace
+    }
+
+    /// Runs `op` in a write transaction and commits
+    fn write<R>(
+        &self,
+        op: impl FnOnce(&mut WriteTransaction<'_>) -> Result<R, fjall::Error>,
+    ) -> Result<R, Error> {
+        let mut tx = self.keyspace.write_tx();
+        let res = op(&mut tx)?;
+        tx.commit()?;
+        Ok(res)
+    }
+}
+
+fn insert(
+    tx: &mut WriteTransaction,
+    partition: &TxPartitionHandle,
+    key: &[u8],
+    val: &[u8],
+) -> Result<Option<Slice>, fjall::Error> {
+    let old = tx.get(partition, key)?;
+    tx.insert(partition, key, val);
+    Ok(old)
+}
+
+impl ByteStore for Fjall {
+    type DbError = Error;
+    type Bytes = Slice;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Ok(self.partition.get(key)?)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.write(|tx| tx.take(&self.partition, key))
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.write(|tx| insert(tx, &self.partition, key, val))
+    }
+}
+
+impl byte_store::Atomic for Fjall {
+    fn atomic_update(
+        &self,
+        key: &[u8],
+        mut op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>>,
+    ) -> Result<(), Self::DbError> {
+        self.write(|tx| {
+            let current = tx.get(&self.partition, key)?;
+            match op(current.as_deref()) {
+                Some(new) => tx.insert(&self.partition, key, new),
+                None => tx.remove(&self.partition, key),
+            }
+            Ok(())
+        })
+    }
+
+    fn conditional_update(
+        &self,
+        key: &[u8],
+        new: &[u8],
+        expected: &[u8],
+    ) -> Result<(), Self::DbError> {
+        self.write(|tx| {
+            let current = tx.get(&self.partition, key)?;
+            if current.as_deref() == Some(expected) {
+                tx.insert(&self.partition, key, new);
+            }
+            Ok(())
+        })
+    }
+}
+
+impl byte_store::Ordered for Fjall {
+    fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let read = self.keyspace.read_tx();
+        let entry = read.range::<&[u8], _>(&self.partition, ..key).next_back();
+        Ok(entry.transpose()?)
+    }
+
+    fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let read = self.keyspace.read_tx();
+        let range = (Bound::Excluded(key), Bound::Unbounded);
+        let entry = read.range::<&[u8], _>(&self.partition, range).next();
+        Ok(entry.transpose()?)
+    }
+}
+
+impl byte_store::Ranged for Fjall {
+    type Key = Vec<u8>;
+    type Iter = RangeIter;
+
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+        let entries = self.keyspace.read_tx().range(&self.partition, bounds);
+        RangeIter(Box::new(entries))
+    }
+}
+
+/// Iterates over a range of a [`Fjall`] partition. The iterator sees the
+/// partition as it was when the iterator was created.
+pub struct RangeIter(Box<dyn Iterator<Item = fjall::Result<KvPair>>>);
+
+impl Iterator for RangeIter {
+    type Item = Result<(Slice, Slice), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.0.next()?;
+        Some(entry.map_err(Error::from))
+    }
+}
+
+/// Access to the write transaction of [`FjallTx`]. The transaction borrows
+/// the keyspace, behind this trait that lifetime does not show up in the
+/// type of [`FjallTx`].
+trait WriteTxn {
+    fn get(&self, key: &[u8]) -> Result<Option<Slice>, fjall::Error>;
+    fn remove(&self, key: &[u8]) -> Result<Option<Slice>, fjall::Error>;
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Slice>, fjall::Error>;
+}
+
+impl WriteTxn for (&TxPartitionHandle, RefCell<WriteTransaction<'_>>) {
+    fn get(&self, key: &[u8]) -> Result<Option<Slice>, fjall::Error> {
+        let (partition, tx) = self;
+        tx.borrow().get(partition, key)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Slice>, fjall::Error> {
+        let (partition, tx) = self;
+        tx.borrow_mut().take(partition, key)
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Slice>, fjall::Error> {
+        let (partition, tx) = self;
+        insert(&mut tx.borrow_mut(), partition, key, val)
+    }
+}
+
+/// The data store used inside a transaction on a [`Fjall`].
+#[derive(Clone)]
+pub struct FjallTx<'tx>(&'tx dyn WriteTxn);
+
+impl ByteStore for FjallTx<'_> {
+    type DbError = Error;
+    type Bytes = Slice;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Ok(self.0.get(key)?)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Ok(self.0.remove(key)?)
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Ok(self.0.insert(key, val)?)
+    }
+}
+
+/// Fjall runs one write transaction at the time so `op` runs exactly once.
+/// Do not use the store outside of the transaction from within `op`, that
+/// will deadlock.
+impl data_store::Transactional for Fjall {
+    type TxError = Error;
+    type Tx<'tx> = FjallTx<'tx>;
+
+    fn transaction<R>(
+        &self,
+        op: impl Fn(&Self::Tx<'_>) -> Result<R, crate::Error<Self::TxError>>,
+    ) -> Result<R, crate::Error<Self::DbError>> {
+        let state = (&self.partition, RefCell::new(self.keyspace.write_tx()));
+        // dropping the transaction rolls it back
+        let res = op(&FjallTx(&state))?;
+        let (_, tx) = state;
+        tx.into_inner()
+            .commit()
+            .map_err(|e| crate::Error::Database(Error::from(e)))?;
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byte_store::{Atomic, Ordered, Ranged};
+
+    fn store() -> (tempdir::TempDir, Fjall) {
+        let dir = tempdir::TempDir::new("dbstruct_fjall").unwrap();
+        let store = Fjall::open_path(dir.path().join("db")).unwrap();
+        (dir, store)
+    }
+
+    fn owned(entry: Option<(Slice, Slice)>) -> Option<(Vec<u8>, Vec<u8>)> {
+        entry.map(|(key, val)| (key.to_vec(), val.to_vec()))
+    }
+
+    #[test]
+    fn insert_returns_old_value() {
+        let (_dir, store) = store();
+        assert!(store.insert(&[1], &[2]).unwrap().is_none());
+        assert_eq!(&*store.insert(&[1], &[3]).unwrap().unwrap(), &[2]);
+        assert_eq!(&*store.remove(&[1]).unwrap().unwrap(), &[3]);
+        assert!(store.get(&[1]).unwrap().is_none());
+    }
+
+    #[test]
+    fn get_lt_and_gt_skip_the_key() {
+        let (_dir, store) = store();
+        for key in [1u8, 2, 3] {
+            store.insert(&[key], &[key]).unwrap();
+        }
+        assert_eq!(owned(store.get_lt(&[2]).unwrap()), Some((vec![1], vec![1])));
+        assert_eq!(owned(store.get_gt(&[2]).unwrap()), Some((vec![3], vec![3])));
+        assert!(store.get_lt(&[1]).unwrap().is_none());
+        assert!(store.get_gt(&[3]).unwrap().is_none());
+    }
+
+    #[test]
+    fn range() {
+        let (_dir, store) = store();
+        for key in 0..200u8 {
+            store.insert(&[key], &[]).unwrap();
+        }
+        let keys: Vec<_> = store
+            .range(vec![10]..vec![150])
+            .map(|entry| entry.unwrap().0[0])
+            .collect();
+        assert_eq!(keys, (10..150).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn atomic_update() {
+        let (_dir, store) = store();
+        store.insert(&[1], &[1]).unwrap();
+        store
+            .atomic_update(&[1], |old| old.map(|v| vec![v[0] + 1]))
+            .unwrap();
+        assert_eq!(&*store.get(&[1]).unwrap().unwrap(), &[2]);
+        store.conditional_update(&[1], &[5], &[9]).unwrap();
+        assert_eq!(&*store.get(&[1]).unwrap().unwrap(), &[2]);
+        store.conditional_update(&[1], &[5], &[2]).unwrap();
+        assert_eq!(&*store.get(&[1]).unwrap().unwrap(), &[5]);
+    }
+}
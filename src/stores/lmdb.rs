@@ -0,0 +1,461 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+use std::rc::Rc;
+
+use heed::types::Bytes;
+use heed::{Env, EnvOpenOptions, RoTxn, RwTxn};
+
+use crate::traits::{byte_store, data_store, ByteStore};
+
+/// The database used by the generated `open_path` and `open_env`
+pub const DEFAULT_DATABASE: &str = "DbStruct";
+/// The size of the memory map used by [`Lmdb::open_path`], this is the
+/// maximum size of the database. Use [`Lmdb::open_database`] with your own
+/// [`Env`] to change it.
+pub const DEFAULT_MAP_SIZE: usize = 1 << 30;
+/// The number of named databases [`Lmdb::open_path`] allows
+pub const DEFAULT_MAX_DBS: u32 = 64;
+
+type Database = heed::Database<Bytes, Bytes>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("lmdb returned an error")]
+    Lmdb(#[source] Box<heed::Error>),
+    #[error("could not create the directory for the database")]
+    CreateDir(#[source] std::io::Error),
+}
+
+impl From<heed::Error> for Error {
+    fn from(e: heed::Error) -> Self {
+        Self::Lmdb(Box::new(e))
+    }
+}
+
+/// A named database in an [LMDB](http://www.lmdb.tech/doc/) environment
+/// accessed through [heed](https://crates.io/crates/heed). This is the store
+/// behind `db=lmdb`. Multiple structs can share one environment by each
+/// using their own database.
+///
+/// Reads do not copy, the returned [`LmdbBytes`] point into LMDB's memory
+/// map. Writes are serialized by LMDB, they wait for any running
+/// transaction to finish.
+///
+/// Needs the `lmdb` feature.
+#[derive(Clone)]
+pub struct Lmdb {
+    env: Env,
+    db: Database,
+}
+
+impl Lmdb {
+    /// Opens the environment in the directory `path` creating it if it does
+    /// not exist. Uses the database [`DEFAULT_DATABASE`].
+    ///
+    /// The environment can grow to [`DEFAULT_MAP_SIZE`].
+    pub fn open_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        std::fs::create_dir_all(&path).map_err(Error::CreateDir)?;
+        let mut options = EnvOpenOptions::new();
+        options.map_size(DEFAULT_MAP_SIZE).max_dbs(DEFAULT_MAX_DBS);
+        // Safety: the memory map is only unsafe to use if the files are
+        // changed by something other than LMDB. We do not do that and leave
+        // locking to LMDB.
+        let env = unsafe { options.open(path)? };
+        Self::open_database(env, DEFAULT_DATABASE)
+    }
+
+    /// Use database `name` in `env`, it is created if it does not exist.
+    /// The environment must allow named databases, see
+    /// [`EnvOpenOptions::max_dbs`].
+    pub fn open_database(env: Env, name: &str) -> Result<Self, Error> {
+        let mut txn = env.write_txn()?;
+        let db = env.create_database(&mut txn, Some(name))?;
+        txn.commit()?;
+        Ok(Self { env, db })
+    }
+
+    /// The environment this database is part of.
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    fn read_txn(&self) -> Result<Rc<RoTxn<'static>>, Error> {
+        Ok(Rc::new(self.env.clone().static_read_txn()?))
+    }
+
+    /// Runs `op` in a write transaction and commits
+    fn write<R>(
+        &self,
+        op: impl FnOnce(&mut RwTxn<'_>) -> Result<R, heed::Error>,
+    ) -> Result<R, Error> {
+        let mut txn = self.env.write_txn()?;
+        let res = op(&mut txn)?;
+        txn.commit()?;
+        Ok(res)
+    }
+}
+
+/// Bytes read from an [`Lmdb`] store. They point into the memory map of
+/// LMDB and keep the read transaction they came from open. Drop them soon,
+/// a long running read transaction keeps LMDB from reusing freed pages.
+///
+/// Every read starts its own transaction, so a thread can hold values from
+/// multiple reads. The environment limits how many read transactions are
+/// open at once, see [`EnvOpenOptions::max_readers`].
+pub struct LmdbBytes(Repr);
+
+enum Repr {
+    Mapped {
+        bytes: *const [u8],
+        _txn: Rc<RoTxn<'static>>,
+    },
+    /// Previous values returned by writes, the write transaction has ended
+    /// so these are copied.
+    Owned(Vec<u8>),
+}
+
+impl LmdbBytes {
+    fn mapped(bytes: &[u8], txn: &Rc<RoTxn<'static>>) -> Self {
+        Self(Repr::Mapped {
+            bytes,
+            _txn: Rc::clone(txn),
+        })
+    }
+}
+
+impl AsRef<[u8]> for LmdbBytes {
+    fn as_ref(&self) -> &[u8] {
+        match &self.0 {
+            // Safety: LMDB keeps data returned in a read transaction valid
+            // until the transaction ends. The transaction is kept open by
+            // `_txn` and owns a handle to the environment keeping the memory
+            // map alive.
+            Repr::Mapped { bytes, .. } => unsafe { &**bytes },
+            Repr::Owned(bytes) => bytes,
+        }
+    }
+}
+
+fn mapped_entry(
+    entry: Option<(&[u8], &[u8])>,
+    txn: &Rc<RoTxn<'static>>,
+) -> Option<(LmdbBytes, LmdbBytes)> {
+    entry.map(|(key, val)| (LmdbBytes::mapped(key, txn), LmdbBytes::mapped(val, txn)))
+}
+
+fn owned(bytes: Option<&[u8]>) -> Option<LmdbBytes> {
+    bytes.map(|bytes| LmdbBytes(Repr::Owned(bytes.to_vec())))
+}
+
+fn remove(db: &Database, txn: &mut RwTxn, key: &[u8]) -> Result<Option<LmdbBytes>, heed::Error> {
+    let old = owned(db.get(txn, key)?);
+    db.delete(txn, key)?;
+    Ok(old)
+}
+
+fn insert(
+    db: &Database,
+    txn: &mut RwTxn,
+    key: &[u8],
+    val: &[u8],
+) -> Result<Option<LmdbBytes>, heed::Error> {
+    let old = owned(db.get(txn, key)?);
+    db.put(txn, key, val)?;
+    Ok(old)
+}
+
+impl ByteStore for Lmdb {
+    type DbError = Error;
+    type Bytes = LmdbBytes;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let txn = self.read_txn()?;
+        let val = self.db.get(&txn, key)?;
+        Ok(val.map(|val| LmdbBytes::mapped(val, &txn)))
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.write(|txn| remove(&self.db, txn, key))
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.write(|txn| insert(&self.db, txn, key, val))
+    }
+}
+
+impl byte_store::Atomic for Lmdb {
+    fn atomic_update(
+        &self,
+        key: &[u8],
+        mut op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>>,
+    ) -> Result<(), Self::DbError> {
+        self.write(|txn| {
+            let new = op(self.db.get(txn, key)?);
+            match new {
+                Some(new) => self.db.put(txn, key, &new)?,
+                None => {
+                    self.db.delete(txn, key)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn conditional_update(
+        &self,
+        key: &[u8],
+        new: &[u8],
+        expected: &[u8],
+    ) -> Result<(), Self::DbError> {
+        self.write(|txn| {
+            if self.db.get(txn, key)? == Some(expected) {
+                self.db.put(txn, key, new)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl byte_store::Ordered for Lmdb {
+    fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let txn = self.read_txn()?;
+        let entry = self.db.get_lower_than(&txn, key)?;
+        Ok(mapped_entry(entry, &txn))
+    }
+
+    fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let txn = self.read_txn()?;
+        let entry = self.db.get_greater_than(&txn, key)?;
+        Ok(mapped_entry(entry, &txn))
+    }
+}
+
+impl byte_store::Ranged for Lmdb {
+    type Key = Vec<u8>;
+    type Iter = RangeIter;
+
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        RangeIter {
+            store: self.clone(),
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+            batch: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+/// Iterates over a range of an [`Lmdb`] database. The entries are read in
+/// small batches, each in its own read transaction.
+pub struct RangeIter {
+    store: Lmdb,
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+    batch: VecDeque<(LmdbBytes, LmdbBytes)>,
+    done: bool,
+}
+
+impl RangeIter {
+    const BATCH_SIZE: usize = 64;
+
+    fn read_batch(&mut self) -> Result<(), Error> {
+        let txn = self.store.read_txn()?;
+        let range = (as_slice(self.start.as_ref()), as_slice(self.end.as_ref()));
+        for entry in self.store.db.range(&txn, &range)?.take(Self::BATCH_SIZE) {
+            let (key, val) = entry?;
+            self.batch
+                .push_back((LmdbBytes::mapped(key, &txn), LmdbBytes::mapped(val, &txn)));
+        }
+
+        match self.batch.back() {
+            Some((last, _)) if self.batch.len() == Self::BATCH_SIZE => {
+                self.start = Bound::Excluded(last.as_ref().to_vec());
+            }
+            _ => self.done = true,
+        }
+        Ok(())
+    }
+}
+
+fn as_slice(bound: Bound<&Vec<u8>>) -> Bound<&[u8]> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.as_slice()),
+        Bound::Excluded(key) => Bound::Excluded(key.as_slice()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+impl Iterator for RangeIter {
+    type Item = Result<(LmdbBytes, LmdbBytes), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.batch.is_empty() && !self.done {
+            if let Err(e) = self.read_batch() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        self.batch.pop_front().map(Ok)
+    }
+}
+
+/// Access to the write transaction of [`LmdbTx`]. The transaction borrows
+/// the environment, behind this trait that lifetime does not show up in the
+/// type of [`LmdbTx`].
+trait WriteTxn {
+    fn get(&self, key: &[u8]) -> Result<Option<LmdbBytes>, heed::Error>;
+    fn remove(&self, key: &[u8]) -> Result<Option<LmdbBytes>, heed::Error>;
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<LmdbBytes>, heed::Error>;
+}
+
+impl WriteTxn for (Database, RefCell<RwTxn<'_>>) {
+    fn get(&self, key: &[u8]) -> Result<Option<LmdbBytes>, heed::Error> {
+        let (db, txn) = self;
+        Ok(owned(db.get(&txn.borrow(), key)?))
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<LmdbBytes>, heed::Error> {
+        let (db, txn) = self;
+        remove(db, &mut txn.borrow_mut(), key)
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<LmdbBytes>, heed::Error> {
+        let (db, txn) = self;
+        insert(db, &mut txn.borrow_mut(), key, val)
+    }
+}
+
+/// The data store used inside a transaction on an [`Lmdb`].
+#[derive(Clone)]
+pub struct LmdbTx<'tx>(&'tx dyn WriteTxn);
+
+impl ByteStore for LmdbTx<'_> {
+    type DbError = Error;
+    type Bytes = LmdbBytes;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Ok(self.0.get(key)?)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Ok(self.0.remove(key)?)
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Ok(self.0.insert(key, val)?)
+    }
+}
+
+/// LMDB runs one write transaction at the time so `op` runs exactly once.
+/// Do not use the store outside of the transaction from within `op`, that
+/// will deadlock.
+impl data_store::Transactional for Lmdb {
+    type TxError = Error;
+    type Tx<'tx> = LmdbTx<'tx>;
+
+    fn transaction<R>(
+        &self,
+        op: impl Fn(&Self::Tx<'_>) -> Result<R, crate::Error<Self::TxError>>,
+    ) -> Result<R, crate::Error<Self::DbError>> {
+        let database = |e: heed::Error| crate::Error::Database(Error::from(e));
+        let txn = self.env.write_txn().map_err(database)?;
+        let state = (self.db, RefCell::new(txn));
+        // dropping the transaction aborts it
+        let res = op(&LmdbTx(&state))?;
+        let (_, txn) = state;
+        txn.into_inner().commit().map_err(database)?;
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byte_store::{Atomic, Ordered, Ranged};
+
+    fn store() -> (tempdir::TempDir, Lmdb) {
+        let dir = tempdir::TempDir::new("dbstruct_lmdb").unwrap();
+        let store = Lmdb::open_path(dir.path().join("db")).unwrap();
+        (dir, store)
+    }
+
+    fn owned(entry: Option<(LmdbBytes, LmdbBytes)>) -> Option<(Vec<u8>, Vec<u8>)> {
+        entry.map(|(key, val)| (key.as_ref().to_vec(), val.as_ref().to_vec()))
+    }
+
+    #[test]
+    fn insert_returns_old_value() {
+        let (_dir, store) = store();
+        assert!(store.insert(&[1], &[2]).unwrap().is_none());
+        let old = store.insert(&[1], &[3]).unwrap().unwrap();
+        assert_eq!(old.as_ref(), &[2]);
+        let removed = store.remove(&[1]).unwrap().unwrap();
+        assert_eq!(removed.as_ref(), &[3]);
+        assert!(store.get(&[1]).unwrap().is_none());
+    }
+
+    #[test]
+    fn read_stays_valid_after_write() {
+        let (_dir, store) = store();
+        store.insert(&[1], &[1; 100]).unwrap();
+        let read = store.get(&[1]).unwrap().unwrap();
+        store.insert(&[1], &[2; 100]).unwrap();
+        store.remove(&[1]).unwrap();
+        assert_eq!(read.as_ref(), &[1; 100]);
+    }
+
+    #[test]
+    fn hold_value_across_reads() {
+        let (_dir, store) = store();
+        for key in [1u8, 2, 3] {
+            store.insert(&[key], &[key]).unwrap();
+        }
+        let first = store.get(&[1]).unwrap().unwrap();
+        let next = store.get_gt(first.as_ref()).unwrap();
+        let last = store.get_lt(&[4]).unwrap();
+        assert_eq!(owned(next), Some((vec![2], vec![2])));
+        assert_eq!(owned(last), Some((vec![3], vec![3])));
+        assert_eq!(first.as_ref(), &[1]);
+    }
+
+    #[test]
+    fn get_lt_and_gt_skip_the_key() {
+        let (_dir, store) = store();
+        for key in [1u8, 2, 3] {
+            store.insert(&[key], &[key]).unwrap();
+        }
+        assert_eq!(owned(store.get_lt(&[2]).unwrap()), Some((vec![1], vec![1])));
+        assert_eq!(owned(store.get_gt(&[2]).unwrap()), Some((vec![3], vec![3])));
+        assert!(store.get_lt(&[1]).unwrap().is_none());
+        assert!(store.get_gt(&[3]).unwrap().is_none());
+    }
+
+    #[test]
+    fn range_spans_batches() {
+        let (_dir, store) = store();
+        for key in 0..200u8 {
+            store.insert(&[key], &[]).unwrap();
+        }
+        let keys: Vec<_> = store
+            .range(vec![10]..vec![150])
+            .map(|entry| entry.unwrap().0.as_ref()[0])
+            .collect();
+        assert_eq!(keys, (10..150).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn atomic_update() {
+        let (_dir, store) = store();
+        store.insert(&[1], &[1]).unwrap();
+        store
+            .atomic_update(&[1], |old| old.map(|v| vec![v[0] + 1]))
+            .unwrap();
+        assert_eq!(store.get(&[1]).unwrap().unwrap().as_ref(), &[2]);
+        store.conditional_update(&[1], &[5], &[9]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap().unwrap().as_ref(), &[2]);
+        store.conditional_update(&[1], &[5], &[2]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap().unwrap().as_ref(), &[5]);
+    }
+}
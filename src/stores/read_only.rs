@@ -0,0 +1,125 @@
+use core::fmt;
+use std::ops::RangeBounds;
+
+use crate::traits::{byte_store, ByteStore};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error<E: fmt::Debug> {
+    #[error("the wrapped store returned an error: {0:?}")]
+    Store(E),
+    #[error("the store is read only, refused to write to it")]
+    ReadOnly,
+}
+
+/// Wraps any store refusing every write to it. This is the store used by
+/// `#[dbstruct(readonly)]`, there the wrappers have no methods that write.
+/// This store makes sure nothing slips through regardless.
+///
+/// It does not implement the `Atomic` and `Transactional` traits, the
+/// wrappers that need those are not available.
+#[derive(Debug, Clone)]
+pub struct ReadOnly<S> {
+    store: S,
+}
+
+impl<S> ReadOnly<S> {
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    /// The wrapped store
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+}
+
+impl<S: ByteStore> ByteStore for ReadOnly<S> {
+    type DbError = Error<S::DbError>;
+    type Bytes = S::Bytes;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.store.get(key).map_err(Error::Store)
+    }
+
+    /// Uses the decoded values kept by the wrapped store
+    fn get_decoded<V>(
+        &self,
+        key: &[u8],
+        decode: impl FnOnce(&[u8]) -> Result<V, bincode::error::DecodeError>,
+    ) -> Result<Option<V>, crate::Error<Self::DbError>>
+    where
+        V: Clone + Send + Sync + 'static,
+    {
+        self.store
+            .get_decoded(key, decode)
+            .map_err(|e| e.map_database(Error::Store))
+    }
+
+    fn remove(&self, _: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Err(Error::ReadOnly)
+    }
+
+    fn insert(&self, _: &[u8], _: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Err(Error::ReadOnly)
+    }
+}
+
+impl<S: byte_store::Ordered> byte_store::Ordered for ReadOnly<S> {
+    fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        self.store.get_lt(key).map_err(Error::Store)
+    }
+
+    fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        self.store.get_gt(key).map_err(Error::Store)
+    }
+}
+
+impl<S> byte_store::Ranged for ReadOnly<S>
+where
+    S: byte_store::Ranged<Key = Vec<u8>>,
+{
+    type Key = Vec<u8>;
+    type Iter = RangeIter<S>;
+
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        RangeIter(self.store.range(range))
+    }
+}
+
+/// Iterates over a range of the store wrapped by [`ReadOnly`].
+pub struct RangeIter<S: byte_store::Ranged>(S::Iter);
+
+impl<S: byte_store::Ranged> Iterator for RangeIter<S> {
+    type Item = Result<(S::Bytes, S::Bytes), Error<S::DbError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.map_err(Error::Store))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, ReadOnly};
+    use crate::stores::BTreeMap;
+    use crate::traits::byte_store::{ByteStore, Ordered};
+
+    #[test]
+    fn reads_pass_through() {
+        let store = BTreeMap::new();
+        store.insert(b"a", b"1").unwrap();
+        store.insert(b"b", b"2").unwrap();
+
+        let ds = ReadOnly::new(store);
+        assert_eq!(ds.get(b"a").unwrap().as_deref(), Some(b"1".as_slice()));
+        let (key, _) = ds.get_gt(b"a").unwrap().unwrap();
+        assert_eq!(key.as_slice(), b"b");
+    }
+
+    #[test]
+    fn writes_are_refused() {
+        let ds = ReadOnly::new(BTreeMap::new());
+        assert!(matches!(ds.insert(b"a", b"1"), Err(Error::ReadOnly)));
+        assert!(matches!(ds.remove(b"a"), Err(Error::ReadOnly)));
+        assert_eq!(ds.inner().get(b"a").unwrap(), None);
+    }
+}
@@ -0,0 +1,366 @@
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+use std::sync::Arc;
+
+use redb::{Database, ReadableTable, TableDefinition, WriteTransaction};
+
+use crate::traits::{byte_store, data_store, ByteStore};
+
+/// The table used by the generated `open_path`
+pub const DEFAULT_TABLE: &str = "DbStruct";
+
+/// Errors returned by [`Redb`], the redb error is boxed as it is large
+#[derive(thiserror::Error, Debug)]
+#[error(transparent)]
+pub struct Error(Box<redb::Error>);
+
+impl Error {
+    pub fn into_inner(self) -> redb::Error {
+        *self.0
+    }
+}
+
+macro_rules! from_redb {
+    ($($error:ty),*) => {
+        $(impl From<$error> for Error {
+            fn from(e: $error) -> Self {
+                Self(Box::new(e.into()))
+            }
+        })*
+    };
+}
+
+from_redb!(
+    redb::Error,
+    redb::DatabaseError,
+    redb::TransactionError,
+    redb::TableError,
+    redb::StorageError,
+    redb::CommitError
+);
+
+type Table<'a> = TableDefinition<'a, &'static [u8], &'static [u8]>;
+
+/// A table in a [redb](https://crates.io/crates/redb) database. This is the
+/// store behind `db=redb`. Multiple structs can share one database by each
+/// using their own table.
+///
+/// Every change outside a transaction is committed on its own. Redb allows
+/// only one write transaction at the time, writes wait for any running
+/// transaction to finish.
+///
+/// Needs the `redb` feature.
+#[derive(Clone)]
+pub struct Redb {
+    db: Arc<Database>,
+    table: Arc<str>,
+}
+
+impl Redb {
+    /// Opens the database at `path` creating it if it does not exist. Uses
+    /// the table [`DEFAULT_TABLE`].
+    pub fn open_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let db = Database::create(path)?;
+        Self::open_table(Arc::new(db), DEFAULT_TABLE)
+    }
+
+    /// Use table `name` of `db`, it is created if it does not exist.
+    pub fn open_table(db: Arc<Database>, name: &str) -> Result<Self, Error> {
+        let store = Self {
+            db,
+            table: name.into(),
+        };
+        let tx = store.db.begin_write()?;
+        tx.open_table(store.definition())?;
+        tx.commit()?;
+        Ok(store)
+    }
+
+    /// The database this table is part of.
+    pub fn database(&self) -> &Arc<Database> {
+        &self.db
+    }
+
+    fn definition(&self) -> Table<'_> {
+        TableDefinition::new(&self.table)
+    }
+
+    /// Runs `op` on the table in a write transaction and commits
+    fn write<R>(
+        &self,
+        op: impl FnOnce(&mut redb::Table<'_, &'static [u8], &'static [u8]>) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        let tx = self.db.begin_write()?;
+        let res = {
+            let mut table = tx.open_table(self.definition())?;
+            op(&mut table)?
+        };
+        tx.commit()?;
+        Ok(res)
+    }
+
+    fn read_only(&self) -> Result<redb::ReadOnlyTable<&'static [u8], &'static [u8]>, Error> {
+        let tx = self.db.begin_read()?;
+        Ok(tx.open_table(self.definition())?)
+    }
+}
+
+type Entry<'a> = (
+    redb::AccessGuard<'a, &'static [u8]>,
+    redb::AccessGuard<'a, &'static [u8]>,
+);
+
+fn to_owned((key, val): Entry) -> (Vec<u8>, Vec<u8>) {
+    (key.value().to_vec(), val.value().to_vec())
+}
+
+impl ByteStore for Redb {
+    type DbError = Error;
+    type Bytes = Vec<u8>;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let table = self.read_only()?;
+        let val = table.get(key)?;
+        Ok(val.map(|val| val.value().to_vec()))
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.write(|table| {
+            let old = table.remove(key)?;
+            Ok(old.map(|old| old.value().to_vec()))
+        })
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.write(|table| {
+            let old = table.insert(key, val)?;
+            Ok(old.map(|old| old.value().to_vec()))
+        })
+    }
+}
+
+impl byte_store::Atomic for Redb {
+    fn atomic_update(
+        &self,
+        key: &[u8],
+        mut op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>>,
+    ) -> Result<(), Self::DbError> {
+        self.write(|table| {
+            let current = table.get(key)?.map(|val| val.value().to_vec());
+            match op(current.as_deref()) {
+                Some(new) => table.insert(key, new.as_slice())?,
+                None => table.remove(key)?,
+            };
+            Ok(())
+        })
+    }
+
+    fn conditional_update(
+        &self,
+        key: &[u8],
+        new: &[u8],
+        expected: &[u8],
+    ) -> Result<(), Self::DbError> {
+        self.write(|table| {
+            let matches = table
+                .get(key)?
+                .is_some_and(|current| current.value() == expected);
+            if matches {
+                table.insert(key, new)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl byte_store::Ordered for Redb {
+    fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let table = self.read_only()?;
+        let entry = table.range::<&[u8]>(..key)?.next_back().transpose()?;
+        Ok(entry.map(to_owned))
+    }
+
+    fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let table = self.read_only()?;
+        let range = (Bound::Excluded(key), Bound::Unbounded);
+        let entry = table.range::<&[u8]>(range)?.next().transpose()?;
+        Ok(entry.map(to_owned))
+    }
+}
+
+fn as_slice(bound: Bound<&Vec<u8>>) -> Bound<&[u8]> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.as_slice()),
+        Bound::Excluded(key) => Bound::Excluded(key.as_slice()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+impl byte_store::Ranged for Redb {
+    type Key = Vec<u8>;
+    type Iter = RangeIter;
+
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        let bounds = (as_slice(range.start_bound()), as_slice(range.end_bound()));
+        let range = self
+            .read_only()
+            .and_then(|table| Ok(table.range::<&[u8]>(bounds)?));
+        match range {
+            Ok(range) => RangeIter {
+                range: Some(range),
+                error: None,
+            },
+            Err(e) => RangeIter {
+                range: None,
+                error: Some(e),
+            },
+        }
+    }
+}
+
+/// Iterates over a range of a [`Redb`] table. The iterator sees the table as
+/// it was when the iterator was created.
+pub struct RangeIter {
+    range: Option<redb::Range<'static, &'static [u8], &'static [u8]>>,
+    error: Option<Error>,
+}
+
+impl Iterator for RangeIter {
+    type Item = Result<(Vec<u8>, Vec<u8>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+        let entry = self.range.as_mut()?.next()?;
+        Some(entry.map(to_owned).map_err(Error::from))
+    }
+}
+
+/// The data store used inside a transaction on a [`Redb`].
+#[derive(Clone)]
+pub struct RedbTx<'tx> {
+    tx: &'tx WriteTransaction,
+    table: Table<'tx>,
+}
+
+impl ByteStore for RedbTx<'_> {
+    type DbError = Error;
+    type Bytes = Vec<u8>;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let table = self.tx.open_table(self.table)?;
+        let val = table.get(key)?;
+        Ok(val.map(|val| val.value().to_vec()))
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let mut table = self.tx.open_table(self.table)?;
+        let old = table.remove(key)?;
+        Ok(old.map(|old| old.value().to_vec()))
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let mut table = self.tx.open_table(self.table)?;
+        let old = table.insert(key, val)?;
+        Ok(old.map(|old| old.value().to_vec()))
+    }
+}
+
+/// Redb runs one write transaction at the time so `op` runs exactly once.
+/// Do not use the store outside of the transaction from within `op`, that
+/// will deadlock.
+impl data_store::Transactional for Redb {
+    type TxError = Error;
+    type Tx<'tx> = RedbTx<'tx>;
+
+    fn transaction<R>(
+        &self,
+        op: impl Fn(&Self::Tx<'_>) -> Result<R, crate::Error<Self::TxError>>,
+    ) -> Result<R, crate::Error<Self::DbError>> {
+        let database = |e: Error| crate::Error::Database(e);
+        let tx = self.db.begin_write().map_err(|e| database(e.into()))?;
+        let res = op(&RedbTx {
+            tx: &tx,
+            table: self.definition(),
+        });
+        match res {
+            Ok(res) => {
+                tx.commit().map_err(|e| database(e.into()))?;
+                Ok(res)
+            }
+            Err(e) => {
+                tx.abort().map_err(|e| database(e.into()))?;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byte_store::{Atomic, Ordered, Ranged};
+
+    fn store() -> (tempdir::TempDir, Redb) {
+        let dir = tempdir::TempDir::new("dbstruct_redb").unwrap();
+        let store = Redb::open_path(dir.path().join("db")).unwrap();
+        (dir, store)
+    }
+
+    #[test]
+    fn insert_returns_old_value() {
+        let (_dir, store) = store();
+        assert_eq!(store.insert(&[1], &[2]).unwrap(), None);
+        assert_eq!(store.insert(&[1], &[3]).unwrap(), Some(vec![2]));
+        assert_eq!(store.remove(&[1]).unwrap(), Some(vec![3]));
+        assert_eq!(store.get(&[1]).unwrap(), None);
+    }
+
+    #[test]
+    fn get_lt_and_gt_skip_the_key() {
+        let (_dir, store) = store();
+        for key in [1u8, 2, 3] {
+            store.insert(&[key], &[key]).unwrap();
+        }
+        assert_eq!(store.get_lt(&[2]).unwrap(), Some((vec![1], vec![1])));
+        assert_eq!(store.get_gt(&[2]).unwrap(), Some((vec![3], vec![3])));
+        assert_eq!(store.get_lt(&[1]).unwrap(), None);
+        assert_eq!(store.get_gt(&[3]).unwrap(), None);
+    }
+
+    #[test]
+    fn range() {
+        let (_dir, store) = store();
+        for key in 0..20u8 {
+            store.insert(&[key], &[]).unwrap();
+        }
+        let keys: Vec<_> = store
+            .range(vec![5]..=vec![10])
+            .map(|entry| entry.unwrap().0[0])
+            .collect();
+        assert_eq!(keys, (5..=10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn tables_are_separate() {
+        let (_dir, store) = store();
+        let other = Redb::open_table(store.database().clone(), "other").unwrap();
+        store.insert(&[1], &[1]).unwrap();
+        assert_eq!(other.get(&[1]).unwrap(), None);
+    }
+
+    #[test]
+    fn atomic_update() {
+        let (_dir, store) = store();
+        store.insert(&[1], &[1]).unwrap();
+        store
+            .atomic_update(&[1], |old| old.map(|v| vec![v[0] + 1]))
+            .unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![2]));
+        store.conditional_update(&[1], &[5], &[9]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![2]));
+        store.conditional_update(&[1], &[5], &[2]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![5]));
+    }
+}
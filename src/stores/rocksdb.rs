@@ -1,57 +1,411 @@
-use crate::ByteStore;
-use rocksdb::{ThreadMode, TransactionDB};
+use std::collections::VecDeque;
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+use std::sync::Arc;
 
-// default off, needs libclang-dev package on ubuntu
+use rocksdb::{
+    BoundColumnFamily, DBAccess, DBRawIteratorWithThreadMode, ErrorKind, MultiThreaded, Options,
+    Transaction, TransactionDB, TransactionDBOptions,
+};
 
-/* TODO: when GATS stabalize we can use rocksdb::get_pinned
-and save an allocation. Alternatively we could implement
-DataStore directly and use pinnend in that implementation
-<07-08-22, dvdsk> */
+use crate::traits::{byte_store, data_store, ByteStore};
 
-impl<TH: ThreadMode> ByteStore for TransactionDB<TH> {
-    type Error = rocksdb::Error;
+/// The RocksDB database a [`RocksDb`] store uses one column family of
+pub type Database = TransactionDB<MultiThreaded>;
+
+/// The column family used by the generated `open_path` and `open_db`
+pub const DEFAULT_COLUMN_FAMILY: &str = "DbStruct";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("rocksdb returned an error")]
+    RocksDb(#[from] rocksdb::Error),
+    #[error("column family `{0}` does not exist, was it dropped?")]
+    MissingColumnFamily(Arc<str>),
+}
+
+impl Error {
+    /// RocksDB reports a conflict with another transaction as the key being
+    /// busy or the lock timing out.
+    fn is_conflict(&self) -> bool {
+        match self {
+            Error::RocksDb(e) => matches!(
+                e.kind(),
+                ErrorKind::Busy | ErrorKind::TimedOut | ErrorKind::TryAgain
+            ),
+            Error::MissingColumnFamily(_) => false,
+        }
+    }
+}
+
+/// A column family in a [RocksDB](https://rocksdb.org) database. This is
+/// the store behind `db=rocksdb`, it is what a `sled::Tree` is for
+/// `db=sled`. Multiple structs can share one database by each using their
+/// own column family.
+///
+/// Needs the `rocksdb` feature. Building RocksDB needs `clang`.
+#[derive(Clone)]
+pub struct RocksDb {
+    db: Arc<Database>,
+    cf: Arc<str>,
+}
+
+impl RocksDb {
+    /// Opens the database at `path` creating it if it does not exist. Uses
+    /// the column family [`DEFAULT_COLUMN_FAMILY`].
+    pub fn open_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let db = Self::open_database(path)?;
+        Self::open_cf(db, DEFAULT_COLUMN_FAMILY)
+    }
+
+    /// Opens the database at `path` creating it if it does not exist. All
+    /// column families already in the database are opened.
+    pub fn open_database(path: impl AsRef<Path>) -> Result<Arc<Database>, Error> {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        // listing fails if there is no database yet
+        let existing = rocksdb::DB::list_cf(&options, path.as_ref()).unwrap_or_default();
+        let db = Database::open_cf(&options, &TransactionDBOptions::default(), path, existing)?;
+        Ok(Arc::new(db))
+    }
+
+    /// Use column family `name` of `db`, it is created if it does not exist.
+    pub fn open_cf(db: Arc<Database>, name: &str) -> Result<Self, Error> {
+        if db.cf_handle(name).is_none() {
+            db.create_cf(name, &Options::default())?;
+        }
+        Ok(Self {
+            db,
+            cf: name.into(),
+        })
+    }
+
+    /// The database this column family is part of.
+    pub fn database(&self) -> &Arc<Database> {
+        &self.db
+    }
+
+    fn cf(&self) -> Result<Arc<BoundColumnFamily<'_>>, Error> {
+        self.db
+            .cf_handle(&self.cf)
+            .ok_or_else(|| Error::MissingColumnFamily(self.cf.clone()))
+    }
+
+    /// Runs `op` in a transaction, it is run again if the transaction
+    /// conflicts with another.
+    fn retry<R>(
+        &self,
+        mut op: impl FnMut(&Transaction<'_, Database>, &Arc<BoundColumnFamily<'_>>) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        let cf = self.cf()?;
+        loop {
+            let tx = self.db.transaction();
+            let res = match op(&tx, &cf) {
+                Ok(res) => tx.commit().map(|()| res).map_err(Error::from),
+                Err(e) => Err(e),
+            };
+            match res {
+                Err(e) if e.is_conflict() => continue,
+                other => return other,
+            }
+        }
+    }
+}
+
+impl ByteStore for RocksDb {
+    type DbError = Error;
     type Bytes = Vec<u8>;
 
-    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::Error> {
-        self.get(key)
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let cf = self.cf()?;
+        Ok(self.db.get_cf(&cf, key)?)
     }
 
-    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::Error> {
-        use rocksdb::ErrorKind::*;
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.retry(|tx, cf| {
+            let old = tx.get_for_update_cf(cf, key, true)?;
+            if old.is_some() {
+                tx.delete_cf(cf, key)?;
+            }
+            Ok(old)
+        })
+    }
 
-        let txn = self.transaction();
-        let get_res = txn.get(key);
-        let del_res = txn.delete(key);
-        txn.commit()?;
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.retry(|tx, cf| {
+            let old = tx.get_for_update_cf(cf, key, true)?;
+            tx.put_cf(cf, key, val)?;
+            Ok(old)
+        })
+    }
+}
+
+impl byte_store::Atomic for RocksDb {
+    fn atomic_update(
+        &self,
+        key: &[u8],
+        mut op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>>,
+    ) -> Result<(), Self::DbError> {
+        self.retry(|tx, cf| {
+            let current = tx.get_for_update_cf(cf, key, true)?;
+            match op(current.as_deref()) {
+                Some(new) => tx.put_cf(cf, key, new)?,
+                None => tx.delete_cf(cf, key)?,
+            }
+            Ok(())
+        })
+    }
+
+    fn conditional_update(
+        &self,
+        key: &[u8],
+        new: &[u8],
+        expected: &[u8],
+    ) -> Result<(), Self::DbError> {
+        self.retry(|tx, cf| {
+            let current = tx.get_for_update_cf(cf, key, true)?;
+            if current.as_deref() == Some(expected) {
+                tx.put_cf(cf, key, new)?;
+            }
+            Ok(())
+        })
+    }
+}
 
-        let val = match get_res {
-            Ok(None) => return Ok(None),
-            Err(e) if e.kind() == NotFound => return Ok(None),
-            Err(e) => return Err(e),
-            Ok(Some(val)) => val,
-        };
+fn current_entry<D: DBAccess>(
+    iter: &DBRawIteratorWithThreadMode<'_, D>,
+) -> Result<Option<(Vec<u8>, Vec<u8>)>, Error> {
+    match iter.item() {
+        Some((key, val)) => Ok(Some((key.to_vec(), val.to_vec()))),
+        None => {
+            iter.status()?;
+            Ok(None)
+        }
+    }
+}
 
-        match del_res {
-            Err(e) if e.kind() == NotFound => return Ok(None),
-            Err(e) => return Err(e),
-            Ok(()) => return Ok(Some(val)),
+impl byte_store::Ordered for RocksDb {
+    fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let cf = self.cf()?;
+        let mut iter = self.db.raw_iterator_cf(&cf);
+        iter.seek_for_prev(key);
+        if iter.key() == Some(key) {
+            iter.prev();
         }
+        current_entry(&iter)
     }
 
-    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::Error> {
-        use rocksdb::ErrorKind::*;
+    fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let cf = self.cf()?;
+        let mut iter = self.db.raw_iterator_cf(&cf);
+        iter.seek(key);
+        if iter.key() == Some(key) {
+            iter.next();
+        }
+        current_entry(&iter)
+    }
+}
 
-        let txn = self.transaction();
-        let get_res = txn.get(key);
-        let del_res = txn.put(key, val);
-        txn.commit()?;
+impl byte_store::Ranged for RocksDb {
+    type Key = Vec<u8>;
+    type Iter = RangeIter;
 
-        del_res?;
-        match get_res {
-            Ok(None) => Ok(None),
-            Err(e) if e.kind() == NotFound => Ok(None),
-            Err(e) => Err(e),
-            Ok(Some(val)) => Ok(Some(val)),
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        RangeIter {
+            store: self.clone(),
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+            batch: VecDeque::new(),
+            done: false,
         }
     }
 }
+
+/// Iterates over a range of a [`RocksDb`] column family. The entries are
+/// read in small batches, each batch sees the latest state of the database.
+pub struct RangeIter {
+    store: RocksDb,
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+    batch: VecDeque<(Vec<u8>, Vec<u8>)>,
+    done: bool,
+}
+
+impl RangeIter {
+    const BATCH_SIZE: usize = 64;
+
+    fn read_batch(&mut self) -> Result<(), Error> {
+        let cf = self.store.cf()?;
+        let mut iter = self.store.db.raw_iterator_cf(&cf);
+        match &self.start {
+            Bound::Included(key) => iter.seek(key),
+            Bound::Excluded(key) => {
+                iter.seek(key);
+                if iter.key() == Some(key.as_slice()) {
+                    iter.next();
+                }
+            }
+            Bound::Unbounded => iter.seek_to_first(),
+        }
+
+        while self.batch.len() < Self::BATCH_SIZE {
+            let Some((key, val)) = current_entry(&iter)? else {
+                self.done = true;
+                return Ok(());
+            };
+            let in_range = match &self.end {
+                Bound::Included(end) => key <= *end,
+                Bound::Excluded(end) => key < *end,
+                Bound::Unbounded => true,
+            };
+            if !in_range {
+                self.done = true;
+                return Ok(());
+            }
+            self.batch.push_back((key, val));
+            iter.next();
+        }
+
+        let (last, _) = self.batch.back().expect("batch is full");
+        self.start = Bound::Excluded(last.clone());
+        Ok(())
+    }
+}
+
+impl Iterator for RangeIter {
+    type Item = Result<(Vec<u8>, Vec<u8>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.batch.is_empty() && !self.done {
+            if let Err(e) = self.read_batch() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        self.batch.pop_front().map(Ok)
+    }
+}
+
+/// The data store used inside a transaction on a [`RocksDb`]. Every key
+/// read or written is locked until the transaction completes.
+#[derive(Clone)]
+pub struct RocksDbTx<'tx> {
+    tx: &'tx Transaction<'tx, Database>,
+    cf: &'tx Arc<BoundColumnFamily<'tx>>,
+}
+
+impl ByteStore for RocksDbTx<'_> {
+    type DbError = Error;
+    type Bytes = Vec<u8>;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Ok(self.tx.get_for_update_cf(self.cf, key, true)?)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let old = self.tx.get_for_update_cf(self.cf, key, true)?;
+        if old.is_some() {
+            self.tx.delete_cf(self.cf, key)?;
+        }
+        Ok(old)
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let old = self.tx.get_for_update_cf(self.cf, key, true)?;
+        self.tx.put_cf(self.cf, key, val)?;
+        Ok(old)
+    }
+}
+
+impl data_store::Transactional for RocksDb {
+    type TxError = Error;
+    type Tx<'tx> = RocksDbTx<'tx>;
+
+    fn transaction<R>(
+        &self,
+        op: impl Fn(&Self::Tx<'_>) -> Result<R, crate::Error<Self::TxError>>,
+    ) -> Result<R, crate::Error<Self::DbError>> {
+        let cf = self.cf().map_err(crate::Error::Database)?;
+        loop {
+            let tx = self.db.transaction();
+            let res = match op(&RocksDbTx { tx: &tx, cf: &cf }) {
+                Ok(res) => tx
+                    .commit()
+                    .map(|()| res)
+                    .map_err(|e| crate::Error::Database(Error::from(e))),
+                // dropping the transaction rolls it back
+                Err(e) => Err(e),
+            };
+            match res {
+                Err(crate::Error::Database(e)) if e.is_conflict() => continue,
+                other => return other,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byte_store::{Atomic, Ordered, Ranged};
+
+    fn store() -> (tempdir::TempDir, RocksDb) {
+        let dir = tempdir::TempDir::new("dbstruct_rocksdb").unwrap();
+        let store = RocksDb::open_path(dir.path()).unwrap();
+        (dir, store)
+    }
+
+    #[test]
+    fn insert_returns_old_value() {
+        let (_dir, store) = store();
+        assert_eq!(store.insert(&[1], &[2]).unwrap(), None);
+        assert_eq!(store.insert(&[1], &[3]).unwrap(), Some(vec![2]));
+        assert_eq!(store.remove(&[1]).unwrap(), Some(vec![3]));
+        assert_eq!(store.get(&[1]).unwrap(), None);
+    }
+
+    #[test]
+    fn get_lt_and_gt_skip_the_key() {
+        let (_dir, store) = store();
+        for key in [1u8, 2, 3] {
+            store.insert(&[key], &[key]).unwrap();
+        }
+        assert_eq!(store.get_lt(&[2]).unwrap(), Some((vec![1], vec![1])));
+        assert_eq!(store.get_gt(&[2]).unwrap(), Some((vec![3], vec![3])));
+        assert_eq!(store.get_lt(&[1]).unwrap(), None);
+        assert_eq!(store.get_gt(&[3]).unwrap(), None);
+    }
+
+    #[test]
+    fn range_spans_batches() {
+        let (_dir, store) = store();
+        for key in 0..200u8 {
+            store.insert(&[key], &[]).unwrap();
+        }
+        let keys: Vec<_> = store
+            .range(vec![10]..vec![150])
+            .map(|entry| entry.unwrap().0[0])
+            .collect();
+        assert_eq!(keys, (10..150).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn column_families_are_separate() {
+        let (_dir, store) = store();
+        let other = RocksDb::open_cf(store.database().clone(), "other").unwrap();
+        store.insert(&[1], &[1]).unwrap();
+        assert_eq!(other.get(&[1]).unwrap(), None);
+    }
+
+    #[test]
+    fn atomic_update() {
+        let (_dir, store) = store();
+        store.insert(&[1], &[1]).unwrap();
+        store
+            .atomic_update(&[1], |old| old.map(|v| vec![v[0] + 1]))
+            .unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![2]));
+        store.conditional_update(&[1], &[5], &[9]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![2]));
+    }
+}
@@ -1,4 +1,9 @@
-use crate::traits::{byte_store, ByteStore};
+use sled::transaction::{
+    ConflictableTransactionError, TransactionError, TransactionalTree, UnabortableTransactionError,
+};
+
+use crate::traits::{byte_store, data_store, ByteStore};
+use crate::Error;
 
 impl ByteStore for sled::Tree {
     type DbError = sled::Error;
@@ -54,3 +59,57 @@ impl byte_store::Ranged for sled::Tree {
         self.range(range)
     }
 }
+
+/// The data store used inside a transaction on a [`sled::Tree`]
+#[derive(Clone)]
+pub struct SledTx<'tx>(&'tx TransactionalTree);
+
+impl ByteStore for SledTx<'_> {
+    type DbError = UnabortableTransactionError;
+    type Bytes = sled::IVec;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.0.get(key)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.0.remove(key)
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.0.insert(key, val)
+    }
+}
+
+impl data_store::Transactional for sled::Tree {
+    type TxError = UnabortableTransactionError;
+    type Tx<'tx> = SledTx<'tx>;
+
+    fn transaction<R>(
+        &self,
+        op: impl Fn(&Self::Tx<'_>) -> Result<R, Error<Self::TxError>>,
+    ) -> Result<R, Error<Self::DbError>> {
+        let res = sled::Tree::transaction(self, |tree| match op(&SledTx(tree)) {
+            Ok(res) => Ok(res),
+            // sled retries the transaction on conflict
+            Err(Error::Database(UnabortableTransactionError::Conflict)) => {
+                Err(ConflictableTransactionError::Conflict)
+            }
+            Err(Error::Database(UnabortableTransactionError::Storage(e))) => {
+                Err(ConflictableTransactionError::Storage(e))
+            }
+            Err(other) => Err(ConflictableTransactionError::Abort(other)),
+        });
+
+        match res {
+            Ok(res) => Ok(res),
+            Err(TransactionError::Storage(e)) => Err(Error::Database(e)),
+            Err(TransactionError::Abort(e)) => Err(e.map_database(|e| match e {
+                UnabortableTransactionError::Storage(e) => e,
+                UnabortableTransactionError::Conflict => {
+                    unreachable!("conflicts are retried not aborted")
+                }
+            })),
+        }
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::VecDeque;
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, TransactionBehavior};
+
+use crate::traits::{byte_store, data_store, ByteStore};
+
+/// The table used by the generated `open_path`
+pub const DEFAULT_TABLE: &str = "dbstruct";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("sqlite returned an error")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("mutex was poisoned (another thread panicked while holding the connection)")]
+    Poisoned,
+}
+
+/// A table in a [SQLite](https://sqlite.org) database. This is the store
+/// behind `db=sqlite`. Keys and values are stored as BLOBs in a table with
+/// the columns `key` and `value`. You can inspect the table with the
+/// `sqlite3` shell or any other SQLite tool.
+///
+/// Multiple structs can share one database file by each using their own
+/// table. Needs the `sqlite` feature, SQLite is compiled in.
+#[derive(Clone)]
+pub struct Sqlite {
+    conn: Arc<Mutex<Connection>>,
+    /// quoted table name
+    table: Arc<str>,
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+impl Sqlite {
+    /// Opens the database at `path` creating it if it does not exist. Uses
+    /// the table [`DEFAULT_TABLE`].
+    pub fn open_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let conn = Connection::open(path)?;
+        Self::open_table(Arc::new(Mutex::new(conn)), DEFAULT_TABLE)
+    }
+
+    /// Use table `name` in the database of `conn`, it is created if it does
+    /// not exist.
+    pub fn open_table(conn: Arc<Mutex<Connection>>, name: &str) -> Result<Self, Error> {
+        let store = Self {
+            conn,
+            table: quote(name).into(),
+        };
+        store.lock()?.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                key BLOB PRIMARY KEY NOT NULL,
+                value BLOB NOT NULL
+            ) WITHOUT ROWID",
+            store.table
+        ))?;
+        Ok(store)
+    }
+
+    /// The connection to the database this table is part of.
+    pub fn connection(&self) -> &Arc<Mutex<Connection>> {
+        &self.conn
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Connection>, Error> {
+        self.conn.lock().map_err(|_| Error::Poisoned)
+    }
+
+    /// Runs `op` in a transaction that holds the write lock on the database
+    fn write<R>(
+        &self,
+        op: impl FnOnce(&Connection, &str) -> Result<R, rusqlite::Error>,
+    ) -> Result<R, Error> {
+        let mut conn = self.lock()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let res = op(&tx, &self.table)?;
+        tx.commit()?;
+        Ok(res)
+    }
+}
+
+fn get(conn: &Connection, table: &str, key: &[u8]) -> Result<Option<Vec<u8>>, rusqlite::Error> {
+    conn.prepare_cached(&format!("SELECT value FROM {table} WHERE key = ?1"))?
+        .query_row(params![key], |row| row.get(0))
+        .optional()
+}
+
+fn remove(conn: &Connection, table: &str, key: &[u8]) -> Result<Option<Vec<u8>>, rusqlite::Error> {
+    conn.prepare_cached(&format!(
+        "DELETE FROM {table} WHERE key = ?1 RETURNING value"
+    ))?
+    .query_row(params![key], |row| row.get(0))
+    .optional()
+}
+
+fn put(conn: &Connection, table: &str, key: &[u8], val: &[u8]) -> Result<(), rusqlite::Error> {
+    conn.prepare_cached(&format!(
+        "INSERT OR REPLACE INTO {table} (key, value) VALUES (?1, ?2)"
+    ))?
+    .execute(params![key, val])?;
+    Ok(())
+}
+
+fn insert(
+    conn: &Connection,
+    table: &str,
+    key: &[u8],
+    val: &[u8],
+) -> Result<Option<Vec<u8>>, rusqlite::Error> {
+    let old = get(conn, table, key)?;
+    put(conn, table, key, val)?;
+    Ok(old)
+}
+
+type Entry = (Vec<u8>, Vec<u8>);
+
+/// The first entry in `order` for which `condition` holds
+fn entry(
+    conn: &Connection,
+    table: &str,
+    condition: &str,
+    order: &str,
+    key: &[u8],
+) -> Result<Option<Entry>, rusqlite::Error> {
+    conn.prepare_cached(&format!(
+        "SELECT key, value FROM {table} WHERE key {condition} ?1 ORDER BY key {order} LIMIT 1"
+    ))?
+    .query_row(params![key], |row| Ok((row.get(0)?, row.get(1)?)))
+    .optional()
+}
+
+impl ByteStore for Sqlite {
+    type DbError = Error;
+    type Bytes = Vec<u8>;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let conn = self.lock()?;
+        Ok(get(&conn, &self.table, key)?)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        let conn = self.lock()?;
+        Ok(remove(&conn, &self.table, key)?)
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        self.write(|conn, table| insert(conn, table, key, val))
+    }
+}
+
+impl byte_store::Atomic for Sqlite {
+    fn atomic_update(
+        &self,
+        key: &[u8],
+        mut op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>>,
+    ) -> Result<(), Self::DbError> {
+        self.write(|conn, table| {
+            let current = get(conn, table, key)?;
+            match op(current.as_deref()) {
+                Some(new) => put(conn, table, key, &new)?,
+                None => {
+                    remove(conn, table, key)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn conditional_update(
+        &self,
+        key: &[u8],
+        new: &[u8],
+        expected: &[u8],
+    ) -> Result<(), Self::DbError> {
+        self.write(|conn, table| {
+            conn.prepare_cached(&format!(
+                "UPDATE {table} SET value = ?2 WHERE key = ?1 AND value = ?3"
+            ))?
+            .execute(params![key, new, expected])?;
+            Ok(())
+        })
+    }
+}
+
+impl byte_store::Ordered for Sqlite {
+    fn get_lt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let conn = self.lock()?;
+        Ok(entry(&conn, &self.table, "<", "DESC", key)?)
+    }
+
+    fn get_gt(&self, key: &[u8]) -> Result<Option<(Self::Bytes, Self::Bytes)>, Self::DbError> {
+        let conn = self.lock()?;
+        Ok(entry(&conn, &self.table, ">", "ASC", key)?)
+    }
+}
+
+impl byte_store::Ranged for Sqlite {
+    type Key = Vec<u8>;
+    type Iter = RangeIter;
+
+    fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter {
+        RangeIter {
+            store: self.clone(),
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+            batch: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+/// Iterates over a range of a [`Sqlite`] table. The entries are read in
+/// small batches, each batch sees the latest state of the database.
+pub struct RangeIter {
+    store: Sqlite,
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+    batch: VecDeque<Entry>,
+    done: bool,
+}
+
+impl RangeIter {
+    const BATCH_SIZE: usize = 64;
+
+    fn read_batch(&mut self) -> Result<(), Error> {
+        let mut conditions = vec!["1"];
+        let mut params = Vec::new();
+        for (bound, included, excluded) in [
+            (&self.start, "key >= ?", "key > ?"),
+            (&self.end, "key <= ?", "key < ?"),
+        ] {
+            match bound {
+                Bound::Included(key) => {
+                    conditions.push(included);
+                    params.push(key.as_slice());
+                }
+                Bound::Excluded(key) => {
+                    conditions.push(excluded);
+                    params.push(key.as_slice());
+                }
+                Bound::Unbounded => (),
+            }
+        }
+
+        let conn = self.store.lock()?;
+        let mut statement = conn.prepare_cached(&format!(
+            "SELECT key, value FROM {} WHERE {} ORDER BY key LIMIT {}",
+            self.store.table,
+            conditions.join(" AND "),
+            Self::BATCH_SIZE
+        ))?;
+        let rows = statement.query_map(params_from_iter(params), |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        for row in rows {
+            self.batch.push_back(row?);
+        }
+
+        match self.batch.back() {
+            Some((last, _)) if self.batch.len() == Self::BATCH_SIZE => {
+                self.start = Bound::Excluded(last.clone());
+            }
+            _ => self.done = true,
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for RangeIter {
+    type Item = Result<Entry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.batch.is_empty() && !self.done {
+            if let Err(e) = self.read_batch() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        self.batch.pop_front().map(Ok)
+    }
+}
+
+/// The data store used inside a transaction on a [`Sqlite`].
+#[derive(Clone)]
+pub struct SqliteTx<'tx> {
+    conn: &'tx Connection,
+    table: &'tx str,
+}
+
+impl ByteStore for SqliteTx<'_> {
+    type DbError = Error;
+    type Bytes = Vec<u8>;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Ok(get(self.conn, self.table, key)?)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Ok(remove(self.conn, self.table, key)?)
+    }
+
+    fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError> {
+        Ok(insert(self.conn, self.table, key, val)?)
+    }
+}
+
+/// The transaction takes the write lock on the database right away so `op`
+/// runs exactly once. Do not use the store outside of the transaction from
+/// within `op`, that will deadlock.
+impl data_store::Transactional for Sqlite {
+    type TxError = Error;
+    type Tx<'tx> = SqliteTx<'tx>;
+
+    fn transaction<R>(
+        &self,
+        op: impl Fn(&Self::Tx<'_>) -> Result<R, crate::Error<Self::TxError>>,
+    ) -> Result<R, crate::Error<Self::DbError>> {
+        let database = |e: rusqlite::Error| crate::Error::Database(Error::Sqlite(e));
+        let mut conn = self.lock().map_err(crate::Error::Database)?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(database)?;
+        // dropping the transaction rolls it back
+        let res = op(&SqliteTx {
+            conn: &tx,
+            table: &self.table,
+        })?;
+        tx.commit().map_err(database)?;
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byte_store::{Atomic, Ordered, Ranged};
+
+    fn store() -> Sqlite {
+        let conn = Connection::open_in_memory().unwrap();
+        Sqlite::open_table(Arc::new(Mutex::new(conn)), DEFAULT_TABLE).unwrap()
+    }
+
+    #[test]
+    fn insert_returns_old_value() {
+        let store = store();
+        assert_eq!(store.insert(&[1], &[2]).unwrap(), None);
+        assert_eq!(store.insert(&[1], &[3]).unwrap(), Some(vec![2]));
+        assert_eq!(store.remove(&[1]).unwrap(), Some(vec![3]));
+        assert_eq!(store.get(&[1]).unwrap(), None);
+    }
+
+    #[test]
+    fn blobs_sort_like_bytes() {
+        let store = store();
+        for key in [vec![255], vec![1, 0], vec![1], vec![]] {
+            store.insert(&key, &[]).unwrap();
+        }
+        let keys: Vec<_> = store.range(..).map(|entry| entry.unwrap().0).collect();
+        assert_eq!(keys, vec![vec![], vec![1], vec![1, 0], vec![255]]);
+    }
+
+    #[test]
+    fn get_lt_and_gt_skip_the_key() {
+        let store = store();
+        for key in [1u8, 2, 3] {
+            store.insert(&[key], &[key]).unwrap();
+        }
+        assert_eq!(store.get_lt(&[2]).unwrap(), Some((vec![1], vec![1])));
+        assert_eq!(store.get_gt(&[2]).unwrap(), Some((vec![3], vec![3])));
+        assert_eq!(store.get_lt(&[1]).unwrap(), None);
+        assert_eq!(store.get_gt(&[3]).unwrap(), None);
+    }
+
+    #[test]
+    fn range_spans_batches() {
+        let store = store();
+        for key in 0..200u8 {
+            store.insert(&[key], &[]).unwrap();
+        }
+        let keys: Vec<_> = store
+            .range(vec![10]..vec![150])
+            .map(|entry| entry.unwrap().0[0])
+            .collect();
+        assert_eq!(keys, (10..150).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn atomic_update() {
+        let store = store();
+        store.insert(&[1], &[1]).unwrap();
+        store
+            .atomic_update(&[1], |old| old.map(|v| vec![v[0] + 1]))
+            .unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![2]));
+        store.conditional_update(&[1], &[5], &[9]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![2]));
+        store.conditional_update(&[1], &[5], &[2]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![5]));
+    }
+
+    #[test]
+    fn table_names_are_quoted() {
+        let store = store();
+        let odd = Sqlite::open_table(store.connection().clone(), "odd \"name").unwrap();
+        odd.insert(&[1], &[1]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), None);
+        assert_eq!(odd.get(&[1]).unwrap(), Some(vec![1]));
+    }
+}
@@ -1,8 +1,16 @@
 //! You can implement some of these traits to use an unsupported database
 
+#[cfg(feature = "async")]
+pub mod async_byte_store;
+#[cfg(feature = "async")]
+pub mod async_data_store;
 pub mod byte_store;
 pub mod data_store;
 
+#[cfg(feature = "async")]
+pub use async_byte_store::AsyncByteStore;
+#[cfg(feature = "async")]
+pub use async_data_store::AsyncDataStore;
 pub use byte_store::ByteStore;
 pub use data_store::DataStore;
 
@@ -0,0 +1,281 @@
+#![allow(clippy::type_complexity)]
+//! Async helper traits that are easier to implement. These implement the
+//! similarly named trait in [`async_data_store`]. Natively async databases can
+//! implement these. To use a blocking [`ByteStore`](super::ByteStore) wrap it
+//! in [`stores::Blocking`](crate::stores::Blocking).
+
+use core::fmt;
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::trace;
+
+use super::async_data_store::{self, AsyncDataStore};
+use super::byte_store::val_config;
+use crate::{key, Error};
+
+/// A helper trait, implementing this automatically implements
+/// [`AsyncDataStore`]
+pub trait AsyncByteStore: Send + Sync {
+    type DbError: fmt::Debug + Send;
+    fn get(
+        &self,
+        key: Vec<u8>,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>, Self::DbError>> + Send;
+    fn remove(
+        &self,
+        key: Vec<u8>,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>, Self::DbError>> + Send;
+    fn insert(
+        &self,
+        key: Vec<u8>,
+        val: Vec<u8>,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>, Self::DbError>> + Send;
+}
+
+/// A helper trait, implementing this automatically implements
+/// [`async_data_store::Atomic`]
+pub trait Atomic: AsyncByteStore {
+    fn atomic_update(
+        &self,
+        key: Vec<u8>,
+        op: impl FnMut(Option<&[u8]>) -> Option<Vec<u8>> + Send + 'static,
+    ) -> impl Future<Output = Result<(), Self::DbError>> + Send;
+    fn conditional_update(
+        &self,
+        key: Vec<u8>,
+        new: Vec<u8>,
+        expected: Vec<u8>,
+    ) -> impl Future<Output = Result<(), Self::DbError>> + Send;
+}
+
+/// A helper trait, implementing this automatically implements
+/// [`async_data_store::Ordered`]
+pub trait Ordered: AsyncByteStore {
+    /// Returns the previous key value pair before key
+    fn get_lt(
+        &self,
+        key: Vec<u8>,
+    ) -> impl Future<Output = Result<Option<(Vec<u8>, Vec<u8>)>, Self::DbError>> + Send;
+    /// Returns the next key value pair after key
+    fn get_gt(
+        &self,
+        key: Vec<u8>,
+    ) -> impl Future<Output = Result<Option<(Vec<u8>, Vec<u8>)>, Self::DbError>> + Send;
+}
+
+fn encode_key<E: fmt::Debug>(key: &(impl Serialize + ?Sized)) -> Result<Vec<u8>, Error<E>> {
+    key::encode(key).map_err(Error::SerializingKey)
+}
+
+fn encode_val<E: fmt::Debug>(val: &(impl Serialize + ?Sized)) -> Result<Vec<u8>, Error<E>> {
+    bincode::serde::encode_to_vec(val, val_config()).map_err(Error::SerializingValue)
+}
+
+fn decode_val<E: fmt::Debug, V: DeserializeOwned>(
+    bytes: Option<Vec<u8>>,
+) -> Result<Option<V>, Error<E>> {
+    let Some(bytes) = bytes else {
+        return Ok(None);
+    };
+    trace!("bytes of value: {bytes:?}");
+    let (val, _) =
+        bincode::serde::decode_from_slice(&bytes, val_config()).map_err(Error::DeSerializingVal)?;
+    Ok(Some(val))
+}
+
+fn decode_entry<E: fmt::Debug, K, V>(
+    entry: Option<(Vec<u8>, Vec<u8>)>,
+) -> Result<Option<(K, V)>, Error<E>>
+where
+    K: DeserializeOwned,
+    V: DeserializeOwned,
+{
+    let Some((key, val)) = entry else {
+        return Ok(None);
+    };
+    trace!("key: {key:?}, val: {val:?}");
+    let key = key::decode(&key).map_err(Error::DeSerializingKey)?;
+    let (val, _) =
+        bincode::serde::decode_from_slice(&val, val_config()).map_err(Error::DeSerializingVal)?;
+    Ok(Some((key, val)))
+}
+
+// The keys and values are serialized before the futures are created. That way
+// the futures do not borrow them and are Send even if they are not Sync.
+impl<E, BS> AsyncDataStore for BS
+where
+    E: fmt::Debug + Send,
+    BS: AsyncByteStore<DbError = E>,
+{
+    type DbError = E;
+
+    fn get<K, V>(&self, key: &K) -> impl Future<Output = Result<Option<V>, Error<E>>> + Send
+    where
+        K: Serialize,
+        V: DeserializeOwned,
+    {
+        let key = encode_key(key);
+        async move {
+            let bytes = BS::get(self, key?).await.map_err(Error::Database)?;
+            decode_val(bytes)
+        }
+    }
+
+    fn remove<K, V>(&self, key: &K) -> impl Future<Output = Result<Option<V>, Error<E>>> + Send
+    where
+        K: Serialize,
+        V: DeserializeOwned,
+    {
+        let key = encode_key(key);
+        async move {
+            let bytes = BS::remove(self, key?).await.map_err(Error::Database)?;
+            decode_val(bytes)
+        }
+    }
+
+    fn clear<K>(&self, key: &K) -> impl Future<Output = Result<(), Error<E>>> + Send
+    where
+        K: Serialize,
+    {
+        let key = encode_key(key);
+        async move {
+            BS::remove(self, key?).await.map_err(Error::Database)?;
+            Ok(())
+        }
+    }
+
+    fn contains<K>(&self, key: &K) -> impl Future<Output = Result<bool, Error<E>>> + Send
+    where
+        K: Serialize,
+    {
+        let key = encode_key(key);
+        async move {
+            let bytes = BS::get(self, key?).await.map_err(Error::Database)?;
+            Ok(bytes.is_some())
+        }
+    }
+
+    fn insert<K, V, OwnedV>(
+        &self,
+        key: &K,
+        val: &V,
+    ) -> impl Future<Output = Result<Option<OwnedV>, Error<E>>> + Send
+    where
+        K: Serialize,
+        V: Serialize + ?Sized,
+        OwnedV: std::borrow::Borrow<V> + DeserializeOwned,
+    {
+        let key = encode_key(key);
+        let val = encode_val(val);
+        async move {
+            let existing = BS::insert(self, key?, val?)
+                .await
+                .map_err(Error::Database)?;
+            decode_val(existing)
+        }
+    }
+}
+
+impl<E, BS> async_data_store::Atomic for BS
+where
+    E: fmt::Debug + Send,
+    BS: Atomic<DbError = E>,
+{
+    fn atomic_update<K, V>(
+        &self,
+        key: &K,
+        mut op: impl FnMut(V) -> V + Send + 'static,
+    ) -> impl Future<Output = Result<(), Error<E>>> + Send
+    where
+        K: Serialize,
+        V: Serialize + DeserializeOwned,
+    {
+        let key = encode_key(key);
+        // op may run on another thread, it reports errors through here
+        let res: Arc<Mutex<Result<(), Error<Infallible>>>> = Arc::new(Mutex::new(Ok(())));
+        let op_res = res.clone();
+        let bytes_op = move |old: Option<&[u8]>| -> Option<Vec<u8>> {
+            let old = old?;
+            trace!("bytes of current value: {old:?}");
+            let new = bincode::serde::decode_from_slice(old, val_config())
+                .map_err(Error::DeSerializingVal)
+                .and_then(|(val, _)| encode_val(&op(val)));
+            match new {
+                Ok(new_bytes) => Some(new_bytes),
+                Err(e) => {
+                    *op_res.lock().expect("op never panics") = Err(e);
+                    Some(old.to_vec())
+                }
+            }
+        };
+        async move {
+            BS::atomic_update(self, key?, bytes_op)
+                .await
+                .map_err(Error::Database)?;
+            let res = std::mem::replace(&mut *res.lock().expect("op never panics"), Ok(()));
+            res.map_err(|e| e.map_database(|never| match never {}))
+        }
+    }
+
+    fn conditional_update<K, V>(
+        &self,
+        key: &K,
+        new: &V,
+        expected: &V,
+    ) -> impl Future<Output = Result<(), Error<E>>> + Send
+    where
+        K: Serialize + ?Sized,
+        V: Serialize + ?Sized,
+    {
+        let key = encode_key(key);
+        let new = encode_val(new);
+        let expected = encode_val(expected);
+        async move {
+            BS::conditional_update(self, key?, new?, expected?)
+                .await
+                .map_err(Error::Database)
+        }
+    }
+}
+
+impl<E, BS> async_data_store::Ordered for BS
+where
+    E: fmt::Debug + Send,
+    BS: Ordered<DbError = E>,
+{
+    fn get_lt<InKey, OutKey, Value>(
+        &self,
+        key: &InKey,
+    ) -> impl Future<Output = Result<Option<(OutKey, Value)>, Error<E>>> + Send
+    where
+        InKey: Serialize,
+        OutKey: Serialize + DeserializeOwned,
+        Value: Serialize + DeserializeOwned,
+    {
+        let key = encode_key(key);
+        async move {
+            let entry = Ordered::get_lt(self, key?).await.map_err(Error::Database)?;
+            decode_entry(entry)
+        }
+    }
+
+    fn get_gt<InKey, OutKey, Value>(
+        &self,
+        key: &InKey,
+    ) -> impl Future<Output = Result<Option<(OutKey, Value)>, Error<E>>> + Send
+    where
+        InKey: Serialize,
+        OutKey: Serialize + DeserializeOwned,
+        Value: Serialize + DeserializeOwned,
+    {
+        let key = encode_key(key);
+        async move {
+            let entry = Ordered::get_gt(self, key?).await.map_err(Error::Database)?;
+            decode_entry(entry)
+        }
+    }
+}
@@ -0,0 +1,99 @@
+#![allow(clippy::type_complexity)]
+//! The async versions of the [`data_store`](super::data_store) traits, used
+//! by the [async wrappers](crate::wrapper::asynchronous). It is usually more
+//! convenient to implement [`AsyncByteStore`][super::async_byte_store::AsyncByteStore]
+//! instead.
+use core::fmt;
+use std::future::Future;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Base trait needed by every async wrapper.
+pub trait AsyncDataStore: Send + Sync {
+    type DbError: fmt::Debug + Send;
+    fn get<K, V>(
+        &self,
+        key: &K,
+    ) -> impl Future<Output = Result<Option<V>, crate::Error<Self::DbError>>> + Send
+    where
+        K: Serialize,
+        V: DeserializeOwned;
+    fn remove<K, V>(
+        &self,
+        key: &K,
+    ) -> impl Future<Output = Result<Option<V>, crate::Error<Self::DbError>>> + Send
+    where
+        K: Serialize,
+        V: DeserializeOwned;
+    fn clear<K>(
+        &self,
+        key: &K,
+    ) -> impl Future<Output = Result<(), crate::Error<Self::DbError>>> + Send
+    where
+        K: Serialize;
+    fn contains<K>(
+        &self,
+        key: &K,
+    ) -> impl Future<Output = Result<bool, crate::Error<Self::DbError>>> + Send
+    where
+        K: Serialize;
+    fn insert<K, V, OwnedV>(
+        &self,
+        key: &K,
+        val: &V,
+    ) -> impl Future<Output = Result<Option<OwnedV>, crate::Error<Self::DbError>>> + Send
+    where
+        K: Serialize,
+        V: Serialize + ?Sized,
+        OwnedV: std::borrow::Borrow<V> + DeserializeOwned;
+}
+
+/// This trait enables the async wrappers to provide `update` and
+/// `conditional_update`.
+pub trait Atomic: AsyncDataStore {
+    fn atomic_update<K, V>(
+        &self,
+        key: &K,
+        op: impl FnMut(V) -> V + Send + 'static,
+    ) -> impl Future<Output = Result<(), crate::Error<Self::DbError>>> + Send
+    where
+        K: Serialize,
+        V: Serialize + DeserializeOwned;
+    /// On error the update is aborted
+    fn conditional_update<K, V>(
+        &self,
+        key: &K,
+        new: &V,
+        expected: &V,
+    ) -> impl Future<Output = Result<(), crate::Error<Self::DbError>>> + Send
+    where
+        K: Serialize + ?Sized,
+        V: Serialize + ?Sized;
+}
+
+/// This trait is needed for the async Vec, VecDeque and Map wrappers.
+///
+/// The order follows the `Ord` implementation for `Vec<u8>`, see
+/// [`data_store::Ordered`](super::data_store::Ordered).
+pub trait Ordered: AsyncDataStore {
+    /// Retrieve the previous key and value from the store before the provided key.
+    fn get_lt<InKey, OutKey, Value>(
+        &self,
+        key: &InKey,
+    ) -> impl Future<Output = Result<Option<(OutKey, Value)>, crate::Error<Self::DbError>>> + Send
+    where
+        InKey: Serialize,
+        OutKey: Serialize + DeserializeOwned,
+        Value: Serialize + DeserializeOwned;
+
+    /// Retrieve the next key and value from the store after the provided key.
+    fn get_gt<InKey, OutKey, Value>(
+        &self,
+        key: &InKey,
+    ) -> impl Future<Output = Result<Option<(OutKey, Value)>, crate::Error<Self::DbError>>> + Send
+    where
+        InKey: Serialize,
+        OutKey: Serialize + DeserializeOwned,
+        Value: Serialize + DeserializeOwned;
+}
@@ -11,7 +11,7 @@ use tracing::{instrument, trace};
 use super::byte_store;
 use super::data_store;
 use super::data_store::DataStore;
-use crate::Error;
+use crate::{key, Error};
 
 /// A helper trait, implementing this automatically implements
 /// [`DataStore`]
@@ -21,6 +21,24 @@ pub trait ByteStore {
     fn get(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError>;
     fn remove(&self, key: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError>;
     fn insert(&self, key: &[u8], val: &[u8]) -> Result<Option<Self::Bytes>, Self::DbError>;
+
+    /// Gets the value of `key` decoded using `decode`. Stores that keep
+    /// decoded values in memory, such as `stores::Cached`, return a clone of
+    /// the value decoded earlier instead of calling `decode`.
+    fn get_decoded<V>(
+        &self,
+        key: &[u8],
+        decode: impl FnOnce(&[u8]) -> Result<V, bincode::error::DecodeError>,
+    ) -> Result<Option<V>, Error<Self::DbError>>
+    where
+        V: Clone + Send + Sync + 'static,
+    {
+        let Some(bytes) = self.get(key).map_err(Error::Database)? else {
+            return Ok(None);
+        };
+        let val = decode(bytes.as_ref()).map_err(Error::DeSerializingVal)?;
+        Ok(Some(val))
+    }
 }
 
 /// A helper trait, implementing this automatically implements
@@ -58,14 +76,6 @@ pub trait Ranged: Ordered {
     fn range(&self, range: impl RangeBounds<Self::Key>) -> Self::Iter;
 }
 
-/// Bincode config used to encode and decode keys. This uses big endian fixed
-/// integer encoding to ensure the order follows that of the struct
-pub(crate) fn key_config() -> impl bincode::config::Config {
-    bincode::config::standard()
-        .with_big_endian()
-        .with_fixed_int_encoding()
-}
-
 /// Bincode config used to encode and decode values.
 pub(crate) fn val_config() -> impl bincode::config::Config {
     bincode::config::standard()
@@ -85,8 +95,7 @@ where
         K: Serialize,
         V: DeserializeOwned,
     {
-        let key = bincode::serde::encode_to_vec(key, key_config())
-            .map_err(Error::<Self::DbError>::SerializingKey)?;
+        let key = key::encode(key).map_err(Error::<Self::DbError>::SerializingKey)?;
         trace!("getting value for key: {key:?}");
         let val = BS::get(self, &key).map_err(Error::Database)?;
         Ok(match val {
@@ -100,14 +109,28 @@ where
         })
     }
 
+    #[instrument(skip_all, level = "trace", err)]
+    fn get_cloned<K, V>(&self, key: &K) -> Result<Option<V>, Error<Self::DbError>>
+    where
+        K: Serialize,
+        V: DeserializeOwned + Clone + Send + Sync + 'static,
+    {
+        let key = key::encode(key).map_err(Error::<Self::DbError>::SerializingKey)?;
+        trace!("getting value for key: {key:?}");
+        BS::get_decoded(self, &key, |bytes| {
+            trace!("bytes of value: {:?}", bytes);
+            let (val, _) = bincode::serde::decode_from_slice(bytes, val_config())?;
+            Ok(val)
+        })
+    }
+
     #[instrument(skip_all, level = "trace", err)]
     fn remove<K, V>(&self, key: &K) -> Result<Option<V>, Error<Self::DbError>>
     where
         K: Serialize,
         V: DeserializeOwned,
     {
-        let key = bincode::serde::encode_to_vec(key, key_config())
-            .map_err(Error::<Self::DbError>::SerializingKey)?;
+        let key = key::encode(key).map_err(Error::<Self::DbError>::SerializingKey)?;
         trace!("removing at key: {key:?}");
         let val = BS::remove(self, &key).map_err(Error::Database)?;
         Ok(match val {
@@ -126,8 +149,7 @@ where
     where
         K: Serialize,
     {
-        let key = bincode::serde::encode_to_vec(key, key_config())
-            .map_err(Error::<Self::DbError>::SerializingKey)?;
+        let key = key::encode(key).map_err(Error::<Self::DbError>::SerializingKey)?;
         trace!("removing at key: {key:?}");
         let _ = BS::remove(self, &key).map_err(Error::Database)?;
         Ok(())
@@ -138,8 +160,7 @@ where
     where
         K: Serialize,
     {
-        let key = bincode::serde::encode_to_vec(key, key_config())
-            .map_err(Error::<Self::DbError>::SerializingKey)?;
+        let key = key::encode(key).map_err(Error::<Self::DbError>::SerializingKey)?;
         trace!("removing at key: {key:?}");
         let val = BS::get(self, &key).map_err(Error::Database)?;
         Ok(val.is_some())
@@ -152,8 +173,7 @@ where
         V: Serialize + ?Sized,
         OwnedV: std::borrow::Borrow<V> + DeserializeOwned,
     {
-        let key = bincode::serde::encode_to_vec(key, key_config())
-            .map_err(Error::<Self::DbError>::SerializingKey)?;
+        let key = key::encode(key).map_err(Error::<Self::DbError>::SerializingKey)?;
         let val = bincode::serde::encode_to_vec(val, val_config())
             .map_err(Error::<Self::DbError>::SerializingValue)?;
         trace!("inserting key: {key:?}, val: {val:?}");
@@ -188,8 +208,7 @@ where
         K: Serialize,
         V: Serialize + DeserializeOwned,
     {
-        let key =
-            bincode::serde::encode_to_vec(key, key_config()).map_err(Error::SerializingKey)?;
+        let key = key::encode(key).map_err(Error::SerializingKey)?;
         let mut res = Ok(());
         let bytes_op = |old: Option<&[u8]>| -> Option<Vec<u8>> {
             if let Some(old) = old {
@@ -228,8 +247,7 @@ where
         K: Serialize + ?Sized,
         V: Serialize + ?Sized,
     {
-        let key =
-            bincode::serde::encode_to_vec(key, key_config()).map_err(Error::SerializingKey)?;
+        let key = key::encode(key).map_err(Error::SerializingKey)?;
         let new =
             bincode::serde::encode_to_vec(new, val_config()).map_err(Error::SerializingValue)?;
         let expected = bincode::serde::encode_to_vec(expected, val_config())
@@ -254,8 +272,7 @@ where
         OutKey: Serialize + DeserializeOwned,
         OutVal: Serialize + DeserializeOwned,
     {
-        let key =
-            bincode::serde::encode_to_vec(key, key_config()).map_err(Error::SerializingKey)?;
+        let key = key::encode(key).map_err(Error::SerializingKey)?;
         trace!("getting less then key: {key:?}");
         Ok(
             match byte_store::Ordered::get_lt(self, &key).map_err(Error::Database)? {
@@ -268,8 +285,7 @@ where
                         std::any::type_name::<OutVal>(),
                         val.as_ref()
                     );
-                    let (key, _) = bincode::serde::decode_from_slice(key.as_ref(), key_config())
-                        .map_err(Error::DeSerializingKey)?;
+                    let key = key::decode(key.as_ref()).map_err(Error::DeSerializingKey)?;
                     let (val, _) = bincode::serde::decode_from_slice(val.as_ref(), val_config())
                         .map_err(Error::DeSerializingVal)?;
                     Some((key, val))
@@ -287,8 +303,7 @@ where
         OutKey: Serialize + DeserializeOwned,
         Value: Serialize + DeserializeOwned,
     {
-        let key =
-            bincode::serde::encode_to_vec(key, key_config()).map_err(Error::SerializingKey)?;
+        let key = key::encode(key).map_err(Error::SerializingKey)?;
         trace!("getting greater then key: {key:?}");
         Ok(
             match byte_store::Ordered::get_gt(self, &key).map_err(Error::Database)? {
@@ -301,8 +316,7 @@ where
                         std::any::type_name::<dyn Value>(),
                         val.as_ref()
                     );
-                    let (key, _) = bincode::serde::decode_from_slice(key.as_ref(), key_config())
-                        .map_err(Error::DeSerializingKey)?;
+                    let key = key::decode(key.as_ref()).map_err(Error::DeSerializingKey)?;
                     let (val, _) = bincode::serde::decode_from_slice(val.as_ref(), val_config())
                         .map_err(Error::DeSerializingVal)?;
                     Some((key, val))
@@ -310,6 +324,10 @@ where
             },
         )
     }
+
+    fn upgrade_keys(&self, fields: &[crate::schema::KeyedField]) -> Result<(), Error<E>> {
+        crate::migrate::upgrade_keys(self, fields)
+    }
 }
 
 struct IterWrapper<I, OutKey, Value, Bytes, Error> {
@@ -332,9 +350,9 @@ where
 
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next().map(|res| match res {
-            Ok((key, val)) => bincode::serde::decode_from_slice(key.as_ref(), key_config())
+            Ok((key, val)) => key::decode(key.as_ref())
                 .map_err(Error::DeSerializingKey)
-                .and_then(|(key, _)| {
+                .and_then(|key| {
                     bincode::serde::decode_from_slice(val.as_ref(), val_config())
                         .map_err(Error::DeSerializingVal)
                         .map(|(val, _)| (key, val))
@@ -364,21 +382,21 @@ where
     {
         use std::ops::Bound;
         let start_bound = match range.start_bound() {
-            Bound::Included(key) => Bound::Included(
-                bincode::serde::encode_to_vec(key, key_config()).map_err(Error::SerializingKey)?,
-            ),
-            Bound::Excluded(key) => Bound::Excluded(
-                bincode::serde::encode_to_vec(key, key_config()).map_err(Error::SerializingKey)?,
-            ),
+            Bound::Included(key) => {
+                Bound::Included(key::encode(key).map_err(Error::SerializingKey)?)
+            }
+            Bound::Excluded(key) => {
+                Bound::Excluded(key::encode(key).map_err(Error::SerializingKey)?)
+            }
             Bound::Unbounded => Bound::Unbounded,
         };
         let end_bound = match range.end_bound() {
-            Bound::Included(key) => Bound::Included(
-                bincode::serde::encode_to_vec(key, key_config()).map_err(Error::SerializingKey)?,
-            ),
-            Bound::Excluded(key) => Bound::Excluded(
-                bincode::serde::encode_to_vec(key, key_config()).map_err(Error::SerializingKey)?,
-            ),
+            Bound::Included(key) => {
+                Bound::Included(key::encode(key).map_err(Error::SerializingKey)?)
+            }
+            Bound::Excluded(key) => {
+                Bound::Excluded(key::encode(key).map_err(Error::SerializingKey)?)
+            }
             Bound::Unbounded => Bound::Unbounded,
         };
 
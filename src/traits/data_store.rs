@@ -14,6 +14,15 @@ pub trait DataStore {
     where
         K: Serialize,
         V: DeserializeOwned;
+    /// Like [`get`](DataStore::get). Stores that keep decoded values in
+    /// memory return a clone instead of decoding the value again.
+    fn get_cloned<K, V>(&self, key: &K) -> Result<Option<V>, crate::Error<Self::DbError>>
+    where
+        K: Serialize,
+        V: DeserializeOwned + Clone + Send + Sync + 'static,
+    {
+        self.get(key)
+    }
     fn remove<K, V>(&self, key: &K) -> Result<Option<V>, crate::Error<Self::DbError>>
     where
         K: Serialize,
@@ -95,6 +104,16 @@ pub trait Ordered: DataStore {
         InKey: Serialize,
         OutKey: Serialize + DeserializeOwned,
         Value: Serialize + DeserializeOwned;
+
+    /// Called when a struct is opened. Re-encodes the keys of a database
+    /// written by version 0.6 or earlier, see [`schema`](crate::schema).
+    #[doc(hidden)]
+    fn upgrade_keys(
+        &self,
+        _fields: &[crate::schema::KeyedField],
+    ) -> Result<(), crate::Error<Self::DbError>> {
+        Ok(())
+    }
 }
 
 /// This trait expand the functionality of the Map wrapper. It is usually more
@@ -117,3 +136,20 @@ pub trait Ranged: DataStore {
         OutKey: Serialize + DeserializeOwned,
         Value: Serialize + DeserializeOwned;
 }
+
+/// Enables running multiple operations as a single transaction. Either all
+/// changes made during the transaction are applied or none are.
+pub trait Transactional: DataStore {
+    /// Error returned by the data store used inside a transaction
+    type TxError: fmt::Debug;
+    /// The data store used inside a transaction
+    type Tx<'tx>: DataStore<DbError = Self::TxError> + Clone;
+
+    /// Runs `op` inside a transaction. If `op` returns an error the
+    /// transaction is aborted. The database may run `op` multiple times
+    /// if the transaction conflicts with another one.
+    fn transaction<R>(
+        &self,
+        op: impl Fn(&Self::Tx<'_>) -> Result<R, crate::Error<Self::TxError>>,
+    ) -> Result<R, crate::Error<Self::DbError>>;
+}
@@ -8,11 +8,21 @@
 //!   database you chose implements they may also support `update` and
 //!   `conditional_update`.
 //! - Wrapper that mimic the API of a standard library type.
+//!
+//! A `#[dbstruct(readonly)]` struct returns the wrappers in [`read_only`]
+//! instead, these lack every method that writes.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
+#[cfg(feature = "async")]
+pub mod asynchronous;
 mod default_trait;
 mod default_val;
 pub mod map;
 mod option;
+pub mod ordered_map;
+pub mod read_only;
+pub mod set;
 mod vec;
 mod vec_deque;
 
@@ -20,6 +30,8 @@ pub use default_trait::DefaultTrait;
 pub use default_val::DefaultValue;
 pub use map::Map;
 pub use option::OptionValue;
+pub use ordered_map::OrderedMap;
+pub use set::Set;
 pub use vec::Vec;
 pub use vec_deque::VecDeque;
 
@@ -30,6 +42,9 @@ pub use vec::Prefixed as VecPrefixed;
 #[doc(hidden)]
 pub use vec_deque::Prefixed as DequePrefixed;
 
-/// Not all wrappers should be sync, negative marker traits are unstable
-/// instead we use this.
-pub(crate) type PhantomUnsync = std::marker::PhantomData<::std::cell::Cell<()>>;
+/// The `Vec` length, `VecDeque` head and tail and the count of a counted
+/// `Map` are only changed after the database write succeeded. A panic while holding their lock can not leave
+/// them inconsistent, we therefore ignore poisoning.
+pub(crate) fn lock<T>(state: &Mutex<T>) -> MutexGuard<'_, T> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,64 @@
+//! The wrappers used by structs with the `async` option. They offer the API
+//! of the blocking [wrappers](super) except that every method that touches
+//! the database is an `async fn`. Where they differ:
+//!  - Iterators are not [`Iterator`]s, call their async `next` until it
+//!    returns `None`. Those of the map and set can also be walked from the
+//!    back using `next_back`.
+//!  - [`Map`] and [`OrderedMap`] have no entry API and no `prefix`. Async
+//!    maps and sets can not be `counted`.
+//!  - [`Vec`] moves elements one at a time in `insert`, `remove` and
+//!    `retain`, never in a transaction.
+//!  - Migrations and read only structs are not supported.
+//!
+//! The database is accessed through an
+//! [`AsyncDataStore`](crate::traits::AsyncDataStore). For a blocking
+//! database such as `sled` the generated code wraps it in
+//! [`stores::Blocking`](crate::stores::Blocking). That runs every operation on
+//! the blocking thread pool of tokio.
+//!
+//! # Example
+//! ```
+//! #[dbstruct::dbstruct(db=btreemap, async)]
+//! struct Test {
+//!     queue: VecDeque<u32>,
+//!     done: HashMap<u32, bool>,
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let db = Test::new().await?;
+//! db.queue().push_back(&42).await?;
+//! if let Some(job) = db.queue().pop_front().await? {
+//!     db.done().insert(&job, &true).await?;
+//! }
+//! assert_eq!(db.done().get(&42).await?, Some(true));
+//! # Ok(())
+//! # }
+//! ```
+
+mod default_trait;
+mod default_val;
+pub mod map;
+mod option;
+mod ordered_map;
+pub mod set;
+mod vec;
+mod vec_deque;
+
+pub use default_trait::DefaultTrait;
+pub use default_val::DefaultValue;
+pub use map::Map;
+pub use option::OptionValue;
+pub use ordered_map::OrderedMap;
+pub use set::Set;
+pub use vec::Vec;
+pub use vec_deque::VecDeque;
+
+// Needed by the generated code when opening the database
+#[doc(hidden)]
+pub use vec::stored_len;
+#[doc(hidden)]
+pub use vec_deque::{stored_head, stored_tail};
+// The lengths, heads and tails in the generated struct are behind this lock
+#[doc(hidden)]
+pub use tokio::sync::Mutex;
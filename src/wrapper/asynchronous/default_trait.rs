@@ -0,0 +1,47 @@
+use core::fmt;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::AsyncDataStore;
+use crate::Error;
+
+/// handles missing values by generating a replacement using the types [`Default`] implementation
+pub struct DefaultTrait<T, DS>
+where
+    T: Serialize + DeserializeOwned + Default,
+    DS: AsyncDataStore,
+{
+    // fn() -> T keeps the wrapper Sync, so its futures can be Send
+    phantom: PhantomData<fn() -> T>,
+    ds: DS,
+    key: u8,
+}
+
+impl<T, E, DS> DefaultTrait<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned + Default,
+    DS: AsyncDataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, key: u8) -> Self {
+        Self {
+            phantom: PhantomData,
+            ds,
+            key,
+        }
+    }
+
+    /// Sets the value in the database
+    pub async fn set(&mut self, value: &T) -> Result<(), Error<E>> {
+        self.ds.insert::<_, T, T>(&self.key, value).await?;
+        Ok(())
+    }
+
+    /// Gets the value from the database or the default if it is not set.
+    pub async fn get(&self) -> Result<T, Error<E>> {
+        Ok(self.ds.get(&self.key).await?.unwrap_or_default())
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::async_data_store::{self, AsyncDataStore};
+use crate::Error;
+
+/// handles missing values by generating a replacement from an expression.
+pub struct DefaultValue<T, DS>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    DS: AsyncDataStore,
+{
+    default_value: T,
+    ds: DS,
+    key: u8,
+}
+
+impl<T, E, DS> DefaultValue<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned + Clone,
+    DS: AsyncDataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, key: u8, default_value: T) -> Self {
+        Self {
+            default_value,
+            ds,
+            key,
+        }
+    }
+
+    /// Sets the value in the database
+    pub async fn set(&mut self, value: &T) -> Result<(), Error<E>> {
+        self.ds.insert::<_, T, T>(&self.key, value).await?;
+        Ok(())
+    }
+
+    /// Gets the value from the database or the default if it is not set.
+    pub async fn get(&self) -> Result<T, Error<E>> {
+        Ok(self
+            .ds
+            .get(&self.key)
+            .await?
+            .unwrap_or_else(|| self.default_value.clone()))
+    }
+}
+
+impl<T, E, DS> DefaultValue<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned + Clone,
+    DS: async_data_store::Atomic<DbError = E>,
+{
+    /// Atomically updates the value by applying `op` to it. Does nothing
+    /// if no value is set. `op` may run multiple times.
+    pub async fn update(&self, op: impl FnMut(T) -> T + Send + 'static) -> Result<(), Error<E>> {
+        self.ds.atomic_update(&self.key, op).await
+    }
+
+    /// Sets the value to `new` only if it currently is `old`.
+    pub async fn conditional_update(&self, old: T, new: T) -> Result<(), Error<E>> {
+        self.ds.conditional_update(&self.key, &new, &old).await
+    }
+}
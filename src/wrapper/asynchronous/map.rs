@@ -0,0 +1,277 @@
+use core::fmt;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::schema::{Meta, META_PREFIX};
+use crate::traits::async_data_store::{self, AsyncDataStore};
+use crate::wrapper::map::Prefixed;
+use crate::Error;
+
+mod extend;
+mod iterator;
+pub use iterator::{Iter, Keys, Values};
+
+/// mimics the API of [`HashMap`][std::collections::HashMap]
+///
+/// See the [module docs](super) for how this differs from the blocking
+/// [`Map`](crate::wrapper::Map).
+pub struct Map<Key, Value, DS>
+where
+    Key: Serialize,
+    Value: Serialize + DeserializeOwned,
+    DS: AsyncDataStore,
+{
+    // fn() -> T keeps the wrapper Sync, so its futures can be Send
+    phantom_key: PhantomData<fn() -> Key>,
+    phantom_val: PhantomData<fn() -> Value>,
+    pub(super) tree: DS,
+    pub(super) prefix: u8,
+}
+
+/// Removes the stored count of the map under `prefix`, see
+/// [`forget_len`](crate::wrapper::map::forget_len). Async maps are never
+/// counted, the generated code calls this for each map and set field.
+#[doc(hidden)]
+pub async fn forget_len<DS>(ds: &DS, prefix: u8) -> Result<(), Error<DS::DbError>>
+where
+    DS: AsyncDataStore,
+{
+    ds.clear(&(META_PREFIX, Meta::Count(prefix))).await
+}
+
+impl<Key, Value, E, DS> Map<Key, Value, DS>
+where
+    E: fmt::Debug + Send,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(tree: DS, prefix: u8) -> Self {
+        Self {
+            phantom_key: PhantomData,
+            phantom_val: PhantomData,
+            tree,
+            prefix,
+        }
+    }
+
+    /// Inserts a key-value pair into the map. If the map did have this key
+    /// present, the value is updated, and the old value is returned.
+    ///
+    /// The key and value may be any borrowed form of the map's types, as
+    /// long as the serialized form matches between borrowed and not
+    /// borrowed.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     map: HashMap<u16, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// assert_eq!(db.map().insert(&1, "a").await?, None);
+    /// assert_eq!(db.map().insert(&1, "b").await?, Some("a".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn insert<K, V>(&self, key: &K, value: &V) -> Result<Option<Value>, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+        Value: std::borrow::Borrow<V>,
+        V: Serialize + ?Sized,
+    {
+        let key = Prefixed {
+            prefix: self.prefix,
+            key,
+        };
+        self.tree.insert(&key, value).await
+    }
+
+    /// Returns the value corresponding to the key.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     map: HashMap<u16, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.map().insert(&1, "a").await?;
+    /// assert_eq!(db.map().get(&1).await?, Some("a".to_owned()));
+    /// assert_eq!(db.map().get(&2).await?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get<K>(&self, key: &K) -> Result<Option<Value>, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+    {
+        let key = Prefixed {
+            prefix: self.prefix,
+            key,
+        };
+        self.tree.get(&key).await
+    }
+
+    /// Removes a key from the map, returning the value if the key was
+    /// previously in the map.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     map: HashMap<u16, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.map().insert(&1, "a").await?;
+    /// assert_eq!(db.map().remove(&1).await?, Some("a".to_owned()));
+    /// assert_eq!(db.map().remove(&1).await?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn remove<K>(&self, key: &K) -> Result<Option<Value>, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+    {
+        let key = Prefixed {
+            prefix: self.prefix,
+            key,
+        };
+        self.tree.remove(&key).await
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     map: HashMap<u16, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.map().insert(&1, "a").await?;
+    /// assert!(db.map().contains_key(&1).await?);
+    /// assert!(!db.map().contains_key(&2).await?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn contains_key<K>(&self, key: &K) -> Result<bool, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+    {
+        let key = Prefixed {
+            prefix: self.prefix,
+            key,
+        };
+        self.tree.contains(&key).await
+    }
+}
+
+impl<Key, Value, E, DS> Map<Key, Value, DS>
+where
+    E: fmt::Debug + Send,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: async_data_store::Ordered<DbError = E>,
+{
+    /// The first key in the map. Keys of other fields follow the map, check
+    /// the prefix before decoding the key.
+    async fn first_key(&self) -> Result<Option<Key>, Error<E>> {
+        match self.tree.get_gt::<_, u8, ()>(&self.prefix).await? {
+            Some((prefix, ())) if prefix == self.prefix => (),
+            _ => return Ok(None),
+        }
+        let entry = self.tree.get_gt::<_, (u8, Key), ()>(&self.prefix).await?;
+        Ok(entry.map(|((_, key), ())| key))
+    }
+
+    /// Clears the map, removing all key-value pairs.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     map: HashMap<u16, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.map().insert(&1, "a").await?;
+    /// db.map().clear().await?;
+    /// assert!(db.map().is_empty().await?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn clear(&self) -> Result<(), Error<E>> {
+        while let Some(key) = self.first_key().await? {
+            self.remove(&key).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the map contains no elements.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     map: HashMap<u16, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// assert!(db.map().is_empty().await?);
+    /// db.map().insert(&1, "a").await?;
+    /// assert!(!db.map().is_empty().await?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn is_empty(&self) -> Result<bool, Error<E>> {
+        Ok(self.first_key().await?.is_none())
+    }
+}
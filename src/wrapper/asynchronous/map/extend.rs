@@ -0,0 +1,72 @@
+use std::borrow::Borrow;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::{AsyncDataStore, ExtendError};
+
+use super::Map;
+
+/// Inserts all new key-values from the iterator and replaces values with
+/// existing keys with new values returned from the iterator.
+impl<Key, Value, E, DS> Map<Key, Value, DS>
+where
+    E: fmt::Debug + Send,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    /// Extends the map with the contents of an iterator of tuples.
+    ///
+    /// The key and value in the tuple may be any borrowed form. As long as
+    /// the serialized form matches between borrowed and not borrowed.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     map: HashMap<u16, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.map().extend([(&1, "one"), (&2, "two"), (&3, "three")]).await?;
+    /// assert_eq!(db.map().get(&1).await?, Some("one".to_owned()));
+    /// assert_eq!(db.map().get(&3).await?, Some("three".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub async fn extend<'a, I, K, V>(
+        &self,
+        iter: I,
+    ) -> Result<(), ExtendError<I::Item, I::IntoIter, crate::Error<E>>>
+    where
+        I: IntoIterator<Item = (&'a K, &'a V)>,
+        Key: Borrow<K>,
+        K: Serialize + ?Sized + 'a,
+        Value: Borrow<V>,
+        V: Serialize + ?Sized + 'a,
+    {
+        let mut iter = iter.into_iter();
+        loop {
+            let Some((key, value)) = iter.next() else {
+                return Ok(());
+            };
+
+            if let Err(error) = self.insert(key, value).await {
+                return Err(ExtendError {
+                    unadded: (key, value),
+                    iter,
+                    error,
+                });
+            }
+        }
+    }
+}
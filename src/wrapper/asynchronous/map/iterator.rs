@@ -0,0 +1,440 @@
+use core::marker::PhantomData;
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::async_byte_store::{self, AsyncByteStore};
+use crate::wrapper::map::{decode, successor, Prefixed};
+use crate::{key, Error};
+
+use super::Map;
+
+/// Visits the entries of an async [`Map`]. This is not an
+/// [`Iterator`], call [`next`](Iter::next) or [`next_back`](Iter::next_back)
+/// until they return `None`.
+pub struct Iter<'a, K, V, E, DS>
+where
+    E: fmt::Debug + Send,
+    DS: async_byte_store::Ordered<DbError = E>,
+{
+    /// Key of the entry last returned by `next`
+    front: Vec<u8>,
+    /// The range starts at `front` and `next` has not looked it up yet
+    front_included: bool,
+    /// Key of the entry last returned by `next_back`
+    back: Vec<u8>,
+    // fn() -> T keeps the iterator Sync, so its futures can be Send
+    phantom: PhantomData<fn() -> (K, V)>,
+    ds: &'a DS,
+}
+
+impl<'a, K, V, E, DS> Iter<'a, K, V, E, DS>
+where
+    E: fmt::Debug + Send,
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+    DS: async_byte_store::Ordered<DbError = E>,
+{
+    /// Iterates over all keys that start with `start`
+    fn starting_with(ds: &'a DS, start: Vec<u8>) -> Self {
+        Self {
+            back: successor(start.clone()),
+            front: start,
+            front_included: false,
+            phantom: PhantomData,
+            ds,
+        }
+    }
+
+    async fn next_raw(&mut self) -> Option<Result<(Vec<u8>, Vec<u8>), Error<E>>> {
+        if self.front_included {
+            self.front_included = false;
+            match AsyncByteStore::get(self.ds, self.front.clone()).await {
+                // `next_back` may have returned it already
+                Ok(Some(val)) if self.front < self.back => {
+                    return Some(Ok((self.front.clone(), val)))
+                }
+                Ok(_) => (),
+                Err(e) => return Some(Err(Error::Database(e))),
+            }
+        }
+
+        let entry = async_byte_store::Ordered::get_gt(self.ds, self.front.clone()).await;
+        let (key, val) = match entry {
+            Ok(Some(entry)) => entry,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(Error::Database(e))),
+        };
+
+        // the key belongs to the next field or was returned by `next_back`
+        if key >= self.back {
+            return None;
+        }
+
+        self.front.clone_from(&key);
+        Some(Ok((key, val)))
+    }
+
+    async fn next_back_raw(&mut self) -> Option<Result<(Vec<u8>, Vec<u8>), Error<E>>> {
+        let entry = async_byte_store::Ordered::get_lt(self.ds, self.back.clone()).await;
+        let (key, val) = match entry {
+            Ok(Some(entry)) => entry,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(Error::Database(e))),
+        };
+
+        // the key belongs to the previous field or was returned by `next`
+        if key < self.front || (key == self.front && !self.front_included) {
+            return None;
+        }
+
+        self.back.clone_from(&key);
+        Some(Ok((key, val)))
+    }
+
+    /// Returns the next entry, or `None` when all entries have been visited.
+    pub async fn next(&mut self) -> Option<Result<(K, V), Error<E>>> {
+        let entry = self.next_raw().await?;
+        Some(entry.and_then(|(key, val)| decode(&key, &val)))
+    }
+
+    /// Returns the last entry not yet visited, or `None` when all entries
+    /// have been visited.
+    pub async fn next_back(&mut self) -> Option<Result<(K, V), Error<E>>> {
+        let entry = self.next_back_raw().await?;
+        Some(entry.and_then(|(key, val)| decode(&key, &val)))
+    }
+}
+
+/// Visits the values of an async [`Map`], see [`Iter`].
+pub struct Values<'a, K, V, E, DS>(Iter<'a, K, V, E, DS>)
+where
+    E: fmt::Debug + Send,
+    DS: async_byte_store::Ordered<DbError = E>;
+
+impl<K, V, E, DS> Values<'_, K, V, E, DS>
+where
+    E: fmt::Debug + Send,
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+    DS: async_byte_store::Ordered<DbError = E>,
+{
+    /// Returns the next value, or `None` when all values have been visited.
+    pub async fn next(&mut self) -> Option<Result<V, Error<E>>> {
+        let entry = self.0.next().await?;
+        Some(entry.map(|(_, val)| val))
+    }
+
+    /// Returns the last value not yet visited, or `None` when all values
+    /// have been visited.
+    pub async fn next_back(&mut self) -> Option<Result<V, Error<E>>> {
+        let entry = self.0.next_back().await?;
+        Some(entry.map(|(_, val)| val))
+    }
+}
+
+/// Visits the keys of an async [`Map`], see [`Iter`].
+pub struct Keys<'a, K, V, E, DS>(Iter<'a, K, V, E, DS>)
+where
+    E: fmt::Debug + Send,
+    DS: async_byte_store::Ordered<DbError = E>;
+
+impl<K, V, E, DS> Keys<'_, K, V, E, DS>
+where
+    E: fmt::Debug + Send,
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+    DS: async_byte_store::Ordered<DbError = E>,
+{
+    /// Returns the next key, or `None` when all keys have been visited.
+    pub async fn next(&mut self) -> Option<Result<K, Error<E>>> {
+        let entry = self.0.next().await?;
+        Some(entry.map(|(key, _)| key))
+    }
+
+    /// Returns the last key not yet visited, or `None` when all keys have
+    /// been visited.
+    pub async fn next_back(&mut self) -> Option<Result<K, Error<E>>> {
+        let entry = self.0.next_back().await?;
+        Some(entry.map(|(key, _)| key))
+    }
+}
+
+impl<Key, Value, E, DS> Map<Key, Value, DS>
+where
+    E: fmt::Debug + Send,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: async_byte_store::Ordered<DbError = E>,
+{
+    /// Visits all key-value pairs in the order of their serialized keys, see
+    /// [`OrderedMap`](crate::wrapper::OrderedMap) for what that order is.
+    /// Use [`next_back`](Iter::next_back) to visit them back to front.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     map: HashMap<u16, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.map().insert(&1, "a").await?;
+    /// db.map().insert(&2, "b").await?;
+    /// db.map().insert(&3, "c").await?;
+    ///
+    /// let map = db.map();
+    /// let mut entries = map.iter();
+    /// while let Some(entry) = entries.next().await {
+    ///     let (key, val) = entry?;
+    ///     println!("key: {key} val: {val}");
+    /// }
+    ///
+    /// let last = map.iter().next_back().await.transpose()?;
+    /// assert_eq!(last, Some((3, "c".to_owned())));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter(&self) -> Iter<'_, Key, Value, E, DS> {
+        Iter::starting_with(&self.tree, vec![self.prefix])
+    }
+
+    /// Visits the key-value pairs whose key lies in `range`, in the same
+    /// order as [`iter`](Map::iter). A range whose start lies after its end
+    /// visits nothing.
+    ///
+    /// The range bounds may be any borrowed form of the map’s key type, but
+    /// the serialized form must match that of the owned key type.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     map: BTreeMap<i16, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.map().extend([(&-1, "a"), (&2, "b"), (&3, "c")]).await?;
+    ///
+    /// let map = db.map();
+    /// let mut entries = map.range(-1..=2)?;
+    /// assert_eq!(entries.next().await.transpose()?, Some((-1, "a".to_owned())));
+    /// assert_eq!(entries.next().await.transpose()?, Some((2, "b".to_owned())));
+    /// assert_eq!(entries.next().await.transpose()?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn range<K, R>(&self, range: R) -> Result<Iter<'_, Key, Value, E, DS>, Error<E>>
+    where
+        Key: Borrow<K>,
+        K: Serialize + ?Sized,
+        R: RangeBounds<K>,
+    {
+        let prefix = self.prefix;
+        let encode =
+            |key: &K| key::encode(&Prefixed { prefix, key }).map_err(Error::SerializingKey);
+        let (front, front_included) = match range.start_bound() {
+            Bound::Included(key) => (encode(key)?, true),
+            Bound::Excluded(key) => (encode(key)?, false),
+            Bound::Unbounded => (vec![prefix], false),
+        };
+        let back = match range.end_bound() {
+            // Appending a zero gives the first possible key after `key`
+            Bound::Included(key) => {
+                let mut back = encode(key)?;
+                back.push(0);
+                back
+            }
+            Bound::Excluded(key) => encode(key)?,
+            Bound::Unbounded => successor(vec![prefix]),
+        };
+        Ok(Iter {
+            front,
+            back,
+            front_included,
+            phantom: PhantomData,
+            ds: &self.tree,
+        })
+    }
+
+    /// Visits all keys in the same order as [`iter`](Map::iter).
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     map: HashMap<u16, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.map().insert(&1, "a").await?;
+    /// db.map().insert(&2, "b").await?;
+    ///
+    /// let map = db.map();
+    /// let mut keys = map.keys();
+    /// assert_eq!(keys.next().await.transpose()?, Some(1));
+    /// assert_eq!(keys.next().await.transpose()?, Some(2));
+    /// assert_eq!(keys.next().await.transpose()?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn keys(&self) -> Keys<'_, Key, Value, E, DS> {
+        Keys(self.iter())
+    }
+
+    /// Visits all values in the same order as [`iter`](Map::iter).
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     map: HashMap<u16, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.map().insert(&1, "a").await?;
+    /// db.map().insert(&2, "b").await?;
+    ///
+    /// let map = db.map();
+    /// let mut values = map.values();
+    /// assert_eq!(values.next_back().await.transpose()?, Some("b".to_owned()));
+    /// assert_eq!(values.next_back().await.transpose()?, Some("a".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn values(&self) -> Values<'_, Key, Value, E, DS> {
+        Values(self.iter())
+    }
+
+    /// Returns the number of elements in the map. This reads every element,
+    /// avoid it on large maps.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     map: HashMap<u16, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.map().insert(&1, "a").await?;
+    /// db.map().insert(&2, "b").await?;
+    /// db.map().insert(&2, "c").await?;
+    /// assert_eq!(db.map().len().await?, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn len(&self) -> Result<usize, Error<E>> {
+        let mut entries = self.iter();
+        let mut len = 0;
+        while let Some(entry) = entries.next_raw().await {
+            entry?;
+            len += 1;
+        }
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stores;
+
+    #[tokio::test]
+    async fn iterate_from_both_ends() {
+        let ds = stores::Blocking::new(stores::BTreeMap::new());
+        let map: Map<u8, (), _> = Map::new(ds.clone(), 1);
+        let next_field: Map<u8, (), _> = Map::new(ds, 2);
+        next_field.insert(&0, &()).await.unwrap();
+        for key in 0..5 {
+            map.insert(&key, &()).await.unwrap();
+        }
+
+        let mut keys = map.keys();
+        assert_eq!(keys.next().await.unwrap().unwrap(), 0);
+        assert_eq!(keys.next_back().await.unwrap().unwrap(), 4);
+        assert_eq!(keys.next_back().await.unwrap().unwrap(), 3);
+        assert_eq!(keys.next().await.unwrap().unwrap(), 1);
+        assert_eq!(keys.next().await.unwrap().unwrap(), 2);
+        assert!(keys.next_back().await.is_none());
+        assert!(keys.next().await.is_none());
+        assert_eq!(map.len().await.unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn range_bounds() {
+        let ds = stores::Blocking::new(stores::BTreeMap::new());
+        let map: Map<i8, (), _> = Map::new(ds.clone(), 1);
+        let next_field: Map<u8, (), _> = Map::new(ds, 2);
+        next_field.insert(&0, &()).await.unwrap();
+        for key in -3..3 {
+            map.insert(&key, &()).await.unwrap();
+        }
+
+        async fn keys<DS>(mut entries: Iter<'_, i8, (), DS::DbError, DS>) -> Vec<i8>
+        where
+            DS: async_byte_store::Ordered,
+        {
+            let mut keys = Vec::new();
+            while let Some(entry) = entries.next().await {
+                keys.push(entry.unwrap().0);
+            }
+            keys
+        }
+        assert_eq!(keys(map.range(-1..1).unwrap()).await, [-1, 0]);
+        assert_eq!(keys(map.range(-1..=1).unwrap()).await, [-1, 0, 1]);
+        assert_eq!(keys(map.range(1..).unwrap()).await, [1, 2]);
+        assert_eq!(keys(map.range(..-2).unwrap()).await, [-3]);
+        assert_eq!(
+            keys(
+                map.range((Bound::Included(2), Bound::Excluded(-2)))
+                    .unwrap()
+            )
+            .await,
+            [0i8; 0]
+        );
+
+        let mut entries = map.range(0..=1).unwrap();
+        assert_eq!(entries.next_back().await.unwrap().unwrap().0, 1);
+        assert_eq!(entries.next_back().await.unwrap().unwrap().0, 0);
+        assert!(entries.next().await.is_none());
+        assert!(entries.next_back().await.is_none());
+    }
+}
@@ -0,0 +1,87 @@
+use core::fmt;
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::async_data_store::{self, AsyncDataStore};
+use crate::Error;
+
+/// Here missing values are represented by [`Option::None`].
+pub struct OptionValue<T, DS>
+where
+    DS: AsyncDataStore,
+{
+    // fn() -> T keeps the wrapper Sync, so its futures can be Send
+    phantom: PhantomData<fn() -> T>,
+    ds: DS,
+    key: u8,
+}
+
+impl<T, E, DS> OptionValue<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, key: u8) -> Self {
+        Self {
+            phantom: PhantomData,
+            ds,
+            key,
+        }
+    }
+
+    /// Sets the value in the database or removes it if `value` is `None`.
+    pub async fn set<Q>(&mut self, value: Option<&Q>) -> Result<(), Error<E>>
+    where
+        T: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        if let Some(v) = value {
+            self.ds.insert::<_, Q, T>(&self.key, v).await?;
+        } else {
+            self.ds.clear::<_>(&self.key).await?;
+        }
+        Ok(())
+    }
+
+    /// Gets the value from the database or `None` if it is not set.
+    pub async fn get(&self) -> Result<Option<T>, Error<E>> {
+        self.ds.get(&self.key).await
+    }
+
+    /// Returns `true` if no value is set
+    pub async fn is_none(&self) -> Result<bool, Error<E>> {
+        self.is_some().await.map(|b| !b)
+    }
+
+    /// Returns `true` if a value is set
+    pub async fn is_some(&self) -> Result<bool, Error<E>> {
+        self.ds.contains(&self.key).await
+    }
+}
+
+impl<T, E, DS> OptionValue<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: async_data_store::Atomic<DbError = E>,
+{
+    /// Atomically updates the value by applying `op` to it. Does nothing
+    /// if no value is set. `op` may run multiple times.
+    pub async fn update(&self, op: impl FnMut(T) -> T + Send + 'static) -> Result<(), Error<E>> {
+        self.ds.atomic_update(&self.key, op).await
+    }
+
+    /// Sets the value to `new` only if it currently is `old`.
+    pub async fn conditional_update<Q>(&self, old: &Q, new: &Q) -> Result<(), Error<E>>
+    where
+        T: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        self.ds.conditional_update(&self.key, new, old).await
+    }
+}
@@ -0,0 +1,246 @@
+use core::fmt;
+use std::borrow::Borrow;
+use std::ops::RangeBounds;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::async_byte_store;
+use crate::traits::async_data_store::{self, AsyncDataStore};
+use crate::traits::ExtendError;
+use crate::Error;
+
+use super::map::{Iter, Keys, Values};
+use super::Map;
+
+/// mimics the API of [`BTreeMap`][std::collections::BTreeMap]
+///
+/// The entries are ordered like those of the blocking
+/// [`OrderedMap`](crate::wrapper::OrderedMap). See the [module docs](super)
+/// for how this differs from it.
+pub struct OrderedMap<Key, Value, DS>
+where
+    Key: Serialize,
+    Value: Serialize + DeserializeOwned,
+    DS: AsyncDataStore,
+{
+    map: Map<Key, Value, DS>,
+}
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug + Send,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(tree: DS, prefix: u8) -> Self {
+        Self {
+            map: Map::new(tree, prefix),
+        }
+    }
+
+    /// Inserts a key-value pair into the map, returns the previous value if
+    /// there was one.
+    pub async fn insert<K, V>(&self, key: &K, value: &V) -> Result<Option<Value>, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+        Value: std::borrow::Borrow<V>,
+        V: Serialize + ?Sized,
+    {
+        self.map.insert(key, value).await
+    }
+
+    /// Returns the value corresponding to the key.
+    pub async fn get<K>(&self, key: &K) -> Result<Option<Value>, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+    {
+        self.map.get(key).await
+    }
+
+    /// Removes a key from the map, returning the value if the key was
+    /// previously in the map.
+    pub async fn remove<K>(&self, key: &K) -> Result<Option<Value>, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+    {
+        self.map.remove(key).await
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    pub async fn contains_key<K>(&self, key: &K) -> Result<bool, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+    {
+        self.map.contains_key(key).await
+    }
+
+    /// Extends the map with the contents of an iterator of tuples, see
+    /// [`Map::extend`].
+    #[allow(clippy::type_complexity)]
+    pub async fn extend<'a, I, K, V>(
+        &self,
+        iter: I,
+    ) -> Result<(), ExtendError<I::Item, I::IntoIter, Error<E>>>
+    where
+        I: IntoIterator<Item = (&'a K, &'a V)>,
+        Key: Borrow<K>,
+        K: Serialize + ?Sized + 'a,
+        Value: Borrow<V>,
+        V: Serialize + ?Sized + 'a,
+    {
+        self.map.extend(iter).await
+    }
+}
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug + Send,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: async_byte_store::Ordered<DbError = E>,
+{
+    /// Visits all key-value pairs in order of their keys, see
+    /// [`Map::iter`].
+    pub fn iter(&self) -> Iter<'_, Key, Value, E, DS> {
+        self.map.iter()
+    }
+
+    /// Visits all keys in order, see [`Map::keys`].
+    pub fn keys(&self) -> Keys<'_, Key, Value, E, DS> {
+        self.map.keys()
+    }
+
+    /// Visits all values in the order of their keys, see [`Map::values`].
+    pub fn values(&self) -> Values<'_, Key, Value, E, DS> {
+        self.map.values()
+    }
+
+    /// Visits the key-value pairs whose key lies in `range`, see
+    /// [`Map::range`].
+    ///
+    /// # Errors
+    /// This can fail if serializing the bounds failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     events: BTreeMap<u64, String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.events().insert(&1_000, "started").await?;
+    /// db.events().insert(&2_000, "running").await?;
+    /// db.events().insert(&3_000, "stopped").await?;
+    ///
+    /// let events = db.events();
+    /// let mut since = events.range(2_000..)?;
+    /// assert_eq!(since.next().await.transpose()?, Some((2_000, "running".to_owned())));
+    /// assert_eq!(since.next_back().await.transpose()?, Some((3_000, "stopped".to_owned())));
+    /// assert_eq!(since.next().await.transpose()?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn range<K, R>(&self, range: R) -> Result<Iter<'_, Key, Value, E, DS>, Error<E>>
+    where
+        Key: Borrow<K>,
+        K: Serialize + ?Sized,
+        R: RangeBounds<K>,
+    {
+        self.map.range(range)
+    }
+
+    /// Returns the number of elements in the map. This reads every element,
+    /// avoid it on large maps.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem.
+    pub async fn len(&self) -> Result<usize, Error<E>> {
+        self.map.len().await
+    }
+}
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug + Send,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: async_data_store::Ordered<DbError = E>,
+{
+    /// Returns the first key-value pair in the map. The key in this pair is
+    /// the minimum key in the map.
+    pub async fn first_key_value(&self) -> Result<Option<(Key, Value)>, Error<E>> {
+        let (tree, prefix) = (&self.map.tree, self.map.prefix);
+        // Keys of other fields follow the map, check the prefix before
+        // decoding the key.
+        match tree.get_gt::<_, u8, ()>(&prefix).await? {
+            Some((found, ())) if found == prefix => (),
+            _ => return Ok(None),
+        }
+        let entry = tree.get_gt::<_, (u8, Key), Value>(&prefix).await?;
+        Ok(entry.map(|((_, key), value)| (key, value)))
+    }
+
+    /// Returns the last key-value pair in the map. The key in this pair is
+    /// the maximum key in the map.
+    pub async fn last_key_value(&self) -> Result<Option<(Key, Value)>, Error<E>> {
+        let (tree, prefix) = (&self.map.tree, self.map.prefix);
+        // Fields never use the reserved prefix u8::MAX, this can not overflow
+        let end = prefix + 1;
+        // Keys of other fields precede the map, check the prefix before
+        // decoding the key.
+        match tree.get_lt::<_, u8, ()>(&end).await? {
+            Some((found, ())) if found == prefix => (),
+            _ => return Ok(None),
+        }
+        let entry = tree.get_lt::<_, (u8, Key), Value>(&end).await?;
+        Ok(entry.map(|((_, key), value)| (key, value)))
+    }
+
+    /// Removes and returns the first element in the map. The key of this
+    /// element is the minimum key that was in the map.
+    pub async fn pop_first(&self) -> Result<Option<(Key, Value)>, Error<E>> {
+        loop {
+            let Some((key, _)) = self.first_key_value().await? else {
+                return Ok(None);
+            };
+            // Another task may have popped the entry since we read it
+            if let Some(value) = self.map.remove(&key).await? {
+                return Ok(Some((key, value)));
+            }
+        }
+    }
+
+    /// Removes and returns the last element in the map. The key of this
+    /// element is the maximum key that was in the map.
+    pub async fn pop_last(&self) -> Result<Option<(Key, Value)>, Error<E>> {
+        loop {
+            let Some((key, _)) = self.last_key_value().await? else {
+                return Ok(None);
+            };
+            // Another task may have popped the entry since we read it
+            if let Some(value) = self.map.remove(&key).await? {
+                return Ok(Some((key, value)));
+            }
+        }
+    }
+
+    /// Clears the map, removing all key-value pairs.
+    pub async fn clear(&self) -> Result<(), Error<E>> {
+        self.map.clear().await
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub async fn is_empty(&self) -> Result<bool, Error<E>> {
+        self.map.is_empty().await
+    }
+}
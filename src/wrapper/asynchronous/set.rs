@@ -0,0 +1,86 @@
+use core::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::async_data_store::{self, AsyncDataStore};
+use crate::Error;
+
+use super::Map;
+
+mod extend;
+mod iterator;
+pub use iterator::Iter;
+
+/// mimics the API of [`HashSet`][std::collections::HashSet]
+///
+/// See the [module docs](super) for how this differs from the blocking
+/// [`Set`](crate::wrapper::Set).
+pub struct Set<T, DS>
+where
+    T: Serialize,
+    DS: AsyncDataStore,
+{
+    /// The elements are the keys, their values are empty
+    map: Map<T, (), DS>,
+}
+
+impl<T, E, DS> Set<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, prefix: u8) -> Self {
+        Self {
+            map: Map::new(ds, prefix),
+        }
+    }
+
+    /// Adds a value to the set. Returns whether the value was newly
+    /// inserted.
+    pub async fn insert<Q>(&self, value: &Q) -> Result<bool, Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        Ok(self.map.insert(value, &()).await?.is_none())
+    }
+
+    /// Removes a value from the set. Returns whether the value was present
+    /// in the set.
+    pub async fn remove<Q>(&self, value: &Q) -> Result<bool, Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        Ok(self.map.remove(value).await?.is_some())
+    }
+
+    /// Returns `true` if the set contains the value.
+    pub async fn contains<Q>(&self, value: &Q) -> Result<bool, Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        self.map.contains_key(value).await
+    }
+}
+
+impl<T, E, DS> Set<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: async_data_store::Ordered<DbError = E>,
+{
+    /// Clears the set, removing all values.
+    pub async fn clear(&self) -> Result<(), Error<E>> {
+        self.map.clear().await
+    }
+
+    /// Returns `true` if the set contains no elements.
+    pub async fn is_empty(&self) -> Result<bool, Error<E>> {
+        self.map.is_empty().await
+    }
+}
@@ -0,0 +1,67 @@
+use std::borrow::Borrow;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::{AsyncDataStore, ExtendError};
+
+use super::Set;
+
+impl<T, E, DS> Set<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    /// Extends the set with the contents of an iterator.
+    ///
+    /// The iterator item may be any borrowed form of the sets value type,
+    /// as long as the serialized form matches between borrowed and not borrowed.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     names: HashSet<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.names().extend(["alice", "bob", "alice"]).await?;
+    /// assert!(db.names().contains("bob").await?);
+    /// assert_eq!(db.names().len().await?, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub async fn extend<'a, I, Q>(
+        &self,
+        iter: I,
+    ) -> Result<(), ExtendError<I::Item, I::IntoIter, crate::Error<E>>>
+    where
+        I: IntoIterator<Item = &'a Q>,
+        T: Borrow<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        let mut iter = iter.into_iter();
+        loop {
+            let Some(item) = iter.next() else {
+                return Ok(());
+            };
+
+            if let Err(error) = self.insert::<Q>(item).await {
+                return Err(ExtendError {
+                    unadded: item,
+                    iter,
+                    error,
+                });
+            }
+        }
+    }
+}
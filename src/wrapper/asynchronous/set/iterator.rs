@@ -0,0 +1,100 @@
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::async_byte_store;
+use crate::wrapper::asynchronous::map;
+use crate::Error;
+
+use super::Set;
+
+/// Visits the elements of an async [`Set`]. This is not an [`Iterator`],
+/// call [`next`](Iter::next) or [`next_back`](Iter::next_back) until they
+/// return `None`.
+pub struct Iter<'a, T, E, DS>(map::Keys<'a, T, (), E, DS>)
+where
+    E: fmt::Debug + Send,
+    DS: async_byte_store::Ordered<DbError = E>;
+
+impl<T, E, DS> Iter<'_, T, E, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: async_byte_store::Ordered<DbError = E>,
+{
+    /// Returns the next element, or `None` when all elements have been
+    /// visited.
+    pub async fn next(&mut self) -> Option<Result<T, Error<E>>> {
+        self.0.next().await
+    }
+
+    /// Returns the last element not yet visited, or `None` when all
+    /// elements have been visited.
+    pub async fn next_back(&mut self) -> Option<Result<T, Error<E>>> {
+        self.0.next_back().await
+    }
+}
+
+impl<T, E, DS> Set<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: async_byte_store::Ordered<DbError = E>,
+{
+    /// Visits all elements in the order of their serialized form.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     seen: HashSet<u16>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.seen().extend(&[3, 1, 2]).await?;
+    ///
+    /// let seen = db.seen();
+    /// let mut elements = seen.iter();
+    /// assert_eq!(elements.next().await.transpose()?, Some(1));
+    /// assert_eq!(elements.next_back().await.transpose()?, Some(3));
+    /// assert_eq!(elements.next().await.transpose()?, Some(2));
+    /// assert_eq!(elements.next().await.transpose()?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T, E, DS> {
+        Iter(self.map.keys())
+    }
+
+    /// Returns the number of elements in the set. This reads every element,
+    /// avoid it on large sets.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     seen: HashSet<u16>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.seen().extend(&[1, 2, 2]).await?;
+    /// assert_eq!(db.seen().len().await?, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn len(&self) -> Result<usize, Error<E>> {
+        self.map.len().await
+    }
+}
@@ -0,0 +1,704 @@
+use core::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::traits::async_data_store::{self, AsyncDataStore};
+use crate::wrapper::VecPrefixed as Prefixed;
+use crate::Error;
+
+mod extend;
+mod iterator;
+
+/// mimics the API of [`Vec`]
+pub struct Vec<T, DS>
+where
+    DS: AsyncDataStore,
+{
+    // fn() -> T keeps the wrapper Sync, so its futures can be Send
+    phantom: PhantomData<fn() -> T>,
+    ds: DS,
+    prefix: u8,
+    // Held while writing to the database so concurrent pushes and pops can
+    // not claim the same index
+    len: Arc<Mutex<usize>>,
+}
+
+/// Finds the length of the vec stored under `prefix`. Used by the generated
+/// code when opening the database.
+pub async fn stored_len<DS>(ds: &DS, prefix: u8) -> Result<usize, Error<DS::DbError>>
+where
+    DS: async_data_store::Ordered,
+{
+    // The key before could belong to another field. Check its prefix
+    // before decoding it as ours.
+    match ds.get_lt::<_, u8, ()>(&Prefixed::max(prefix)).await? {
+        Some((found, ())) if found == prefix => (),
+        _ => return Ok(0),
+    }
+    Ok(ds
+        .get_lt::<_, Prefixed, ()>(&Prefixed::max(prefix))
+        .await?
+        .map(|(key, ())| key.index() + 1) // a vecs len is index + 1
+        .unwrap_or(0))
+}
+
+impl<T, E, DS> Vec<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
+        Self {
+            phantom: PhantomData,
+            ds,
+            prefix,
+            len,
+        }
+    }
+
+    /// Returns the element at `index` if there is one.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.list().push("a").await?;
+    /// db.list().push("b").await?;
+    /// assert_eq!(db.list().get(0).await?, Some("a".to_owned()));
+    /// assert_eq!(db.list().get(2).await?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get(&self, index: usize) -> Result<Option<T>, Error<E>> {
+        let len = self.len.lock().await;
+        if index >= *len {
+            return Ok(None);
+        }
+
+        let key = Prefixed {
+            prefix: self.prefix,
+            index,
+        };
+        self.ds.get(&key).await
+    }
+
+    /// Appends an element to the back of the collection.
+    ///
+    /// The item may be any borrowed form of the lists item type, but the
+    /// serialized form must match the not borrowed serialized form.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.list().push("a").await?;
+    /// db.list().push("b").await?;
+    /// assert_eq!(db.list().len().await, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn push<Q>(&self, value: &Q) -> Result<(), Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let mut len = self.len.lock().await;
+        let key = Prefixed {
+            prefix: self.prefix,
+            index: *len,
+        };
+        debug!("pushing onto vector (index: {})", *len);
+        self.ds.insert::<Prefixed, Q, T>(&key, value).await?;
+        *len += 1;
+        Ok(())
+    }
+
+    /// Removes the last element and returns it, or `None` if it is empty
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.list().extend(["a", "b", "c"]).await?;
+    /// assert_eq!(db.list().pop().await?, Some("c".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn pop(&self) -> Result<Option<T>, Error<E>> {
+        let mut len = self.len.lock().await;
+        let Some(index) = len.checked_sub(1) else {
+            return Ok(None);
+        };
+        let key = Prefixed {
+            prefix: self.prefix,
+            index,
+        };
+
+        debug!("popping from vector (index: {index})");
+        let value = self.ds.remove(&key).await?;
+        *len = index;
+        Ok(value)
+    }
+
+    /// Reads an element that should exist as it is below the length
+    async fn get_existing(&self, index: usize) -> Result<T, Error<E>> {
+        let key = Prefixed {
+            prefix: self.prefix,
+            index,
+        };
+        self.ds
+            .get(&key)
+            .await?
+            .ok_or(Error::MissingElement { index })
+    }
+
+    /// Removes elements from the back until the list is `new_len` long
+    async fn shrink(&self, len: &mut usize, new_len: usize) -> Result<(), Error<E>> {
+        while *len > new_len {
+            let index = *len - 1;
+            let key = Prefixed {
+                prefix: self.prefix,
+                index,
+            };
+            self.ds.remove::<Prefixed, T>(&key).await?;
+            *len = index;
+        }
+        Ok(())
+    }
+
+    /// Writes `value` to `index` returning the element that was there
+    async fn put<Q>(&self, index: usize, value: &Q) -> Result<Option<T>, Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let key = Prefixed {
+            prefix: self.prefix,
+            index,
+        };
+        self.ds.insert::<Prefixed, Q, T>(&key, value).await
+    }
+
+    /// Returns the first element of the list, or `None` if it is empty.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// assert_eq!(db.list().first().await?, None);
+    /// db.list().extend(["a", "b", "c"]).await?;
+    /// assert_eq!(db.list().first().await?, Some("a".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn first(&self) -> Result<Option<T>, Error<E>> {
+        self.get(0).await
+    }
+
+    /// Returns the last element of the list, or `None` if it is empty.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// assert_eq!(db.list().last().await?, None);
+    /// db.list().extend(["a", "b", "c"]).await?;
+    /// assert_eq!(db.list().last().await?, Some("c".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn last(&self) -> Result<Option<T>, Error<E>> {
+        let len = self.len.lock().await;
+        match len.checked_sub(1) {
+            Some(index) => self.get_existing(index).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Overwrites the element at `index`.
+    ///
+    /// The item may be any borrowed form of the lists item type, but the
+    /// serialized form must match the not borrowed serialized form.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.list().extend(["a", "b", "c"]).await?;
+    /// db.list().set(1, "z").await?;
+    /// assert_eq!(db.list().get(1).await?, Some("z".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn set<Q>(&self, index: usize, value: &Q) -> Result<(), Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let len = self.len.lock().await;
+        assert!(
+            index < *len,
+            "set index (is {index}) should be < len (is {})",
+            *len
+        );
+        self.put(index, value).await?;
+        Ok(())
+    }
+
+    /// Inserts an element at position `index` within the list, shifting all
+    /// elements after it to the right.
+    ///
+    /// This rewrites every element after `index`, it takes O(n) database
+    /// writes. They are moved one at a time starting at the back, if a write
+    /// fails the list is left with one of the moved elements duplicated but
+    /// no element is lost.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Panics
+    /// Panics if `index > len`.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.list().extend(["a", "c"]).await?;
+    /// db.list().insert(1, "b").await?;
+    /// assert_eq!(db.list().get(1).await?, Some("b".to_owned()));
+    /// assert_eq!(db.list().last().await?, Some("c".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn insert<Q>(&self, index: usize, value: &Q) -> Result<(), Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let mut len = self.len.lock().await;
+        assert!(
+            index <= *len,
+            "insertion index (is {index}) should be <= len (is {})",
+            *len
+        );
+
+        debug!("inserting into vector (index: {index}, len: {})", *len);
+        let end = *len;
+        for from in (index..end).rev() {
+            let moved = self.get_existing(from).await?;
+            self.put::<T>(from + 1, &moved).await?;
+            // the element at `end` is stored now, it belongs to the list
+            *len = end + 1;
+        }
+        self.put(index, value).await?;
+        *len = end + 1;
+        Ok(())
+    }
+
+    /// Removes and returns the element at position `index` within the list,
+    /// shifting all elements after it to the left.
+    ///
+    /// This rewrites every element after `index`, it takes O(n) database
+    /// writes. Use [`swap_remove`](Self::swap_remove) if the order does not
+    /// matter. A failed write leaves the list with one of the moved elements
+    /// duplicated.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.list().extend(["a", "b", "c"]).await?;
+    /// assert_eq!(db.list().remove(1).await?, "b");
+    /// assert_eq!(db.list().get(1).await?, Some("c".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn remove(&self, index: usize) -> Result<T, Error<E>> {
+        let mut len = self.len.lock().await;
+        assert!(
+            index < *len,
+            "removal index (is {index}) should be < len (is {})",
+            *len
+        );
+
+        debug!("removing from vector (index: {index}, len: {})", *len);
+        let removed = self.get_existing(index).await?;
+        for from in index + 1..*len {
+            let moved = self.get_existing(from).await?;
+            self.put::<T>(from - 1, &moved).await?;
+        }
+        let last = *len - 1;
+        self.shrink(&mut len, last).await?;
+        Ok(removed)
+    }
+
+    /// Removes an element from the list and returns it. The removed element
+    /// is replaced by the last element of the list.
+    ///
+    /// This does not preserve ordering but takes at most three database
+    /// operations.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.list().extend(["a", "b", "c", "d"]).await?;
+    /// assert_eq!(db.list().swap_remove(1).await?, "b");
+    /// assert_eq!(db.list().get(1).await?, Some("d".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn swap_remove(&self, index: usize) -> Result<T, Error<E>> {
+        let mut len = self.len.lock().await;
+        assert!(
+            index < *len,
+            "swap_remove index (is {index}) should be < len (is {})",
+            *len
+        );
+
+        let last = *len - 1;
+        let removed = if index == last {
+            self.get_existing(index).await?
+        } else {
+            let moved = self.get_existing(last).await?;
+            self.put::<T>(index, &moved)
+                .await?
+                .ok_or(Error::MissingElement { index })?
+        };
+        self.shrink(&mut len, last).await?;
+        Ok(removed)
+    }
+
+    /// Shortens the list, keeping the first `len` elements and removing the
+    /// rest. Has no effect if `len` is greater than or equal to the lists
+    /// current length.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.list().extend(["a", "b", "c"]).await?;
+    /// db.list().truncate(1).await?;
+    /// assert_eq!(db.list().len().await, 1);
+    /// assert_eq!(db.list().last().await?, Some("a".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn truncate(&self, new_len: usize) -> Result<(), Error<E>> {
+        let mut len = self.len.lock().await;
+        self.shrink(&mut len, new_len).await
+    }
+
+    /// Retains only the elements specified by the predicate. Removes all
+    /// elements `e` for which `f(&e)` returns `false`, the order of the
+    /// retained elements is preserved.
+    ///
+    /// This reads every element and rewrites each retained element that has
+    /// to move. A failed write can leave the list with some retained elements
+    /// duplicated.
+    ///
+    /// The list is locked during the whole pass, using it inside `f`
+    /// deadlocks.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<u32>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.list().extend(&[1, 2, 3, 4]).await?;
+    /// db.list().retain(|x| x % 2 == 0).await?;
+    /// assert_eq!(db.list().len().await, 2);
+    /// assert_eq!(db.list().first().await?, Some(2));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn retain<F>(&self, mut f: F) -> Result<(), Error<E>>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut len = self.len.lock().await;
+        let mut kept = 0;
+        for index in 0..*len {
+            let element = self.get_existing(index).await?;
+            if !f(&element) {
+                continue;
+            }
+            if kept != index {
+                self.put::<T>(kept, &element).await?;
+            }
+            kept += 1;
+        }
+        self.shrink(&mut len, kept).await
+    }
+
+    /// Clears the list, removing all values.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.list().extend(["a", "b"]).await?;
+    /// db.list().clear().await?;
+    /// assert!(db.list().is_empty().await);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn clear(&self) -> Result<(), Error<E>> {
+        let mut len = self.len.lock().await;
+        while let Some(index) = len.checked_sub(1) {
+            let key = Prefixed {
+                prefix: self.prefix,
+                index,
+            };
+            self.ds.remove::<Prefixed, T>(&key).await?;
+            *len = index;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of elements in the list.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// assert_eq!(db.list().len().await, 0);
+    /// db.list().push("a").await?;
+    /// assert_eq!(db.list().len().await, 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn len(&self) -> usize {
+        *self.len.lock().await
+    }
+
+    /// Returns `true` if the list has a length of 0.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// assert!(db.list().is_empty().await);
+    /// db.list().push("a").await?;
+    /// assert!(!db.list().is_empty().await);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::stores;
+    use crate::wrapper::asynchronous::Vec;
+    use crate::Error;
+
+    async fn filled(elements: &[u8]) -> Vec<u8, stores::Blocking<stores::BTreeMap>> {
+        let ds = stores::Blocking::new(stores::BTreeMap::new());
+        let vec = Vec::new(ds, 1, Arc::new(Mutex::new(0)));
+        vec.extend(elements).await.unwrap();
+        vec
+    }
+
+    async fn elements(vec: &Vec<u8, stores::Blocking<stores::BTreeMap>>) -> std::vec::Vec<u8> {
+        let mut elements = std::vec::Vec::new();
+        let mut iter = vec.iter();
+        while let Some(element) = iter.next().await {
+            elements.push(element.unwrap());
+        }
+        elements
+    }
+
+    #[tokio::test]
+    async fn matches_std() {
+        let mut std_vec = vec![1u8, 2, 3, 4, 5, 6];
+        let vec = filled(&std_vec).await;
+
+        vec.insert(0, &0).await.unwrap();
+        std_vec.insert(0, 0);
+        vec.insert(3, &9).await.unwrap();
+        std_vec.insert(3, 9);
+        vec.insert(std_vec.len(), &7).await.unwrap();
+        std_vec.insert(std_vec.len(), 7);
+        assert_eq!(elements(&vec).await, std_vec);
+
+        assert_eq!(vec.remove(2).await.unwrap(), std_vec.remove(2));
+        assert_eq!(vec.swap_remove(1).await.unwrap(), std_vec.swap_remove(1));
+        vec.set(0, &8).await.unwrap();
+        std_vec[0] = 8;
+        assert_eq!(elements(&vec).await, std_vec);
+
+        vec.retain(|n| n % 2 == 0).await.unwrap();
+        std_vec.retain(|n| n % 2 == 0);
+        assert_eq!(elements(&vec).await, std_vec);
+        assert_eq!(vec.first().await.unwrap(), std_vec.first().copied());
+        assert_eq!(vec.last().await.unwrap(), std_vec.last().copied());
+
+        vec.truncate(1).await.unwrap();
+        std_vec.truncate(1);
+        assert_eq!(elements(&vec).await, std_vec);
+        assert_eq!(vec.len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn missing_element_is_an_error() {
+        let ds = stores::Blocking::new(stores::BTreeMap::new());
+        let vec: Vec<u8, _> = Vec::new(ds, 1, Arc::new(Mutex::new(2)));
+        vec.push(&7).await.unwrap();
+        assert!(matches!(vec.last().await, Ok(Some(7))));
+        assert!(matches!(
+            vec.retain(|_| true).await,
+            Err(Error::MissingElement { index: 0 })
+        ));
+    }
+}
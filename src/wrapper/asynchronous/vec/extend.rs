@@ -0,0 +1,97 @@
+use std::borrow::Borrow;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::{AsyncDataStore, ExtendError};
+
+use super::Vec;
+
+impl<T, E, DS> Vec<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    /// Extends the list with the contents of an iterator.
+    ///
+    /// The iterator item may be any borrowed form of the lists item type,
+    /// as long as the serialized form matches between borrowed and not borrowed.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.list().extend(["one", "two", "three"]).await?;
+    /// assert_eq!(db.list().get(0).await?, Some("one".to_owned()));
+    /// assert_eq!(db.list().get(2).await?, Some("three".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub async fn extend<'a, I, Q>(
+        &self,
+        iter: I,
+    ) -> Result<(), ExtendError<I::Item, I::IntoIter, crate::Error<E>>>
+    where
+        I: IntoIterator<Item = &'a Q>,
+        T: Borrow<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        let mut iter = iter.into_iter();
+        loop {
+            let Some(item) = iter.next() else {
+                return Ok(());
+            };
+
+            if let Err(error) = self.push::<Q>(item).await {
+                return Err(ExtendError {
+                    unadded: item,
+                    iter,
+                    error,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::stores;
+    use crate::wrapper::asynchronous::Vec;
+
+    #[tokio::test]
+    async fn error() {
+        let ds = stores::Faulty::new(stores::BTreeMap::new());
+        let vec: Vec<u16, _> = Vec::new(
+            stores::Blocking::new(ds.clone()),
+            1,
+            Arc::new(Mutex::new(0)),
+        );
+
+        let iter = [1, 2, 3, 4];
+        ds.fail_nth(1);
+        let err = vec
+            .extend(&iter)
+            .await
+            .expect_err("we forced the datastore to crash on access");
+        assert_eq!(err.unadded, &1);
+        assert_eq!(err.iter.collect::<std::vec::Vec<_>>(), vec![&2, &3, &4]);
+        assert_eq!(vec.len().await, 0);
+    }
+}
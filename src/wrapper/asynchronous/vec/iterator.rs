@@ -0,0 +1,101 @@
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::AsyncDataStore;
+use crate::Error;
+
+use super::Vec;
+
+/// Visits the elements of an async [`Vec`] front to back. This is not an
+/// [`Iterator`], call [`next`](Iter::next) until it returns `None`.
+pub struct Iter<'a, T, DS>
+where
+    DS: AsyncDataStore,
+{
+    current: usize,
+    vec: &'a Vec<T, DS>,
+}
+
+impl<T, E, DS> Iter<'_, T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    /// Returns the next element, or `None` when the end of the list is
+    /// reached.
+    pub async fn next(&mut self) -> Option<Result<T, Error<E>>> {
+        let elem = self.vec.get(self.current).await;
+        self.current += 1;
+        elem.transpose()
+    }
+}
+
+impl<T, E, DS> Vec<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    /// Visits the elements front to back. This gets each element from the
+    /// database individually. Elements pushed while iterating are visited
+    /// too.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     list: Vec<u32>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.list().extend(&[1, 2, 3]).await?;
+    ///
+    /// let mut sum = 0;
+    /// let list = db.list();
+    /// let mut elements = list.iter();
+    /// while let Some(elem) = elements.next().await {
+    ///     sum += elem?;
+    /// }
+    /// assert_eq!(sum, 6);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T, DS> {
+        Iter {
+            current: 0,
+            vec: self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::stores;
+    use crate::wrapper::asynchronous::Vec;
+
+    #[tokio::test]
+    async fn pop_during_iter() {
+        let ds = stores::Blocking::new(stores::BTreeMap::new());
+        let vec: Vec<u16, _> = Vec::new(ds, 1, Arc::new(Mutex::new(0)));
+        vec.push(&42).await.unwrap();
+        vec.push(&13).await.unwrap();
+
+        let mut iter = vec.iter();
+        assert_eq!(iter.next().await.unwrap().unwrap(), 42);
+        vec.pop().await.unwrap();
+        assert!(iter.next().await.is_none());
+    }
+}
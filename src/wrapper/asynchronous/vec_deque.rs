@@ -0,0 +1,369 @@
+use core::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::traits::async_data_store::{self, AsyncDataStore};
+use crate::wrapper::DequePrefixed as Prefixed;
+use crate::Error;
+
+mod extend;
+mod iterator;
+
+/// mimics the API of [`VecDeque`](std::collections::VecDeque)
+pub struct VecDeque<T, DS>
+where
+    DS: AsyncDataStore,
+{
+    // fn() -> T keeps the wrapper Sync, so its futures can be Send
+    phantom: PhantomData<fn() -> T>,
+    ds: DS,
+    prefix: u8,
+    // Points to the current free slot. Held while writing to the database,
+    // when locking both always lock head before tail.
+    head: Arc<Mutex<u64>>,
+    // Points to the current free slot
+    tail: Arc<Mutex<u64>>,
+}
+
+/// Finds the free slot after the last item of the deque stored under
+/// `prefix`. Used by the generated code when opening the database.
+pub async fn stored_tail<DS>(ds: &DS, prefix: u8) -> Result<u64, Error<DS::DbError>>
+where
+    DS: async_data_store::Ordered,
+{
+    // The key before could belong to another field. Check its prefix
+    // before decoding it as ours.
+    match ds.get_lt::<_, u8, ()>(&Prefixed::max(prefix)).await? {
+        Some((found, ())) if found == prefix => (),
+        _ => return Ok(u64::MAX / 2),
+    }
+    Ok(ds
+        .get_lt::<_, Prefixed, ()>(&Prefixed::max(prefix))
+        .await?
+        .map(|(key, ())| key.index() + 1)
+        .unwrap_or(u64::MAX / 2))
+}
+
+/// Finds the free slot before the first item of the deque stored under
+/// `prefix`. Used by the generated code when opening the database.
+pub async fn stored_head<DS>(ds: &DS, prefix: u8) -> Result<u64, Error<DS::DbError>>
+where
+    DS: async_data_store::Ordered,
+{
+    // The key after could belong to another field or be metadata. Check
+    // its prefix before decoding it as ours.
+    match ds.get_gt::<_, u8, ()>(&Prefixed::min(prefix)).await? {
+        Some((found, ())) if found == prefix => (),
+        _ => return Ok(u64::MAX / 2 - 1),
+    }
+    Ok(ds
+        .get_gt::<_, Prefixed, ()>(&Prefixed::min(prefix))
+        .await?
+        .map(|(key, ())| key.index() - 1)
+        .unwrap_or(u64::MAX / 2 - 1))
+}
+
+impl<T, E, DS> VecDeque<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, prefix: u8, head: Arc<Mutex<u64>>, tail: Arc<Mutex<u64>>) -> Self {
+        Self {
+            phantom: PhantomData,
+            ds,
+            prefix,
+            head,
+            tail,
+        }
+    }
+
+    /// Returns the element at `index` if there is one, index 0 is the
+    /// front of the deque.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     queue: VecDeque<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.queue().push_back("b").await?;
+    /// db.queue().push_front("a").await?;
+    /// assert_eq!(db.queue().get(0).await?, Some("a".to_owned()));
+    /// assert_eq!(db.queue().get(2).await?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get(&self, index: usize) -> Result<Option<T>, Error<E>> {
+        let head = self.head.lock().await;
+        let tail = self.tail.lock().await;
+        if index as u64 >= *tail - *head - 1 {
+            return Ok(None);
+        }
+
+        let key = Prefixed {
+            prefix: self.prefix,
+            index: index as u64 + *head + 1,
+        };
+        self.ds.get(&key).await
+    }
+
+    /// Appends an element to the back of the deque.
+    ///
+    /// The item may be any borrowed form of the deques item type, but the
+    /// serialized form must match the not borrowed serialized form.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     queue: VecDeque<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.queue().push_back("a").await?;
+    /// db.queue().push_back("b").await?;
+    /// assert_eq!(db.queue().get(1).await?, Some("b".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn push_back<Q>(&self, value: &Q) -> Result<(), Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let mut tail = self.tail.lock().await;
+        let key = Prefixed {
+            prefix: self.prefix,
+            index: *tail,
+        };
+
+        self.ds.insert::<Prefixed, Q, T>(&key, value).await?;
+        *tail += 1;
+        Ok(())
+    }
+
+    /// Prepends an element to the deque.
+    ///
+    /// The item may be any borrowed form of the deques item type, but the
+    /// serialized form must match the not borrowed serialized form.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     queue: VecDeque<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.queue().push_front("a").await?;
+    /// db.queue().push_front("b").await?;
+    /// assert_eq!(db.queue().get(0).await?, Some("b".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn push_front<Q>(&self, value: &Q) -> Result<(), Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let mut head = self.head.lock().await;
+        let key = Prefixed {
+            prefix: self.prefix,
+            index: *head,
+        };
+
+        self.ds.insert::<Prefixed, Q, T>(&key, value).await?;
+        *head -= 1;
+        Ok(())
+    }
+
+    /// Removes the last element and returns it, or `None` if the deque is
+    /// empty.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     queue: VecDeque<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.queue().extend(["a", "b"]).await?;
+    /// assert_eq!(db.queue().pop_back().await?, Some("b".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn pop_back(&self) -> Result<Option<T>, Error<E>> {
+        let head = self.head.lock().await;
+        let mut tail = self.tail.lock().await;
+        let last = *tail - 1;
+        if last == *head {
+            return Ok(None);
+        }
+
+        let key = Prefixed {
+            prefix: self.prefix,
+            index: last,
+        };
+
+        let value = self.ds.remove(&key).await?;
+        *tail = last;
+        Ok(value)
+    }
+
+    /// Removes the first element and returns it, or `None` if the deque is
+    /// empty.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     queue: VecDeque<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.queue().extend(["a", "b"]).await?;
+    /// assert_eq!(db.queue().pop_front().await?, Some("a".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn pop_front(&self) -> Result<Option<T>, Error<E>> {
+        let mut head = self.head.lock().await;
+        let tail = self.tail.lock().await;
+        let first = *head + 1;
+        if first == *tail {
+            return Ok(None);
+        }
+
+        let key = Prefixed {
+            prefix: self.prefix,
+            index: first,
+        };
+
+        let value = self.ds.remove(&key).await?;
+        *head = first;
+        Ok(value)
+    }
+
+    /// Removes all elements.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     queue: VecDeque<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.queue().extend(["a", "b"]).await?;
+    /// db.queue().clear().await?;
+    /// assert!(db.queue().is_empty().await);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn clear(&self) -> Result<(), Error<E>> {
+        let head = self.head.lock().await;
+        let mut tail = self.tail.lock().await;
+        while *tail - 1 != *head {
+            let key = Prefixed {
+                prefix: self.prefix,
+                index: *tail - 1,
+            };
+            self.ds.remove::<Prefixed, T>(&key).await?;
+            *tail -= 1;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of elements in the deque.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     queue: VecDeque<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.queue().push_back("a").await?;
+    /// db.queue().push_front("b").await?;
+    /// assert_eq!(db.queue().len().await, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn len(&self) -> usize {
+        let head = self.head.lock().await;
+        let tail = self.tail.lock().await;
+
+        ((*tail - *head) - 1) as usize
+    }
+
+    /// Returns `true` if the deque is empty.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     queue: VecDeque<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// assert!(db.queue().is_empty().await);
+    /// db.queue().push_back("a").await?;
+    /// assert!(!db.queue().is_empty().await);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
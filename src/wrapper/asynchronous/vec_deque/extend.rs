@@ -0,0 +1,68 @@
+use std::borrow::Borrow;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::{AsyncDataStore, ExtendError};
+
+use super::VecDeque;
+
+impl<T, E, DS> VecDeque<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    /// Extends the deque with the contents of an iterator, the items are
+    /// added to the back.
+    ///
+    /// The iterator item may be any borrowed form of the deques item type,
+    /// as long as the serialized form matches between borrowed and not borrowed.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     queue: VecDeque<String>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.queue().extend(["one", "two", "three"]).await?;
+    /// assert_eq!(db.queue().pop_front().await?, Some("one".to_owned()));
+    /// assert_eq!(db.queue().pop_back().await?, Some("three".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub async fn extend<'a, I, Q>(
+        &self,
+        iter: I,
+    ) -> Result<(), ExtendError<I::Item, I::IntoIter, crate::Error<E>>>
+    where
+        I: IntoIterator<Item = &'a Q>,
+        T: Borrow<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        let mut iter = iter.into_iter();
+        loop {
+            let Some(item) = iter.next() else {
+                return Ok(());
+            };
+
+            if let Err(error) = self.push_back::<Q>(item).await {
+                return Err(ExtendError {
+                    unadded: item,
+                    iter,
+                    error,
+                });
+            }
+        }
+    }
+}
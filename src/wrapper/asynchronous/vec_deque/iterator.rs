@@ -0,0 +1,76 @@
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::AsyncDataStore;
+use crate::Error;
+
+use super::VecDeque;
+
+/// Visits the elements of an async [`VecDeque`] front to back. This is not
+/// an [`Iterator`], call [`next`](Iter::next) until it returns `None`.
+pub struct Iter<'a, T, DS>
+where
+    DS: AsyncDataStore,
+{
+    current: usize,
+    deque: &'a VecDeque<T, DS>,
+}
+
+impl<T, E, DS> Iter<'_, T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    /// Returns the next element, or `None` when the back of the deque is
+    /// reached.
+    pub async fn next(&mut self) -> Option<Result<T, Error<E>>> {
+        let elem = self.deque.get(self.current).await;
+        self.current += 1;
+        elem.transpose()
+    }
+}
+
+impl<T, E, DS> VecDeque<T, DS>
+where
+    E: fmt::Debug + Send,
+    T: Serialize + DeserializeOwned,
+    DS: AsyncDataStore<DbError = E>,
+{
+    /// Visits the elements front to back. This gets each element from the
+    /// database individually.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap, async)]
+    /// struct Test {
+    ///     queue: VecDeque<u32>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new().await?;
+    /// db.queue().push_back(&2).await?;
+    /// db.queue().push_front(&1).await?;
+    ///
+    /// let queue = db.queue();
+    /// let mut elements = queue.iter();
+    /// assert_eq!(elements.next().await.transpose()?, Some(1));
+    /// assert_eq!(elements.next().await.transpose()?, Some(2));
+    /// assert_eq!(elements.next().await.transpose()?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T, DS> {
+        Iter {
+            current: 0,
+            deque: self,
+        }
+    }
+}
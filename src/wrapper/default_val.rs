@@ -37,10 +37,15 @@ where
         Ok(())
     }
 
-    pub fn get(&self) -> Result<T, Error<E>> {
+    /// Returns the value, or the default if it was never set. Structs with
+    /// the `cache` option keep the decoded value, getting it again clones it.
+    pub fn get(&self) -> Result<T, Error<E>>
+    where
+        T: Send + Sync + 'static,
+    {
         Ok(self
             .ds
-            .get(&self.key)?
+            .get_cloned(&self.key)?
             .unwrap_or_else(|| self.default_value.clone()))
     }
 }
@@ -2,19 +2,34 @@ use core::fmt;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
 use tracing::instrument;
 
-use crate::traits::{byte_store, DataStore};
-use crate::Error;
+use crate::schema::{Meta, META_PREFIX};
+use crate::traits::byte_store::{self, val_config, ByteStore};
+use crate::traits::{data_store, DataStore};
+use crate::{key, Error};
 
-use super::PhantomUnsync;
+use super::lock;
 
 mod entry;
 pub use entry::{Entry, OccupiedEntry, VacantEntry};
 mod extend;
 mod iterator;
+pub use iterator::{Iter, Keys, Values};
+#[cfg(feature = "async")]
+pub(crate) use iterator::{decode, successor};
 
 /// mimics the API of [`HashMap`][std::collections::HashMap]
+///
+/// Mark the field `#[dbstruct(counted)]` to make [`len`](Map::len) cheap. The
+/// number of entries is then stored in the database, updated by every insert
+/// and remove and read back when the struct is opened. Databases that support
+/// transactions write an entry and the count in one transaction. On the others
+/// the count is removed before writing the entry and stored again afterwards,
+/// should that not happen the entries are counted the next time the struct is
+/// opened. Opening a struct where the field is not counted removes the count
+/// too.
 pub struct Map<Key, Value, DS>
 where
     Key: Serialize,
@@ -23,15 +38,112 @@ where
 {
     phantom_key: PhantomData<Key>,
     phantom_val: PhantomData<Value>,
-    phantom2: PhantomUnsync,
-    tree: DS,
+    pub(super) tree: DS,
+    pub(super) prefix: u8,
+    /// The number of entries for maps marked `#[dbstruct(counted)]`. Held
+    /// while writing so concurrent inserts and removes can not miscount.
+    len: Option<Arc<Mutex<usize>>>,
+    /// Set if the database can write an entry and the count in one
+    /// transaction
+    write_counted: Option<WriteCounted<DS>>,
+}
+
+/// Writes an encoded entry and the count that results in one transaction,
+/// see [`write_counted`].
+type WriteCounted<DS> = fn(
+    &DS,
+    u8,
+    &[u8],
+    Option<&[u8]>,
+    usize,
+) -> Result<(Option<Vec<u8>>, usize), Error<<DS as DataStore>::DbError>>;
+
+/// Inserts `val` under the encoded `key`, or removes the entry if `val` is
+/// `None`, and stores the count that results. Both happen in one
+/// transaction. Returns the previous value and the new count.
+#[allow(clippy::type_complexity)]
+fn write_counted<DS>(
+    ds: &DS,
     prefix: u8,
+    key: &[u8],
+    val: Option<&[u8]>,
+    len: usize,
+) -> Result<(Option<Vec<u8>>, usize), Error<DS::DbError>>
+where
+    DS: data_store::Transactional,
+    for<'tx> DS::Tx<'tx>: ByteStore<DbError = DS::TxError>,
+{
+    ds.transaction(|tx| {
+        let existing = match val {
+            Some(val) => ByteStore::insert(tx, key, val),
+            None => ByteStore::remove(tx, key),
+        }
+        .map_err(Error::Database)?
+        .map(|bytes| bytes.as_ref().to_vec());
+        let len = match (val, &existing) {
+            (Some(_), None) => len + 1,
+            (None, Some(_)) => len - 1,
+            _ => return Ok((existing, len)),
+        };
+        DataStore::insert::<_, _, usize>(tx, &(META_PREFIX, Meta::Count(prefix)), &len)?;
+        Ok((existing, len))
+    })
 }
 
 #[derive(Serialize)]
 pub struct Prefixed<'a, K: ?Sized> {
-    prefix: u8,
-    key: &'a K,
+    pub(crate) prefix: u8,
+    pub(crate) key: &'a K,
+}
+
+/// Finds the number of entries in the counted map stored under `prefix`.
+/// Used by the generated code when opening the database.
+///
+/// Reads the stored count. If there is none, for example because the field
+/// just became counted or a write was interrupted, the entries are counted
+/// instead.
+#[doc(hidden)]
+pub fn stored_len<Key, DS>(ds: &DS, prefix: u8) -> Result<usize, Error<DS::DbError>>
+where
+    Key: Serialize + DeserializeOwned,
+    DS: data_store::Ordered,
+{
+    if let Some(len) = ds.get(&(META_PREFIX, Meta::Count(prefix)))? {
+        return Ok(len);
+    }
+
+    let mut len = 0;
+    let mut last: Option<Key> = None;
+    loop {
+        let found = match &last {
+            None => ds.get_gt::<_, u8, ()>(&prefix)?,
+            Some(key) => ds.get_gt::<_, u8, ()>(&Prefixed { prefix, key })?,
+        };
+        // The next key could belong to another field. Check its prefix
+        // before decoding it as ours.
+        match found {
+            Some((found, ())) if found == prefix => (),
+            _ => return Ok(len),
+        }
+        let next = match &last {
+            None => ds.get_gt::<_, (u8, Key), ()>(&prefix)?,
+            Some(key) => ds.get_gt::<_, (u8, Key), ()>(&Prefixed { prefix, key })?,
+        };
+        let Some(((_, key), ())) = next else {
+            return Ok(len);
+        };
+        len += 1;
+        last = Some(key);
+    }
+}
+
+/// Removes the stored count of the map under `prefix`. Used by the generated
+/// code when opening the database if the field is not counted. Writes made
+/// through it do not update the count, this makes sure it is recounted once
+/// the field is counted again.
+#[doc(hidden)]
+pub fn forget_len<DS: DataStore>(ds: &DS, prefix: u8) -> Result<(), Error<DS::DbError>> {
+    ds.clear(&(META_PREFIX, Meta::Count(prefix)))
 }
 
 impl<Key, Value, E, DS> Map<Key, Value, DS>
@@ -47,12 +159,65 @@ where
         Self {
             phantom_key: PhantomData,
             phantom_val: PhantomData,
-            phantom2: PhantomData,
             tree,
             prefix,
+            len: None,
+            write_counted: None,
+        }
+    }
+
+    #[doc(hidden)]
+    #[instrument(skip(tree, len), level = "debug")]
+    pub fn counted(tree: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
+        Self {
+            phantom_key: PhantomData,
+            phantom_val: PhantomData,
+            tree,
+            prefix,
+            len: Some(len),
+            write_counted: None,
         }
     }
 
+    /// Removes the stored count of a counted map before a write that
+    /// changes it. Until it is stored again opening the struct counts the
+    /// entries.
+    fn mark_len_stale(&self) -> Result<(), Error<E>> {
+        self.tree.clear(&(META_PREFIX, Meta::Count(self.prefix)))
+    }
+
+    /// Stores the count of a counted map. The in memory count is already
+    /// updated, if this fails the next successful write stores it.
+    fn store_len(&self, len: usize) -> Result<(), Error<E>> {
+        self.tree
+            .insert::<_, _, usize>(&(META_PREFIX, Meta::Count(self.prefix)), &len)?;
+        Ok(())
+    }
+
+    /// Writes an entry of a counted map together with the count using
+    /// `write`, see [`write_counted`].
+    fn write_with_count(
+        &self,
+        write: WriteCounted<DS>,
+        len: &mut usize,
+        key: &Prefixed<'_, impl Serialize + ?Sized>,
+        value: Option<&(impl Serialize + ?Sized)>,
+    ) -> Result<Option<Value>, Error<E>> {
+        let key = key::encode(key).map_err(Error::SerializingKey)?;
+        let value = value
+            .map(|value| bincode::serde::encode_to_vec(value, val_config()))
+            .transpose()
+            .map_err(Error::SerializingValue)?;
+        let (existing, new_len) = write(&self.tree, self.prefix, &key, value.as_deref(), *len)?;
+        *len = new_len;
+        let Some(existing) = existing else {
+            return Ok(None);
+        };
+        let (existing, _) = bincode::serde::decode_from_slice(&existing, val_config())
+            .map_err(Error::DeSerializingVal)?;
+        Ok(Some(existing))
+    }
+
     /// Inserts a key-value pair into the map.
     ///
     /// If the map did not have this key present, [`None`] is returned.
@@ -98,7 +263,24 @@ where
             prefix: self.prefix,
             key,
         };
+        let Some(len) = &self.len else {
+            return self.tree.insert(&key, value);
+        };
+
+        let mut len = lock(len);
+        if self.tree.contains(&key)? {
+            // Replaces a value, the count stays the same
+            return self.tree.insert(&key, value);
+        }
+        if let Some(write) = self.write_counted {
+            return self.write_with_count(write, &mut len, &key, Some(value));
+        }
+        self.mark_len_stale()?;
         let existing = self.tree.insert(&key, value)?;
+        if existing.is_none() {
+            *len += 1;
+        }
+        self.store_len(*len)?;
         Ok(existing)
     }
 
@@ -176,7 +358,23 @@ where
             prefix: self.prefix,
             key,
         };
+        let Some(len) = &self.len else {
+            return self.tree.remove(&key);
+        };
+
+        let mut len = lock(len);
+        if !self.tree.contains(&key)? {
+            return Ok(None);
+        }
+        if let Some(write) = self.write_counted {
+            return self.write_with_count(write, &mut len, &key, None::<&Value>);
+        }
+        self.mark_len_stale()?;
         let value = self.tree.remove(&key)?;
+        if value.is_some() {
+            *len -= 1;
+        }
+        self.store_len(*len)?;
         Ok(value)
     }
 
@@ -219,6 +417,26 @@ where
     }
 }
 
+impl<Key, Value, E, DS> Map<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: data_store::Transactional<DbError = E>,
+    for<'tx> DS::Tx<'tx>: ByteStore<DbError = DS::TxError>,
+{
+    /// Like [`counted`](Map::counted) but writes each entry together with
+    /// the count in one transaction.
+    #[doc(hidden)]
+    #[instrument(skip(tree, len), level = "debug")]
+    pub fn counted_transactional(tree: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
+        Self {
+            write_counted: Some(write_counted::<DS>),
+            ..Self::counted(tree, prefix, len)
+        }
+    }
+}
+
 impl<Key, Value, E, DS> Map<Key, Value, DS>
 where
     E: fmt::Debug,
@@ -283,6 +501,43 @@ where
     pub fn is_empty(&self) -> Result<bool, Error<E>> {
         Ok(self.iter().next().is_none())
     }
+
+    /// Returns the number of elements in the map. For a map marked
+    /// `#[dbstruct(counted)]` this reads a counter that is kept up to date
+    /// by every write. Otherwise it reads every element, avoid that on large
+    /// maps.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     #[dbstruct(counted)]
+    ///     sessions: HashMap<u64, String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.sessions().insert(&1, "alice")?;
+    /// db.sessions().insert(&2, "bob")?;
+    /// db.sessions().insert(&2, "carol")?;
+    /// assert_eq!(db.sessions().len()?, 2);
+    ///
+    /// db.sessions().remove(&1)?;
+    /// assert_eq!(db.sessions().len()?, 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn len(&self) -> Result<usize, Error<E>> {
+        match &self.len {
+            Some(len) => Ok(*lock(len)),
+            None => Ok(self.iter().try_fold(0, |len, res| res.map(|_| len + 1))?),
+        }
+    }
 }
 
 impl<Key, Value, E, DS> fmt::Debug for Map<Key, Value, DS>
@@ -310,3 +565,51 @@ where
         f.write_str("]\n")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stores::{BTreeMap, Faulty};
+
+    type Counted = Map<u8, u8, Faulty<BTreeMap>>;
+
+    /// Fails each operation in turn while writing to a counted map. The
+    /// count must match the entries that made it into the database.
+    fn count_survives_faults(open: fn(Faulty<BTreeMap>, u8, Arc<Mutex<usize>>) -> Counted) {
+        for nth in 1.. {
+            let ds = BTreeMap::new();
+            let faulty = Faulty::new(ds.clone());
+            let map = open(faulty.clone(), 1, Arc::new(Mutex::new(0)));
+            faulty.fail_nth(nth);
+            let res = (|| {
+                for key in 0..4 {
+                    map.insert(&key, &key)?;
+                }
+                map.insert(&2, &0)?;
+                map.remove(&1)?;
+                map.remove(&7)?;
+                Ok::<_, Error<_>>(())
+            })();
+            faulty.heal();
+
+            let entries = Map::<u8, u8, _>::new(ds.clone(), 1).len().unwrap();
+            assert_eq!(map.len().unwrap(), entries, "failed at operation {nth}");
+            let stored = stored_len::<u8, _>(&ds, 1).unwrap();
+            assert_eq!(stored, entries, "failed at operation {nth}");
+            if res.is_ok() {
+                assert_eq!(entries, 3);
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn count_survives_faults_without_transactions() {
+        count_survives_faults(Map::counted);
+    }
+
+    #[test]
+    fn count_survives_faults_in_transactions() {
+        count_survives_faults(Map::counted_transactional);
+    }
+}
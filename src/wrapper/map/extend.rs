@@ -79,11 +79,11 @@ mod tests {
 
         #[test]
         fn while_db_errors() {
-            let ds = stores::BTreeMap::new();
+            let ds = stores::Faulty::new(stores::BTreeMap::new());
             let mut map: Map<String, u16, _> = Map::new(ds.clone(), 1);
 
             let iter = [("a", &1)];
-            ds.force_error();
+            ds.fail_nth(1);
             let err = map
                 .extend(iter)
                 .expect_err("we forced the datastore to crash on access");
@@ -1,13 +1,17 @@
-use crate::Error;
+use crate::{key, Error};
 use core::marker::PhantomData;
 use std::fmt;
+use std::ops::{Bound, RangeBounds};
 
-use crate::traits::byte_store::{self, key_config, val_config};
+use crate::stores;
+use crate::traits::byte_store::{self, val_config};
+use crate::traits::data_store;
 
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::ser::SerializeTuple;
+use serde::{Serialize, Serializer};
 
-use super::Map;
+use super::{Map, Prefixed};
 
 pub struct Iter<'a, K, V, E, DS>
 where
@@ -16,13 +20,60 @@ where
     E: fmt::Debug,
     DS: byte_store::Ordered<DbError = E>,
 {
-    prefix: u8,
-    prev_key_bytes: Vec<u8>,
+    /// Key of the entry last returned by `next`
+    front: Vec<u8>,
+    /// Key of the entry last returned by `next_back`
+    back: Vec<u8>,
     phantom_val: PhantomData<V>,
     phantom_key: PhantomData<K>,
     ds: &'a DS,
 }
 
+impl<'a, K, V, E, DS> Iter<'a, K, V, E, DS>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+    E: fmt::Debug,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    /// Iterates over all keys that start with `start`
+    fn starting_with(ds: &'a DS, start: Vec<u8>) -> Self {
+        Self {
+            back: successor(start.clone()),
+            front: start,
+            phantom_val: PhantomData,
+            phantom_key: PhantomData,
+            ds,
+        }
+    }
+}
+
+/// The smallest key greater than all keys starting with `bytes`. The first
+/// byte is the prefix of a field. Fields never use the reserved prefix
+/// `u8::MAX` so this always finds a byte to increment.
+pub(crate) fn successor(mut bytes: Vec<u8>) -> Vec<u8> {
+    while let Some(last) = bytes.pop() {
+        if last < u8::MAX {
+            bytes.push(last + 1);
+            break;
+        }
+    }
+    bytes
+}
+
+pub(crate) fn decode<K, V, E>(key: &[u8], val: &[u8]) -> Result<(K, V), Error<E>>
+where
+    K: DeserializeOwned,
+    V: DeserializeOwned,
+    E: fmt::Debug,
+{
+    let key = &key[1..]; // strip prefix
+    let key = key::decode(key).map_err(Error::DeSerializingKey)?;
+    let (val, _) =
+        bincode::serde::decode_from_slice(val, val_config()).map_err(Error::DeSerializingVal)?;
+    Ok((key, val))
+}
+
 impl<K, V, E, DS> Iterator for Iter<'_, K, V, E, DS>
 where
     E: fmt::Debug,
@@ -33,34 +84,47 @@ where
     type Item = Result<(K, V), Error<E>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let (key, val) = match byte_store::Ordered::get_gt(self.ds, &self.prev_key_bytes) {
+        let (key, val) = match byte_store::Ordered::get_gt(self.ds, &self.front) {
             Ok(Some((key, val))) => (key, val),
             Ok(None) => return None,
             Err(e) => return Some(Err(Error::Database(e))),
         };
 
+        // the key belongs to the next field or was returned by `next_back`
         let key = key.as_ref();
-        if key[0] != self.prefix {
+        if key >= self.back.as_slice() {
             return None;
         }
 
-        self.prev_key_bytes.clear();
-        self.prev_key_bytes.extend_from_slice(key);
+        self.front.clear();
+        self.front.extend_from_slice(key);
+        Some(decode(key, val.as_ref()))
+    }
+}
 
-        let key = &key[1..]; // strip prefix
-        let (key, _) = match bincode::serde::decode_from_slice(key, key_config())
-            .map_err(Error::DeSerializingKey)
-        {
-            Ok(key) => key,
-            Err(e) => return Some(Err(e)),
-        };
-        let (val, _) = match bincode::serde::decode_from_slice(val.as_ref(), val_config())
-            .map_err(Error::DeSerializingVal)
-        {
-            Ok(val) => val,
-            Err(e) => return Some(Err(e)),
+impl<K, V, E, DS> DoubleEndedIterator for Iter<'_, K, V, E, DS>
+where
+    E: fmt::Debug,
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (key, val) = match byte_store::Ordered::get_lt(self.ds, &self.back) {
+            Ok(Some((key, val))) => (key, val),
+            Ok(None) => return None,
+            Err(e) => return Some(Err(Error::Database(e))),
         };
-        Some(Ok((key, val)))
+
+        // the key belongs to the previous field or was returned by `next`
+        let key = key.as_ref();
+        if key <= self.front.as_slice() {
+            return None;
+        }
+
+        self.back.clear();
+        self.back.extend_from_slice(key);
+        Some(decode(key, val.as_ref()))
     }
 }
 
@@ -85,6 +149,18 @@ where
     }
 }
 
+impl<K, V, E, DS> DoubleEndedIterator for Values<'_, K, V, E, DS>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+    E: fmt::Debug,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|res| res.map(|(_, val)| val))
+    }
+}
+
 pub struct Keys<'a, K, V, E, DS>(Iter<'a, K, V, E, DS>)
 where
     K: Serialize + DeserializeOwned,
@@ -106,6 +182,18 @@ where
     }
 }
 
+impl<K, V, E, DS> DoubleEndedIterator for Keys<'_, K, V, E, DS>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+    E: fmt::Debug,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|res| res.map(|(key, _)| key))
+    }
+}
+
 impl<Key, Value, E, DS> Map<Key, Value, DS>
 where
     E: fmt::Debug,
@@ -113,9 +201,11 @@ where
     Value: Serialize + DeserializeOwned,
     DS: byte_store::Ordered<DbError = E>,
 {
-    /// An iterator visiting all key-value pairs in fixed though arbitrary order. The
-    /// order depending on the underlying database implementation. The iterator element
-    /// type is `Result<(Key, Value), dbstruct::Error<E>>`.
+    /// An iterator visiting all key-value pairs in the order of their
+    /// serialized keys, see [`OrderedMap`](crate::wrapper::OrderedMap) for
+    /// what that order is. Use [`rev`](Iterator::rev) to visit them
+    /// back to front. The iterator element type is
+    /// `Result<(Key, Value), dbstruct::Error<E>>`.
     ///
     /// # Errors
     /// This can fail if the underlying database ran into a problem
@@ -139,17 +229,14 @@ where
     ///     let (key, val) = res?;
     ///     println!("key: {key} val: {val}");
     /// }
+    ///
+    /// let last = db.map().iter().rev().next().transpose()?;
+    /// assert_eq!(last, Some((3, "c".to_owned())));
     /// # Ok(())
     /// # }
     /// ```
     pub fn iter(&self) -> Iter<'_, Key, Value, E, DS> {
-        Iter {
-            prefix: self.prefix,
-            prev_key_bytes: vec![self.prefix],
-            phantom_val: PhantomData,
-            phantom_key: PhantomData,
-            ds: &self.tree,
-        }
+        Iter::starting_with(&self.tree, vec![self.prefix])
     }
 
     /// An iterator visiting all key in fixed though arbitrary order. The
@@ -182,13 +269,7 @@ where
     /// # }
     /// ```
     pub fn values(&self) -> Values<'_, Key, Value, E, DS> {
-        Values(Iter {
-            prefix: self.prefix,
-            prev_key_bytes: vec![self.prefix],
-            phantom_val: PhantomData,
-            phantom_key: PhantomData,
-            ds: &self.tree,
-        })
+        Values(self.iter())
     }
 
     /// An iterator visiting all values in fixed though arbitrary order. The
@@ -221,12 +302,224 @@ where
     /// # }
     /// ```
     pub fn keys(&self) -> Keys<'_, Key, Value, E, DS> {
-        Keys(Iter {
-            prefix: self.prefix,
-            prev_key_bytes: vec![self.prefix],
-            phantom_val: PhantomData,
-            phantom_key: PhantomData,
-            ds: &self.tree,
-        })
+        Keys(self.iter())
+    }
+
+    /// An iterator visiting the key-value pairs whose key starts with
+    /// `prefix`, use it to look up all entries sharing the first element of
+    /// a tuple or struct key. The iterator element type is
+    /// `Result<(Key, Value), dbstruct::Error<E>>`.
+    ///
+    /// The serialized form of `prefix` must match that of the start of the
+    /// key. The entries are visited in the same order as [`iter`](Map::iter),
+    /// this iterator can also be reversed.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     /// Logins by user id and timestamp
+    ///     logins: HashMap<(u32, u64), String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.logins().insert(&(1, 100), "laptop")?;
+    /// db.logins().insert(&(2, 150), "phone")?;
+    /// db.logins().insert(&(1, 200), "phone")?;
+    ///
+    /// let latest = db.logins().prefix(&1u32)?.next_back().transpose()?;
+    /// assert_eq!(latest, Some(((1, 200), "phone".to_owned())));
+    /// assert_eq!(db.logins().prefix(&2u32)?.count(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prefix<P>(&self, prefix: &P) -> Result<Iter<'_, Key, Value, E, DS>, Error<E>>
+    where
+        P: Serialize + ?Sized,
+    {
+        let start = Encoded::key(self.prefix, prefix)?;
+        Ok(Iter::starting_with(&self.tree, start.0))
+    }
+}
+
+/// A key that is already serialized. It serializes to its bytes without a
+/// length, this makes it usable as bound for [`data_store::Ranged::range`].
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Encoded(Vec<u8>);
+
+impl Encoded {
+    fn key<K, E>(prefix: u8, key: &K) -> Result<Self, Error<E>>
+    where
+        K: Serialize + ?Sized,
+        E: fmt::Debug,
+    {
+        let key = Prefixed { prefix, key };
+        key::encode(&key).map(Self).map_err(Error::SerializingKey)
+    }
+}
+
+impl Serialize for Encoded {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(self.0.len())?;
+        for byte in &self.0 {
+            tuple.serialize_element(byte)?;
+        }
+        tuple.end()
+    }
+}
+
+impl<Key, Value, E, DS> Map<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: data_store::Ranged<DbError = E>,
+{
+    /// Constructs an iterator over a sub-range of the entries in the map.
+    /// The iterator element type is `Result<(Key, Value), dbstruct::Error<E>>`.
+    ///
+    /// The entries are ordered by their serialized keys, see
+    /// [`OrderedMap`](crate::wrapper::OrderedMap) for what that order is.
+    /// The range bounds may be any borrowed form of the map’s key type, but
+    /// the serialized form must match that of the owned key type. A range
+    /// that ends before it starts yields no entries.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     map: HashMap<u16, String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.map().insert(&1, "a")?;
+    /// db.map().insert(&2, "b")?;
+    /// db.map().insert(&3, "c")?;
+    ///
+    /// let keys: Vec<u16> = db
+    ///     .map()
+    ///     .range(2..)?
+    ///     .map(|entry| entry.map(|(key, _)| key))
+    ///     .collect::<Result<_, _>>()?;
+    /// assert_eq!(keys, [2, 3]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn range<K, R>(
+        &self,
+        range: R,
+    ) -> Result<impl Iterator<Item = Result<(Key, Value), Error<E>>> + '_, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+        R: RangeBounds<K>,
+    {
+        let prefix = self.prefix;
+        let start = match range.start_bound() {
+            Bound::Included(key) => Bound::Included(Encoded::key(prefix, key)?),
+            Bound::Excluded(key) => Bound::Excluded(Encoded::key(prefix, key)?),
+            Bound::Unbounded => Bound::Included(Encoded(vec![prefix])),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(key) => Bound::Included(Encoded::key(prefix, key)?),
+            Bound::Excluded(key) => Bound::Excluded(Encoded::key(prefix, key)?),
+            Bound::Unbounded => Bound::Excluded(Encoded(successor(vec![prefix]))),
+        };
+
+        // not every database accepts a range that ends before it starts
+        let entries = if stores::is_empty_range(start.as_ref(), end.as_ref()) {
+            None
+        } else {
+            Some(data_store::Ranged::range::<Encoded, (u8, Key), Value>(
+                &self.tree,
+                (start, end),
+            )?)
+        };
+        Ok(entries
+            .into_iter()
+            .flatten()
+            .map(|entry| entry.map(|((_, key), value)| (key, value))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn successor_carries() {
+        assert_eq!(successor(vec![1]), [2]);
+        assert_eq!(successor(vec![1, 4, 255, 255]), [1, 5]);
+    }
+
+    #[test]
+    fn iterate_from_both_ends() {
+        let map: Map<u8, (), _> = Map::new(stores::BTreeMap::new(), 1);
+        for key in 0..5 {
+            map.insert(&key, &()).unwrap();
+        }
+        let mut keys = map.keys();
+        assert_eq!(keys.next().unwrap().unwrap(), 0);
+        assert_eq!(keys.next_back().unwrap().unwrap(), 4);
+        assert_eq!(keys.next_back().unwrap().unwrap(), 3);
+        assert_eq!(keys.next().unwrap().unwrap(), 1);
+        assert_eq!(keys.next().unwrap().unwrap(), 2);
+        assert!(keys.next_back().is_none());
+        assert!(keys.next().is_none());
+    }
+
+    #[test]
+    fn range_bounds() {
+        let map: Map<u32, u32, _> = Map::new(stores::BTreeMap::new(), 1);
+        for key in [1, 2, 256, 257, 70_000] {
+            map.insert(&key, &(key * 2)).unwrap();
+        }
+        let keys = |range: (Bound<&u32>, Bound<&u32>)| -> Vec<u32> {
+            map.range(range)
+                .unwrap()
+                .map(|res| res.unwrap().0)
+                .collect()
+        };
+        assert_eq!(keys((Bound::Excluded(&1), Bound::Included(&256))), [2, 256]);
+        assert_eq!(keys((Bound::Included(&2), Bound::Excluded(&257))), [2, 256]);
+        assert_eq!(keys((Bound::Unbounded, Bound::Excluded(&256))), [1, 2]);
+        assert_eq!(keys((Bound::Excluded(&257), Bound::Unbounded)), [70_000]);
+        assert!(keys((Bound::Included(&257), Bound::Excluded(&2))).is_empty());
+        assert!(keys((Bound::Excluded(&256), Bound::Excluded(&256))).is_empty());
+        assert!(keys((Bound::Excluded(&256), Bound::Included(&256))).is_empty());
+    }
+
+    #[test]
+    fn prefix_stays_within_leading_key() {
+        let ds = stores::BTreeMap::new();
+        let map: Map<(u8, u16), u8, _> = Map::new(ds.clone(), 1);
+        let next_field: Map<u8, u8, _> = Map::new(ds, 2);
+        next_field.insert(&0, &0).unwrap();
+        for key in [(0, 9), (1, 0), (1, u16::MAX), (255, 1), (255, u16::MAX)] {
+            map.insert(&key, &key.0).unwrap();
+        }
+
+        let keys = |leading: u8| -> Vec<(u8, u16)> {
+            let entries = map.prefix(&leading).unwrap();
+            entries.map(|res| res.unwrap().0).collect()
+        };
+        assert_eq!(keys(1), [(1, 0), (1, u16::MAX)]);
+        assert_eq!(keys(255), [(255, 1), (255, u16::MAX)]);
+        assert_eq!(keys(2), []);
+        let last = map.prefix(&255u8).unwrap().next_back().unwrap().unwrap();
+        assert_eq!(last.0, (255, u16::MAX));
     }
 }
@@ -0,0 +1,461 @@
+use core::fmt;
+use std::ops::RangeBounds;
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::byte_store;
+use crate::traits::{data_store, ByteStore, DataStore};
+use crate::Error;
+
+use super::map::Entry;
+use super::Map;
+
+mod extend;
+mod iterator;
+pub use super::map::{Iter, Keys, Values};
+
+/// mimics the API of [`BTreeMap`][std::collections::BTreeMap]
+///
+/// The entries are ordered by the serialized form of their keys. The keys
+/// are serialized such that integers, floats, strings, byte slices, `Option`,
+/// sequences and tuples keep the order of [`Ord`]. So do structs and enums
+/// deriving `Ord`, their fields and variants are compared in the order they
+/// are declared. Keys that implement `Ord` by hand, or that serialize in a
+/// different way than they compare, follow the order of their serialized
+/// fields instead.
+///
+/// Like [`Map`] the field can be marked `#[dbstruct(counted)]` to make
+/// [`len`](OrderedMap::len) cheap.
+pub struct OrderedMap<Key, Value, DS>
+where
+    Key: Serialize,
+    Value: Serialize + DeserializeOwned,
+    DS: DataStore,
+{
+    map: Map<Key, Value, DS>,
+}
+
+/// All keys of the map sort before this, it is the prefix of the next field.
+/// Fields never use [`META_PREFIX`](crate::schema::META_PREFIX) (`u8::MAX`)
+/// so this can not overflow.
+fn end_of(prefix: u8) -> u8 {
+    prefix + 1
+}
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: DataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(tree: DS, prefix: u8) -> Self {
+        Self {
+            map: Map::new(tree, prefix),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn counted(tree: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
+        Self {
+            map: Map::counted(tree, prefix, len),
+        }
+    }
+
+    /// Inserts a key-value pair into the map. Returns the previous value if
+    /// there was one, see [`Map::insert`].
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     map: BTreeMap<u16, String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// assert_eq!(db.map().insert(&37, "a")?, None);
+    /// assert_eq!(db.map().insert(&37, "b")?, Some("a".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert<K, V>(&self, key: &K, value: &V) -> Result<Option<Value>, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+        Value: std::borrow::Borrow<V>,
+        V: Serialize + ?Sized,
+    {
+        self.map.insert(key, value)
+    }
+
+    /// Returns a copy of the value corresponding to the key, see
+    /// [`Map::get`].
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn get<K>(&self, key: &K) -> Result<Option<Value>, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+    {
+        self.map.get(key)
+    }
+
+    /// Removes a key from the map, returning the value at the key if the key
+    /// was previously in the map, see [`Map::remove`].
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn remove<K>(&self, key: &K) -> Result<Option<Value>, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+    {
+        self.map.remove(key)
+    }
+
+    /// Returns `true` if the map contains a value for the specific key, see
+    /// [`Map::contains_key`].
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn contains_key<K>(&self, key: &K) -> Result<bool, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Gets the given key’s corresponding entry in the map for in-place
+    /// manipulation, see [`Map::entry`].
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn entry(&self, key: Key) -> Result<Entry<'_, Key, Value, DS>, Error<E>> {
+        self.map.entry(key)
+    }
+}
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: data_store::Transactional<DbError = E>,
+    for<'tx> DS::Tx<'tx>: ByteStore<DbError = DS::TxError>,
+{
+    #[doc(hidden)]
+    pub fn counted_transactional(tree: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
+        Self {
+            map: Map::counted_transactional(tree, prefix, len),
+        }
+    }
+}
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: data_store::Ordered<DbError = E>,
+{
+    /// Returns the first key-value pair in the map. The key in this pair is
+    /// the minimum key in the map.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     map: BTreeMap<u16, String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// assert_eq!(db.map().first_key_value()?, None);
+    /// db.map().insert(&2, "b")?;
+    /// db.map().insert(&1, "a")?;
+    /// assert_eq!(db.map().first_key_value()?, Some((1, "a".to_owned())));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn first_key_value(&self) -> Result<Option<(Key, Value)>, Error<E>> {
+        let prefix = self.map.prefix;
+        // Keys of other fields follow the map, check the prefix before
+        // decoding the key.
+        match data_store::Ordered::get_gt::<_, u8, ()>(&self.map.tree, &prefix)? {
+            Some((found, ())) if found == prefix => (),
+            _ => return Ok(None),
+        }
+        let entry = data_store::Ordered::get_gt::<_, (u8, Key), Value>(&self.map.tree, &prefix)?;
+        Ok(entry.map(|((_, key), value)| (key, value)))
+    }
+
+    /// Returns the last key-value pair in the map. The key in this pair is
+    /// the maximum key in the map.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     map: BTreeMap<u16, String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// assert_eq!(db.map().last_key_value()?, None);
+    /// db.map().insert(&2, "b")?;
+    /// db.map().insert(&1, "a")?;
+    /// assert_eq!(db.map().last_key_value()?, Some((2, "b".to_owned())));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn last_key_value(&self) -> Result<Option<(Key, Value)>, Error<E>> {
+        let prefix = self.map.prefix;
+        let end = end_of(prefix);
+        // Keys of other fields precede the map, check the prefix before
+        // decoding the key.
+        match data_store::Ordered::get_lt::<_, u8, ()>(&self.map.tree, &end)? {
+            Some((found, ())) if found == prefix => (),
+            _ => return Ok(None),
+        }
+        let entry = data_store::Ordered::get_lt::<_, (u8, Key), Value>(&self.map.tree, &end)?;
+        Ok(entry.map(|((_, key), value)| (key, value)))
+    }
+
+    /// Removes and returns the first element in the map. The key of this
+    /// element is the minimum key that was in the map.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     map: BTreeMap<u16, String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.map().insert(&1, "a")?;
+    /// db.map().insert(&2, "b")?;
+    /// while let Some((key, _)) = db.map().pop_first()? {
+    ///     assert!(db.map().iter().all(|res| res.unwrap().0 > key));
+    /// }
+    /// assert!(db.map().is_empty()?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pop_first(&self) -> Result<Option<(Key, Value)>, Error<E>> {
+        loop {
+            let Some((key, _)) = self.first_key_value()? else {
+                return Ok(None);
+            };
+            // Another thread may have popped the entry since we read it
+            if let Some(value) = self.map.remove(&key)? {
+                return Ok(Some((key, value)));
+            }
+        }
+    }
+
+    /// Removes and returns the last element in the map. The key of this
+    /// element is the maximum key that was in the map.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     map: BTreeMap<u16, String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.map().insert(&1, "a")?;
+    /// db.map().insert(&2, "b")?;
+    /// while let Some((key, _)) = db.map().pop_last()? {
+    ///     assert!(db.map().iter().all(|res| res.unwrap().0 < key));
+    /// }
+    /// assert!(db.map().is_empty()?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pop_last(&self) -> Result<Option<(Key, Value)>, Error<E>> {
+        loop {
+            let Some((key, _)) = self.last_key_value()? else {
+                return Ok(None);
+            };
+            // Another thread may have popped the entry since we read it
+            if let Some(value) = self.map.remove(&key)? {
+                return Ok(Some((key, value)));
+            }
+        }
+    }
+}
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: data_store::Ranged<DbError = E>,
+{
+    /// Constructs an iterator over a sub-range of the entries in the map.
+    /// The iterator element type is `Result<(Key, Value), dbstruct::Error<E>>`.
+    ///
+    /// The range bounds may be any borrowed form of the map’s key type, but
+    /// the serialized form must match that of the owned key type. The bounds
+    /// are compared in the order described on [`OrderedMap`], a range that
+    /// ends before it starts yields no entries.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     events: BTreeMap<u64, String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.events().insert(&1_000, "started")?;
+    /// db.events().insert(&2_000, "running")?;
+    /// db.events().insert(&3_000, "stopped")?;
+    ///
+    /// let since: Vec<_> = db.events().range(2_000..)?.collect::<Result<_, _>>()?;
+    /// assert_eq!(
+    ///     since,
+    ///     [(2_000, "running".to_owned()), (3_000, "stopped".to_owned())]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn range<K, R>(
+        &self,
+        range: R,
+    ) -> Result<impl Iterator<Item = Result<(Key, Value), Error<E>>> + '_, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+        R: RangeBounds<K>,
+    {
+        self.map.range(range)
+    }
+}
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    /// Clears the map, removing all key-value pairs.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn clear(&self) -> Result<(), Error<E>> {
+        self.map.clear()
+    }
+
+    /// Returns true if the map contains no elements.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn is_empty(&self) -> Result<bool, Error<E>> {
+        self.map.is_empty()
+    }
+
+    /// Returns the number of elements in the map, see [`Map::len`].
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn len(&self) -> Result<usize, Error<E>> {
+        self.map.len()
+    }
+}
+
+impl<Key, Value, E, DS> fmt::Debug for OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned + fmt::Debug,
+    Value: Serialize + DeserializeOwned + fmt::Debug,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("{\n")?;
+        for element in self.iter() {
+            match element {
+                Ok((key, val)) => f.write_fmt(format_args!("    {key:?}: {val:?},\n"))?,
+                Err(err) => {
+                    f.write_fmt(format_args!(
+                        "ERROR while printing full map, could \
+                         not read next element from db: {err}"
+                    ))?;
+                    return Ok(());
+                }
+            }
+        }
+        f.write_str("}\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stores;
+
+    #[test]
+    fn does_not_see_other_fields() {
+        let ds = stores::BTreeMap::new();
+        let before: OrderedMap<u8, u8, _> = OrderedMap::new(ds.clone(), 1);
+        let map: OrderedMap<String, u8, _> = OrderedMap::new(ds.clone(), 2);
+        let after: OrderedMap<u8, u8, _> = OrderedMap::new(ds, 3);
+        before.insert(&1, &1).unwrap();
+        after.insert(&2, &2).unwrap();
+
+        assert_eq!(map.first_key_value().unwrap(), None);
+        assert_eq!(map.last_key_value().unwrap(), None);
+        assert_eq!(map.range::<str, _>(..).unwrap().count(), 0);
+        assert_eq!(map.iter().rev().count(), 0);
+
+        map.insert("a", &0).unwrap();
+        assert_eq!(map.range::<str, _>(..).unwrap().count(), 1);
+        assert_eq!(map.iter().rev().count(), 1);
+        assert_eq!(map.pop_last().unwrap(), Some(("a".to_owned(), 0)));
+        assert_eq!(before.pop_first().unwrap(), Some((1, 1)));
+        assert_eq!(after.pop_first().unwrap(), Some((2, 2)));
+    }
+}
@@ -0,0 +1,52 @@
+use std::borrow::Borrow;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::ExtendError;
+use crate::DataStore;
+
+use super::OrderedMap;
+
+impl<Key, Value, DS> OrderedMap<Key, Value, DS>
+where
+    DS: DataStore,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+{
+    /// Extends the map with the contents of an iterator of tuples, see
+    /// [`Map::extend`](crate::wrapper::Map::extend).
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     map: BTreeMap<u16, String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.map().extend([(&3, "three"), (&1, "one"), (&2, "two")])?;
+    /// assert_eq!(db.map().first_key_value()?, Some((1, "one".to_owned())));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn extend<'a, I, K, V>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), ExtendError<I::Item, I::IntoIter, crate::Error<DS::DbError>>>
+    where
+        I: IntoIterator<Item = (&'a K, &'a V)>,
+        Key: Borrow<K>,
+        K: Serialize + ?Sized,
+        Value: Borrow<V>,
+        V: Serialize + ?Sized,
+    {
+        self.map.extend(iter)
+    }
+}
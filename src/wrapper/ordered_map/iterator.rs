@@ -0,0 +1,147 @@
+use std::fmt;
+
+use crate::traits::byte_store;
+use crate::Error;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::{Iter, Keys, OrderedMap, Values};
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    /// An iterator visiting all key-value pairs in ascending key order. Use
+    /// [`rev`](Iterator::rev) to visit them in descending order. The iterator
+    /// element type is `Result<(Key, Value), dbstruct::Error<E>>`.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     map: BTreeMap<u16, String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.map().insert(&3, "c")?;
+    /// db.map().insert(&1, "a")?;
+    /// db.map().insert(&2, "b")?;
+    ///
+    /// let newest_first: Vec<_> = db.map().iter().rev().collect::<Result<_, _>>()?;
+    /// assert_eq!(
+    ///     newest_first,
+    ///     [(3, "c".to_owned()), (2, "b".to_owned()), (1, "a".to_owned())]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter(&self) -> Iter<'_, Key, Value, E, DS> {
+        self.map.iter()
+    }
+
+    /// An iterator visiting all keys in ascending order. The iterator
+    /// element type is `Result<Key, dbstruct::Error<E>>`.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     map: BTreeMap<u16, String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.map().insert(&2, "b")?;
+    /// db.map().insert(&1, "a")?;
+    ///
+    /// let keys: Vec<u16> = db.map().keys().collect::<Result<_, _>>()?;
+    /// assert_eq!(keys, [1, 2]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn keys(&self) -> Keys<'_, Key, Value, E, DS> {
+        self.map.keys()
+    }
+
+    /// An iterator visiting all values in the ascending order of their
+    /// keys. The iterator element type is `Result<Value, dbstruct::Error<E>>`.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     map: BTreeMap<u16, String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.map().insert(&2, "b")?;
+    /// db.map().insert(&1, "a")?;
+    ///
+    /// let values: Vec<String> = db.map().values().collect::<Result<_, _>>()?;
+    /// assert_eq!(values, ["a", "b"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn values(&self) -> Values<'_, Key, Value, E, DS> {
+        self.map.values()
+    }
+
+    /// An iterator visiting the key-value pairs whose key starts with
+    /// `prefix` in ascending key order, see [`Map::prefix`](crate::wrapper::Map::prefix).
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     /// Readings by sensor id and timestamp
+    ///     readings: BTreeMap<(u16, u64), f32>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.readings().insert(&(7, 10), &20.5)?;
+    /// db.readings().insert(&(8, 10), &3.0)?;
+    /// db.readings().insert(&(7, 20), &21.0)?;
+    ///
+    /// let sensor_7: Vec<f32> = db
+    ///     .readings()
+    ///     .prefix(&7u16)?
+    ///     .map(|entry| entry.map(|(_, reading)| reading))
+    ///     .collect::<Result<_, _>>()?;
+    /// assert_eq!(sensor_7, [20.5, 21.0]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prefix<P>(&self, prefix: &P) -> Result<Iter<'_, Key, Value, E, DS>, Error<E>>
+    where
+        P: Serialize + ?Sized,
+    {
+        self.map.prefix(prefix)
+    }
+}
@@ -0,0 +1,579 @@
+//! The wrappers returned by a `#[dbstruct(readonly)]` struct. They offer the
+//! same API as the normal wrappers minus every method that writes.
+
+use core::fmt;
+use std::ops::RangeBounds;
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::{map, ordered_map};
+use crate::traits::{byte_store, data_store, DataStore};
+use crate::Error;
+
+/// mimics the read only API of [`Vec`], see [`wrapper::Vec`](super::Vec)
+pub struct Vec<T, DS>(super::Vec<T, DS>)
+where
+    DS: DataStore;
+
+impl<T, E, DS> Vec<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: DataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
+        Self(super::Vec::new(ds, prefix, len))
+    }
+
+    /// Returns the element at `index` if there is one.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn get(&self, index: usize) -> Result<Option<T>, Error<E>> {
+        self.0.get(index)
+    }
+
+    /// Returns the first element of the vector, or `None` if it is empty.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn first(&self) -> Result<Option<T>, Error<E>> {
+        self.0.first()
+    }
+
+    /// Returns the last element of the vector, or `None` if it is empty.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn last(&self) -> Result<Option<T>, Error<E>> {
+        self.0.last()
+    }
+
+    /// Returns the number of elements in the vector, also referred to
+    /// as its 'length'.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the vector contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over the elements of the vector. The iterator
+    /// element type is `Result<T, dbstruct::Error<E>>`.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = Result<T, Error<E>>> + 'a
+    where
+        E: 'a,
+    {
+        self.0.iter()
+    }
+}
+
+/// mimics the read only API of [`VecDeque`][std::collections::VecDeque],
+/// see [`wrapper::VecDeque`](super::VecDeque)
+pub struct VecDeque<T, DS>(super::VecDeque<T, DS>)
+where
+    DS: DataStore;
+
+impl<T, E, DS> VecDeque<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: DataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, prefix: u8, head: Arc<Mutex<u64>>, tail: Arc<Mutex<u64>>) -> Self {
+        Self(super::VecDeque::new(ds, prefix, head, tail))
+    }
+
+    /// Returns the element at `index` if there is one. Index 0 is the front
+    /// of the queue.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn get(&self, index: usize) -> Result<Option<T>, Error<E>> {
+        self.0.get(index)
+    }
+
+    /// Returns the number of elements in the deque.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the deque is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns a front-to-back iterator. The iterator element type is
+    /// `Result<T, dbstruct::Error<E>>`.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = Result<T, Error<E>>> + 'a
+    where
+        E: 'a,
+    {
+        self.0.iter()
+    }
+}
+
+/// mimics the read only API of [`HashMap`][std::collections::HashMap], see
+/// [`wrapper::Map`](super::Map)
+pub struct Map<Key, Value, DS>(super::Map<Key, Value, DS>)
+where
+    Key: Serialize,
+    Value: Serialize + DeserializeOwned,
+    DS: DataStore;
+
+impl<Key, Value, E, DS> Map<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: DataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(tree: DS, prefix: u8) -> Self {
+        Self(super::Map::new(tree, prefix))
+    }
+
+    #[doc(hidden)]
+    pub fn counted(tree: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
+        Self(super::Map::counted(tree, prefix, len))
+    }
+
+    /// Returns the value corresponding to the key.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn get<K>(&self, key: &K) -> Result<Option<Value>, Error<E>>
+    where
+        K: Serialize + ?Sized,
+        Key: std::borrow::Borrow<K>,
+    {
+        self.0.get(key)
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn contains_key<K>(&self, key: &K) -> Result<bool, Error<E>>
+    where
+        K: Serialize + ?Sized,
+        Key: std::borrow::Borrow<K>,
+    {
+        self.0.contains_key(key)
+    }
+}
+
+impl<Key, Value, E, DS> Map<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    /// An iterator visiting all key-value pairs in the order of their
+    /// serialized keys, it can be reversed. The iterator element type is
+    /// `Result<(Key, Value), dbstruct::Error<E>>`.
+    pub fn iter(&self) -> map::Iter<'_, Key, Value, E, DS> {
+        self.0.iter()
+    }
+
+    /// An iterator visiting all keys. The iterator element type is
+    /// `Result<Key, dbstruct::Error<E>>`.
+    pub fn keys(&self) -> map::Keys<'_, Key, Value, E, DS> {
+        self.0.keys()
+    }
+
+    /// An iterator visiting all values. The iterator element type is
+    /// `Result<Value, dbstruct::Error<E>>`.
+    pub fn values(&self) -> map::Values<'_, Key, Value, E, DS> {
+        self.0.values()
+    }
+
+    /// An iterator visiting the key-value pairs whose key starts with
+    /// `prefix`, see [`wrapper::Map::prefix`](super::Map::prefix).
+    ///
+    /// # Errors
+    /// This can fail if serialization failed.
+    pub fn prefix<P>(&self, prefix: &P) -> Result<map::Iter<'_, Key, Value, E, DS>, Error<E>>
+    where
+        P: Serialize + ?Sized,
+    {
+        self.0.prefix(prefix)
+    }
+
+    /// Returns `true` if the map contains no elements.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem.
+    pub fn is_empty(&self) -> Result<bool, Error<E>> {
+        self.0.is_empty()
+    }
+
+    /// Returns the number of elements in the map, see
+    /// [`wrapper::Map::len`](super::Map::len).
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn len(&self) -> Result<usize, Error<E>> {
+        self.0.len()
+    }
+}
+
+impl<Key, Value, E, DS> Map<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: data_store::Ranged<DbError = E>,
+{
+    /// An iterator over a sub-range of the entries in the map.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    #[allow(clippy::type_complexity)]
+    pub fn range<K, R>(
+        &self,
+        range: R,
+    ) -> Result<impl Iterator<Item = Result<(Key, Value), Error<E>>> + '_, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+        R: RangeBounds<K>,
+    {
+        self.0.range(range)
+    }
+}
+
+/// mimics the read only API of [`BTreeMap`][std::collections::BTreeMap], see
+/// [`wrapper::OrderedMap`](super::OrderedMap)
+pub struct OrderedMap<Key, Value, DS>(super::OrderedMap<Key, Value, DS>)
+where
+    Key: Serialize,
+    Value: Serialize + DeserializeOwned,
+    DS: DataStore;
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: DataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(tree: DS, prefix: u8) -> Self {
+        Self(super::OrderedMap::new(tree, prefix))
+    }
+
+    #[doc(hidden)]
+    pub fn counted(tree: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
+        Self(super::OrderedMap::counted(tree, prefix, len))
+    }
+
+    /// Returns the value corresponding to the key.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn get<K>(&self, key: &K) -> Result<Option<Value>, Error<E>>
+    where
+        K: Serialize + ?Sized,
+        Key: std::borrow::Borrow<K>,
+    {
+        self.0.get(key)
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn contains_key<K>(&self, key: &K) -> Result<bool, Error<E>>
+    where
+        K: Serialize + ?Sized,
+        Key: std::borrow::Borrow<K>,
+    {
+        self.0.contains_key(key)
+    }
+}
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: data_store::Ordered<DbError = E>,
+{
+    /// Returns the key-value pair with the minimum key.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn first_key_value(&self) -> Result<Option<(Key, Value)>, Error<E>> {
+        self.0.first_key_value()
+    }
+
+    /// Returns the key-value pair with the maximum key.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn last_key_value(&self) -> Result<Option<(Key, Value)>, Error<E>> {
+        self.0.last_key_value()
+    }
+}
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: data_store::Ranged<DbError = E>,
+{
+    /// An iterator over a sub-range of the entries in the map.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    #[allow(clippy::type_complexity)]
+    pub fn range<K, R>(
+        &self,
+        range: R,
+    ) -> Result<impl Iterator<Item = Result<(Key, Value), Error<E>>> + '_, Error<E>>
+    where
+        Key: std::borrow::Borrow<K>,
+        K: Serialize + ?Sized,
+        R: RangeBounds<K>,
+    {
+        self.0.range(range)
+    }
+}
+
+impl<Key, Value, E, DS> OrderedMap<Key, Value, DS>
+where
+    E: fmt::Debug,
+    Key: Serialize + DeserializeOwned,
+    Value: Serialize + DeserializeOwned,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    /// An iterator visiting all key-value pairs in ascending key order. The
+    /// iterator element type is `Result<(Key, Value), dbstruct::Error<E>>`.
+    pub fn iter(&self) -> ordered_map::Iter<'_, Key, Value, E, DS> {
+        self.0.iter()
+    }
+
+    /// An iterator visiting all keys in ascending order. The iterator
+    /// element type is `Result<Key, dbstruct::Error<E>>`.
+    pub fn keys(&self) -> ordered_map::Keys<'_, Key, Value, E, DS> {
+        self.0.keys()
+    }
+
+    /// An iterator visiting all values in the ascending order of their keys.
+    /// The iterator element type is `Result<Value, dbstruct::Error<E>>`.
+    pub fn values(&self) -> ordered_map::Values<'_, Key, Value, E, DS> {
+        self.0.values()
+    }
+
+    /// An iterator visiting the key-value pairs whose key starts with
+    /// `prefix`, see [`wrapper::Map::prefix`](super::Map::prefix).
+    ///
+    /// # Errors
+    /// This can fail if serialization failed.
+    pub fn prefix<P>(
+        &self,
+        prefix: &P,
+    ) -> Result<ordered_map::Iter<'_, Key, Value, E, DS>, Error<E>>
+    where
+        P: Serialize + ?Sized,
+    {
+        self.0.prefix(prefix)
+    }
+
+    /// Returns `true` if the map contains no elements.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem.
+    pub fn is_empty(&self) -> Result<bool, Error<E>> {
+        self.0.is_empty()
+    }
+
+    /// Returns the number of elements in the map, see
+    /// [`wrapper::Map::len`](super::Map::len).
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn len(&self) -> Result<usize, Error<E>> {
+        self.0.len()
+    }
+}
+
+/// handles missing values by returning [`Default::default()`], see
+/// [`wrapper::DefaultTrait`](super::DefaultTrait)
+pub struct DefaultTrait<T, DS>(super::DefaultTrait<T, DS>)
+where
+    T: Serialize + DeserializeOwned + Default,
+    DS: DataStore;
+
+impl<T, E, DS> DefaultTrait<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned + Default,
+    DS: DataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, key: u8) -> Self {
+        Self(super::DefaultTrait::new(ds, key))
+    }
+
+    pub fn get(&self) -> Result<T, Error<E>> {
+        self.0.get()
+    }
+}
+
+/// handles missing values by generating a replacement from an expression,
+/// see [`wrapper::DefaultValue`](super::DefaultValue)
+pub struct DefaultValue<T, DS>(super::DefaultValue<T, DS>)
+where
+    T: Serialize + DeserializeOwned + Clone,
+    DS: DataStore;
+
+impl<T, E, DS> DefaultValue<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned + Clone,
+    DS: DataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, key: u8, default_value: T) -> Self {
+        Self(super::DefaultValue::new(ds, key, default_value))
+    }
+
+    pub fn get(&self) -> Result<T, Error<E>>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.0.get()
+    }
+}
+
+/// handles missing values by returning `None`, see
+/// [`wrapper::OptionValue`](super::OptionValue)
+pub struct OptionValue<T, DS>(super::OptionValue<T, DS>)
+where
+    DS: DataStore;
+
+impl<T, E, DS> OptionValue<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: DataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, key: u8) -> Self {
+        Self(super::OptionValue::new(ds, key))
+    }
+
+    /// Returns the value if there is one.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn get(&self) -> Result<Option<T>, Error<E>> {
+        self.0.get()
+    }
+
+    /// Returns `true` if there is a value.
+    pub fn is_some(&self) -> Result<bool, Error<E>> {
+        self.0.is_some()
+    }
+
+    /// Returns `true` if there is no value.
+    pub fn is_none(&self) -> Result<bool, Error<E>> {
+        self.0.is_none()
+    }
+}
+
+/// mimics the read only API of [`HashSet`][std::collections::HashSet], see
+/// [`wrapper::Set`](super::Set)
+pub struct Set<T, DS>(super::Set<T, DS>)
+where
+    T: Serialize,
+    DS: DataStore;
+
+impl<T, E, DS> Set<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: DataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, prefix: u8) -> Self {
+        Self(super::Set::new(ds, prefix))
+    }
+
+    #[doc(hidden)]
+    pub fn counted(ds: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
+        Self(super::Set::counted(ds, prefix, len))
+    }
+
+    /// Returns `true` if the set contains the value.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn contains<Q>(&self, value: &Q) -> Result<bool, Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        self.0.contains(value)
+    }
+}
+
+impl<T, E, DS> Set<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    /// An iterator visiting all elements. The iterator element type is
+    /// `Result<T, dbstruct::Error<E>>`.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = Result<T, Error<E>>> + 'a
+    where
+        E: 'a,
+    {
+        self.0.iter()
+    }
+
+    /// Returns the number of elements in the set. Unless the field is marked
+    /// `#[dbstruct(counted)]` this reads every element.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn len(&self) -> Result<usize, Error<E>> {
+        self.0.len()
+    }
+
+    /// Returns `true` if the set contains no elements.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem.
+    pub fn is_empty(&self) -> Result<bool, Error<E>> {
+        self.0.is_empty()
+    }
+}
@@ -0,0 +1,288 @@
+use core::fmt;
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::{byte_store, data_store, ByteStore, DataStore};
+use crate::Error;
+
+use super::Map;
+
+mod extend;
+mod iterator;
+pub use iterator::Iter;
+
+/// mimics the API of [`HashSet`][std::collections::HashSet]
+///
+/// Each element is stored under its own key, inserting an element does not
+/// touch the others. The elements are iterated in the order of their
+/// serialized form. Mark the field `#[dbstruct(counted)]` to store the number
+/// of elements so [`len`](Set::len) does not read every element.
+pub struct Set<T, DS>
+where
+    T: Serialize,
+    DS: DataStore,
+{
+    /// The elements are the keys, their values are empty
+    map: Map<T, (), DS>,
+}
+
+impl<T, E, DS> Set<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: DataStore<DbError = E>,
+{
+    #[doc(hidden)]
+    pub fn new(ds: DS, prefix: u8) -> Self {
+        Self {
+            map: Map::new(ds, prefix),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn counted(ds: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
+        Self {
+            map: Map::counted(ds, prefix, len),
+        }
+    }
+
+    /// Adds a value to the set. Returns whether the value was newly
+    /// inserted.
+    ///
+    /// The value may be any borrowed form of the sets value type, but the
+    /// serialized form must match that of the owned type.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     seen: HashSet<u64>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// assert_eq!(db.seen().insert(&2)?, true);
+    /// assert_eq!(db.seen().insert(&2)?, false);
+    /// assert_eq!(db.seen().len()?, 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert<Q>(&self, value: &Q) -> Result<bool, Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        Ok(self.map.insert(value, &())?.is_none())
+    }
+
+    /// Removes a value from the set. Returns whether the value was present
+    /// in the set.
+    ///
+    /// The value may be any borrowed form of the sets value type, but the
+    /// serialized form must match that of the owned type.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     seen: HashSet<u64>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.seen().insert(&2)?;
+    /// assert_eq!(db.seen().remove(&2)?, true);
+    /// assert_eq!(db.seen().remove(&2)?, false);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove<Q>(&self, value: &Q) -> Result<bool, Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        Ok(self.map.remove(value)?.is_some())
+    }
+
+    /// Returns `true` if the set contains the value.
+    ///
+    /// The value may be any borrowed form of the sets value type, but the
+    /// serialized form must match that of the owned type.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     names: BTreeSet<String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.names().insert("alice")?;
+    /// assert_eq!(db.names().contains("alice")?, true);
+    /// assert_eq!(db.names().contains("bob")?, false);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn contains<Q>(&self, value: &Q) -> Result<bool, Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        self.map.contains_key(value)
+    }
+}
+
+impl<T, E, DS> Set<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: data_store::Transactional<DbError = E>,
+    for<'tx> DS::Tx<'tx>: ByteStore<DbError = DS::TxError>,
+{
+    #[doc(hidden)]
+    pub fn counted_transactional(ds: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
+        Self {
+            map: Map::counted_transactional(ds, prefix, len),
+        }
+    }
+}
+
+impl<T, E, DS> Set<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    /// Returns the number of elements in the set. Unless the field is marked
+    /// `#[dbstruct(counted)]` this reads every element, avoid it on large
+    /// sets.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     seen: HashSet<u64>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.seen().extend(&[1, 2, 3])?;
+    /// assert_eq!(db.seen().len()?, 3);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn len(&self) -> Result<usize, Error<E>> {
+        self.map.len()
+    }
+
+    /// Returns `true` if the set contains no elements.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    pub fn is_empty(&self) -> Result<bool, Error<E>> {
+        self.map.is_empty()
+    }
+
+    /// Clears the set, removing all values.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     seen: HashSet<u64>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.seen().extend(&[1, 2, 3])?;
+    /// db.seen().clear()?;
+    /// assert!(db.seen().is_empty()?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clear(&self) -> Result<(), Error<E>> {
+        self.map.clear()
+    }
+}
+
+impl<T, E, DS> fmt::Debug for Set<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned + fmt::Debug,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("{\n")?;
+        for element in self.iter() {
+            match element {
+                Ok(val) => f.write_fmt(format_args!("    {val:?},\n"))?,
+                Err(err) => {
+                    f.write_fmt(format_args!(
+                        "ERROR while printing full set, could \
+                         not read next element from db: {err}"
+                    ))?;
+                    return Ok(());
+                }
+            }
+        }
+        f.write_str("}\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stores;
+
+    fn empty() -> Set<String, stores::BTreeMap> {
+        Set::new(stores::BTreeMap::new(), 1)
+    }
+
+    #[test]
+    fn insert_then_remove() {
+        let set = empty();
+        assert!(set.insert("a").unwrap());
+        assert!(!set.insert("a").unwrap());
+        assert!(set.contains("a").unwrap());
+        assert!(set.remove("a").unwrap());
+        assert!(!set.contains("a").unwrap());
+        assert!(set.is_empty().unwrap());
+    }
+
+    #[test]
+    fn does_not_see_other_fields() {
+        let ds = stores::BTreeMap::new();
+        let first: Set<u8, _> = Set::new(ds.clone(), 1);
+        let second: Set<u8, _> = Set::new(ds, 2);
+        first.insert(&1).unwrap();
+        second.insert(&2).unwrap();
+        second.insert(&3).unwrap();
+        assert_eq!(first.len().unwrap(), 1);
+        second.clear().unwrap();
+        assert_eq!(first.iter().map(Result::unwrap).collect::<Vec<_>>(), [1]);
+    }
+}
@@ -0,0 +1,87 @@
+use std::borrow::Borrow;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::traits::ExtendError;
+use crate::DataStore;
+
+use super::Set;
+
+impl<T, DS> Set<T, DS>
+where
+    DS: DataStore,
+    T: Serialize + DeserializeOwned,
+{
+    /// Extends the set with the contents of an iterator.
+    ///
+    /// The iterator item may be any borrowed form of the sets value type,
+    /// as long as the serialized form matches between borrowed and not borrowed.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     names: HashSet<String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.names().extend(["alice", "bob", "alice"])?;
+    /// assert!(db.names().contains("bob")?);
+    /// assert_eq!(db.names().len()?, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn extend<'a, I, Q>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), ExtendError<I::Item, I::IntoIter, crate::Error<DS::DbError>>>
+    where
+        I: IntoIterator<Item = &'a Q>,
+        T: Borrow<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        let mut iter = iter.into_iter();
+        loop {
+            let Some(item) = iter.next() else {
+                return Ok(());
+            };
+
+            if let Err(error) = self.insert::<Q>(item) {
+                return Err(ExtendError {
+                    unadded: item,
+                    iter,
+                    error,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stores;
+    use crate::wrapper::Set;
+
+    #[test]
+    fn error() {
+        let ds = stores::Faulty::new(stores::BTreeMap::new());
+        let mut set: Set<u16, _> = Set::new(ds.clone(), 1);
+
+        let iter = [1, 2, 3, 4];
+        ds.fail_nth(2);
+        let err = set
+            .extend(&iter)
+            .expect_err("we made the datastore fail the second insert");
+        assert_eq!(err.unadded, &2);
+        assert_eq!(err.iter.collect::<std::vec::Vec<_>>(), vec![&3, &4]);
+        assert!(set.contains(&1).unwrap());
+        assert!(!set.contains(&2).unwrap());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::Error;
+use std::fmt;
+
+use crate::traits::byte_store;
+use crate::wrapper::map;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::Set;
+
+pub struct Iter<'a, T, E, DS>(map::Keys<'a, T, (), E, DS>)
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: byte_store::Ordered<DbError = E>;
+
+impl<T, E, DS> Iterator for Iter<'_, T, E, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    type Item = Result<T, Error<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<'a, T, E, DS> IntoIterator for &'a Set<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    type IntoIter = Iter<'a, T, E, DS>;
+    type Item = Result<T, Error<E>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, E, DS> Set<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: byte_store::Ordered<DbError = E>,
+{
+    /// An iterator visiting all elements in the order of their serialized
+    /// form. The iterator element type is `Result<T, dbstruct::Error<E>>`.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     seen: HashSet<u16>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.seen().extend(&[3, 1, 2])?;
+    /// let seen: Vec<u16> = db.seen().iter().collect::<Result<_, _>>()?;
+    /// assert_eq!(seen, [1, 2, 3]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T, E, DS> {
+        Iter(self.map.keys())
+    }
+}
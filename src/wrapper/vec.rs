@@ -1,16 +1,17 @@
 use core::fmt;
 use std::marker::PhantomData;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
 
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
-use crate::traits::DataStore;
-use crate::Error;
+use crate::traits::byte_store::{val_config, ByteStore};
+use crate::traits::{data_store, DataStore};
+use crate::{key, Error};
 
-use super::PhantomUnsync;
+use super::lock;
 
 mod extend;
 mod iterator;
@@ -21,16 +22,62 @@ where
     DS: DataStore,
 {
     phantom: PhantomData<T>,
-    phantom2: PhantomUnsync,
     ds: DS,
     prefix: u8,
-    len: Arc<AtomicUsize>,
+    // Held while writing to the database so concurrent pushes and pops can
+    // not claim the same index
+    len: Arc<Mutex<usize>>,
+    /// Set if the database can move elements in one transaction
+    shift: Option<ShiftFn<DS>>,
+}
+
+/// Elements of a list that move together, used by the methods that shift
+/// elements
+struct Shift<'a> {
+    /// Each element is copied from the first index to the second, in order
+    moves: std::vec::Vec<(usize, usize)>,
+    /// An encoded element written after the moves
+    put: Option<(usize, &'a [u8])>,
+    /// The elements removed from the end afterwards
+    removed: Range<usize>,
+}
+
+/// Applies a [`Shift`] in one transaction, see [`shift_in_transaction`]
+type ShiftFn<DS> = fn(&DS, u8, &Shift<'_>) -> Result<(), Error<<DS as DataStore>::DbError>>;
+
+/// Applies `shift` to the list under `prefix` in one transaction. Either every
+/// element moves or none does.
+fn shift_in_transaction<DS>(
+    ds: &DS,
+    prefix: u8,
+    shift: &Shift<'_>,
+) -> Result<(), Error<DS::DbError>>
+where
+    DS: data_store::Transactional,
+    for<'tx> DS::Tx<'tx>: ByteStore<DbError = DS::TxError>,
+{
+    let key = |index| key::encode(&Prefixed { prefix, index }).map_err(Error::SerializingKey);
+    ds.transaction(|tx| {
+        for &(from, to) in &shift.moves {
+            let element = ByteStore::get(tx, &key(from)?)
+                .map_err(Error::Database)?
+                .ok_or(Error::MissingElement { index: from })?;
+            ByteStore::insert(tx, &key(to)?, element.as_ref()).map_err(Error::Database)?;
+        }
+        if let Some((index, element)) = shift.put {
+            ByteStore::insert(tx, &key(index)?, element).map_err(Error::Database)?;
+        }
+        for index in shift.removed.clone() {
+            ByteStore::remove(tx, &key(index)?).map_err(Error::Database)?;
+        }
+        Ok(())
+    })
 }
 
 #[derive(Serialize, Deserialize, Hash, Eq, PartialEq, Clone, Debug)]
 pub struct Prefixed {
-    prefix: u8,
-    index: usize,
+    pub(crate) prefix: u8,
+    pub(crate) index: usize,
 }
 
 impl Prefixed {
@@ -44,6 +91,24 @@ impl Prefixed {
             index: usize::MAX,
         }
     }
+
+    /// Finds the length of the vec stored under `prefix`. Used by the
+    /// generated code when opening the database.
+    pub fn stored_len<DS>(ds: &DS, prefix: u8) -> Result<usize, Error<DS::DbError>>
+    where
+        DS: data_store::Ordered,
+    {
+        // The key before could belong to another field. Check its prefix
+        // before decoding it as ours.
+        match ds.get_lt::<_, u8, ()>(&Self::max(prefix))? {
+            Some((found, ())) if found == prefix => (),
+            _ => return Ok(0),
+        }
+        Ok(ds
+            .get_lt::<_, Self, ()>(&Self::max(prefix))?
+            .map(|(key, ())| key.index + 1) // a vecs len is index + 1
+            .unwrap_or(0))
+    }
 }
 
 impl<T, E, DS> Vec<T, DS>
@@ -53,13 +118,13 @@ where
     DS: DataStore<DbError = E>,
 {
     #[doc(hidden)]
-    pub fn new(ds: DS, prefix: u8, len: Arc<AtomicUsize>) -> Self {
+    pub fn new(ds: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
         Self {
             phantom: PhantomData,
-            phantom2: PhantomData,
             ds,
             prefix,
             len,
+            shift: None,
         }
     }
 
@@ -86,8 +151,8 @@ where
     /// # }
     /// ```
     pub fn get(&self, index: usize) -> Result<Option<T>, Error<E>> {
-        let len = self.len();
-        if index >= len {
+        let len = lock(&self.len);
+        if index >= *len {
             return Ok(None);
         }
 
@@ -126,13 +191,14 @@ where
         T: std::borrow::Borrow<Q>,
         Q: Serialize + ?Sized,
     {
-        let prev_len = self.len.fetch_add(1, Ordering::SeqCst);
+        let mut len = lock(&self.len);
         let key = Prefixed {
             prefix: self.prefix,
-            index: prev_len,
+            index: *len,
         };
-        debug!("pushing onto vector (index: {prev_len})");
+        debug!("pushing onto vector (index: {})", *len);
         self.ds.insert::<Prefixed, Q, T>(&key, value)?;
+        *len += 1;
         Ok(())
     }
 
@@ -158,14 +224,8 @@ where
     /// # }
     /// ```
     pub fn pop(&self) -> Result<Option<T>, Error<E>> {
-        let old_len = self
-            .len
-            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |len| {
-                Some(len.saturating_sub(1))
-            })
-            .expect("closure never returns None");
-
-        let index = match old_len.checked_sub(1) {
+        let mut len = lock(&self.len);
+        let index = match len.checked_sub(1) {
             Some(idx) => idx,
             None => return Ok(None),
         };
@@ -175,7 +235,418 @@ where
         };
 
         debug!("popping from vector (index: {index})");
-        self.ds.remove(&key)
+        let value = self.ds.remove(&key)?;
+        *len = index;
+        Ok(value)
+    }
+
+    /// Reads an element that should exist as it is below the length
+    fn get_existing(&self, index: usize) -> Result<T, Error<E>> {
+        let key = Prefixed {
+            prefix: self.prefix,
+            index,
+        };
+        self.ds.get(&key)?.ok_or(Error::MissingElement { index })
+    }
+
+    /// Removes elements from the back until the list is `new_len` long
+    fn shrink(&self, len: &mut usize, new_len: usize) -> Result<(), Error<E>> {
+        while *len > new_len {
+            let index = *len - 1;
+            let key = Prefixed {
+                prefix: self.prefix,
+                index,
+            };
+            self.ds.remove::<Prefixed, T>(&key)?;
+            *len = index;
+        }
+        Ok(())
+    }
+
+    /// Writes `value` to `index` returning the element that was there
+    fn put<Q>(&self, index: usize, value: &Q) -> Result<Option<T>, Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let key = Prefixed {
+            prefix: self.prefix,
+            index,
+        };
+        self.ds.insert::<Prefixed, Q, T>(&key, value)
+    }
+
+    /// Returns the first element of the list, or `None` if it is empty.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// assert_eq!(db.list().first()?, None);
+    /// db.list().extend(["a", "b", "c"])?;
+    /// assert_eq!(db.list().first()?, Some("a".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn first(&self) -> Result<Option<T>, Error<E>> {
+        self.get(0)
+    }
+
+    /// Returns the last element of the list, or `None` if it is empty.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// assert_eq!(db.list().last()?, None);
+    /// db.list().extend(["a", "b", "c"])?;
+    /// assert_eq!(db.list().last()?, Some("c".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn last(&self) -> Result<Option<T>, Error<E>> {
+        let len = lock(&self.len);
+        match len.checked_sub(1) {
+            Some(index) => self.get_existing(index).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Overwrites the element at `index`.
+    ///
+    /// The item may be any borrowed form of the lists item type, but the
+    /// serialized form must match the not borrowed serialized form.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.list().extend(["a", "b", "c"])?;
+    /// db.list().set(1, "z")?;
+    /// assert_eq!(db.list().get(1)?, Some("z".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set<Q>(&self, index: usize, value: &Q) -> Result<(), Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let len = lock(&self.len);
+        assert!(
+            index < *len,
+            "set index (is {index}) should be < len (is {})",
+            *len
+        );
+        self.put(index, value)?;
+        Ok(())
+    }
+
+    /// Inserts an element at position `index` within the list, shifting all
+    /// elements after it to the right.
+    ///
+    /// This rewrites every element after `index`, it takes O(n) database
+    /// writes. Databases that support transactions move them in one
+    /// transaction. On others they are moved one at a time starting at the
+    /// back, if a write fails the list is left with one of the moved elements
+    /// duplicated but no element is lost.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Panics
+    /// Panics if `index > len`.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.list().extend(["a", "c"])?;
+    /// db.list().insert(1, "b")?;
+    /// let list: Vec<String> = db.list().iter().collect::<Result<_, _>>()?;
+    /// assert_eq!(list, ["a", "b", "c"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert<Q>(&self, index: usize, value: &Q) -> Result<(), Error<E>>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let mut len = lock(&self.len);
+        assert!(
+            index <= *len,
+            "insertion index (is {index}) should be <= len (is {})",
+            *len
+        );
+
+        debug!("inserting into vector (index: {index}, len: {})", *len);
+        let end = *len;
+        if let Some(shift) = self.shift {
+            let value = bincode::serde::encode_to_vec(value, val_config())
+                .map_err(Error::SerializingValue)?;
+            let shift_tail = Shift {
+                moves: (index..end).rev().map(|from| (from, from + 1)).collect(),
+                put: Some((index, &value)),
+                removed: 0..0,
+            };
+            shift(&self.ds, self.prefix, &shift_tail)?;
+            *len = end + 1;
+            return Ok(());
+        }
+        for from in (index..end).rev() {
+            let moved = self.get_existing(from)?;
+            self.put::<T>(from + 1, &moved)?;
+            // the element at `end` is stored now, it belongs to the list
+            *len = end + 1;
+        }
+        self.put(index, value)?;
+        *len = end + 1;
+        Ok(())
+    }
+
+    /// Removes and returns the element at position `index` within the list,
+    /// shifting all elements after it to the left.
+    ///
+    /// This rewrites every element after `index`, it takes O(n) database
+    /// writes. Use [`swap_remove`](Self::swap_remove) if the order does not
+    /// matter. Databases that support transactions move the elements in one
+    /// transaction. On others a failed write leaves the list with one of the
+    /// moved elements duplicated.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.list().extend(["a", "b", "c"])?;
+    /// assert_eq!(db.list().remove(1)?, "b");
+    /// let list: Vec<String> = db.list().iter().collect::<Result<_, _>>()?;
+    /// assert_eq!(list, ["a", "c"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove(&self, index: usize) -> Result<T, Error<E>> {
+        let mut len = lock(&self.len);
+        assert!(
+            index < *len,
+            "removal index (is {index}) should be < len (is {})",
+            *len
+        );
+
+        debug!("removing from vector (index: {index}, len: {})", *len);
+        let removed = self.get_existing(index)?;
+        if let Some(shift) = self.shift {
+            let shift_tail = Shift {
+                moves: (index + 1..*len).map(|from| (from, from - 1)).collect(),
+                put: None,
+                removed: *len - 1..*len,
+            };
+            shift(&self.ds, self.prefix, &shift_tail)?;
+            *len -= 1;
+            return Ok(removed);
+        }
+        for from in index + 1..*len {
+            let moved = self.get_existing(from)?;
+            self.put::<T>(from - 1, &moved)?;
+        }
+        let last = *len - 1;
+        self.shrink(&mut len, last)?;
+        Ok(removed)
+    }
+
+    /// Removes an element from the list and returns it. The removed element
+    /// is replaced by the last element of the list.
+    ///
+    /// This does not preserve ordering but takes at most three database
+    /// operations.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.list().extend(["a", "b", "c", "d"])?;
+    /// assert_eq!(db.list().swap_remove(1)?, "b");
+    /// let list: Vec<String> = db.list().iter().collect::<Result<_, _>>()?;
+    /// assert_eq!(list, ["a", "d", "c"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn swap_remove(&self, index: usize) -> Result<T, Error<E>> {
+        let mut len = lock(&self.len);
+        assert!(
+            index < *len,
+            "swap_remove index (is {index}) should be < len (is {})",
+            *len
+        );
+
+        let last = *len - 1;
+        let removed = if index == last {
+            self.get_existing(index)?
+        } else {
+            let moved = self.get_existing(last)?;
+            self.put::<T>(index, &moved)?
+                .ok_or(Error::MissingElement { index })?
+        };
+        self.shrink(&mut len, last)?;
+        Ok(removed)
+    }
+
+    /// Shortens the list, keeping the first `len` elements and removing the
+    /// rest. Has no effect if `len` is greater than or equal to the lists
+    /// current length.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     list: Vec<String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.list().extend(["a", "b", "c"])?;
+    /// db.list().truncate(1)?;
+    /// assert_eq!(db.list().len(), 1);
+    /// assert_eq!(db.list().last()?, Some("a".to_owned()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn truncate(&self, new_len: usize) -> Result<(), Error<E>> {
+        let mut len = lock(&self.len);
+        self.shrink(&mut len, new_len)
+    }
+
+    /// Retains only the elements specified by the predicate. Removes all
+    /// elements `e` for which `f(&e)` returns `false`, the order of the
+    /// retained elements is preserved.
+    ///
+    /// This reads every element and rewrites each retained element that has
+    /// to move. Databases that support transactions move them in one
+    /// transaction. On others a failed write can leave the list with some
+    /// retained elements duplicated.
+    ///
+    /// The list is locked during the whole pass, using it inside `f`
+    /// deadlocks.
+    ///
+    /// # Errors
+    /// This can fail if the underlying database ran into a problem
+    /// or if serialization failed.
+    ///
+    /// # Examples
+    /// ```
+    /// #[dbstruct::dbstruct(db=btreemap)]
+    /// struct Test {
+    ///     list: Vec<u32>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = Test::new()?;
+    /// db.list().extend(&[1, 2, 3, 4])?;
+    /// db.list().retain(|x| x % 2 == 0)?;
+    /// let list: Vec<u32> = db.list().iter().collect::<Result<_, _>>()?;
+    /// assert_eq!(list, [2, 4]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn retain<F>(&self, mut f: F) -> Result<(), Error<E>>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut len = lock(&self.len);
+        let end = *len;
+        let mut retained = std::vec::Vec::with_capacity(end);
+        for index in 0..end {
+            if f(&self.get_existing(index)?) {
+                retained.push(index);
+            }
+        }
+
+        let moves: std::vec::Vec<_> = retained
+            .iter()
+            .enumerate()
+            .filter(|(to, from)| *to != **from)
+            .map(|(to, from)| (*from, to))
+            .collect();
+        let kept = retained.len();
+        if let Some(shift) = self.shift {
+            let removed = Shift {
+                moves,
+                put: None,
+                removed: kept..end,
+            };
+            shift(&self.ds, self.prefix, &removed)?;
+            *len = kept;
+            return Ok(());
+        }
+        for (from, to) in moves {
+            let element = self.get_existing(from)?;
+            self.put::<T>(to, &element)?;
+        }
+        self.shrink(&mut len, kept)
     }
 
     /// Clears the list, removing all values.
@@ -201,8 +672,14 @@ where
     /// # }
     /// ```
     pub fn clear(&self) -> Result<(), Error<E>> {
-        for _ in 0..self.len() {
-            self.pop()?;
+        let mut len = lock(&self.len);
+        while let Some(index) = len.checked_sub(1) {
+            let key = Prefixed {
+                prefix: self.prefix,
+                index,
+            };
+            self.ds.remove::<Prefixed, T>(&key)?;
+            *len = index;
         }
         Ok(())
     }
@@ -224,7 +701,7 @@ where
     /// # }
     /// ```
     pub fn len(&self) -> usize {
-        self.len.load(Ordering::SeqCst)
+        *lock(&self.len)
     }
 
     /// Returns `true` if the list has a length of 0.
@@ -250,6 +727,23 @@ where
     }
 }
 
+impl<T, E, DS> Vec<T, DS>
+where
+    E: fmt::Debug,
+    T: Serialize + DeserializeOwned,
+    DS: data_store::Transactional<DbError = E>,
+    for<'tx> DS::Tx<'tx>: ByteStore<DbError = DS::TxError>,
+{
+    /// Like [`new`](Vec::new) but moves elements in one transaction
+    #[doc(hidden)]
+    pub fn new_transactional(ds: DS, prefix: u8, len: Arc<Mutex<usize>>) -> Self {
+        Self {
+            shift: Some(shift_in_transaction::<DS>),
+            ..Self::new(ds, prefix, len)
+        }
+    }
+}
+
 impl<T, E, DS> fmt::Debug for Vec<T, DS>
 where
     E: fmt::Debug,
@@ -282,7 +776,7 @@ mod tests {
     pub(crate) type TestVec<T> = Vec<T, stores::BTreeMap>;
     pub(crate) fn empty<T: Serialize + DeserializeOwned>() -> TestVec<T> {
         let ds = stores::BTreeMap::new();
-        let len = Arc::new(AtomicUsize::new(0));
+        let len = Arc::new(Mutex::new(0));
 
         Vec::new(ds, 1, len)
     }
@@ -336,4 +830,146 @@ mod tests {
             assert_eq!(elem, None)
         }
     }
+
+    mod given_4_long_vec {
+        use super::*;
+
+        fn abcd() -> TestVec<char> {
+            let mut vec = empty();
+            vec.extend(&['a', 'b', 'c', 'd']).unwrap();
+            vec
+        }
+
+        fn collect(vec: &TestVec<char>) -> std::vec::Vec<char> {
+            vec.iter().map(Result::unwrap).collect()
+        }
+
+        #[test]
+        fn insert_shifts_the_tail() {
+            let vec = abcd();
+            vec.insert(0, &'x').unwrap();
+            vec.insert(5, &'y').unwrap();
+            vec.insert(3, &'z').unwrap();
+            assert_eq!(collect(&vec), ['x', 'a', 'b', 'z', 'c', 'd', 'y']);
+            assert_eq!(vec.len(), 7);
+        }
+
+        #[test]
+        fn remove_shifts_the_tail() {
+            let vec = abcd();
+            assert_eq!(vec.remove(1).unwrap(), 'b');
+            assert_eq!(vec.remove(2).unwrap(), 'd');
+            assert_eq!(collect(&vec), ['a', 'c']);
+            assert_eq!(vec.len(), 2);
+        }
+
+        #[test]
+        fn swap_remove_moves_the_last() {
+            let vec = abcd();
+            assert_eq!(vec.swap_remove(0).unwrap(), 'a');
+            assert_eq!(vec.swap_remove(2).unwrap(), 'c');
+            assert_eq!(collect(&vec), ['d', 'b']);
+        }
+
+        #[test]
+        fn retain_keeps_the_order() {
+            let vec = abcd();
+            vec.retain(|c| *c != 'a' && *c != 'c').unwrap();
+            assert_eq!(collect(&vec), ['b', 'd']);
+            assert_eq!(vec.last().unwrap(), Some('d'));
+        }
+
+        #[test]
+        fn truncate_past_the_end_does_nothing() {
+            let vec = abcd();
+            vec.truncate(10).unwrap();
+            assert_eq!(vec.len(), 4);
+            vec.truncate(1).unwrap();
+            assert_eq!(collect(&vec), ['a']);
+        }
+
+        #[test]
+        #[should_panic(expected = "should be < len")]
+        fn set_out_of_bounds_panics() {
+            let vec = abcd();
+            vec.set(4, &'e').unwrap();
+        }
+    }
+
+    #[test]
+    fn failed_insert_loses_nothing() {
+        let ds = stores::Faulty::new(stores::BTreeMap::new());
+        let mut vec = Vec::new(ds.clone(), 1, Arc::new(Mutex::new(0)));
+        vec.extend(&['a', 'b', 'c']).unwrap();
+
+        // read and write c, read b, fail writing b
+        ds.fail_nth(4);
+        vec.insert(0, &'x').unwrap_err();
+        let list: std::vec::Vec<char> = vec.iter().map(Result::unwrap).collect();
+        assert_eq!(list, ['a', 'b', 'c', 'c']);
+    }
+
+    type FaultyVec = Vec<char, stores::Faulty<stores::BTreeMap>>;
+
+    /// Fails each operation in turn while running `op` on a transactional
+    /// list holding a, b, c and d. Afterwards the list must either be
+    /// unchanged or hold `expected`.
+    fn shifts_all_or_nothing(op: fn(&FaultyVec) -> Result<(), FaultyError>, expected: &[char]) {
+        for nth in 1.. {
+            let ds = stores::Faulty::new(stores::BTreeMap::new());
+            let mut vec = Vec::new_transactional(ds.clone(), 1, Arc::new(Mutex::new(0)));
+            vec.extend(&['a', 'b', 'c', 'd']).unwrap();
+            ds.fail_nth(ds.ops() + nth);
+            let res = op(&vec);
+            ds.heal();
+
+            let len = Prefixed::stored_len(&ds, 1).unwrap();
+            let reopened = Vec::new(ds, 1, Arc::new(Mutex::new(len)));
+            let list: std::vec::Vec<char> = reopened.iter().map(Result::unwrap).collect();
+            assert_eq!(vec.len(), list.len(), "failed at operation {nth}");
+            if res.is_ok() {
+                assert_eq!(list, expected);
+                break;
+            }
+            assert_eq!(list, ['a', 'b', 'c', 'd'], "failed at operation {nth}");
+        }
+    }
+
+    type FaultyError = Error<stores::FaultyError<stores::BTreeMapError>>;
+
+    #[test]
+    fn insert_is_all_or_nothing() {
+        shifts_all_or_nothing(|vec| vec.insert(1, &'x'), &['a', 'x', 'b', 'c', 'd']);
+    }
+
+    #[test]
+    fn remove_is_all_or_nothing() {
+        shifts_all_or_nothing(|vec| vec.remove(1).map(|_| ()), &['a', 'c', 'd']);
+    }
+
+    #[test]
+    fn retain_is_all_or_nothing() {
+        shifts_all_or_nothing(|vec| vec.retain(|c| *c != 'b'), &['a', 'c', 'd']);
+    }
+
+    #[test]
+    fn retain_missing_element_is_an_error() {
+        let vec: TestVec<u8> = Vec::new(stores::BTreeMap::new(), 1, Arc::new(Mutex::new(2)));
+        vec.push(&7).unwrap();
+        assert!(matches!(
+            vec.retain(|_| true),
+            Err(Error::MissingElement { index: 0 })
+        ));
+    }
+
+    #[test]
+    fn missing_element_is_an_error() {
+        let vec: TestVec<u8> = Vec::new(stores::BTreeMap::new(), 1, Arc::new(Mutex::new(2)));
+        vec.push(&7).unwrap();
+        assert!(matches!(vec.last(), Ok(Some(7))));
+        assert!(matches!(
+            vec.remove(0),
+            Err(Error::MissingElement { index: 0 })
+        ));
+    }
 }
@@ -68,16 +68,16 @@ where
 mod tests {
     use crate::stores;
     use crate::wrapper::Vec;
-    use std::sync::{atomic::AtomicUsize, Arc};
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn error() {
-        let ds = stores::BTreeMap::new();
-        let len = Arc::new(AtomicUsize::new(0));
-        let mut vec: Vec<u16, stores::BTreeMap> = Vec::new(ds.clone(), 1, len);
+        let ds = stores::Faulty::new(stores::BTreeMap::new());
+        let len = Arc::new(Mutex::new(0));
+        let mut vec: Vec<u16, _> = Vec::new(ds.clone(), 1, len);
 
         let iter = [1, 2, 3, 4];
-        ds.force_error();
+        ds.fail_nth(1);
         let err = vec
             .extend(&iter)
             .expect_err("we forced the datastore to crash on access");
@@ -88,7 +88,7 @@ mod tests {
     #[test]
     fn push_str_slices() {
         let ds = stores::BTreeMap::new();
-        let len = Arc::new(AtomicUsize::new(0));
+        let len = Arc::new(Mutex::new(0));
         let mut vec: Vec<String, _> = Vec::new(ds.clone(), 1, len);
 
         let iter = ["1", "2", "3", "4"];
@@ -99,7 +99,7 @@ mod tests {
     #[test]
     fn push_strings() {
         let ds = stores::BTreeMap::new();
-        let len = Arc::new(AtomicUsize::new(0));
+        let len = Arc::new(Mutex::new(0));
         let mut vec: Vec<String, _> = Vec::new(ds.clone(), 1, len);
 
         let iter = [
@@ -1,15 +1,14 @@
 use core::fmt;
 use std::marker::PhantomData;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use crate::traits::DataStore;
+use crate::traits::{data_store, DataStore};
 use crate::Error;
 
-use super::PhantomUnsync;
+use super::lock;
 
 mod extend;
 mod iterator;
@@ -20,21 +19,21 @@ where
     DS: DataStore,
 {
     phantom: PhantomData<T>,
-    phantom2: PhantomUnsync,
     ds: DS,
     prefix: u8,
+    // Points to the current free slot. Held while writing to the database,
+    // when locking both always lock head before tail.
+    head: Arc<Mutex<u64>>,
     // Points to the current free slot
-    head: Arc<AtomicU64>,
-    // Points to the current free slot
-    tail: Arc<AtomicU64>,
+    tail: Arc<Mutex<u64>>,
 }
 
 #[derive(Serialize, Deserialize, Hash, Eq, PartialEq, Clone, Debug)]
 pub struct Prefixed {
-    prefix: u8,
+    pub(crate) prefix: u8,
     /// Rather large so we can *just* start in the middle and be sure
     /// you can never reach an edge
-    index: u64,
+    pub(crate) index: u64,
 }
 
 impl Prefixed {
@@ -50,6 +49,42 @@ impl Prefixed {
             index: u64::MAX,
         }
     }
+
+    /// Finds the free slot after the last item of the deque stored under
+    /// `prefix`. Used by the generated code when opening the database.
+    pub fn stored_tail<DS>(ds: &DS, prefix: u8) -> Result<u64, Error<DS::DbError>>
+    where
+        DS: data_store::Ordered,
+    {
+        // The key before could belong to another field. Check its prefix
+        // before decoding it as ours.
+        match ds.get_lt::<_, u8, ()>(&Self::max(prefix))? {
+            Some((found, ())) if found == prefix => (),
+            _ => return Ok(u64::MAX / 2),
+        }
+        Ok(ds
+            .get_lt::<_, Self, ()>(&Self::max(prefix))?
+            .map(|(key, ())| key.index + 1)
+            .unwrap_or(u64::MAX / 2))
+    }
+
+    /// Finds the free slot before the first item of the deque stored under
+    /// `prefix`. Used by the generated code when opening the database.
+    pub fn stored_head<DS>(ds: &DS, prefix: u8) -> Result<u64, Error<DS::DbError>>
+    where
+        DS: data_store::Ordered,
+    {
+        // The key after could belong to another field or be metadata. Check
+        // its prefix before decoding it as ours.
+        match ds.get_gt::<_, u8, ()>(&Self::min(prefix))? {
+            Some((found, ())) if found == prefix => (),
+            _ => return Ok(u64::MAX / 2 - 1),
+        }
+        Ok(ds
+            .get_gt::<_, Self, ()>(&Self::min(prefix))?
+            .map(|(key, ())| key.index - 1)
+            .unwrap_or(u64::MAX / 2 - 1))
+    }
 }
 
 impl<T, E, DS> VecDeque<T, DS>
@@ -59,15 +94,10 @@ where
     DS: DataStore<DbError = E>,
 {
     #[doc(hidden)]
-    pub fn new(ds: DS, prefix: u8, head: Arc<AtomicU64>, tail: Arc<AtomicU64>) -> Self {
-        assert_ne!(
-            head.load(Ordering::Relaxed),
-            tail.load(Ordering::Relaxed),
-            "VecDeque::new failed"
-        );
+    pub fn new(ds: DS, prefix: u8, head: Arc<Mutex<u64>>, tail: Arc<Mutex<u64>>) -> Self {
+        assert_ne!(*lock(&head), *lock(&tail), "VecDeque::new failed");
         Self {
             phantom: PhantomData,
-            phantom2: PhantomData,
             ds,
             prefix,
             head,
@@ -98,12 +128,13 @@ where
     /// # }
     /// ```
     pub fn get(&self, index: usize) -> Result<Option<T>, Error<E>> {
-        if index >= self.len() {
+        let head = lock(&self.head);
+        let tail = lock(&self.tail);
+        if index as u64 >= *tail - *head - 1 {
             return Ok(None);
         }
 
-        let head = self.head.load(Ordering::Relaxed);
-        let db_index = index as u64 + head + 1;
+        let db_index = index as u64 + *head + 1;
 
         let key = Prefixed {
             prefix: self.prefix,
@@ -140,13 +171,14 @@ where
         T: std::borrow::Borrow<Q>,
         Q: Serialize + ?Sized,
     {
-        let free_tail = self.tail.fetch_add(1, Ordering::SeqCst);
+        let mut tail = lock(&self.tail);
         let key = Prefixed {
             prefix: self.prefix,
-            index: free_tail,
+            index: *tail,
         };
 
         self.ds.insert::<Prefixed, Q, T>(&key, value)?;
+        *tail += 1;
         Ok(())
     }
 
@@ -178,13 +210,14 @@ where
         T: std::borrow::Borrow<Q>,
         Q: Serialize + ?Sized,
     {
-        let free_head = self.head.fetch_sub(1, Ordering::SeqCst);
+        let mut head = lock(&self.head);
         let key = Prefixed {
             prefix: self.prefix,
-            index: free_head,
+            index: *head,
         };
 
         self.ds.insert::<Prefixed, Q, T>(&key, value)?;
+        *head -= 1;
         Ok(())
     }
 
@@ -210,20 +243,21 @@ where
     /// # }
     /// ```
     pub fn pop_back(&self) -> Result<Option<T>, Error<E>> {
-        let tail = self.tail.load(Ordering::Relaxed);
-        let head = self.head.load(Ordering::Relaxed);
-        let next_tail = tail - 1;
-        if next_tail != head {
-            self.tail.store(tail - 1, Ordering::Relaxed);
+        let head = lock(&self.head);
+        let mut tail = lock(&self.tail);
+        let last = *tail - 1;
+        if last == *head {
+            return Ok(None);
         }
-        // TODO TAIL AND HEAD COLLIDE, TEST THIS AND FIX POP_FRONT SIMILARLY
 
         let key = Prefixed {
             prefix: self.prefix,
-            index: tail - 1,
+            index: last,
         };
 
-        self.ds.remove(&key)
+        let value = self.ds.remove(&key)?;
+        *tail = last;
+        Ok(value)
     }
 
     /// Removes the first element from this database deque and returns it,
@@ -248,13 +282,21 @@ where
     /// # }
     /// ```
     pub fn pop_front(&self) -> Result<Option<T>, Error<E>> {
-        let free_head = self.head.fetch_add(1, Ordering::Relaxed);
+        let mut head = lock(&self.head);
+        let tail = lock(&self.tail);
+        let first = *head + 1;
+        if first == *tail {
+            return Ok(None);
+        }
+
         let key = Prefixed {
             prefix: self.prefix,
-            index: free_head + 1,
+            index: first,
         };
 
-        self.ds.remove(&key)
+        let value = self.ds.remove(&key)?;
+        *head = first;
+        Ok(value)
     }
 
     /// Clears the list, removing all values.
@@ -282,9 +324,16 @@ where
     /// # }
     /// ```
     pub fn clear(&self) -> Result<(), Error<E>> {
-        // Keep going back until nothing left, as long as pop_back works
-        // concurrently this is safe too
-        while self.pop_back()?.is_some() {}
+        let head = lock(&self.head);
+        let mut tail = lock(&self.tail);
+        while *tail - 1 != *head {
+            let key = Prefixed {
+                prefix: self.prefix,
+                index: *tail - 1,
+            };
+            self.ds.remove::<Prefixed, T>(&key)?;
+            *tail -= 1;
+        }
         Ok(())
     }
 
@@ -305,10 +354,10 @@ where
     /// # }
     /// ```
     pub fn len(&self) -> usize {
-        let head = self.head.load(Ordering::Acquire);
-        let tail = self.tail.load(Ordering::Acquire);
+        let head = lock(&self.head);
+        let tail = lock(&self.tail);
 
-        ((tail - head) - 1) as usize
+        ((*tail - *head) - 1) as usize
     }
 
     /// Returns `true` if the list has a length of 0.
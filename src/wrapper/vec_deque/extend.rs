@@ -68,19 +68,17 @@ where
 mod tests {
     use crate::stores;
     use crate::wrapper::VecDeque;
-    use std::sync::atomic::AtomicU64;
-    use std::sync::Arc;
-    use std::u64;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn error() {
-        let ds = stores::BTreeMap::new();
-        let tail = Arc::new(AtomicU64::new(u64::MAX / 2));
-        let head = Arc::new(AtomicU64::new(u64::MAX / 2 - 1));
-        let mut vec: VecDeque<u16, stores::BTreeMap> = VecDeque::new(ds.clone(), 1, tail, head);
+        let ds = stores::Faulty::new(stores::BTreeMap::new());
+        let tail = Arc::new(Mutex::new(u64::MAX / 2));
+        let head = Arc::new(Mutex::new(u64::MAX / 2 - 1));
+        let mut vec: VecDeque<u16, _> = VecDeque::new(ds.clone(), 1, tail, head);
 
         let iter = [1, 2, 3, 4];
-        ds.force_error();
+        ds.fail_nth(1);
         let err = vec
             .extend(&iter)
             .expect_err("we forced the datastore to crash on access");
@@ -0,0 +1,177 @@
+#![cfg(feature = "async")]
+
+mod btreemap {
+    #[dbstruct::dbstruct(db=btreemap, async)]
+    pub struct Test {
+        pub queue: VecDeque<u32>,
+        pub done: HashMap<u32, bool>,
+        pub seen: HashSet<u32>,
+        pub events: BTreeMap<u64, String>,
+        pub log: Vec<String>,
+        pub name: Option<String>,
+        #[dbstruct(Default = "5")]
+        pub retries: u8,
+    }
+}
+
+mod sled {
+    #[dbstruct::dbstruct(db=sled, async)]
+    pub struct Test {
+        pub queue: VecDeque<u32>,
+        pub log: Vec<String>,
+    }
+}
+
+mod generic {
+    #[dbstruct::dbstruct(db=trait, async)]
+    pub struct Test {
+        pub log: Vec<String>,
+        #[dbstruct(Default)]
+        pub count: u32,
+        pub events: BTreeMap<u64, String>,
+    }
+}
+
+#[tokio::test]
+async fn wrappers() {
+    let db = btreemap::Test::new().await.unwrap();
+
+    db.queue().push_back(&1).await.unwrap();
+    db.queue().push_front(&0).await.unwrap();
+    assert_eq!(db.queue().len().await, 2);
+    assert_eq!(db.queue().pop_front().await.unwrap(), Some(0));
+
+    db.done().insert(&1, &true).await.unwrap();
+    assert!(db.done().contains_key(&1).await.unwrap());
+    db.done().clear().await.unwrap();
+    assert!(db.done().is_empty().await.unwrap());
+
+    assert!(db.seen().insert(&7).await.unwrap());
+    assert!(db.seen().contains(&7).await.unwrap());
+    assert!(db.seen().remove(&7).await.unwrap());
+    assert!(db.seen().is_empty().await.unwrap());
+    db.seen().extend(&[3, 1, 2, 3]).await.unwrap();
+    assert_eq!(db.seen().len().await.unwrap(), 3);
+    let seen = db.seen();
+    let mut elements = seen.iter();
+    assert_eq!(elements.next_back().await.unwrap().unwrap(), 3);
+    assert_eq!(elements.next().await.unwrap().unwrap(), 1);
+    assert_eq!(elements.next().await.unwrap().unwrap(), 2);
+    assert!(elements.next().await.is_none());
+
+    db.events().insert(&20, "stopped").await.unwrap();
+    db.events().insert(&10, "started").await.unwrap();
+    let first = db.events().first_key_value().await.unwrap();
+    assert_eq!(first, Some((10, "started".to_owned())));
+    let last = db.events().pop_last().await.unwrap();
+    assert_eq!(last, Some((20, "stopped".to_owned())));
+    assert_eq!(db.events().pop_first().await.unwrap().unwrap().0, 10);
+    assert!(db.events().is_empty().await.unwrap());
+
+    db.events()
+        .extend([(&3, "c"), (&1, "a"), (&2, "b"), (&4, "d")])
+        .await
+        .unwrap();
+    assert_eq!(db.events().len().await.unwrap(), 4);
+    let events = db.events();
+    let mut range = events.range(2..=3).unwrap();
+    assert_eq!(range.next().await.unwrap().unwrap().0, 2);
+    assert_eq!(range.next_back().await.unwrap().unwrap().0, 3);
+    assert!(range.next().await.is_none());
+    let mut keys = events.keys();
+    assert_eq!(keys.next_back().await.unwrap().unwrap(), 4);
+    let mut values = events.values();
+    assert_eq!(values.next().await.unwrap().unwrap(), "a");
+
+    db.log().push("started").await.unwrap();
+    assert_eq!(db.log().get(0).await.unwrap().as_deref(), Some("started"));
+    db.log().insert(0, "created").await.unwrap();
+    db.log()
+        .extend(["stopped", "waiting", "restarted"])
+        .await
+        .unwrap();
+    db.log().retain(|line| line.ends_with("ed")).await.unwrap();
+    assert_eq!(db.log().remove(1).await.unwrap(), "started");
+    assert_eq!(db.log().last().await.unwrap().as_deref(), Some("restarted"));
+    db.log().truncate(1).await.unwrap();
+    assert_eq!(db.log().first().await.unwrap().as_deref(), Some("created"));
+
+    assert!(db.name().is_none().await.unwrap());
+    db.name().set(Some("worker")).await.unwrap();
+    assert_eq!(db.name().get().await.unwrap().as_deref(), Some("worker"));
+
+    assert_eq!(db.retries().get().await.unwrap(), 5);
+    db.retries().set(&6).await.unwrap();
+    db.retries().update(|n| n + 1).await.unwrap();
+    assert_eq!(db.retries().get().await.unwrap(), 7);
+}
+
+#[tokio::test]
+async fn accessors_can_be_used_in_spawned_tasks() {
+    let db = btreemap::Test::new().await.unwrap();
+    let task = tokio::spawn(async move {
+        db.log().push("from a task").await.unwrap();
+        db.log().insert(0, "first").await.unwrap();
+        db.log().swap_remove(0).await.unwrap();
+        db.log().pop().await.unwrap()
+    });
+    assert_eq!(task.await.unwrap().as_deref(), Some("from a task"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn concurrent_pushes_are_not_lost() {
+    let db = std::sync::Arc::new(btreemap::Test::new().await.unwrap());
+    let tasks: Vec<_> = (0..8)
+        .map(|t| {
+            let db = db.clone();
+            tokio::spawn(async move {
+                for i in 0..50 {
+                    db.queue().push_back(&(t * 50 + i)).await.unwrap();
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    assert_eq!(db.queue().len().await, 400);
+    let mut seen = std::collections::HashSet::new();
+    while let Some(item) = db.queue().pop_front().await.unwrap() {
+        assert!(seen.insert(item));
+    }
+    assert_eq!(seen.len(), 400);
+}
+
+#[tokio::test]
+async fn sled_persistance() {
+    let dir = tempdir::TempDir::new("dbstruct_async_sled").unwrap();
+    // reopening by path can race with sled releasing its file lock
+    let sled_db = dbstruct::sled::open(dir.path().join("db")).unwrap();
+
+    let db = sled::Test::open_db(sled_db.clone()).await.unwrap();
+    db.queue().push_back(&1).await.unwrap();
+    db.queue().push_back(&2).await.unwrap();
+    db.log().push("a").await.unwrap();
+    std::mem::drop(db);
+
+    let db = sled::Test::open_db(sled_db).await.unwrap();
+    assert_eq!(db.queue().len().await, 2);
+    assert_eq!(db.queue().pop_front().await.unwrap(), Some(1));
+    assert_eq!(db.log().pop().await.unwrap().as_deref(), Some("a"));
+}
+
+#[tokio::test]
+async fn trait_backend() {
+    let ds = dbstruct::stores::Blocking::new(dbstruct::stores::BTreeMap::new());
+    let db = generic::Test::open(ds.clone()).await.unwrap();
+    db.log().push("a").await.unwrap();
+    db.count().set(&3).await.unwrap();
+    db.events().insert(&1, "a").await.unwrap();
+    std::mem::drop(db);
+
+    let db = generic::Test::open(ds).await.unwrap();
+    assert_eq!(db.log().len().await, 1);
+    assert_eq!(db.count().get().await.unwrap(), 3);
+    assert_eq!(db.events().last_key_value().await.unwrap().unwrap().0, 1);
+}
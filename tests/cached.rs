@@ -0,0 +1,46 @@
+#![cfg(feature = "cache")]
+
+#[dbstruct::dbstruct(db=sled, cache = 16)]
+pub struct Test {
+    #[dbstruct(Default = "\"light\".to_owned()")]
+    theme: String,
+    counter: Option<u32>,
+    primes: Vec<u32>,
+}
+
+#[test]
+fn wrappers() {
+    let dir = tempdir::TempDir::new("dbstruct_cached_wrappers").unwrap();
+    let db = Test::open_path(dir.path().join("db")).unwrap();
+
+    assert_eq!(db.theme().get().unwrap(), "light");
+    db.theme().set(&"dark".to_owned()).unwrap();
+    assert_eq!(db.theme().get().unwrap(), "dark");
+
+    assert_eq!(db.counter().get().unwrap(), None);
+    db.counter().set(Some(&1)).unwrap();
+    db.counter().update(|n| n + 1).unwrap();
+    assert_eq!(db.counter().get().unwrap(), Some(2));
+
+    db.primes().extend(&[2, 3, 5]).unwrap();
+    assert_eq!(db.primes().get(2).unwrap(), Some(5));
+    assert_eq!(db.primes().pop().unwrap(), Some(5));
+    assert_eq!(db.primes().get(2).unwrap(), None);
+}
+
+#[test]
+fn transaction() {
+    let dir = tempdir::TempDir::new("dbstruct_cached_transaction").unwrap();
+    let db = Test::open_path(dir.path().join("db")).unwrap();
+
+    assert_eq!(db.counter().get().unwrap(), None);
+    assert_eq!(db.theme().get().unwrap(), "light");
+    db.transaction(|tx| {
+        tx.counter().set(Some(&7))?;
+        tx.theme().set(&"dark".to_owned())?;
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(db.counter().get().unwrap(), Some(7));
+    assert_eq!(db.theme().get().unwrap(), "dark");
+}
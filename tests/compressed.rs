@@ -0,0 +1,152 @@
+#![cfg(feature = "compression")]
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Computer {
+    name: String,
+    log: Vec<String>,
+}
+
+#[dbstruct::dbstruct(db=sled, compress = "zstd")]
+pub struct Test {
+    #[dbstruct(Default)]
+    computers: HashMap<String, Computer>,
+    primes: Vec<u32>,
+}
+
+fn computers() -> HashMap<String, Computer> {
+    let log = vec!["booted and ran the nightly backup".to_owned(); 100];
+    (0..10)
+        .map(|i| {
+            let name = format!("computer {i}");
+            let computer = Computer {
+                name: name.clone(),
+                log: log.clone(),
+            };
+            (name, computer)
+        })
+        .collect()
+}
+
+#[test]
+fn wrappers() {
+    let dir = tempdir::TempDir::new("dbstruct_compressed_wrappers").unwrap();
+    let db = Test::open_path(dir.path().join("db")).unwrap();
+
+    db.computers().set(&computers()).unwrap();
+    assert_eq!(db.computers().get().unwrap(), computers());
+
+    db.primes().extend(&[2, 3, 5, 7]).unwrap();
+    assert_eq!(db.primes().pop().unwrap(), Some(7));
+}
+
+#[test]
+fn reads_uncompressed_database() {
+    #[dbstruct::dbstruct(db=sled)]
+    pub struct Uncompressed {
+        #[dbstruct(Default)]
+        computers: HashMap<String, Computer>,
+        primes: Vec<u32>,
+    }
+
+    let dir = tempdir::TempDir::new("dbstruct_compressed_legacy").unwrap();
+    let sled = dbstruct::sled::open(dir.path().join("db")).unwrap();
+    let db = Uncompressed::open_db(sled.clone()).unwrap();
+    db.computers().set(&computers()).unwrap();
+    db.primes().push(&2).unwrap();
+    std::mem::drop(db);
+
+    let db = Test::open_db(sled).unwrap();
+    assert_eq!(db.computers().get().unwrap(), computers());
+    assert_eq!(db.primes().get(0).unwrap(), Some(2));
+}
+
+#[cfg(feature = "encryption")]
+mod encrypted {
+    use super::computers;
+    use dbstruct::stores::EncryptionKey;
+    use std::collections::HashMap;
+
+    #[dbstruct::dbstruct(db=btreemap, compress = "zstd", encrypt)]
+    pub struct Test {
+        #[dbstruct(Default)]
+        computers: HashMap<String, super::Computer>,
+    }
+
+    #[test]
+    fn transaction_and_snapshot() {
+        let dir = tempdir::TempDir::new("dbstruct_compressed_encrypted").unwrap();
+        let path = dir.path().join("snapshot");
+        let key = EncryptionKey::generate();
+
+        let db = Test::new(&key).unwrap();
+        db.transaction(|tx| {
+            tx.computers().set(&computers())?;
+            Ok(())
+        })
+        .unwrap();
+        db.save(&path).unwrap();
+
+        let db = Test::open_file(&path, &key).unwrap();
+        assert_eq!(db.computers().get().unwrap(), computers());
+    }
+}
+
+mod per_field {
+    use super::computers;
+    use std::collections::HashMap;
+
+    #[dbstruct::dbstruct(db=btreemap)]
+    pub struct Test {
+        #[dbstruct(Default, compress = "zstd")]
+        computers: HashMap<String, super::Computer>,
+        #[dbstruct(Default)]
+        plain: HashMap<String, super::Computer>,
+        #[dbstruct(compress = "zstd")]
+        logs: Vec<String>,
+    }
+
+    #[test]
+    fn only_marked_fields_are_compressed() {
+        let db = Test::new().unwrap();
+        let entry = "booted and ran the nightly backup".repeat(10);
+        db.transaction(|tx| {
+            let compressed = dbstruct::Error::from_compressed;
+            tx.computers().set(&computers()).map_err(compressed)?;
+            tx.logs().push(&entry).map_err(compressed)?;
+            Ok(())
+        })
+        .unwrap();
+        db.plain().set(&computers()).unwrap();
+        assert_eq!(db.computers().get().unwrap(), computers());
+        assert_eq!(db.plain().get().unwrap(), computers());
+        assert_eq!(db.logs().len(), 1);
+
+        let dir = tempdir::TempDir::new("dbstruct_compressed_per_field").unwrap();
+        let path = dir.path().join("snapshot");
+        db.save(&path).unwrap();
+        let snapshot = std::fs::read(&path).unwrap();
+        let plain = bincode::serde::encode_to_vec(computers(), bincode::config::standard());
+        assert!(snapshot.len() < 2 * plain.unwrap().len());
+    }
+
+    #[cfg(feature = "async")]
+    mod asynchronous {
+        #[dbstruct::dbstruct(db=btreemap, async)]
+        pub struct Test {
+            #[dbstruct(compress = "zstd")]
+            logs: Vec<String>,
+        }
+
+        #[tokio::test]
+        async fn compressed_field() {
+            let db = Test::new().await.unwrap();
+            let entry = "booted and ran the nightly backup".repeat(10);
+            db.logs().push(&entry).await.unwrap();
+            assert_eq!(db.logs().get(0).await.unwrap(), Some(entry));
+        }
+    }
+}
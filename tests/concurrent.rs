@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+#[dbstruct::dbstruct(db=btreemap)]
+pub struct Test {
+    list: Vec<u32>,
+    queue: VecDeque<u32>,
+    jobs: BTreeMap<u32, u32>,
+}
+
+const THREADS: u32 = 8;
+const PER_THREAD: u32 = 100;
+
+fn assert_sync<T: Sync>(_: &T) {}
+
+#[test]
+fn struct_is_sync() {
+    let db = Test::new().unwrap();
+    assert_sync(&db);
+}
+
+#[test]
+fn concurrent_push_then_pop() {
+    let db = Arc::new(Test::new().unwrap());
+    std::thread::scope(|s| {
+        for t in 0..THREADS {
+            let db = db.clone();
+            s.spawn(move || {
+                for i in 0..PER_THREAD {
+                    db.list().push(&(t * PER_THREAD + i)).unwrap();
+                }
+            });
+        }
+    });
+    assert_eq!(db.list().len(), (THREADS * PER_THREAD) as usize);
+
+    let popped: Vec<_> = std::thread::scope(|s| {
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let db = db.clone();
+                s.spawn(move || {
+                    let mut popped = Vec::new();
+                    while let Some(item) = db.list().pop().unwrap() {
+                        popped.push(item);
+                    }
+                    popped
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    });
+
+    let unique: HashSet<_> = popped.iter().copied().collect();
+    assert_eq!(popped.len(), (THREADS * PER_THREAD) as usize);
+    assert_eq!(unique.len(), popped.len());
+    assert!(db.list().is_empty());
+}
+
+#[test]
+fn concurrent_deque_producers_and_consumers() {
+    let db = Test::new().unwrap();
+    let popped: Vec<_> = std::thread::scope(|s| {
+        for t in 0..THREADS / 2 {
+            let db = &db;
+            s.spawn(move || {
+                for i in 0..PER_THREAD {
+                    let item = t * PER_THREAD + i;
+                    if i % 2 == 0 {
+                        db.queue().push_back(&item).unwrap();
+                    } else {
+                        db.queue().push_front(&item).unwrap();
+                    }
+                }
+            });
+        }
+        let consumers: Vec<_> = (0..THREADS / 2)
+            .map(|t| {
+                let db = &db;
+                s.spawn(move || {
+                    let mut popped = Vec::new();
+                    for _ in 0..PER_THREAD {
+                        let item = if t % 2 == 0 {
+                            db.queue().pop_front().unwrap()
+                        } else {
+                            db.queue().pop_back().unwrap()
+                        };
+                        popped.extend(item);
+                    }
+                    popped
+                })
+            })
+            .collect();
+        consumers
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    });
+
+    let mut remaining = Vec::new();
+    while let Some(item) = db.queue().pop_front().unwrap() {
+        remaining.push(item);
+    }
+
+    let all: HashSet<_> = popped.iter().chain(&remaining).copied().collect();
+    assert_eq!(
+        popped.len() + remaining.len(),
+        (THREADS / 2 * PER_THREAD) as usize
+    );
+    assert_eq!(all.len(), (THREADS / 2 * PER_THREAD) as usize);
+}
+
+#[test]
+fn concurrent_pops_take_each_entry_once() {
+    let db = Arc::new(Test::new().unwrap());
+    for job in 0..THREADS * PER_THREAD {
+        db.jobs().insert(&job, &job).unwrap();
+    }
+
+    let popped: Vec<_> = std::thread::scope(|s| {
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let db = db.clone();
+                s.spawn(move || {
+                    let mut popped = Vec::new();
+                    loop {
+                        let entry = if t % 2 == 0 {
+                            db.jobs().pop_first().unwrap()
+                        } else {
+                            db.jobs().pop_last().unwrap()
+                        };
+                        let Some((key, value)) = entry else {
+                            return popped;
+                        };
+                        assert_eq!(key, value);
+                        popped.push(key);
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    });
+
+    let unique: HashSet<_> = popped.iter().copied().collect();
+    assert_eq!(popped.len(), (THREADS * PER_THREAD) as usize);
+    assert_eq!(unique.len(), popped.len());
+    assert!(db.jobs().is_empty().unwrap());
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "encryption")]
+
+use dbstruct::stores::{EncryptedError, EncryptionKey};
+
+#[dbstruct::dbstruct(db=sled, encrypt)]
+pub struct Test {
+    #[dbstruct(Default)]
+    the_field: u8,
+    queue: VecDeque<u32>,
+    done: HashMap<u32, bool>,
+    primes: Vec<u32>,
+}
+
+#[test]
+fn wrappers() {
+    let dir = tempdir::TempDir::new("dbstruct_encrypted_wrappers").unwrap();
+    let key = EncryptionKey::generate();
+    let db = Test::open_path(dir.path().join("db"), &key).unwrap();
+
+    db.the_field().set(&8).unwrap();
+    assert_eq!(db.the_field().get().unwrap(), 8);
+
+    db.queue().push_back(&1).unwrap();
+    db.queue().push_front(&0).unwrap();
+    assert_eq!(db.queue().pop_front().unwrap(), Some(0));
+    assert_eq!(db.queue().pop_back().unwrap(), Some(1));
+
+    db.done().insert(&1, &true).unwrap();
+    assert_eq!(db.done().get(&1).unwrap(), Some(true));
+    db.primes().extend(&[2, 3, 5, 7]).unwrap();
+    assert_eq!(db.primes().get(3).unwrap(), Some(7));
+}
+
+#[test]
+fn needs_the_right_key() {
+    let dir = tempdir::TempDir::new("dbstruct_encrypted_key").unwrap();
+    let sled = dbstruct::sled::open(dir.path().join("db")).unwrap();
+    let key = EncryptionKey::from_bytes([7; 32]);
+
+    let db = Test::open_db(sled.clone(), &key).unwrap();
+    db.the_field().set(&8).unwrap();
+    db.primes().extend(&[2, 3, 5, 7]).unwrap();
+
+    std::mem::drop(db);
+    let res = Test::open_db(sled.clone(), &EncryptionKey::generate());
+    assert!(matches!(
+        res,
+        Err(dbstruct::Error::Database(EncryptedError::Decrypt))
+    ));
+
+    let db = Test::open_db(sled, &key).unwrap();
+    assert_eq!(db.the_field().get().unwrap(), 8);
+    assert_eq!(db.primes().len(), 4);
+}
+
+#[test]
+fn transaction() {
+    let dir = tempdir::TempDir::new("dbstruct_encrypted_transaction").unwrap();
+    let db = Test::open_path(dir.path().join("db"), &EncryptionKey::generate()).unwrap();
+
+    db.transaction(|tx| {
+        tx.the_field().set(&3)?;
+        tx.primes().push(&2)?;
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(db.the_field().get().unwrap(), 3);
+    assert_eq!(db.primes().pop().unwrap(), Some(2));
+}
+
+mod btreemap {
+    use dbstruct::stores::EncryptionKey;
+
+    #[dbstruct::dbstruct(db=btreemap, encrypt)]
+    pub struct Test {
+        primes: Vec<u32>,
+    }
+
+    #[test]
+    fn snapshot() {
+        let dir = tempdir::TempDir::new("dbstruct_encrypted_snapshot").unwrap();
+        let path = dir.path().join("snapshot");
+        let key = EncryptionKey::generate();
+
+        let db = Test::new(&key).unwrap();
+        db.primes().extend(&[2, 3, 5]).unwrap();
+        db.save(&path).unwrap();
+
+        let db = Test::open_file(&path, &key).unwrap();
+        assert_eq!(db.primes().len(), 3);
+    }
+}
@@ -17,6 +17,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             dbstruct::Error::DeSerializingKey(e) | dbstruct::Error::DeSerializingVal(e) => {
                 eprintln!("serialization issue: {e}")
             }
+            dbstruct::Error::SchemaMismatch(e) => {
+                eprintln!("the struct changed since the db was created: {e}")
+            }
+            e @ dbstruct::Error::NewerVersion { .. } => eprintln!("{e}"),
+            dbstruct::Error::TransactionAborted => unreachable!("no transactions used"),
+            e @ dbstruct::Error::MissingElement { .. } => eprintln!("{e}"),
         }
     }
 
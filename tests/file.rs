@@ -0,0 +1,66 @@
+#[dbstruct::dbstruct(db=file)]
+pub struct Test {
+    #[dbstruct(Default)]
+    the_field: u8,
+    queue: VecDeque<u32>,
+    done: HashMap<u32, bool>,
+    primes: Vec<u32>,
+}
+
+#[test]
+fn wrappers() {
+    let dir = tempdir::TempDir::new("dbstruct_file_wrappers").unwrap();
+    let db = Test::open_path(dir.path().join("db")).unwrap();
+
+    db.the_field().set(&8).unwrap();
+    assert_eq!(db.the_field().get().unwrap(), 8);
+
+    db.queue().push_back(&1).unwrap();
+    db.queue().push_front(&0).unwrap();
+    assert_eq!(db.queue().pop_front().unwrap(), Some(0));
+    assert_eq!(db.queue().pop_back().unwrap(), Some(1));
+    assert_eq!(db.queue().pop_back().unwrap(), None);
+
+    db.done().insert(&1, &true).unwrap();
+    assert_eq!(db.done().get(&1).unwrap(), Some(true));
+    assert_eq!(db.done().get(&2).unwrap(), None);
+}
+
+#[test]
+fn persistance() {
+    let dir = tempdir::TempDir::new("dbstruct_file_persistence").unwrap();
+    let path = dir.path().join("db");
+
+    let db = Test::open_path(&path).unwrap();
+    db.the_field().set(&8).unwrap();
+    db.primes().extend(&[2, 3, 5, 7]).unwrap();
+
+    std::mem::drop(db);
+    let db = Test::open_path(&path).unwrap();
+    assert_eq!(8u8, db.the_field().get().unwrap());
+    assert_eq!(db.primes().len(), 4);
+    assert_eq!(Some(7), db.primes().pop().unwrap());
+}
+
+#[test]
+fn compact() {
+    let dir = tempdir::TempDir::new("dbstruct_file_compact").unwrap();
+    let path = dir.path().join("db");
+    let file = dbstruct::stores::File::open_path(&path).unwrap();
+    let db = Test::open_file(file.clone()).unwrap();
+
+    for i in 0..100 {
+        db.the_field().set(&i).unwrap();
+        db.queue().push_back(&u32::from(i)).unwrap();
+        db.queue().pop_front().unwrap();
+    }
+    db.primes().extend(&[2, 3, 5]).unwrap();
+    file.compact().unwrap();
+    assert_eq!(file.stale_bytes().unwrap(), 0);
+
+    std::mem::drop((db, file));
+    let db = Test::open_path(&path).unwrap();
+    assert_eq!(db.the_field().get().unwrap(), 99);
+    assert_eq!(db.queue().pop_front().unwrap(), None);
+    assert_eq!(db.primes().len(), 3);
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "fjall")]
+
+#[dbstruct::dbstruct(db=fjall)]
+pub struct Test {
+    #[dbstruct(Default)]
+    the_field: u8,
+    queue: VecDeque<u32>,
+    done: HashMap<u32, bool>,
+    primes: Vec<u32>,
+    scores: BTreeMap<u32, String>,
+}
+
+#[test]
+fn wrappers() {
+    let dir = tempdir::TempDir::new("dbstruct_fjall_wrappers").unwrap();
+    let db = Test::open_path(dir.path().join("db")).unwrap();
+
+    db.the_field().set(&8).unwrap();
+    assert_eq!(db.the_field().get().unwrap(), 8);
+
+    db.queue().push_back(&1).unwrap();
+    db.queue().push_front(&0).unwrap();
+    assert_eq!(db.queue().pop_front().unwrap(), Some(0));
+    assert_eq!(db.queue().pop_back().unwrap(), Some(1));
+    assert_eq!(db.queue().pop_back().unwrap(), None);
+
+    db.done().insert(&1, &true).unwrap();
+    assert_eq!(db.done().get(&1).unwrap(), Some(true));
+    assert_eq!(db.done().get(&2).unwrap(), None);
+
+    for (key, name) in [(3, "c"), (1, "a"), (2, "b")] {
+        db.scores().insert(&key, name).unwrap();
+    }
+    let in_range: Vec<_> = db
+        .scores()
+        .range(2..)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(in_range, [(2, "b".to_owned()), (3, "c".to_owned())]);
+}
+
+#[test]
+fn persistance() {
+    let dir = tempdir::TempDir::new("dbstruct_fjall_persistence").unwrap();
+    let path = dir.path().join("db");
+
+    let db = Test::open_path(&path).unwrap();
+    db.the_field().set(&8).unwrap();
+    db.primes().extend(&[2, 3, 5, 7]).unwrap();
+
+    std::mem::drop(db);
+    let db = Test::open_path(&path).unwrap();
+    assert_eq!(8u8, db.the_field().get().unwrap());
+    assert_eq!(db.primes().len(), 4);
+    assert_eq!(Some(7), db.primes().pop().unwrap());
+}
+
+#[test]
+fn structs_share_a_keyspace() {
+    let dir = tempdir::TempDir::new("dbstruct_fjall_shared").unwrap();
+    let keyspace = dbstruct::stores::Fjall::open_path(dir.path().join("db"))
+        .unwrap()
+        .keyspace()
+        .clone();
+
+    let a = Test::open_keyspace(keyspace.clone()).unwrap();
+    let b = dbstruct::stores::Fjall::open_partition(keyspace, "b").unwrap();
+    let b = Test::open_partition(b).unwrap();
+
+    a.the_field().set(&1).unwrap();
+    assert_eq!(b.the_field().get().unwrap(), 0);
+}
+
+#[test]
+fn transaction() {
+    let dir = tempdir::TempDir::new("dbstruct_fjall_transaction").unwrap();
+    let db = Test::open_path(dir.path().join("db")).unwrap();
+    db.queue().push_back(&1).unwrap();
+
+    db.transaction(|tx| {
+        let job = tx.queue().pop_front()?.expect("queue is not empty");
+        tx.done().insert(&job, &true)?;
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(db.queue().pop_front().unwrap(), None);
+    assert_eq!(db.done().get(&1).unwrap(), Some(true));
+
+    let res: Result<(), _> = db.transaction(|tx| {
+        tx.primes().push(&2)?;
+        Err(dbstruct::Error::TransactionAborted)
+    });
+    assert!(matches!(res, Err(dbstruct::Error::TransactionAborted)));
+    assert_eq!(db.primes().len(), 0);
+}
@@ -0,0 +1,79 @@
+use dbstruct::stores::Faulty;
+use dbstruct::ByteStore;
+
+#[dbstruct::dbstruct(db=trait)]
+pub struct Test {
+    #[dbstruct(key = 0)]
+    names: HashMap<String, u32>,
+    #[dbstruct(key = 1)]
+    offsets: BTreeMap<i32, u8>,
+    #[dbstruct(key = 2)]
+    id: Option<u64>,
+}
+
+/// A database as written by version 0.6, keys were encoded using bincode
+fn written_by_0_6() -> dbstruct::stores::BTreeMap {
+    let key_config = bincode::config::standard()
+        .with_big_endian()
+        .with_fixed_int_encoding();
+    let val_config = bincode::config::standard();
+    let ds = dbstruct::stores::BTreeMap::new();
+    let insert = |key: Vec<u8>, val: Vec<u8>| {
+        ds.insert(&key, &val).unwrap();
+    };
+    for (name, n) in [("alice", 1u32), ("bob", 2)] {
+        insert(
+            bincode::serde::encode_to_vec((0u8, name), key_config).unwrap(),
+            bincode::serde::encode_to_vec(n, val_config).unwrap(),
+        );
+    }
+    for offset in [3i32, -1, -7] {
+        insert(
+            bincode::serde::encode_to_vec((1u8, offset), key_config).unwrap(),
+            bincode::serde::encode_to_vec(offset.unsigned_abs() as u8, val_config).unwrap(),
+        );
+    }
+    insert(
+        bincode::serde::encode_to_vec(2u8, key_config).unwrap(),
+        bincode::serde::encode_to_vec(42u64, val_config).unwrap(),
+    );
+    ds
+}
+
+fn assert_readable(db: &Test<dbstruct::stores::BTreeMap>) {
+    assert_eq!(db.names().get("alice").unwrap(), Some(1));
+    assert_eq!(db.names().get("bob").unwrap(), Some(2));
+    assert_eq!(db.offsets().first_key_value().unwrap(), Some((-7, 7)));
+    assert_eq!(db.offsets().last_key_value().unwrap(), Some((3, 3)));
+    assert_eq!(db.id().get().unwrap(), Some(42));
+}
+
+#[test]
+fn keys_written_by_0_6_are_upgraded() {
+    let ds = written_by_0_6();
+    let db = Test::open(ds.clone()).unwrap();
+    assert_readable(&db);
+    db.offsets().insert(&-3, &3).unwrap();
+    std::mem::drop(db);
+
+    // already upgraded, reopening changes nothing
+    let db = Test::open(ds).unwrap();
+    assert_readable(&db);
+    assert_eq!(db.offsets().get(&-3).unwrap(), Some(3));
+}
+
+#[test]
+fn interrupted_upgrade_completes_on_open() {
+    for nth in 1.. {
+        let ds = written_by_0_6();
+        let faulty = Faulty::new(ds.clone());
+        faulty.fail_nth(nth);
+        let interrupted = Test::open(faulty).is_err();
+
+        let db = Test::open(ds).unwrap();
+        assert_readable(&db);
+        if !interrupted {
+            break;
+        }
+    }
+}
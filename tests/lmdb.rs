@@ -0,0 +1,138 @@
+#![cfg(feature = "lmdb")]
+
+use dbstruct::migrate::Migration;
+use dbstruct::traits::byte_store;
+
+#[dbstruct::dbstruct(db=lmdb)]
+pub struct Test {
+    #[dbstruct(Default)]
+    the_field: u8,
+    queue: VecDeque<u32>,
+    done: HashMap<u32, bool>,
+    primes: Vec<u32>,
+}
+
+#[test]
+fn wrappers() {
+    let dir = tempdir::TempDir::new("dbstruct_lmdb_wrappers").unwrap();
+    let db = Test::open_path(dir.path().join("db")).unwrap();
+
+    db.the_field().set(&8).unwrap();
+    assert_eq!(db.the_field().get().unwrap(), 8);
+
+    db.queue().push_back(&1).unwrap();
+    db.queue().push_front(&0).unwrap();
+    assert_eq!(db.queue().pop_front().unwrap(), Some(0));
+    assert_eq!(db.queue().pop_back().unwrap(), Some(1));
+    assert_eq!(db.queue().pop_back().unwrap(), None);
+
+    db.done().insert(&1, &true).unwrap();
+    assert_eq!(db.done().get(&1).unwrap(), Some(true));
+    assert_eq!(db.done().get(&2).unwrap(), None);
+}
+
+#[test]
+fn persistance() {
+    let dir = tempdir::TempDir::new("dbstruct_lmdb_persistence").unwrap();
+    let path = dir.path().join("db");
+
+    let db = Test::open_path(&path).unwrap();
+    db.the_field().set(&8).unwrap();
+    db.primes().extend(&[2, 3, 5, 7]).unwrap();
+
+    std::mem::drop(db);
+    let db = Test::open_path(&path).unwrap();
+    assert_eq!(8u8, db.the_field().get().unwrap());
+    assert_eq!(db.primes().len(), 4);
+    assert_eq!(Some(7), db.primes().pop().unwrap());
+}
+
+#[test]
+fn structs_share_an_env() {
+    let dir = tempdir::TempDir::new("dbstruct_lmdb_shared").unwrap();
+    let env = dbstruct::stores::Lmdb::open_path(dir.path().join("db"))
+        .unwrap()
+        .env()
+        .clone();
+
+    let a = Test::open_env(env.clone()).unwrap();
+    let b = dbstruct::stores::Lmdb::open_database(env, "b").unwrap();
+    let b = Test::open_database(b).unwrap();
+
+    a.the_field().set(&1).unwrap();
+    assert_eq!(b.the_field().get().unwrap(), 0);
+}
+
+#[test]
+fn transaction() {
+    let dir = tempdir::TempDir::new("dbstruct_lmdb_transaction").unwrap();
+    let db = Test::open_path(dir.path().join("db")).unwrap();
+    db.queue().push_back(&1).unwrap();
+
+    db.transaction(|tx| {
+        let job = tx.queue().pop_front()?.expect("queue is not empty");
+        tx.done().insert(&job, &true)?;
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(db.queue().pop_front().unwrap(), None);
+    assert_eq!(db.done().get(&1).unwrap(), Some(true));
+
+    let res: Result<(), _> = db.transaction(|tx| {
+        tx.primes().push(&2)?;
+        Err(dbstruct::Error::TransactionAborted)
+    });
+    assert!(matches!(res, Err(dbstruct::Error::TransactionAborted)));
+    assert_eq!(db.primes().len(), 0);
+}
+
+mod v0 {
+    #[dbstruct::dbstruct(db=lmdb)]
+    pub struct Scores {
+        pub scores: HashMap<String, u16>,
+        pub best: Option<u16>,
+        pub history: Vec<u16>,
+    }
+}
+
+/// The scores grow to u32
+fn to_v1<DS: byte_store::Ordered>(
+    migration: &Migration<DS>,
+) -> Result<(), dbstruct::Error<<DS as dbstruct::ByteStore>::DbError>> {
+    migration.transform_values(0, |score: u16| score as u32)?;
+    migration.transform_values(1, |best: u16| best as u32)?;
+    migration.transform_values(2, |score: u16| score as u32)
+}
+
+mod v1 {
+    use super::to_v1;
+
+    #[dbstruct::dbstruct(db=lmdb, version = 1, migrations = [to_v1])]
+    pub struct Scores {
+        pub scores: HashMap<String, u32>,
+        pub best: Option<u32>,
+        pub history: Vec<u32>,
+    }
+}
+
+#[test]
+fn migration() {
+    let dir = tempdir::TempDir::new("dbstruct_lmdb_migration").unwrap();
+    let path = dir.path().join("db");
+
+    let db = v0::Scores::open_path(&path).unwrap();
+    for (name, score) in [("ada", 7), ("bob", 3), ("eve", 9)] {
+        db.scores().insert(name, &score).unwrap();
+    }
+    db.best().set(Some(&9)).unwrap();
+    db.history().extend(&(0..200).collect::<Vec<_>>()).unwrap();
+    std::mem::drop(db);
+
+    let db = v1::Scores::open_path(&path).unwrap();
+    assert_eq!(db.scores().get("ada").unwrap(), Some(7));
+    assert_eq!(db.scores().get("eve").unwrap(), Some(9));
+    assert_eq!(db.scores().len().unwrap(), 3);
+    assert_eq!(db.best().get().unwrap(), Some(9));
+    assert_eq!(db.history().len(), 200);
+    assert_eq!(db.history().pop().unwrap(), Some(199));
+}
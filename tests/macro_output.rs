@@ -1,7 +1,5 @@
 use std::error::Error;
-use std::marker::PhantomData;
-use std::sync::atomic::AtomicUsize;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use dbstruct::traits::data_store;
 use dbstruct::traits::DataStore;
@@ -38,8 +36,7 @@ struct MacroInput {
 // note the macro would use absolute paths for everything
 pub struct MacroOutput<DS: DataStore> {
     ds: DS,
-    queue_len: Arc<AtomicUsize>,
-    phantom: PhantomData<::std::cell::Cell<()>>,
+    queue_len: Arc<Mutex<usize>>,
 }
 
 impl<DS> MacroOutput<DS>
@@ -57,8 +54,7 @@ where
         tracing::debug!("opening vector queue with len: {queue_len}");
         Ok(Self {
             ds,
-            queue_len: Arc::new(AtomicUsize::new(queue_len)),
-            phantom: PhantomData,
+            queue_len: Arc::new(Mutex::new(queue_len)),
         })
     }
 
@@ -5,6 +5,7 @@ pub struct Test {
     range: HashMap<Range<u32>, u32>,
     to_digit: HashMap<String, u32>,
     plus10: HashMap<u8, u8>,
+    logins: HashMap<(String, u64), bool>,
 }
 
 #[test]
@@ -43,3 +44,34 @@ fn update_rng_key() {
 
     assert_eq!(test.range().get(&key).unwrap(), Some(10));
 }
+
+#[test]
+fn iterate_part_of_the_map() {
+    let test = Test::new().unwrap();
+    for key in [5, 1, 4, 2, 3] {
+        test.plus10().insert(&key, &(key + 10)).unwrap();
+    }
+    for (user, time) in [("ann", 20), ("bob", 15), ("ann", 10), ("annie", 5)] {
+        test.logins()
+            .insert(&(user.to_owned(), time), &true)
+            .unwrap();
+    }
+
+    let reversed: Vec<u8> = test.plus10().keys().rev().map(Result::unwrap).collect();
+    assert_eq!(reversed, [5, 4, 3, 2, 1]);
+    let middle: Vec<u8> = test
+        .plus10()
+        .range(2..=4)
+        .unwrap()
+        .map(|res| res.unwrap().0)
+        .collect();
+    assert_eq!(middle, [2, 3, 4]);
+
+    let ann: Vec<u64> = test
+        .logins()
+        .prefix("ann")
+        .unwrap()
+        .map(|res| res.unwrap().0 .1)
+        .collect();
+    assert_eq!(ann, [10, 20]);
+}
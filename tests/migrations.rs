@@ -0,0 +1,101 @@
+use dbstruct::migrate::Migration;
+use dbstruct::stores::{BTreeMap, Faulty};
+use dbstruct::traits::byte_store;
+
+mod v0 {
+    #[dbstruct::dbstruct(db=trait)]
+    pub struct Test {
+        #[dbstruct(key = 0)]
+        pub id: Option<u32>,
+        #[dbstruct(key = 1)]
+        pub scores: Vec<u16>,
+        #[dbstruct(key = 2)]
+        pub unused: Option<bool>,
+    }
+}
+
+type Result<DS> = std::result::Result<(), dbstruct::Error<<DS as dbstruct::ByteStore>::DbError>>;
+
+/// the id becomes a name
+fn to_v1<DS: byte_store::Ordered>(migration: &Migration<DS>) -> Result<DS> {
+    migration.transform_values(0, |id: u32| format!("user {id}"))
+}
+
+/// scores grow to u32 and move to key 3, unused is dropped
+fn to_v2<DS: byte_store::Ordered>(migration: &Migration<DS>) -> Result<DS> {
+    migration.transform_values(1, |score: u16| score as u32)?;
+    migration.move_prefix(1, 3)?;
+    migration.drop_prefix(2)
+}
+
+mod v2 {
+    use super::{to_v1, to_v2};
+
+    #[dbstruct::dbstruct(db=trait, version = 2, migrations = [to_v1, to_v2])]
+    pub struct Test {
+        #[dbstruct(key = 0)]
+        pub name: Option<String>,
+        #[dbstruct(key = 3)]
+        pub scores: Vec<u32>,
+    }
+}
+
+#[test]
+fn migrate_from_unversioned() {
+    let ds = dbstruct::stores::BTreeMap::new();
+    let db = v0::Test::open(ds.clone()).unwrap();
+    db.id().set(Some(&42)).unwrap();
+    db.scores().extend([1, 2, 3].iter()).unwrap();
+    db.unused().set(Some(&true)).unwrap();
+    std::mem::drop(db);
+
+    let db = v2::Test::open(ds.clone()).unwrap();
+    assert_eq!(db.name().get().unwrap(), Some("user 42".to_owned()));
+    assert_eq!(db.scores().len(), 3);
+    assert_eq!(db.scores().pop().unwrap(), Some(3u32));
+    std::mem::drop(db);
+
+    // already migrated, reopening changes nothing
+    let db = v2::Test::open(ds.clone()).unwrap();
+    assert_eq!(db.name().get().unwrap(), Some("user 42".to_owned()));
+    assert_eq!(db.scores().len(), 2);
+    std::mem::drop(db);
+
+    let Err(err) = v0::Test::open(ds) else {
+        panic!("opening with an older version should fail");
+    };
+    assert!(matches!(
+        err,
+        dbstruct::Error::NewerVersion {
+            stored: 2,
+            compiled: 0
+        }
+    ));
+}
+
+fn filled_v0() -> BTreeMap {
+    let ds = BTreeMap::new();
+    let db = v0::Test::open(ds.clone()).unwrap();
+    db.id().set(Some(&42)).unwrap();
+    db.scores().extend([1, 2, 3].iter()).unwrap();
+    db.unused().set(Some(&true)).unwrap();
+    ds
+}
+
+#[test]
+fn interrupted_migration_completes_on_open() {
+    for nth in 1.. {
+        let ds = filled_v0();
+        let faulty = Faulty::new(ds.clone());
+        faulty.fail_nth(nth);
+        let interrupted = v2::Test::open(faulty).is_err();
+
+        let db = v2::Test::open(ds).unwrap();
+        assert_eq!(db.name().get().unwrap(), Some("user 42".to_owned()));
+        assert_eq!(db.scores().len(), 3, "failed at operation {nth}");
+        assert_eq!(db.scores().pop().unwrap(), Some(3u32));
+        if !interrupted {
+            break;
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+
+#[dbstruct::dbstruct(db=sled)]
+pub struct Test {
+    before: Option<u8>,
+    events: BTreeMap<u64, String>,
+    offsets: BTreeMap<i32, u8>,
+    names: BTreeSet<String>,
+    #[dbstruct(Default)]
+    after: u8,
+}
+
+#[test]
+fn entries_persist() {
+    let dir = tempdir::TempDir::new("dbstruct_ordered_map").unwrap();
+    let sled = dbstruct::sled::open(dir.path().join("db")).unwrap();
+
+    let db = Test::open_db(sled.clone()).unwrap();
+    db.events().insert(&300, "stopped").unwrap();
+    db.events().insert(&100, "started").unwrap();
+    std::mem::drop(db);
+
+    let db = Test::open_db(sled).unwrap();
+    let first = db.events().first_key_value().unwrap();
+    assert_eq!(first, Some((100, "started".to_owned())));
+    let last = db.events().last_key_value().unwrap();
+    assert_eq!(last, Some((300, "stopped".to_owned())));
+}
+
+#[test]
+fn ordered_map_matches_std() {
+    let dir = tempdir::TempDir::new("dbstruct_ordered_map_std").unwrap();
+    let db = Test::open_path(dir.path().join("db")).unwrap();
+    db.before().set(Some(&1)).unwrap();
+    db.after().set(&2).unwrap();
+
+    let mut std_map = BTreeMap::new();
+    for time in [5, 260, 1, 70_000, 9, 256] {
+        let event = format!("event at {time}");
+        db.events().insert(&time, &event).unwrap();
+        std_map.insert(time, event);
+    }
+
+    let all: Vec<_> = db.events().iter().map(Result::unwrap).collect();
+    assert_eq!(all, std_map.clone().into_iter().collect::<Vec<_>>());
+    let reversed: Vec<_> = db.events().keys().rev().map(Result::unwrap).collect();
+    assert_eq!(reversed, std_map.keys().rev().copied().collect::<Vec<_>>());
+
+    for range in [(9..260), (0..9), (256..u64::MAX)] {
+        let found: Vec<_> = db.events().range(range.clone()).unwrap().collect();
+        let found: Vec<_> = found.into_iter().map(Result::unwrap).collect();
+        let expected: Vec<_> = std_map.range(range).map(|(k, v)| (*k, v.clone())).collect();
+        assert_eq!(found, expected);
+    }
+
+    assert_eq!(db.events().pop_first().unwrap(), std_map.pop_first());
+    assert_eq!(db.events().pop_last().unwrap(), std_map.pop_last());
+    let left: Vec<_> = db.events().values().map(Result::unwrap).collect();
+    assert_eq!(left, std_map.into_values().collect::<Vec<_>>());
+
+    db.events().clear().unwrap();
+    assert!(db.events().is_empty().unwrap());
+    assert_eq!(db.events().last_key_value().unwrap(), None);
+    assert_eq!(db.before().get().unwrap(), Some(1));
+    assert_eq!(db.after().get().unwrap(), 2);
+}
+
+#[test]
+fn keys_keep_their_order() {
+    let dir = tempdir::TempDir::new("dbstruct_ordered_map_order").unwrap();
+    let db = Test::open_path(dir.path().join("db")).unwrap();
+
+    let offsets = [-70_000, 3, -1, 0, i32::MIN, 256, i32::MAX];
+    for (i, offset) in offsets.iter().enumerate() {
+        db.offsets().insert(offset, &(i as u8)).unwrap();
+    }
+    let mut sorted = offsets;
+    sorted.sort();
+    let keys: Vec<_> = db.offsets().keys().map(Result::unwrap).collect();
+    assert_eq!(keys, sorted);
+    let first = db.offsets().pop_first().unwrap().unwrap();
+    assert_eq!(first, (i32::MIN, 4));
+    let found: Vec<_> = db.offsets().range(-1..=3).unwrap().collect();
+    let found: Vec<_> = found.into_iter().map(|e| e.unwrap().0).collect();
+    assert_eq!(found, [-1, 0, 3]);
+
+    let names = ["bob", "alice", "al", "", "zoë", "b"];
+    db.names().extend(names).unwrap();
+    let stored: Vec<_> = db.names().iter().map(Result::unwrap).collect();
+    let mut sorted = names.map(str::to_owned);
+    sorted.sort();
+    assert_eq!(stored, sorted);
+}
@@ -3,6 +3,21 @@ pub struct Test {
     #[dbstruct(Default)]
     the_field: u8,
     primes: Vec<u32>,
+    #[dbstruct(counted)]
+    sessions: HashMap<u64, String>,
+    #[dbstruct(counted)]
+    seen: HashSet<u64>,
+}
+
+mod uncounted {
+    #[dbstruct::dbstruct(db=sled)]
+    pub struct Test {
+        #[dbstruct(Default)]
+        pub the_field: u8,
+        pub primes: Vec<u32>,
+        pub sessions: HashMap<u64, String>,
+        pub seen: HashSet<u64>,
+    }
 }
 
 #[test]
@@ -42,3 +57,91 @@ fn clear_persistence() {
     db.primes().push(&2).unwrap();
     assert_eq!(Some(2), db.primes().pop().unwrap());
 }
+
+#[test]
+fn count_persistence() {
+    let dir = tempdir::TempDir::new("dbstruct_count_persistence").unwrap();
+    let path = dir.path().join("db");
+    let db = Test::open_path(&path).unwrap();
+
+    db.sessions().insert(&1, "alice").unwrap();
+    db.sessions().insert(&2, "bob").unwrap();
+    db.sessions().insert(&3, "carol").unwrap();
+    db.sessions().remove(&2).unwrap();
+    db.seen().extend(&[1, 2, 3]).unwrap();
+    db.seen().insert(&3).unwrap();
+    db.seen().remove(&1).unwrap();
+
+    std::mem::drop(db);
+    let db = Test::open_path(&path).unwrap();
+    assert_eq!(2, db.sessions().len().unwrap());
+    assert_eq!(2, db.seen().len().unwrap());
+}
+
+#[test]
+fn count_recovered_when_missing() {
+    let dir = tempdir::TempDir::new("dbstruct_count_recovered").unwrap();
+    let path = dir.path().join("db");
+    let db = uncounted::Test::open_path(&path).unwrap();
+
+    db.primes().push(&2).unwrap();
+    db.sessions().insert(&1, "alice").unwrap();
+    db.sessions().insert(&2, "bob").unwrap();
+    db.seen().insert(&7).unwrap();
+
+    std::mem::drop(db);
+    let db = Test::open_path(&path).unwrap();
+    assert_eq!(2, db.sessions().len().unwrap());
+    db.sessions().insert(&3, "carol").unwrap();
+    assert_eq!(3, db.sessions().len().unwrap());
+    assert_eq!(1, db.seen().len().unwrap());
+    db.seen().clear().unwrap();
+    assert_eq!(0, db.seen().len().unwrap());
+}
+
+#[test]
+fn count_recovered_after_uncounted_writes() {
+    let dir = tempdir::TempDir::new("dbstruct_count_uncounted_writes").unwrap();
+    let path = dir.path().join("db");
+    let db = Test::open_path(&path).unwrap();
+    db.sessions().insert(&1, "alice").unwrap();
+    db.seen().insert(&1).unwrap();
+
+    std::mem::drop(db);
+    let db = uncounted::Test::open_path(&path).unwrap();
+    db.sessions().insert(&2, "bob").unwrap();
+    db.seen().remove(&1).unwrap();
+
+    std::mem::drop(db);
+    let db = Test::open_path(&path).unwrap();
+    assert_eq!(2, db.sessions().len().unwrap());
+    assert_eq!(0, db.seen().len().unwrap());
+}
+
+mod snapshot {
+    #[dbstruct::dbstruct(db=btreemap)]
+    pub struct Test {
+        #[dbstruct(Default)]
+        pub the_field: u8,
+        pub primes: Vec<u32>,
+    }
+}
+
+#[test]
+fn snapshot_persistence() {
+    let dir = tempdir::TempDir::new("dbstruct_snapshot_persistence").unwrap();
+    let path = dir.path().join("snapshot");
+
+    let db = snapshot::Test::open_file(&path).unwrap();
+    assert_eq!(0, db.primes().len());
+    db.the_field().set(&8).unwrap();
+    db.primes().extend(&[2, 3, 5, 7]).unwrap();
+    db.save(&path).unwrap();
+
+    db.primes().push(&11).unwrap();
+    std::mem::drop(db);
+    let db = snapshot::Test::open_file(&path).unwrap();
+    assert_eq!(8u8, db.the_field().get().unwrap());
+    assert_eq!(4, db.primes().len());
+    assert_eq!(Some(7), db.primes().pop().unwrap());
+}
@@ -0,0 +1,36 @@
+mod v1 {
+    #[dbstruct::dbstruct(db=trait)]
+    pub struct Test {
+        #[dbstruct(key = 0)]
+        pub counter: Option<u32>,
+        pub names: Vec<String>,
+    }
+}
+
+mod v2 {
+    /// Same as v1 but with a new field that sorts before all other fields
+    #[dbstruct::dbstruct(db=trait)]
+    pub struct Test {
+        #[dbstruct(Default)]
+        pub added: u8,
+        #[dbstruct(key = 0)]
+        pub counter: Option<u32>,
+        #[dbstruct(key = 1)]
+        pub names: Vec<String>,
+    }
+}
+
+#[test]
+fn adding_field_keeps_data() {
+    let ds = dbstruct::stores::BTreeMap::new();
+
+    let db = v1::Test::open(ds.clone()).unwrap();
+    db.counter().set(Some(&42)).unwrap();
+    db.names().push("alice").unwrap();
+    std::mem::drop(db);
+
+    let db = v2::Test::open(ds).unwrap();
+    assert_eq!(Some(42), db.counter().get().unwrap());
+    assert_eq!(Some("alice".to_owned()), db.names().pop().unwrap());
+    assert_eq!(0, db.added().get().unwrap());
+}
@@ -0,0 +1,112 @@
+use dbstruct::stores::ReadOnlyError;
+
+mod writer {
+    #[dbstruct::dbstruct(db=sled)]
+    pub struct Test {
+        #[dbstruct(Default)]
+        pub the_field: u8,
+        pub name: Option<String>,
+        pub queue: VecDeque<u32>,
+        pub done: HashMap<u32, bool>,
+        #[dbstruct(counted)]
+        pub seen: HashSet<u32>,
+        #[dbstruct(counted)]
+        pub events: BTreeMap<u64, String>,
+        pub primes: Vec<u32>,
+    }
+}
+
+mod reader {
+    #[dbstruct::dbstruct(db=sled, readonly)]
+    pub struct Test {
+        #[dbstruct(Default)]
+        pub the_field: u8,
+        pub name: Option<String>,
+        pub queue: VecDeque<u32>,
+        pub done: HashMap<u32, bool>,
+        #[dbstruct(counted)]
+        pub seen: HashSet<u32>,
+        #[dbstruct(counted)]
+        pub events: BTreeMap<u64, String>,
+        pub primes: Vec<u32>,
+    }
+}
+
+#[test]
+fn reads_what_was_written() {
+    let dir = tempdir::TempDir::new("dbstruct_read_only").unwrap();
+    let sled = dbstruct::sled::open(dir.path().join("db")).unwrap();
+
+    let db = writer::Test::open_db(sled.clone()).unwrap();
+    db.the_field().set(&8).unwrap();
+    db.name().set(Some("analytics")).unwrap();
+    db.queue().push_back(&1).unwrap();
+    db.queue().push_back(&2).unwrap();
+    db.done().insert(&1, &true).unwrap();
+    db.seen().insert(&4).unwrap();
+    db.events().insert(&1, "started").unwrap();
+    db.events().insert(&2, "stopped").unwrap();
+    db.primes().extend(&[2, 3, 5]).unwrap();
+
+    let db = reader::Test::open_db(sled).unwrap();
+    assert_eq!(db.the_field().get().unwrap(), 8);
+    assert_eq!(db.name().get().unwrap().as_deref(), Some("analytics"));
+    assert_eq!(db.queue().len(), 2);
+    assert_eq!(db.queue().get(1).unwrap(), Some(2));
+    assert_eq!(db.done().get(&1).unwrap(), Some(true));
+    let done: Vec<_> = db.done().iter().map(Result::unwrap).collect();
+    assert_eq!(done, vec![(1, true)]);
+    assert!(db.seen().contains(&4).unwrap());
+    assert_eq!(db.seen().len().unwrap(), 1);
+    let last = db.events().last_key_value().unwrap();
+    assert_eq!(last, Some((2, "stopped".to_owned())));
+    assert_eq!(db.events().len().unwrap(), 2);
+    let since: Vec<_> = db
+        .events()
+        .range(2..)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(since, vec![(2, "stopped".to_owned())]);
+    let primes: Vec<_> = db.primes().iter().map(Result::unwrap).collect();
+    assert_eq!(primes, vec![2, 3, 5]);
+}
+
+#[test]
+fn empty_database_is_refused() {
+    let dir = tempdir::TempDir::new("dbstruct_read_only_empty").unwrap();
+    let res = reader::Test::open_path(dir.path().join("db"));
+    assert!(matches!(
+        res,
+        Err(dbstruct::Error::Database(ReadOnlyError::ReadOnly))
+    ));
+}
+
+#[cfg(feature = "encryption")]
+mod encrypted {
+    use dbstruct::stores::EncryptionKey;
+
+    #[dbstruct::dbstruct(db=btreemap, encrypt)]
+    pub struct Writer {
+        primes: Vec<u32>,
+    }
+
+    #[dbstruct::dbstruct(db=btreemap, encrypt, readonly)]
+    pub struct Reader {
+        primes: Vec<u32>,
+    }
+
+    #[test]
+    fn snapshot() {
+        let dir = tempdir::TempDir::new("dbstruct_read_only_encrypted").unwrap();
+        let path = dir.path().join("snapshot");
+        let key = EncryptionKey::generate();
+
+        let db = Writer::new(&key).unwrap();
+        db.primes().extend(&[2, 3, 5]).unwrap();
+        db.save(&path).unwrap();
+
+        let db = Reader::open_file(&path, &key).unwrap();
+        assert_eq!(db.primes().get(2).unwrap(), Some(5));
+    }
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "rocksdb")]
+
+#[dbstruct::dbstruct(db=rocksdb)]
+pub struct Test {
+    #[dbstruct(Default)]
+    the_field: u8,
+    queue: VecDeque<u32>,
+    done: HashMap<u32, bool>,
+    primes: Vec<u32>,
+}
+
+#[test]
+fn wrappers() {
+    let dir = tempdir::TempDir::new("dbstruct_rocksdb_wrappers").unwrap();
+    let db = Test::open_path(dir.path()).unwrap();
+
+    db.the_field().set(&8).unwrap();
+    assert_eq!(db.the_field().get().unwrap(), 8);
+
+    db.queue().push_back(&1).unwrap();
+    db.queue().push_front(&0).unwrap();
+    assert_eq!(db.queue().pop_front().unwrap(), Some(0));
+    assert_eq!(db.queue().pop_back().unwrap(), Some(1));
+    assert_eq!(db.queue().pop_back().unwrap(), None);
+
+    db.done().insert(&1, &true).unwrap();
+    assert_eq!(db.done().get(&1).unwrap(), Some(true));
+    assert_eq!(db.done().get(&2).unwrap(), None);
+}
+
+#[test]
+fn persistance() {
+    let dir = tempdir::TempDir::new("dbstruct_rocksdb_persistence").unwrap();
+    let path = dir.path().join("db");
+
+    let db = Test::open_path(&path).unwrap();
+    db.the_field().set(&8).unwrap();
+    db.primes().extend(&[2, 3, 5, 7]).unwrap();
+
+    std::mem::drop(db);
+    let db = Test::open_path(&path).unwrap();
+    assert_eq!(8u8, db.the_field().get().unwrap());
+    assert_eq!(db.primes().len(), 4);
+    assert_eq!(Some(7), db.primes().pop().unwrap());
+}
+
+#[test]
+fn structs_share_a_database() {
+    let dir = tempdir::TempDir::new("dbstruct_rocksdb_shared").unwrap();
+    let database = dbstruct::stores::RocksDb::open_database(dir.path()).unwrap();
+
+    let a = Test::open_db(database.clone()).unwrap();
+    let cf = dbstruct::stores::RocksDb::open_cf(database, "other").unwrap();
+    let b = Test::open_cf(cf).unwrap();
+
+    a.the_field().set(&1).unwrap();
+    assert_eq!(b.the_field().get().unwrap(), 0);
+}
+
+#[test]
+fn transaction() {
+    let dir = tempdir::TempDir::new("dbstruct_rocksdb_transaction").unwrap();
+    let db = Test::open_path(dir.path()).unwrap();
+    db.queue().push_back(&1).unwrap();
+
+    db.transaction(|tx| {
+        let job = tx.queue().pop_front()?.expect("queue is not empty");
+        tx.done().insert(&job, &true)?;
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(db.queue().pop_front().unwrap(), None);
+    assert_eq!(db.done().get(&1).unwrap(), Some(true));
+
+    let res: Result<(), _> = db.transaction(|tx| {
+        tx.primes().push(&2)?;
+        Err(dbstruct::Error::TransactionAborted)
+    });
+    assert!(res.is_err());
+    assert_eq!(db.primes().len(), 0);
+}
@@ -0,0 +1,89 @@
+mod v1 {
+    #[dbstruct::dbstruct(db=trait)]
+    pub struct Test {
+        pub name: Option<String>,
+    }
+}
+
+mod v2 {
+    #[dbstruct::dbstruct(db=trait)]
+    pub struct Test {
+        pub name: Option<u32>,
+    }
+}
+
+mod v3 {
+    #[dbstruct::dbstruct(db=trait)]
+    pub struct Test {
+        pub age: Option<u32>,
+        pub name: Option<String>,
+    }
+}
+
+mod v4 {
+    #[dbstruct::dbstruct(db=trait)]
+    pub struct Test {
+        pub name: Option<std::string::String>,
+    }
+}
+
+#[test]
+fn changed_type_is_reported() {
+    let ds = dbstruct::stores::BTreeMap::new();
+    let db = v1::Test::open(ds.clone()).unwrap();
+    db.name().set(Some("Elijah")).unwrap();
+    std::mem::drop(db);
+
+    let Err(err) = v2::Test::open(ds) else {
+        panic!("opening with a different type should fail");
+    };
+    assert!(matches!(err, dbstruct::Error::SchemaMismatch(_)));
+}
+
+#[test]
+fn module_path_is_ignored() {
+    let ds = dbstruct::stores::BTreeMap::new();
+    let db = v1::Test::open(ds.clone()).unwrap();
+    db.name().set(Some("Elijah")).unwrap();
+    std::mem::drop(db);
+
+    let db = v4::Test::open(ds).unwrap();
+    assert_eq!(db.name().get().unwrap(), Some("Elijah".to_owned()));
+}
+
+#[test]
+fn moved_field_is_reported() {
+    let ds = dbstruct::stores::BTreeMap::new();
+    let db = v1::Test::open(ds.clone()).unwrap();
+    std::mem::drop(db);
+
+    // age sorts before name, shifting its key
+    let Err(err) = v3::Test::open(ds) else {
+        panic!("opening with shifted keys should fail");
+    };
+    assert!(matches!(err, dbstruct::Error::SchemaMismatch(_)));
+}
+
+#[dbstruct::dbstruct(db=trait)]
+pub struct Neighbours {
+    a: Option<u8>,
+    b: Vec<u8>,
+    c: VecDeque<u8>,
+    #[dbstruct(Default)]
+    d: u16,
+}
+
+#[test]
+fn empty_collections_next_to_other_fields() {
+    let ds = dbstruct::stores::BTreeMap::new();
+    let db = Neighbours::open(ds.clone()).unwrap();
+    db.a().set(Some(&1)).unwrap();
+    db.d().set(&1).unwrap();
+    std::mem::drop(db);
+
+    let db = Neighbours::open(ds).unwrap();
+    assert_eq!(db.b().len(), 0);
+    assert_eq!(db.c().len(), 0);
+    db.c().push_back(&3).unwrap();
+    assert_eq!(db.c().pop_front().unwrap(), Some(3));
+}
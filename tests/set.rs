@@ -0,0 +1,46 @@
+use std::collections::{BTreeSet, HashSet};
+
+#[dbstruct::dbstruct(db=sled)]
+pub struct Test {
+    seen: HashSet<u64>,
+    tags: BTreeSet<String>,
+}
+
+#[test]
+fn elements_persist() {
+    let dir = tempdir::TempDir::new("dbstruct_set").unwrap();
+    let sled = dbstruct::sled::open(dir.path().join("db")).unwrap();
+
+    let db = Test::open_db(sled.clone()).unwrap();
+    db.seen().extend(&[1, 2, 3]).unwrap();
+    db.tags().insert("urgent").unwrap();
+    db.tags().insert("backend").unwrap();
+    std::mem::drop(db);
+
+    let db = Test::open_db(sled).unwrap();
+    assert_eq!(db.seen().len().unwrap(), 3);
+    assert!(db.seen().contains(&2).unwrap());
+    let tags: BTreeSet<String> = db.tags().iter().map(Result::unwrap).collect();
+    let expected: BTreeSet<String> = ["backend", "urgent"].map(str::to_owned).into();
+    assert_eq!(tags, expected);
+}
+
+#[test]
+fn set_matches_std() {
+    let dir = tempdir::TempDir::new("dbstruct_set_std").unwrap();
+    let db = Test::open_path(dir.path().join("db")).unwrap();
+
+    let mut std_set = HashSet::new();
+    for id in [5, 1, 5, 9, 1, 3] {
+        assert_eq!(db.seen().insert(&id).unwrap(), std_set.insert(id));
+    }
+    for id in [1, 2] {
+        assert_eq!(db.seen().remove(&id).unwrap(), std_set.remove(&id));
+    }
+    let seen: HashSet<u64> = db.seen().iter().map(Result::unwrap).collect();
+    assert_eq!(seen, std_set);
+
+    db.seen().clear().unwrap();
+    assert!(db.seen().is_empty().unwrap());
+    assert!(!db.tags().contains("urgent").unwrap());
+}
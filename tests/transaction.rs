@@ -0,0 +1,125 @@
+mod btreemap {
+    #[dbstruct::dbstruct(db=btreemap)]
+    pub struct Test {
+        pub queue: VecDeque<u32>,
+        #[dbstruct(counted)]
+        pub done: HashMap<u32, bool>,
+        pub log: Vec<u32>,
+    }
+}
+
+mod sled {
+    #[dbstruct::dbstruct(db=sled)]
+    pub struct Test {
+        pub queue: VecDeque<u32>,
+        pub done: HashMap<u32, bool>,
+        pub log: Vec<u32>,
+    }
+}
+
+#[test]
+fn btreemap_commit() {
+    let db = btreemap::Test::new().unwrap();
+    db.queue().push_back(&1).unwrap();
+    db.queue().push_back(&2).unwrap();
+
+    let moved = db
+        .transaction(|tx| {
+            let job = tx.queue().pop_front()?.expect("queue is not empty");
+            tx.done().insert(&job, &true)?;
+            tx.log().push(&job)?;
+            Ok(job)
+        })
+        .unwrap();
+
+    assert_eq!(moved, 1);
+    assert_eq!(db.queue().pop_front().unwrap(), Some(2));
+    assert_eq!(db.done().get(&1).unwrap(), Some(true));
+    assert_eq!(db.done().len().unwrap(), 1);
+    assert_eq!(db.log().len(), 1);
+    assert_eq!(db.log().get(0).unwrap(), Some(1));
+}
+
+#[test]
+fn btreemap_abort() {
+    let db = btreemap::Test::new().unwrap();
+    db.queue().push_back(&1).unwrap();
+
+    let res: Result<(), _> = db.transaction(|tx| {
+        let job = tx.queue().pop_front()?.expect("queue is not empty");
+        tx.done().insert(&job, &true)?;
+        tx.log().push(&job)?;
+        Err(dbstruct::Error::TransactionAborted)
+    });
+
+    assert!(matches!(res, Err(dbstruct::Error::TransactionAborted)));
+    assert_eq!(db.done().get(&1).unwrap(), None);
+    assert_eq!(db.done().len().unwrap(), 0);
+    assert!(db.log().is_empty());
+    assert_eq!(db.queue().pop_front().unwrap(), Some(1));
+    assert_eq!(db.queue().pop_front().unwrap(), None);
+}
+
+#[test]
+fn btreemap_iterate_in_transaction() {
+    let db = btreemap::Test::new().unwrap();
+    db.done().insert(&1, &true).unwrap();
+    db.done().insert(&3, &true).unwrap();
+
+    let (keys, reversed) = db
+        .transaction(|tx| {
+            tx.done().insert(&2, &false)?;
+            tx.done().remove(&3)?;
+            let keys: Vec<u32> = tx.done().keys().collect::<Result<_, _>>()?;
+            let reversed: Vec<u32> = tx.done().keys().rev().collect::<Result<_, _>>()?;
+            Ok((keys, reversed))
+        })
+        .unwrap();
+
+    assert_eq!(keys, [1, 2]);
+    assert_eq!(reversed, [2, 1]);
+    assert_eq!(db.done().get(&3).unwrap(), None);
+}
+
+#[test]
+fn sled_commit() {
+    let dir = tempdir::TempDir::new("dbstruct_sled_commit").unwrap();
+    let db = sled::Test::open_path(dir.path().join("db")).unwrap();
+    db.queue().push_back(&1).unwrap();
+    db.queue().push_back(&2).unwrap();
+
+    let moved = db
+        .transaction(|tx| {
+            let job = tx.queue().pop_front()?.expect("queue is not empty");
+            tx.done().insert(&job, &true)?;
+            tx.log().push(&job)?;
+            Ok(job)
+        })
+        .unwrap();
+
+    assert_eq!(moved, 1);
+    assert_eq!(db.queue().pop_front().unwrap(), Some(2));
+    assert_eq!(db.done().get(&1).unwrap(), Some(true));
+    assert_eq!(db.log().len(), 1);
+    assert_eq!(db.log().get(0).unwrap(), Some(1));
+}
+
+#[test]
+fn sled_abort() {
+    let dir = tempdir::TempDir::new("dbstruct_sled_abort").unwrap();
+    let db = sled::Test::open_path(dir.path().join("db")).unwrap();
+    db.queue().push_back(&1).unwrap();
+
+    let res: Result<(), _> = db.transaction(|tx| {
+        let job = tx.queue().pop_front()?.expect("queue is not empty");
+        tx.done().insert(&job, &true)?;
+        tx.log().push(&job)?;
+        Err(dbstruct::Error::TransactionAborted)
+    });
+
+    assert!(matches!(res, Err(dbstruct::Error::TransactionAborted)));
+    assert_eq!(db.done().get(&1).unwrap(), None);
+    assert!(db.log().is_empty());
+    assert_eq!(db.queue().pop_front().unwrap(), Some(1));
+    assert_eq!(db.queue().pop_front().unwrap(), None);
+}
@@ -0,0 +1,9 @@
+use dbstruct::dbstruct;
+
+#[dbstruct(db=btreemap)]
+struct Test {
+    #[dbstruct(counted)]
+    field: Vec<u8>,
+}
+
+fn main() {}
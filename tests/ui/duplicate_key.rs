@@ -0,0 +1,11 @@
+use dbstruct::dbstruct;
+
+#[dbstruct(db=btreemap)]
+struct Test {
+    #[dbstruct(key = 1)]
+    first: Option<u8>,
+    #[dbstruct(Default, key = 1)]
+    second: u8,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+#![deny(deprecated)]
+use dbstruct::dbstruct;
+
+#[dbstruct(db=hashmap)]
+struct Test {
+    #[dbstruct(Default)]
+    field: u8,
+}
+
+fn main() {}
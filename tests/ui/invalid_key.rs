@@ -0,0 +1,9 @@
+use dbstruct::dbstruct;
+
+#[dbstruct(db=btreemap)]
+struct Test {
+    #[dbstruct(key = 256)]
+    field: Option<u8>,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use dbstruct::dbstruct;
+use dbstruct::migrate::Migration;
+
+fn to_v1(_: &Migration<sled::Tree>) -> Result<(), dbstruct::Error<sled::Error>> {
+    Ok(())
+}
+
+#[dbstruct(db=sled, version = 2, migrations = [to_v1])]
+struct Test {
+    field: Option<u8>,
+}
+
+fn main() {}
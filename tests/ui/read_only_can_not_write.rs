@@ -0,0 +1,11 @@
+use dbstruct::dbstruct;
+
+#[dbstruct(db=sled, readonly)]
+struct Test {
+    primes: Vec<u32>,
+}
+
+fn main() {
+    let db = Test::open_path("db").unwrap();
+    db.primes().push(&2).unwrap();
+}